@@ -1,8 +1,26 @@
 from particle_interactions_puzzle.particle_interactions_puzzle import (
+    CsvTrajectoryWriter,
+    QuantizedTrajectoryWriter,
+    RenderOptions,
     Simulation,
+    Trajectory,
+    VtkSeriesWriter,
+    XyzTrajectoryWriter,
+    compare_measurements,
+    distance_threshold_for_neighbors,
+    mean_neighbors_for_distance_threshold,
     optimize_for_critical_noise,
+    plan_capacity,
+    read_quantized_trajectory,
+    run_band_collisions,
+    run_sweep,
+    run_worker,
 )
 from particle_interactions_puzzle.plotting import (
+    animate_inline,
+    animate_simulation_comparison,
+    draw_simulation_timestep,
+    plot_simulation_comparison,
     plot_simulation_timestep,
     plot_stationary_order_parameter,
 )
@@ -1,35 +1,204 @@
 import matplotlib.pyplot as plt
+from matplotlib.lines import Line2D
 import numpy as np
 
-from particle_interactions_puzzle.particle_interactions_puzzle import Simulation
+from particle_interactions_puzzle.particle_interactions_puzzle import (
+    RenderOptions,
+    Simulation,
+)
 
 
-def plot_simulation_timestep(sim):
-    """Plot the simulation's current timestep"""
-    data = sim.get_data()
+def _particle_colors(sim, data, options):
+    """Get per-particle colors (or a single color) for the chosen coloring"""
+    if options.color_by == "none":
+        return options.color
+    if options.color_by == "heading":
+        heading = np.mod(np.arctan2(data.v, data.u), 2 * np.pi) / (2 * np.pi)
+        return plt.get_cmap("hsv")(heading)
+    if options.color_by == "cluster":
+        labels = np.asarray(sim.cluster_labels(options.cluster_distance))
+        return plt.get_cmap("tab20")(labels % 20)
+    if options.color_by == "species":
+        return plt.get_cmap("tab10")(np.asarray(data.tag) % 10)
+    if options.color_by.startswith("scalar:"):
+        values = _scalar_values(data, options.color_by.removeprefix("scalar:"))
+        return plt.get_cmap(options.scalar_cmap)(_scalar_norm([values], options)(values))
 
-    fig, ax = plt.subplots()
-    ax.quiver(
-        data.x,
-        data.y,
-        data.u,
-        data.v,
-        angles="xy",
-        scale_units="xy",
-        scale=5,
-        headlength=12,
-        headwidth=8,
-        headaxislength=10,
-        color="black",
-        pivot="middle",
+    raise ValueError(f"unknown particle coloring `{options.color_by}`")
+
+
+def _scalar_values(data, name):
+    """Get a user-defined scalar's values, complaining if the particles don't have it"""
+    if name not in data.scalars:
+        raise ValueError(f"particles have no scalar `{name}`")
+    return np.asarray(data.scalars[name])
+
+
+def _scalar_norm(values, options):
+    """Map scalar values onto the colormap, over the fixed range if one was given"""
+    if options.scalar_range is not None:
+        return plt.Normalize(*options.scalar_range)
+    return plt.Normalize(
+        min(frame.min() for frame in values), max(frame.max() for frame in values)
     )
+
+
+def draw_simulation_timestep(ax, sim, options=None):
+    """Draw the simulation's current timestep onto existing axes"""
+    options = options or RenderOptions()
+    data = sim.get_data()
+    length = sim.boundary_side_length
+
+    if options.density_overlay:
+        density, _, _ = np.histogram2d(
+            data.x,
+            data.y,
+            bins=options.density_bins,
+            range=[[0, length], [0, length]],
+        )
+        ax.imshow(
+            density.T,
+            origin="lower",
+            extent=(0, length, 0, length),
+            cmap="Greys",
+            alpha=0.4,
+        )
+
+    colors = _particle_colors(sim, data, options)
+
+    if options.glyph == "arrow":
+        ax.quiver(
+            data.x,
+            data.y,
+            data.u,
+            data.v,
+            angles="xy",
+            scale_units="xy",
+            scale=5,
+            headlength=12,
+            headwidth=8,
+            headaxislength=10,
+            color=colors,
+            pivot="middle",
+        )
+    elif options.glyph == "dot":
+        ax.scatter(data.x, data.y, s=8, c=colors)
+    else:
+        raise ValueError(f"unknown particle glyph `{options.glyph}`")
+
     ax.set_aspect("equal")
-    ax.set_xlim(0, sim.boundary_side_length)
-    ax.set_ylim(0, sim.boundary_side_length)
+    ax.set_xlim(0, length)
+    ax.set_ylim(0, length)
+
+
+def plot_simulation_timestep(sim, options=None):
+    """Plot the simulation's current timestep"""
+    fig, ax = plt.subplots()
+    draw_simulation_timestep(ax, sim, options)
     plt.title(f"Particle Simulation, t={sim.current_time:.2f}")
     plt.show()
 
 
+def _draw_shared_legend(fig, axes, sims, options):
+    """Add one legend for every panel, matching the particle coloring"""
+    if options.color_by == "heading":
+        mappable = plt.cm.ScalarMappable(
+            norm=plt.Normalize(0, 2 * np.pi), cmap=plt.get_cmap("hsv")
+        )
+        fig.colorbar(mappable, ax=axes, label="heading (rad)")
+    elif options.color_by == "species":
+        tags = sorted({tag for sim in sims for tag in sim.get_data().tag})
+        handles = [
+            Line2D(
+                [],
+                [],
+                marker="o",
+                linestyle="",
+                color=plt.get_cmap("tab10")(tag % 10),
+                label=f"species {tag}",
+            )
+            for tag in tags
+        ]
+        fig.legend(handles=handles, loc="upper right")
+    elif options.color_by.startswith("scalar:"):
+        name = options.color_by.removeprefix("scalar:")
+        values = [_scalar_values(sim.get_data(), name) for sim in sims]
+        mappable = plt.cm.ScalarMappable(
+            norm=_scalar_norm(values, options), cmap=plt.get_cmap(options.scalar_cmap)
+        )
+        fig.colorbar(mappable, ax=axes, label=name)
+
+
+def _draw_comparison(fig, axes, sims, titles, options):
+    for ax, sim, title in zip(axes, sims, titles):
+        ax.clear()
+        draw_simulation_timestep(ax, sim, options)
+        ax.set_title(f"{title}, t={sim.current_time:.2f}")
+
+
+def plot_simulation_comparison(sims, titles=None, options=None):
+    """Plot several simulations' current timesteps side-by-side"""
+    options = options or RenderOptions()
+    titles = titles or [f"Simulation {idx}" for idx in range(len(sims))]
+
+    fig, axes = plt.subplots(1, len(sims), figsize=(4 * len(sims), 4), squeeze=False)
+    axes = axes[0]
+    _draw_comparison(fig, axes, sims, titles, options)
+    _draw_shared_legend(fig, axes, sims, options)
+    plt.show()
+
+
+def animate_simulation_comparison(
+    sims, num_frames, steps_per_frame=1, titles=None, options=None, interval=50
+):
+    """Animate several simulations side-by-side, stepping them in lockstep
+
+    Returns the matplotlib animation, e.g. to `.save("comparison.gif")` or display in Jupyter.
+    """
+    from matplotlib.animation import FuncAnimation
+
+    options = options or RenderOptions()
+    titles = titles or [f"Simulation {idx}" for idx in range(len(sims))]
+    sims = list(sims)
+
+    fig, axes = plt.subplots(1, len(sims), figsize=(4 * len(sims), 4), squeeze=False)
+    axes = axes[0]
+    _draw_comparison(fig, axes, sims, titles, options)
+    _draw_shared_legend(fig, axes, sims, options)
+
+    def update(_frame):
+        for idx, sim in enumerate(sims):
+            for _ in range(steps_per_frame):
+                sim = sim.to_timestepped()
+            sims[idx] = sim
+        _draw_comparison(fig, axes, sims, titles, options)
+
+    return FuncAnimation(fig, update, frames=num_frames, interval=interval)
+
+
+def animate_inline(sim, num_frames, steps_per_frame=1, options=None, interval=50):
+    """Animate a simulation as an HTML5 video for display inline in Jupyter
+
+    Needs ffmpeg available to matplotlib.
+    """
+    from IPython.display import HTML
+
+    anim = animate_simulation_comparison(
+        [sim],
+        num_frames,
+        steps_per_frame=steps_per_frame,
+        titles=[""],
+        options=options,
+        interval=interval,
+    )
+    video = anim.to_html5_video()
+
+    # Don't leave the first frame behind as a stray static figure
+    plt.close(anim._fig)
+
+    return HTML(video)
+
+
 def compute_stationary_order_parameter(
     noise, num_particles, domain_size, particle_distance_threshold, velocity, timestep
 ):
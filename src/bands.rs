@@ -0,0 +1,357 @@
+use anyhow::{Context, bail};
+
+use crate::{
+    Simulation,
+    control::CancellationToken,
+    particle::{Particle, Particles},
+    random,
+    types::{
+        DomainBoundaryLength, Float, Noise, PI, ParticleDistanceThreshold, RelativeTime, Speed,
+    },
+};
+
+/// Tag of the band that starts on the left heading right
+pub const RIGHTWARD_BAND_TAG: usize = 0;
+
+/// Tag of the band that starts on the right heading left
+pub const LEFTWARD_BAND_TAG: usize = 1;
+
+/// What became of two bands after colliding head-on
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum BandCollisionOutcome {
+    /// The bands now travel the same way, as one flock
+    Merged,
+
+    /// Each band kept its own direction, passing through the other
+    PassedThrough,
+
+    /// Each band turned back the way it came
+    Reflected,
+
+    /// At least one band lost its alignment altogether
+    Dispersed,
+}
+
+/// Controls for a band collision experiment
+#[derive(Clone, Debug)]
+pub struct BandCollisionOptions {
+    pub boundary_side_length: DomainBoundaryLength,
+
+    /// Width of each band across its direction of travel
+    pub band_width: Float,
+
+    pub speed: Speed,
+    pub timestep: RelativeTime,
+    pub particle_distance_threshold: ParticleDistanceThreshold,
+
+    /// Steps run before classifying the outcome, or by default the time for each band to cover
+    /// half the domain, when bands that passed through each other are furthest apart
+    pub num_steps: Option<usize>,
+
+    /// A band whose polarization falls below this counts as dispersed
+    pub polarized_threshold: Float,
+
+    /// Checked between collisions (and steps), so a long experiment can be interrupted
+    pub cancellation: Option<CancellationToken>,
+}
+
+impl BandCollisionOptions {
+    pub fn new(
+        boundary_side_length: DomainBoundaryLength,
+        band_width: Float,
+        speed: Speed,
+        timestep: RelativeTime,
+        particle_distance_threshold: ParticleDistanceThreshold,
+    ) -> Self {
+        Self {
+            boundary_side_length,
+            band_width,
+            speed,
+            timestep,
+            particle_distance_threshold,
+            num_steps: None,
+            polarized_threshold: 0.5,
+            cancellation: None,
+        }
+    }
+}
+
+/// The outcome of one band collision
+#[derive(Copy, Clone, Debug)]
+pub struct BandCollisionResult {
+    pub noise: Noise,
+
+    /// Particles per unit area within each band
+    pub density: Float,
+
+    pub outcome: BandCollisionOutcome,
+
+    /// Each band's mean heading vector at the end, rightward band first. Its length is the band's
+    /// polarization.
+    pub band_headings: [(Float, Float); 2],
+
+    /// The order parameter over both bands at the end
+    pub order: Float,
+}
+
+impl Simulation {
+    /// Instantiate a simulator with two dense bands heading at each other: one tagged
+    /// [`RIGHTWARD_BAND_TAG`] a quarter of the way across the domain, and one tagged
+    /// [`LEFTWARD_BAND_TAG`] three quarters of the way across
+    ///
+    /// # Notes
+    /// Each band spans the whole domain vertically and `band_width` horizontally, with particles
+    /// placed uniformly within it and perfectly aligned.
+    #[allow(clippy::too_many_arguments)]
+    pub fn from_counter_propagating_bands(
+        num_particles_per_band: usize,
+        band_width: Float,
+        boundary_side_length: DomainBoundaryLength,
+        noise: Noise,
+        speed: Speed,
+        timestep: RelativeTime,
+        particle_distance_threshold: ParticleDistanceThreshold,
+    ) -> anyhow::Result<Self> {
+        if band_width.is_nan() || band_width <= 0.0 || band_width > 0.5 * boundary_side_length.0 {
+            bail!(
+                "band width must be positive and at most half the domain, got `{}`",
+                band_width
+            );
+        }
+
+        let bands = [
+            (RIGHTWARD_BAND_TAG, 0.25, 0.0),
+            (LEFTWARD_BAND_TAG, 0.75, PI),
+        ];
+
+        // Each band's particles sit uniformly across its width...
+        let particles = bands.into_iter().flat_map(|(tag, center, heading)| {
+            (0..num_particles_per_band).map(move |_| {
+                let pos_x = center * boundary_side_length.0
+                    + band_width * (random::random::<Float>() - 0.5);
+                let pos_y = boundary_side_length.0 * random::random::<Float>();
+
+                Particle {
+                    tag,
+                    ..Particle::from_state(pos_x, pos_y, heading)
+                }
+            })
+        });
+
+        // ...then they all go in one simulation.
+        Ok(Self::from_initial_particles(
+            Particles::from_reindexed(particles),
+            boundary_side_length,
+            noise,
+            speed,
+            timestep,
+            particle_distance_threshold,
+        )?)
+    }
+
+    /// Classify how the two bands of a [`Simulation::from_counter_propagating_bands`] run ended
+    /// up, from the mean heading of each
+    pub fn band_collision_outcome(&self, polarized_threshold: Float) -> BandCollisionOutcome {
+        let [rightward, leftward] = self.band_headings();
+        let polarization = |(x, y): (Float, Float)| (x * x + y * y).sqrt();
+
+        if polarization(rightward) < polarized_threshold
+            || polarization(leftward) < polarized_threshold
+        {
+            return BandCollisionOutcome::Dispersed;
+        }
+
+        // Bands heading the same way have become one flock, whichever way that is
+        let alignment = rightward.0 * leftward.0 + rightward.1 * leftward.1;
+        if alignment > 0.0 {
+            return BandCollisionOutcome::Merged;
+        }
+
+        match rightward.0 > 0.0 && leftward.0 < 0.0 {
+            true => BandCollisionOutcome::PassedThrough,
+            false => BandCollisionOutcome::Reflected,
+        }
+    }
+
+    /// The mean heading vector of each band, rightward band first
+    fn band_headings(&self) -> [(Float, Float); 2] {
+        [RIGHTWARD_BAND_TAG, LEFTWARD_BAND_TAG].map(|tag| {
+            let (sum_cos, sum_sin, count) = self
+                .particles
+                .iter()
+                .filter(|particle| particle.tag == tag)
+                .fold((0.0, 0.0, 0), |(sum_cos, sum_sin, count), particle| {
+                    (
+                        sum_cos + particle.theta.cos(),
+                        sum_sin + particle.theta.sin(),
+                        count + 1,
+                    )
+                });
+
+            match count {
+                0 => (0.0, 0.0),
+                count => (sum_cos / count as Float, sum_sin / count as Float),
+            }
+        })
+    }
+}
+
+/// Collide two counter-propagating bands at every combination of noise and density (particles
+/// per unit area within a band), classifying each outcome
+///
+/// # Notes
+/// Results come noise-major, i.e. every density for the first noise, then the next. If cancelled,
+/// the combinations finished so far are returned.
+pub fn run_band_collisions(
+    noises: &[Noise],
+    densities: &[Float],
+    options: &BandCollisionOptions,
+) -> anyhow::Result<Vec<BandCollisionResult>> {
+    if let Some(density) = densities
+        .iter()
+        .find(|density| density.is_nan() || **density <= 0.0)
+    {
+        bail!("band densities must be positive, got `{}`", density);
+    }
+
+    let boundary_side_length = options.boundary_side_length.0;
+    let num_steps = options.num_steps.unwrap_or_else(|| {
+        (0.5 * boundary_side_length / (options.speed.0 * options.timestep.0)).ceil() as usize
+    });
+    let is_cancelled = || {
+        options
+            .cancellation
+            .as_ref()
+            .is_some_and(|cancellation| cancellation.is_cancelled())
+    };
+
+    let mut results = Vec::with_capacity(noises.len() * densities.len());
+
+    for &noise in noises {
+        for &density in densities {
+            let num_particles_per_band =
+                ((density * options.band_width * boundary_side_length).round() as usize).max(1);
+
+            let mut sim = Simulation::from_counter_propagating_bands(
+                num_particles_per_band,
+                options.band_width,
+                options.boundary_side_length,
+                noise,
+                options.speed,
+                options.timestep,
+                options.particle_distance_threshold,
+            )
+            .context("could not instantiate band collision")?;
+
+            for _ in 0..num_steps {
+                if is_cancelled() {
+                    return Ok(results);
+                }
+
+                sim = sim.to_timestepped();
+            }
+
+            results.push(BandCollisionResult {
+                noise,
+                density,
+                outcome: sim.band_collision_outcome(options.polarized_threshold),
+                band_headings: sim.band_headings(),
+                order: sim.instantaneous_order.0,
+            });
+        }
+    }
+
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bands() -> Simulation {
+        Simulation::from_counter_propagating_bands(
+            5,
+            1.0,
+            DomainBoundaryLength(8.0),
+            Noise(0.0),
+            Speed(0.1),
+            RelativeTime(1.0),
+            ParticleDistanceThreshold(0.5),
+        )
+        .unwrap()
+    }
+
+    /// Point the rightward band, then the leftward band, along the given headings
+    fn with_headings(sim: &mut Simulation, rightward: Float, leftward: Float) {
+        let (x, y): (Vec<_>, Vec<_>) = sim.particles.iter().map(|p| (p.pos_x, p.pos_y)).unzip();
+        let theta: Vec<_> = sim
+            .particles
+            .iter()
+            .map(|p| match p.tag {
+                RIGHTWARD_BAND_TAG => rightward,
+                _ => leftward,
+            })
+            .collect();
+
+        sim.set_state(&x, &y, &theta).unwrap();
+    }
+
+    #[test]
+    fn band_outcomes_follow_each_band_heading() {
+        let mut sim = bands();
+        assert_eq!(sim.num_particles(), 10);
+        assert!(
+            sim.particles
+                .iter()
+                .all(|p| match p.tag == RIGHTWARD_BAND_TAG {
+                    true => (1.5..=2.5).contains(&p.pos_x),
+                    false => (5.5..=6.5).contains(&p.pos_x),
+                })
+        );
+        assert_eq!(
+            sim.band_collision_outcome(0.5),
+            BandCollisionOutcome::PassedThrough
+        );
+
+        with_headings(&mut sim, PI, 0.0);
+        assert_eq!(
+            sim.band_collision_outcome(0.5),
+            BandCollisionOutcome::Reflected
+        );
+
+        with_headings(&mut sim, 0.5 * PI, 0.5 * PI);
+        assert_eq!(
+            sim.band_collision_outcome(0.5),
+            BandCollisionOutcome::Merged
+        );
+
+        // Even perfectly aligned bands fall short of a threshold above 1
+        assert_eq!(
+            sim.band_collision_outcome(1.5),
+            BandCollisionOutcome::Dispersed
+        );
+    }
+
+    #[test]
+    fn band_widths_and_densities_are_checked() {
+        let too_wide = Simulation::from_counter_propagating_bands(
+            5,
+            5.0,
+            DomainBoundaryLength(8.0),
+            Noise(0.0),
+            Speed(0.1),
+            RelativeTime(1.0),
+            ParticleDistanceThreshold(0.5),
+        );
+        assert!(too_wide.is_err());
+
+        let options = BandCollisionOptions::new(
+            DomainBoundaryLength(8.0),
+            1.0,
+            Speed(0.1),
+            RelativeTime(1.0),
+            ParticleDistanceThreshold(0.5),
+        );
+        assert!(run_band_collisions(&[Noise(0.1)], &[0.0], &options).is_err());
+    }
+}
@@ -0,0 +1,206 @@
+use std::fmt::Write;
+
+use crate::{
+    Simulation,
+    perf::PerformanceCounters,
+    types::{DomainBoundaryLength, Float, Noise, ParticleDistanceThreshold, RelativeTime, Speed},
+};
+
+/// The neighbor search implementations available to benchmark
+// Note: only the brute-force O(n^2) search exists for now; spatial partitioning backends slot in
+// here as they land.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum NeighborBackend {
+    BruteForce,
+}
+
+impl NeighborBackend {
+    /// Every backend compiled into this build
+    pub fn available() -> Vec<Self> {
+        vec![Self::BruteForce]
+    }
+
+    /// A stable, machine-readable name
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::BruteForce => "brute_force",
+        }
+    }
+}
+
+/// One benchmark problem
+#[derive(Copy, Clone, Debug)]
+pub struct Workload {
+    pub num_particles: usize,
+
+    /// Particles per unit area, which sets the domain size
+    pub density: Float,
+
+    pub particle_distance_threshold: ParticleDistanceThreshold,
+
+    /// Number of timed steps
+    pub num_steps: usize,
+}
+
+impl Workload {
+    fn boundary_side_length(&self) -> DomainBoundaryLength {
+        DomainBoundaryLength((self.num_particles as Float / self.density).sqrt())
+    }
+}
+
+/// A grid of workloads varying the particle count, density, and threshold around typical values
+pub fn standard_workloads() -> Vec<Workload> {
+    let mut workloads = Vec::new();
+
+    for num_particles in [100, 400, 1600] {
+        for density in [1.0, 4.0] {
+            for particle_distance_threshold in [0.5, 1.0] {
+                workloads.push(Workload {
+                    num_particles,
+                    density,
+                    particle_distance_threshold: ParticleDistanceThreshold(
+                        particle_distance_threshold,
+                    ),
+                    num_steps: 20,
+                });
+            }
+        }
+    }
+
+    workloads
+}
+
+/// Timings for one workload on one backend
+#[derive(Copy, Clone, Debug)]
+pub struct BenchmarkResult {
+    pub workload: Workload,
+    pub backend: NeighborBackend,
+    pub counters: PerformanceCounters,
+}
+
+/// Results for every workload and backend
+#[derive(Clone, Debug, Default)]
+pub struct BenchmarkReport {
+    pub results: Vec<BenchmarkResult>,
+}
+
+impl BenchmarkReport {
+    /// The fastest backend for a workload matching the given particle count, if benchmarked
+    pub fn fastest_backend(&self, num_particles: usize) -> Option<NeighborBackend> {
+        self.results
+            .iter()
+            .filter(|result| result.workload.num_particles == num_particles)
+            .max_by(|a, b| {
+                a.counters
+                    .steps_per_second()
+                    .total_cmp(&b.counters.steps_per_second())
+            })
+            .map(|result| result.backend)
+    }
+
+    /// Render the report as CSV, one row per result with per-step timings in seconds
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from(
+            "backend,num_particles,density,particle_distance_threshold,num_steps,\
+             steps_per_second,neighbor_search,alignment,integration,observables,total\n",
+        );
+
+        for result in &self.results {
+            let counters = &result.counters;
+            let steps = counters.steps.max(1) as f64;
+
+            // Writing to a String can't fail
+            let _ = writeln!(
+                csv,
+                "{},{},{},{},{},{},{},{},{},{},{}",
+                result.backend.name(),
+                result.workload.num_particles,
+                result.workload.density,
+                result.workload.particle_distance_threshold.0,
+                result.workload.num_steps,
+                counters.steps_per_second(),
+                counters.neighbor_search.as_secs_f64() / steps,
+                counters.alignment.as_secs_f64() / steps,
+                counters.integration.as_secs_f64() / steps,
+                counters.observables.as_secs_f64() / steps,
+                counters.total.as_secs_f64() / steps,
+            );
+        }
+
+        csv
+    }
+}
+
+/// Run every workload on every available backend
+pub fn run_benchmarks(workloads: &[Workload]) -> anyhow::Result<BenchmarkReport> {
+    let mut results = Vec::new();
+
+    for workload in workloads {
+        for backend in NeighborBackend::available() {
+            results.push(BenchmarkResult {
+                workload: *workload,
+                backend,
+                counters: run_workload(workload)?,
+            });
+        }
+    }
+
+    Ok(BenchmarkReport { results })
+}
+
+fn run_workload(workload: &Workload) -> anyhow::Result<PerformanceCounters> {
+    let mut sim = Simulation::new(
+        workload.num_particles,
+        workload.boundary_side_length(),
+        Noise(0.1),
+        Speed(0.03),
+        RelativeTime(1.0),
+        workload.particle_distance_threshold,
+    )?;
+
+    // One untimed step to warm up caches and the allocator
+    sim = sim.to_timestepped();
+    sim.reset_performance_counters();
+
+    for _ in 0..workload.num_steps {
+        sim = sim.to_timestepped();
+    }
+
+    Ok(sim.performance_counters())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn small_workload_reports_one_csv_row_per_backend() {
+        let workload = Workload {
+            num_particles: 16,
+            density: 1.0,
+            particle_distance_threshold: ParticleDistanceThreshold(1.0),
+            num_steps: 3,
+        };
+        assert_eq!(workload.boundary_side_length().0, 4.0);
+
+        let report = run_benchmarks(&[workload]).unwrap();
+        assert_eq!(report.results.len(), NeighborBackend::available().len());
+        assert!(
+            report
+                .results
+                .iter()
+                .all(|result| result.counters.steps == 3)
+        );
+        assert_eq!(
+            report.fastest_backend(16),
+            Some(NeighborBackend::BruteForce)
+        );
+        assert_eq!(report.fastest_backend(17), None);
+
+        let csv = report.to_csv();
+        let mut lines = csv.lines();
+        assert!(lines.next().unwrap().starts_with("backend,num_particles"));
+        assert!(lines.next().unwrap().starts_with("brute_force,16,1,1,3,"));
+        assert!(lines.next().is_none());
+    }
+}
@@ -0,0 +1,188 @@
+use std::{
+    fs::{self, File},
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, anyhow, bail};
+use clap::Args;
+use particle_interactions_puzzle::Float;
+use serde_json::Value;
+
+use crate::{
+    plan::MANIFEST_FILE,
+    sweep::{Combination, Row, write_table},
+};
+
+#[derive(Args)]
+pub struct CollectArgs {
+    /// Directory `pip-sim plan` wrote the plan into
+    #[arg(long)]
+    plan_dir: PathBuf,
+
+    /// Leave out points without a result instead of failing, e.g. while the jobs are still
+    /// running
+    #[arg(long)]
+    partial: bool,
+
+    /// Write the results table here instead of to standard output
+    #[arg(long)]
+    output: Option<PathBuf>,
+}
+
+/// Merge the result of every point in a plan into one CSV table, laid out as `pip-sim sweep`
+/// writes it
+pub fn collect(args: CollectArgs) -> anyhow::Result<()> {
+    let manifest_path = args.plan_dir.join(MANIFEST_FILE);
+    let manifest = read_json(&manifest_path)?;
+    let points = manifest["points"]
+        .as_array()
+        .ok_or_else(|| anyhow!("manifest `{}` lists no points", manifest_path.display()))?;
+
+    let mut combinations = Vec::with_capacity(points.len());
+    let mut rows = Vec::with_capacity(points.len());
+    let mut num_missing = 0;
+
+    for (index, point) in points.iter().enumerate() {
+        let (combination, result_path) = manifest_point(point)
+            .with_context(|| format!("could not read point `{index}` of the manifest"))?;
+
+        let row = match result_path.exists() {
+            true => Some(read_row(&result_path)?),
+            false if args.partial => {
+                num_missing += 1;
+                None
+            }
+            false => bail!(
+                "point `{}` has no result at `{}` yet; pass `--partial` to leave it out",
+                index,
+                result_path.display()
+            ),
+        };
+
+        combinations.push(combination);
+        rows.push(row);
+    }
+
+    if num_missing > 0 {
+        eprintln!(
+            "left out {num_missing} of {} points without a result",
+            points.len()
+        );
+    }
+
+    let writer: Box<dyn Write> = match &args.output {
+        Some(path) => {
+            Box::new(BufWriter::new(File::create(path).with_context(|| {
+                format!("could not create `{}`", path.display())
+            })?))
+        }
+        None => Box::new(std::io::stdout().lock()),
+    };
+
+    write_table(writer, &combinations, &rows).context("could not write collected results")
+}
+
+/// A manifest entry's parameters and where its result goes
+fn manifest_point(point: &Value) -> anyhow::Result<(Combination, PathBuf)> {
+    let float = |key: &str| {
+        point[key]
+            .as_f64()
+            .map(|value| value as Float)
+            .ok_or_else(|| anyhow!("`{}` must be a number", key))
+    };
+
+    let combination = Combination {
+        noise: float("noise")?,
+        density: float("density")?,
+        speed: float("speed")?,
+        num_particles: point["num_particles"]
+            .as_u64()
+            .ok_or_else(|| anyhow!("`num_particles` must be a non-negative integer"))?
+            as usize,
+    };
+    let result_path = point["result"]
+        .as_str()
+        .map(PathBuf::from)
+        .ok_or_else(|| anyhow!("`result` must be a path"))?;
+
+    Ok((combination, result_path))
+}
+
+/// Read a result written by `pip-sim run --result`
+fn read_row(path: &Path) -> anyhow::Result<Row> {
+    let result = read_json(path)?;
+
+    let row = || -> Option<Row> {
+        Some(Row {
+            value: result["stationary_order_parameter"].as_f64()? as Float,
+            converged: result["converged"].as_bool()?,
+            iterations: result["iterations"].as_u64()? as usize,
+        })
+    };
+
+    row().ok_or_else(|| anyhow!("result `{}` is incomplete", path.display()))
+}
+
+fn read_json(path: &Path) -> anyhow::Result<Value> {
+    let read =
+        || -> anyhow::Result<Value> { Ok(serde_json::from_str(&fs::read_to_string(path)?)?) };
+
+    read().with_context(|| format!("could not read `{}`", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+    use serde_json::json;
+
+    use super::*;
+    use crate::{Cli, Command, plan::plan};
+
+    #[test]
+    fn collecting_merges_the_finished_points() {
+        let dir = std::env::temp_dir().join(format!("pip-sim-collect-{}", std::process::id()));
+        let plan_dir = dir.to_str().unwrap();
+        let parse = |args: &[&str]| Cli::try_parse_from(args).unwrap().command;
+
+        let Command::Plan(args) = parse(&[
+            "pip-sim",
+            "plan",
+            "--noise",
+            "0.1:0.2:2",
+            "--output-dir",
+            plan_dir,
+        ]) else {
+            unreachable!()
+        };
+        plan(args).unwrap();
+
+        let result = json!({
+            "stationary_order_parameter": 0.75,
+            "converged": true,
+            "iterations": 40,
+        });
+        fs::write(dir.join("results/point_000001.json"), result.to_string()).unwrap();
+
+        let collect_args = |partial: bool| {
+            let output = dir.join("table.csv");
+            let mut args = vec!["pip-sim", "collect", "--plan-dir", plan_dir];
+            args.extend(["--output", output.to_str().unwrap()]);
+            if partial {
+                args.push("--partial");
+            }
+            let Command::Collect(args) = parse(&args) else {
+                unreachable!()
+            };
+            args
+        };
+        assert!(collect(collect_args(false)).is_err());
+        collect(collect_args(true)).unwrap();
+
+        let table = fs::read_to_string(dir.join("table.csv")).unwrap();
+        let rows: Vec<&str> = table.lines().skip(1).collect();
+        assert_eq!(rows, ["0.2,5,1,125,0.75,true,40"]);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
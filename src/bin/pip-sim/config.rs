@@ -0,0 +1,165 @@
+//! Reading settings from JSON config files, for the subcommands that take one
+
+use std::{
+    fmt::Display,
+    fs::File,
+    io::BufReader,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use anyhow::{Context, anyhow, bail};
+use particle_interactions_puzzle::Float;
+use serde_json::{Map, Value};
+
+/// Read a config file's object, checking every key is one of `keys`
+pub fn read_config(path: &Path, keys: &[&str]) -> anyhow::Result<Map<String, Value>> {
+    read_object(path, keys)
+        .with_context(|| format!("could not read config file `{}`", path.display()))
+}
+
+fn read_object(path: &Path, keys: &[&str]) -> anyhow::Result<Map<String, Value>> {
+    let file = File::open(path)?;
+    let Value::Object(config) = serde_json::from_reader(BufReader::new(file))? else {
+        bail!("config must be a JSON object");
+    };
+    check_keys(&config, keys)?;
+
+    Ok(config)
+}
+
+/// Check every key of a config object is one of `keys`
+pub fn check_keys(config: &Map<String, Value>, keys: &[&str]) -> anyhow::Result<()> {
+    match config.keys().find(|key| !keys.contains(&key.as_str())) {
+        Some(key) => bail!("unknown config key `{}`", key),
+        None => Ok(()),
+    }
+}
+
+pub fn config_float(config: &Map<String, Value>, key: &str) -> anyhow::Result<Option<Float>> {
+    config
+        .get(key)
+        .map(|value| {
+            value
+                .as_f64()
+                .map(|value| value as Float)
+                .ok_or_else(|| anyhow!("config `{}` must be a number", key))
+        })
+        .transpose()
+}
+
+pub fn config_seconds(config: &Map<String, Value>, key: &str) -> anyhow::Result<Option<f64>> {
+    config
+        .get(key)
+        .map(|value| {
+            value
+                .as_f64()
+                .ok_or_else(|| anyhow!("config `{}` must be a number of seconds", key))
+        })
+        .transpose()
+}
+
+pub fn config_count(config: &Map<String, Value>, key: &str) -> anyhow::Result<Option<usize>> {
+    config
+        .get(key)
+        .map(|value| {
+            value
+                .as_u64()
+                .map(|value| value as usize)
+                .ok_or_else(|| anyhow!("config `{}` must be a non-negative integer", key))
+        })
+        .transpose()
+}
+
+pub fn config_bool(config: &Map<String, Value>, key: &str) -> anyhow::Result<Option<bool>> {
+    config
+        .get(key)
+        .map(|value| {
+            value
+                .as_bool()
+                .ok_or_else(|| anyhow!("config `{}` must be a boolean", key))
+        })
+        .transpose()
+}
+
+pub fn config_path(config: &Map<String, Value>, key: &str) -> anyhow::Result<Option<PathBuf>> {
+    config
+        .get(key)
+        .map(|value| {
+            value
+                .as_str()
+                .map(PathBuf::from)
+                .ok_or_else(|| anyhow!("config `{}` must be a path", key))
+        })
+        .transpose()
+}
+
+/// Parse a string setting, e.g. a particle coloring
+pub fn config_parsed<T>(config: &Map<String, Value>, key: &str) -> anyhow::Result<Option<T>>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    config
+        .get(key)
+        .map(|value| {
+            let text = value
+                .as_str()
+                .ok_or_else(|| anyhow!("config `{}` must be a string", key))?;
+
+            text.parse()
+                .map_err(|error| anyhow!("config `{}` is invalid: {}", key, error))
+        })
+        .transpose()
+}
+
+/// Get a nested settings object
+pub fn config_object<'a>(
+    config: &'a Map<String, Value>,
+    key: &str,
+) -> anyhow::Result<Option<&'a Map<String, Value>>> {
+    config
+        .get(key)
+        .map(|value| {
+            value
+                .as_object()
+                .ok_or_else(|| anyhow!("config `{}` must be an object", key))
+        })
+        .transpose()
+}
+
+/// Get a `[first, second]` array of numbers
+pub fn config_float_pair(
+    config: &Map<String, Value>,
+    key: &str,
+) -> anyhow::Result<Option<(Float, Float)>> {
+    config
+        .get(key)
+        .map(|value| match value.as_array().map(Vec::as_slice) {
+            Some([first, second]) => first
+                .as_f64()
+                .zip(second.as_f64())
+                .map(|(first, second)| (first as Float, second as Float))
+                .ok_or_else(|| anyhow!("config `{}` must hold two numbers", key)),
+            _ => Err(anyhow!("config `{}` must be an array of two numbers", key)),
+        })
+        .transpose()
+}
+
+/// Get an array of numbers
+pub fn config_floats(config: &Map<String, Value>, key: &str) -> anyhow::Result<Option<Vec<Float>>> {
+    config
+        .get(key)
+        .map(|value| {
+            value
+                .as_array()
+                .and_then(|values| {
+                    values
+                        .iter()
+                        .map(|value| value.as_f64().map(|value| value as Float))
+                        .collect()
+                })
+                .ok_or_else(|| anyhow!("config `{}` must be an array of numbers", key))
+        })
+        .transpose()
+}
@@ -0,0 +1,83 @@
+//! Command-line front end for running simulations without Python, e.g. on cluster nodes
+
+mod collect;
+mod config;
+mod observables;
+mod optimize;
+mod plan;
+mod render;
+mod replay;
+mod run;
+mod setup;
+mod snapshots;
+mod sweep;
+
+use clap::{Parser, Subcommand};
+use tracing::Level;
+use tracing_subscriber::fmt::format::FmtSpan;
+
+/// Run self-propelled particle simulations from the command line
+#[derive(Parser)]
+#[command(name = "pip-sim", version)]
+struct Cli {
+    /// Log to standard error at this level and above (`error`, `warn`, `info`, `debug`, or
+    /// `trace`), including how long each span (e.g. a step or an optimizer cost evaluation) took
+    #[arg(long, global = true)]
+    log: Option<Level>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Run one simulation, optionally writing its trajectory and order parameter, then print its
+    /// stationary order parameter
+    Run(run::RunArgs),
+
+    /// Compute the stationary order parameter over every combination of noise, density, and
+    /// speed, writing a CSV table
+    Sweep(sweep::SweepArgs),
+
+    /// Write a run config per combination of noise, density, and speed, with a manifest (and
+    /// optionally a SLURM job array script), to farm a sweep out as separate jobs
+    Plan(plan::PlanArgs),
+
+    /// Merge the results of a planned sweep's jobs into one CSV table, laid out as `sweep` writes
+    /// it
+    Collect(collect::CollectArgs),
+
+    /// Run one simulation and render it at chosen times into a directory of frames, with a JSON
+    /// index of what each frame shows
+    Snapshots(snapshots::SnapshotsArgs),
+
+    /// Replay a session recorded from the control panel step for step, optionally writing its
+    /// trajectory and order parameter
+    Replay(replay::ReplayArgs),
+
+    /// Find the distance threshold and speed that put the order-disorder transition at a target
+    /// noise
+    Optimize(optimize::OptimizeArgs),
+}
+
+fn main() -> anyhow::Result<()> {
+    let cli = Cli::parse();
+
+    if let Some(level) = cli.log {
+        tracing_subscriber::fmt()
+            .with_max_level(level)
+            .with_span_events(FmtSpan::CLOSE)
+            .with_writer(std::io::stderr)
+            .init();
+    }
+
+    match cli.command {
+        Command::Run(args) => run::run(args),
+        Command::Sweep(args) => sweep::sweep(args),
+        Command::Plan(args) => plan::plan(args),
+        Command::Collect(args) => collect::collect(args),
+        Command::Snapshots(args) => snapshots::snapshots(args),
+        Command::Replay(args) => replay::replay(args),
+        Command::Optimize(args) => optimize::optimize(args),
+    }
+}
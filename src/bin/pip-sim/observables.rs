@@ -0,0 +1,336 @@
+//! Observables measured along a `run` and written to their own CSV files at their own strides
+
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::PathBuf,
+};
+
+use anyhow::{Context, anyhow, bail};
+use particle_interactions_puzzle::{
+    ComparisonOptions, Float, ParticleSelection, Simulation, SummaryStatistics,
+};
+use serde_json::{Map, Value};
+
+use crate::config::{check_keys, config_count, config_float, config_path};
+
+/// Keys every entry of a config file's `observables` list accepts, besides its kind's own
+const OBSERVABLE_KEYS: [&str; 3] = ["kind", "output", "stride"];
+
+/// Number of distance bins for C(r), unless given
+const DEFAULT_CORRELATION_BINS: usize = 20;
+
+/// Number of cells across the density field, unless given
+const DEFAULT_DENSITY_BINS: usize = 20;
+
+/// What to measure
+#[derive(Clone, Debug)]
+enum Observable {
+    /// Instantaneous order parameter, as `t,order`
+    Order,
+
+    /// Running mean order parameter and susceptibility N(⟨φ²⟩ - ⟨φ⟩²) over every recorded step
+    /// so far, as `t,num_steps,mean_order,susceptibility`
+    Susceptibility,
+
+    /// Number of clusters and their sizes, as `t,num_clusters,largest_cluster,mean_cluster_size`
+    Clusters { cluster_distance: Option<Float> },
+
+    /// Velocity correlation C(r) per distance bin, as `t,r,correlation` with `r` the bin center
+    Correlation { max_distance: Float, bins: usize },
+
+    /// Number density per cell, as `t,x,y,density` with `(x, y)` the cell center
+    Density { bins: usize },
+}
+
+impl Observable {
+    fn header(&self) -> &'static str {
+        match self {
+            Self::Order => "t,order",
+            Self::Susceptibility => "t,num_steps,mean_order,susceptibility",
+            Self::Clusters { .. } => "t,num_clusters,largest_cluster,mean_cluster_size",
+            Self::Correlation { .. } => "t,r,correlation",
+            Self::Density { .. } => "t,x,y,density",
+        }
+    }
+}
+
+/// One observable as configured, before anything is written
+#[derive(Clone, Debug)]
+pub struct ObservableSpec {
+    observable: Observable,
+    output: PathBuf,
+    stride: usize,
+}
+
+/// Read a config file's `observables` list, e.g.
+/// `[{"kind": "order", "output": "order.csv", "stride": 10}]`
+///
+/// # Notes
+/// Every entry needs a `kind` and an `output` path, and takes a `stride` (default 1) of recorded
+/// steps between measurements. `clusters` takes a `cluster_distance` (default the particle
+/// distance threshold), `correlation` a `max_distance` (required) and `bins` (default 20), and
+/// `density` its `bins` across (default 20).
+pub fn config_observables(
+    config: &Map<String, Value>,
+    key: &str,
+) -> anyhow::Result<Vec<ObservableSpec>> {
+    let Some(value) = config.get(key) else {
+        return Ok(Vec::new());
+    };
+    let entries = value
+        .as_array()
+        .ok_or_else(|| anyhow!("config `{}` must be an array of objects", key))?;
+
+    entries
+        .iter()
+        .enumerate()
+        .map(|(idx, entry)| {
+            let entry = entry
+                .as_object()
+                .ok_or_else(|| anyhow!("config `{}` must be an array of objects", key))?;
+
+            observable_spec(entry).with_context(|| format!("invalid observable `{idx}`"))
+        })
+        .collect()
+}
+
+fn observable_spec(entry: &Map<String, Value>) -> anyhow::Result<ObservableSpec> {
+    let kind = entry
+        .get("kind")
+        .and_then(Value::as_str)
+        .ok_or_else(|| anyhow!("observables need a `kind`"))?;
+
+    let (observable, extra_keys): (_, &[&str]) = match kind {
+        "order" => (Observable::Order, &[]),
+        "susceptibility" => (Observable::Susceptibility, &[]),
+        "clusters" => (
+            Observable::Clusters {
+                cluster_distance: config_float(entry, "cluster_distance")?,
+            },
+            &["cluster_distance"],
+        ),
+        "correlation" => (
+            Observable::Correlation {
+                max_distance: config_float(entry, "max_distance")?
+                    .ok_or_else(|| anyhow!("`correlation` needs a `max_distance`"))?,
+                bins: config_count(entry, "bins")?.unwrap_or(DEFAULT_CORRELATION_BINS),
+            },
+            &["max_distance", "bins"],
+        ),
+        "density" => (
+            Observable::Density {
+                bins: config_count(entry, "bins")?.unwrap_or(DEFAULT_DENSITY_BINS),
+            },
+            &["bins"],
+        ),
+        _ => bail!(
+            "observable kinds are order, susceptibility, clusters, correlation, or density, got \
+             `{kind}`"
+        ),
+    };
+
+    let keys: Vec<&str> = OBSERVABLE_KEYS.iter().chain(extra_keys).copied().collect();
+    check_keys(entry, &keys)?;
+
+    let output =
+        config_path(entry, "output")?.ok_or_else(|| anyhow!("observables need an `output`"))?;
+    let stride = config_count(entry, "stride")?.unwrap_or(1);
+    if stride == 0 {
+        bail!("observable stride must be at least 1");
+    }
+
+    Ok(ObservableSpec {
+        observable,
+        output,
+        stride,
+    })
+}
+
+/// An observable being written as the run goes
+pub struct ObservableWriter {
+    spec: ObservableSpec,
+    writer: BufWriter<File>,
+    boundary_side_length: Float,
+    particle_distance_threshold: Float,
+
+    /// Sum of the order parameter and its square over every step seen, for the susceptibility
+    order_sums: (Float, Float),
+    num_steps: usize,
+}
+
+impl ObservableWriter {
+    /// Create the output file and write its header, for a simulation with the given domain size
+    /// and distance threshold
+    pub fn new(
+        spec: ObservableSpec,
+        boundary_side_length: Float,
+        particle_distance_threshold: Float,
+    ) -> anyhow::Result<Self> {
+        let file = File::create(&spec.output)
+            .with_context(|| format!("could not create `{}`", spec.output.display()))?;
+        let mut writer = BufWriter::new(file);
+        writeln!(writer, "{}", spec.observable.header())?;
+
+        Ok(Self {
+            spec,
+            writer,
+            boundary_side_length,
+            particle_distance_threshold,
+            order_sums: (0.0, 0.0),
+            num_steps: 0,
+        })
+    }
+
+    /// Take in the simulation after recorded step `step` (0 being the initial state), writing a
+    /// measurement every `stride` steps
+    pub fn observe(&mut self, step: usize, sim: &Simulation) -> anyhow::Result<()> {
+        let order = sim.order_parameter(&ParticleSelection::All);
+        self.order_sums.0 += order;
+        self.order_sums.1 += order * order;
+        self.num_steps += 1;
+
+        if !step.is_multiple_of(self.spec.stride) {
+            return Ok(());
+        }
+
+        self.write(sim, order)
+            .with_context(|| format!("could not write `{}`", self.spec.output.display()))
+    }
+
+    /// Flush everything written so far
+    pub fn finish(mut self) -> anyhow::Result<()> {
+        self.writer
+            .flush()
+            .with_context(|| format!("could not write `{}`", self.spec.output.display()))
+    }
+
+    fn write(&mut self, sim: &Simulation, order: Float) -> anyhow::Result<()> {
+        let time = sim.current_time().0;
+
+        match &self.spec.observable {
+            Observable::Order => writeln!(self.writer, "{time},{order}")?,
+            Observable::Susceptibility => {
+                let num_steps = self.num_steps as Float;
+                let mean = self.order_sums.0 / num_steps;
+                let variance = (self.order_sums.1 / num_steps - mean * mean).max(0.0);
+                let susceptibility = sim.num_particles() as Float * variance;
+
+                writeln!(
+                    self.writer,
+                    "{time},{},{mean},{susceptibility}",
+                    self.num_steps
+                )?;
+            }
+            Observable::Clusters { cluster_distance } => {
+                let labels = sim
+                    .cluster_labels(cluster_distance.unwrap_or(self.particle_distance_threshold));
+                let num_clusters = labels.iter().max().map_or(0, |&label| label + 1);
+
+                let mut sizes = vec![0usize; num_clusters];
+                for label in labels {
+                    sizes[label] += 1;
+                }
+                let largest = sizes.iter().copied().max().unwrap_or(0);
+                let mean = match num_clusters {
+                    0 => 0.0,
+                    _ => sim.num_particles() as Float / num_clusters as Float,
+                };
+
+                writeln!(self.writer, "{time},{num_clusters},{largest},{mean}")?;
+            }
+            Observable::Correlation { max_distance, bins } => {
+                let options = ComparisonOptions {
+                    correlation_bins: *bins,
+                    ..ComparisonOptions::new(*max_distance, 0.0)
+                };
+                let statistics = SummaryStatistics::from_simulation(sim, 0, &options)?;
+
+                let bin_width = max_distance / *bins as Float;
+                for (idx, correlation) in statistics.velocity_correlation.iter().enumerate() {
+                    let r = (idx as Float + 0.5) * bin_width;
+                    writeln!(self.writer, "{time},{r},{correlation}")?;
+                }
+            }
+            Observable::Density { bins } => {
+                let cell_size = self.boundary_side_length / *bins as Float;
+
+                for (idx, density) in sim.density_field(*bins)?.iter().enumerate() {
+                    let x = (idx % bins) as Float + 0.5;
+                    let y = (idx / bins) as Float + 0.5;
+                    writeln!(
+                        self.writer,
+                        "{time},{},{},{density}",
+                        x * cell_size,
+                        y * cell_size
+                    )?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use particle_interactions_puzzle::{
+        DomainBoundaryLength, Noise, ParticleDistanceThreshold, RelativeTime, Speed,
+    };
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn observables_are_written_every_stride_steps() {
+        let path = std::env::temp_dir().join(format!("pip-sim-order-{}.csv", std::process::id()));
+        let config = json!({
+            "observables": [{"kind": "order", "output": path, "stride": 2}],
+        });
+        let mut specs = config_observables(config.as_object().unwrap(), "observables").unwrap();
+        assert_eq!(specs.len(), 1);
+
+        let mut sim = Simulation::new(
+            5,
+            DomainBoundaryLength(5.0),
+            Noise(0.5),
+            Speed(0.1),
+            RelativeTime(1.0),
+            ParticleDistanceThreshold(1.0),
+        )
+        .unwrap();
+        let mut writer = ObservableWriter::new(specs.remove(0), 5.0, 1.0).unwrap();
+        for step in 0..5 {
+            writer.observe(step, &sim).unwrap();
+            sim.run_for(1).unwrap();
+        }
+        writer.finish().unwrap();
+
+        let written = fs::read_to_string(&path).unwrap();
+        let times: Vec<&str> = written
+            .lines()
+            .skip(1)
+            .filter_map(|line| line.split(',').next())
+            .collect();
+        assert_eq!(written.lines().next(), Some("t,order"));
+        assert_eq!(times, ["0", "2", "4"]);
+
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn invalid_observables_are_rejected() {
+        for entry in [
+            json!({"kind": "order"}),
+            json!({"kind": "order", "output": "order.csv", "stride": 0}),
+            json!({"kind": "correlation", "output": "c.csv"}),
+            json!({"kind": "order", "output": "order.csv", "bins": 4}),
+            json!({"kind": "speed", "output": "speed.csv"}),
+        ] {
+            let config = json!({ "observables": [entry] });
+            assert!(config_observables(config.as_object().unwrap(), "observables").is_err());
+        }
+    }
+}
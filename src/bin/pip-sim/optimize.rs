@@ -0,0 +1,119 @@
+use std::time::Duration;
+
+use anyhow::anyhow;
+use clap::Args;
+use particle_interactions_puzzle::{
+    DEFAULT_BOUNDARY_SIDE_LENGTH, DEFAULT_NUM_PARTICLES, DEFAULT_TIMESTEP, DomainBoundaryLength,
+    Float, Noise, OptimizerOptions, RelativeTime, optimize_for_critical_noise_with, seed_rng,
+};
+
+#[derive(Args)]
+pub struct OptimizeArgs {
+    /// Noise amplitude the order-disorder transition should happen at
+    #[arg(long)]
+    target_noise: Float,
+
+    /// Number of particles [default: 125]
+    #[arg(long)]
+    num_particles: Option<usize>,
+
+    /// Side length of the periodic square domain [default: 5]
+    #[arg(long)]
+    boundary_side_length: Option<Float>,
+
+    /// Timestep [default: 0.25]
+    #[arg(long)]
+    timestep: Option<Float>,
+
+    /// Seed for the random number generator, drawn at random (and printed) if not given
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Stop after this many Nelder-Mead iterations
+    #[arg(long)]
+    max_iterations: Option<u64>,
+
+    /// Stop after this many seconds, keeping the best parameters found so far
+    #[arg(long)]
+    time_budget: Option<f64>,
+}
+
+/// Find the distance threshold and speed that put the transition at the target noise
+pub fn optimize(args: OptimizeArgs) -> anyhow::Result<()> {
+    let seed = args.seed.unwrap_or_else(rand::random::<u64>);
+    seed_rng(seed);
+    println!("seed: {seed}");
+
+    let options = OptimizerOptions {
+        cancellation: None,
+        max_iterations: args.max_iterations,
+        time_budget: args
+            .time_budget
+            .map(|seconds| {
+                Duration::try_from_secs_f64(seconds)
+                    .map_err(|_| anyhow!("`{}` is not a valid time budget", seconds))
+            })
+            .transpose()?,
+    };
+
+    let optimum = optimize_for_critical_noise_with(
+        args.num_particles.unwrap_or(DEFAULT_NUM_PARTICLES),
+        args.boundary_side_length
+            .map_or(DEFAULT_BOUNDARY_SIDE_LENGTH, DomainBoundaryLength),
+        args.timestep.map_or(DEFAULT_TIMESTEP, RelativeTime),
+        Noise(args.target_noise),
+        &options,
+    )?;
+
+    println!("iterations: {}", optimum.iterations);
+    println!("stop reason: {:?}", optimum.stop_reason);
+    println!("elapsed: {:.3} s", optimum.elapsed.as_secs_f64());
+    println!(
+        "particle distance threshold: {}",
+        optimum.particle_distance_threshold.0
+    );
+    println!("speed: {}", optimum.speed.0);
+    println!("residual: {}", optimum.residual);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::*;
+    use crate::{Cli, Command};
+
+    /// Parse an `optimize` command line, without the leading `pip-sim optimize`
+    fn parse(args: &[&str]) -> Result<OptimizeArgs, clap::Error> {
+        let args = ["pip-sim", "optimize"].iter().chain(args);
+        match Cli::try_parse_from(args)?.command {
+            Command::Optimize(args) => Ok(args),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn optimizing_needs_a_target_and_a_valid_budget() {
+        assert!(parse(&["--num-particles", "5"]).is_err());
+
+        let args = parse(&["--target-noise", "0.5", "--time-budget=-1"]).unwrap();
+        assert!(optimize(args).is_err());
+
+        let args = parse(&[
+            "--target-noise",
+            "0.5",
+            "--num-particles",
+            "5",
+            "--seed",
+            "1",
+            "--max-iterations",
+            "1",
+            "--time-budget",
+            "1",
+        ])
+        .unwrap();
+        optimize(args).unwrap();
+    }
+}
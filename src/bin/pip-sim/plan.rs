@@ -0,0 +1,219 @@
+use std::{
+    fs::{self, File},
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+use clap::Args;
+use particle_interactions_puzzle::{
+    DEFAULT_BOUNDARY_SIDE_LENGTH, DEFAULT_PARTICLE_DISTANCE_THRESHOLD, DEFAULT_TIMESTEP,
+    DomainBoundaryLength, Float,
+};
+use serde_json::{Value, json};
+
+use crate::sweep::GridArgs;
+
+/// Name of the manifest `plan` writes and `collect` reads, in the plan directory
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+#[derive(Args)]
+pub struct PlanArgs {
+    #[command(flatten)]
+    grid: GridArgs,
+
+    /// Side length of the periodic square domain [default: 5]
+    #[arg(long)]
+    boundary_side_length: Option<Float>,
+
+    /// Timestep [default: 0.25]
+    #[arg(long)]
+    timestep: Option<Float>,
+
+    /// Distance within which particles align [default: 1]
+    #[arg(long)]
+    particle_distance_threshold: Option<Float>,
+
+    /// Seed for the random number generators; point `i` is seeded with `seed + i`, as in `sweep`
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Give up on converging each stationary order parameter after this many steps
+    #[arg(long)]
+    max_steps: Option<usize>,
+
+    /// Give up on converging each stationary order parameter after this many seconds
+    #[arg(long)]
+    time_budget: Option<f64>,
+
+    /// Directory to write the plan into, created if missing
+    #[arg(long)]
+    output_dir: PathBuf,
+
+    /// Also write `sweep.slurm`, a SLURM job array script running one point per task
+    #[arg(long)]
+    slurm: bool,
+}
+
+/// Write a `pip-sim run` config for every combination of the swept parameters, plus a manifest
+/// listing them, so the points can run as separate jobs and be merged with `pip-sim collect`
+pub fn plan(args: PlanArgs) -> anyhow::Result<()> {
+    let boundary_side_length = args
+        .boundary_side_length
+        .map_or(DEFAULT_BOUNDARY_SIDE_LENGTH, DomainBoundaryLength);
+    let combinations = args.grid.combinations(boundary_side_length);
+
+    let configs_dir = args.output_dir.join("configs");
+    let results_dir = args.output_dir.join("results");
+    for dir in [&configs_dir, &results_dir] {
+        fs::create_dir_all(dir).with_context(|| format!("could not create `{}`", dir.display()))?;
+    }
+
+    // Jobs may start anywhere, so every path they see is absolute
+    let output_dir = args
+        .output_dir
+        .canonicalize()
+        .with_context(|| format!("could not find `{}`", args.output_dir.display()))?;
+    let (configs_dir, results_dir) = (output_dir.join("configs"), output_dir.join("results"));
+
+    let mut points = Vec::with_capacity(combinations.len());
+    for (index, combination) in combinations.iter().enumerate() {
+        let config_path = configs_dir.join(point_file_name(index));
+        let result_path = results_dir.join(point_file_name(index));
+
+        let mut config = json!({
+            "num_particles": combination.num_particles,
+            "boundary_side_length": boundary_side_length.0,
+            "noise": combination.noise,
+            "speed": combination.speed,
+            "timestep": args.timestep.unwrap_or(DEFAULT_TIMESTEP.0),
+            "particle_distance_threshold": args
+                .particle_distance_threshold
+                .unwrap_or(DEFAULT_PARTICLE_DISTANCE_THRESHOLD.0),
+            "result": result_path,
+        });
+        if let Some(seed) = args.seed {
+            config["seed"] = json!(seed.wrapping_add(index as u64));
+        }
+        if let Some(max_steps) = args.max_steps {
+            config["max_steps"] = json!(max_steps);
+        }
+        if let Some(time_budget) = args.time_budget {
+            config["time_budget"] = json!(time_budget);
+        }
+
+        write_json(&config_path, &config)?;
+
+        points.push(json!({
+            "index": index,
+            "noise": combination.noise,
+            "density": combination.density,
+            "speed": combination.speed,
+            "num_particles": combination.num_particles,
+            "config": config_path,
+            "result": result_path,
+        }));
+    }
+
+    write_json(
+        &output_dir.join(MANIFEST_FILE),
+        &json!({ "points": points }),
+    )?;
+
+    if args.slurm {
+        // SLURM opens each task's log before running it, so the directory has to exist already
+        let logs_dir = output_dir.join("logs");
+        fs::create_dir_all(&logs_dir)
+            .with_context(|| format!("could not create `{}`", logs_dir.display()))?;
+
+        let script_path = output_dir.join("sweep.slurm");
+        fs::write(
+            &script_path,
+            slurm_script(&logs_dir, &configs_dir, combinations.len()),
+        )
+        .with_context(|| format!("could not write `{}`", script_path.display()))?;
+    }
+
+    println!("points: {}", combinations.len());
+
+    Ok(())
+}
+
+/// The config and result file name of point `index`, which sort in sweep order
+fn point_file_name(index: usize) -> String {
+    format!("point_{index:06}.json")
+}
+
+/// A job array script running point `$SLURM_ARRAY_TASK_ID` per task, with `pip-sim` on the
+/// `PATH`
+fn slurm_script(logs_dir: &Path, configs_dir: &Path, num_points: usize) -> String {
+    format!(
+        "#!/bin/bash\n\
+         #SBATCH --job-name=pip-sim-sweep\n\
+         #SBATCH --array=0-{last}\n\
+         #SBATCH --output={logs}/point_%a.log\n\
+         \n\
+         pip-sim run --config \"$(printf '{configs}/point_%06d.json' \"$SLURM_ARRAY_TASK_ID\")\"\n",
+        last = num_points.saturating_sub(1),
+        logs = logs_dir.display(),
+        configs = configs_dir.display(),
+    )
+}
+
+fn write_json(path: &Path, value: &Value) -> anyhow::Result<()> {
+    let write = || -> anyhow::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer_pretty(&mut writer, value)?;
+        writeln!(writer)?;
+        writer.flush()?;
+
+        Ok(())
+    };
+
+    write().with_context(|| format!("could not write `{}`", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::*;
+    use crate::{Cli, Command};
+
+    #[test]
+    fn plans_write_a_seeded_config_per_point_and_a_job_array() {
+        let dir = std::env::temp_dir().join(format!("pip-sim-plan-{}", std::process::id()));
+        let args = [
+            "pip-sim",
+            "plan",
+            "--noise",
+            "0.1:0.3:3",
+            "--seed",
+            "7",
+            "--output-dir",
+            dir.to_str().unwrap(),
+            "--slurm",
+        ];
+        let Command::Plan(args) = Cli::try_parse_from(args).unwrap().command else {
+            unreachable!()
+        };
+        plan(args).unwrap();
+
+        let manifest: Value =
+            serde_json::from_str(&fs::read_to_string(dir.join(MANIFEST_FILE)).unwrap()).unwrap();
+        assert_eq!(manifest["points"].as_array().unwrap().len(), 3);
+        assert_eq!(manifest["points"][2]["noise"], 0.3);
+
+        let config_path = dir.join("configs").join(point_file_name(2));
+        let config: Value =
+            serde_json::from_str(&fs::read_to_string(config_path).unwrap()).unwrap();
+        assert_eq!(config["seed"], 9);
+        assert!(Path::new(config["result"].as_str().unwrap()).is_absolute());
+
+        let script = fs::read_to_string(dir.join("sweep.slurm")).unwrap();
+        assert!(script.contains("#SBATCH --array=0-2\n"));
+        assert!(dir.join("logs").is_dir());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,111 @@
+use clap::Args;
+use particle_interactions_puzzle::{Colormap, Float, Glyph, ParticleColoring, RenderOptions, Rgb};
+use serde_json::{Map, Value};
+
+use crate::config::{
+    check_keys, config_bool, config_count, config_float, config_float_pair, config_parsed,
+};
+
+/// Keys accepted in a config file's `render` object, each matching the flag of the same name,
+/// with `scalar_range` given as a `[min, max]` array
+const RENDER_CONFIG_KEYS: [&str; 8] = [
+    "color_by",
+    "glyph",
+    "color",
+    "cluster_distance",
+    "density_overlay",
+    "density_bins",
+    "scalar_cmap",
+    "scalar_range",
+];
+
+/// Styling for rendered frames, shared by every subcommand that draws the particles
+#[derive(Args, Clone, Debug, Default)]
+pub struct RenderArgs {
+    /// What particle colors show: `none`, `heading`, `cluster`, `species`, or `scalar:<name>`
+    /// [default: none]
+    #[arg(long)]
+    color_by: Option<ParticleColoring>,
+
+    /// How particles are drawn: `arrow` or `dot` [default: arrow]
+    #[arg(long)]
+    glyph: Option<Glyph>,
+
+    /// Particle color without `--color-by`, as `#rrggbb` or a name like `red` [default: black]
+    #[arg(long)]
+    color: Option<Rgb>,
+
+    /// Connection distance for `--color-by cluster` [default: the particle distance threshold]
+    #[arg(long)]
+    cluster_distance: Option<Float>,
+
+    /// Whether to shade the particle density underneath the particles [default: false]
+    #[arg(long)]
+    density_overlay: Option<bool>,
+
+    /// Number of cells across the density overlay [default: 20]
+    #[arg(long)]
+    density_bins: Option<usize>,
+
+    /// Colormap for `--color-by scalar:<name>`: `viridis` or `gray` [default: viridis]
+    #[arg(long)]
+    scalar_cmap: Option<Colormap>,
+
+    /// Values at either end of the colormap for `--color-by scalar:<name>`, as `min:max`
+    /// [default: each frame's range]
+    #[arg(long, value_parser = parse_scalar_range)]
+    scalar_range: Option<(Float, Float)>,
+}
+
+impl RenderArgs {
+    /// Fill in every setting not given as a flag from a config file's `render` object
+    pub fn merged_with_config(self, config: &Map<String, Value>) -> anyhow::Result<Self> {
+        check_keys(config, &RENDER_CONFIG_KEYS)?;
+
+        Ok(Self {
+            color_by: self.color_by.or(config_parsed(config, "color_by")?),
+            glyph: self.glyph.or(config_parsed(config, "glyph")?),
+            color: self.color.or(config_parsed(config, "color")?),
+            cluster_distance: self
+                .cluster_distance
+                .or(config_float(config, "cluster_distance")?),
+            density_overlay: self
+                .density_overlay
+                .or(config_bool(config, "density_overlay")?),
+            density_bins: self.density_bins.or(config_count(config, "density_bins")?),
+            scalar_cmap: self.scalar_cmap.or(config_parsed(config, "scalar_cmap")?),
+            scalar_range: self
+                .scalar_range
+                .or(config_float_pair(config, "scalar_range")?),
+        })
+    }
+
+    pub fn to_options(&self) -> RenderOptions {
+        let defaults = RenderOptions::default();
+
+        RenderOptions {
+            coloring: self.color_by.clone().unwrap_or(defaults.coloring),
+            glyph: self.glyph.unwrap_or(defaults.glyph),
+            color: self.color.unwrap_or(defaults.color),
+            cluster_distance: self.cluster_distance,
+            density_overlay: self.density_overlay.unwrap_or(defaults.density_overlay),
+            density_bins: self.density_bins.unwrap_or(defaults.density_bins),
+            scalar_colormap: self.scalar_cmap.unwrap_or(defaults.scalar_colormap),
+            scalar_range: self.scalar_range,
+        }
+    }
+}
+
+fn parse_scalar_range(text: &str) -> Result<(Float, Float), String> {
+    let float = |field: &str| {
+        field
+            .trim()
+            .parse::<Float>()
+            .map_err(|_| format!("`{field}` is not a number"))
+    };
+
+    match text.split_once(':') {
+        Some((min, max)) => Ok((float(min)?, float(max)?)),
+        None => Err(format!("expected `min:max`, got `{text}`")),
+    }
+}
@@ -0,0 +1,92 @@
+use std::{io::Write, path::PathBuf};
+
+use anyhow::Context;
+use clap::Args;
+use particle_interactions_puzzle::{CsvTrajectoryWriter, ParticleSelection, Session};
+
+use crate::run::create;
+
+#[derive(Args)]
+pub struct ReplayArgs {
+    /// Session saved by `pip-sim run --control-panel --record-session`
+    #[arg(long)]
+    session: PathBuf,
+
+    /// Write the replayed steps as a `t,id,x,y,theta` CSV trajectory
+    #[arg(long)]
+    trajectory: Option<PathBuf>,
+
+    /// Write only every `n`th replayed step to the trajectory
+    #[arg(long, default_value_t = 1)]
+    trajectory_stride: usize,
+
+    /// Write the instantaneous order parameter of the replayed steps as a `t,order` CSV file
+    #[arg(long)]
+    order: Option<PathBuf>,
+}
+
+/// Replay a recorded control panel session step for step, from the state and random state it
+/// started from, applying each parameter change at the step it was made
+pub fn replay(args: ReplayArgs) -> anyhow::Result<()> {
+    let session = Session::load(&args.session)?;
+    let mut sim = session.initial_simulation()?;
+
+    let mut trajectory = match &args.trajectory {
+        Some(path) => Some(CsvTrajectoryWriter::new(
+            create(path)?,
+            args.trajectory_stride,
+        )?),
+        None => None,
+    };
+    let mut order = match &args.order {
+        Some(path) => {
+            let mut writer = create(path)?;
+            writeln!(writer, "t,order")?;
+            Some(writer)
+        }
+        None => None,
+    };
+
+    let mut is_first = true;
+    sim.replay_session(&session, |sim| {
+        if let Some(trajectory) = &mut trajectory {
+            match is_first {
+                true => trajectory.write_frame(sim)?,
+                false => {
+                    trajectory.write_step(sim)?;
+                }
+            }
+        }
+        if let Some(order) = &mut order {
+            writeln!(
+                order,
+                "{},{}",
+                sim.current_time().0,
+                sim.order_parameter(&ParticleSelection::All)
+            )?;
+        }
+        is_first = false;
+
+        Ok(())
+    })?;
+
+    if let (Some(path), Some(trajectory)) = (&args.trajectory, trajectory) {
+        trajectory
+            .into_inner()
+            .with_context(|| format!("could not write trajectory `{}`", path.display()))?;
+    }
+    if let (Some(path), Some(mut order)) = (&args.order, order) {
+        order
+            .flush()
+            .with_context(|| format!("could not write order parameter `{}`", path.display()))?;
+    }
+
+    println!("steps: {}", session.num_steps);
+    println!("changes: {}", session.changes.len());
+    println!(
+        "final order parameter: {}",
+        sim.order_parameter(&ParticleSelection::All)
+    );
+
+    Ok(())
+}
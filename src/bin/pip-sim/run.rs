@@ -0,0 +1,386 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use anyhow::{Context, anyhow};
+use clap::Args;
+use particle_interactions_puzzle::{
+    CsvTrajectoryWriter, DEFAULT_BOUNDARY_SIDE_LENGTH, DEFAULT_PARTICLE_DISTANCE_THRESHOLD, Float,
+    OrderHistoryLength, StationaryOrderOptions,
+};
+use serde_json::{Value, json};
+
+#[cfg(feature = "viz")]
+use crate::{config::config_object, render::RenderArgs};
+use crate::{
+    config::{config_bool, config_count, config_path, config_seconds, read_config},
+    observables::{ObservableSpec, ObservableWriter, config_observables},
+    setup::{SIMULATION_CONFIG_KEYS, SimulationArgs},
+};
+
+/// Keys accepted in a `run` config file besides the simulation's own, each matching the flag of
+/// the same name, plus a `render` object styling the live window and an `observables` list
+const CONFIG_KEYS: [&str; 10] = [
+    "num_steps",
+    "trajectory",
+    "trajectory_stride",
+    "order",
+    "max_steps",
+    "time_budget",
+    "stationary",
+    "result",
+    "render",
+    "observables",
+];
+
+#[derive(Args)]
+pub struct RunArgs {
+    /// JSON config file holding an object with any of the settings below, keyed by their snake
+    /// case names (e.g. `{"noise": 0.5, "num_steps": 1000}`). Flags take precedence over it.
+    #[arg(long)]
+    config: Option<PathBuf>,
+
+    #[command(flatten)]
+    simulation: SimulationArgs,
+
+    /// Observables to write along the recorded steps, each to its own file at its own stride,
+    /// which can only be given in the config file
+    #[arg(skip)]
+    observables: Vec<ObservableSpec>,
+
+    /// Number of steps to take and record before computing the stationary order parameter
+    /// [default: 0]
+    #[arg(long)]
+    num_steps: Option<usize>,
+
+    /// Write the recorded steps as a `t,id,x,y,theta` CSV trajectory
+    #[arg(long)]
+    trajectory: Option<PathBuf>,
+
+    /// Write only every `n`th recorded step to the trajectory [default: 1]
+    #[arg(long)]
+    trajectory_stride: Option<usize>,
+
+    /// Write the instantaneous order parameter of the recorded steps as a `t,order` CSV file
+    #[arg(long)]
+    order: Option<PathBuf>,
+
+    /// Give up on converging the stationary order parameter after this many steps
+    #[arg(long)]
+    max_steps: Option<usize>,
+
+    /// Give up on converging the stationary order parameter after this many seconds
+    #[arg(long)]
+    time_budget: Option<f64>,
+
+    /// Whether to compute the stationary order parameter after the recorded steps
+    /// [default: true]
+    #[arg(long)]
+    stationary: Option<bool>,
+
+    /// Write the seed and the stationary order parameter estimate to this JSON file, e.g. for
+    /// `pip-sim collect` to gather
+    #[arg(long)]
+    result: Option<PathBuf>,
+
+    /// Open a window showing the simulation step live instead of recording and computing
+    /// anything
+    #[cfg(feature = "viz")]
+    #[arg(long)]
+    watch: bool,
+
+    #[cfg(feature = "viz")]
+    #[command(flatten)]
+    render: RenderArgs,
+
+    /// Open an interactive control panel to tweak the simulation live instead of recording and
+    /// computing anything
+    #[cfg(feature = "gui")]
+    #[arg(long)]
+    control_panel: bool,
+
+    /// With `--control-panel`, save every parameter change made from the panel, with the steps
+    /// taken and the random state, to this JSON file for `pip-sim replay`
+    #[cfg(feature = "gui")]
+    #[arg(long, requires = "control_panel")]
+    record_session: Option<PathBuf>,
+
+    /// Step forever, broadcasting JSON frames over WebSocket to clients connecting to this
+    /// address (e.g. `0.0.0.0:9001`), instead of recording and computing anything
+    #[cfg(feature = "serve")]
+    #[arg(long)]
+    serve: Option<String>,
+
+    /// With `--serve`, append every change clients make to this file as JSON lines
+    #[cfg(feature = "serve")]
+    #[arg(long, requires = "serve")]
+    event_log: Option<PathBuf>,
+
+    /// With `--serve`, directory to write the snapshots and recordings clients ask for into
+    #[cfg(feature = "serve")]
+    #[arg(long, requires = "serve")]
+    serve_output_dir: Option<PathBuf>,
+}
+
+impl RunArgs {
+    /// Fill in every setting not given as a flag from the config file, if any
+    fn merged_with_config(self) -> anyhow::Result<Self> {
+        let Some(path) = &self.config else {
+            return Ok(self);
+        };
+
+        let keys: Vec<&str> = SIMULATION_CONFIG_KEYS
+            .into_iter()
+            .chain(CONFIG_KEYS)
+            .collect();
+        let config = read_config(path, &keys)?;
+
+        Ok(Self {
+            simulation: self.simulation.merged_with_config(&config)?,
+            observables: config_observables(&config, "observables")?,
+            num_steps: self.num_steps.or(config_count(&config, "num_steps")?),
+            trajectory: self.trajectory.or(config_path(&config, "trajectory")?),
+            trajectory_stride: self
+                .trajectory_stride
+                .or(config_count(&config, "trajectory_stride")?),
+            order: self.order.or(config_path(&config, "order")?),
+            max_steps: self.max_steps.or(config_count(&config, "max_steps")?),
+            time_budget: self.time_budget.or(config_seconds(&config, "time_budget")?),
+            stationary: self.stationary.or(config_bool(&config, "stationary")?),
+            result: self.result.or(config_path(&config, "result")?),
+            #[cfg(feature = "viz")]
+            render: match config_object(&config, "render")? {
+                Some(render) => self.render.merged_with_config(render)?,
+                None => self.render,
+            },
+            ..self
+        })
+    }
+}
+
+/// Run one simulation as described by the arguments
+pub fn run(args: RunArgs) -> anyhow::Result<()> {
+    let args = args.merged_with_config()?;
+
+    let (seed, mut sim) = args.simulation.build()?;
+
+    #[cfg(feature = "viz")]
+    if args.watch {
+        return sim.watch(&particle_interactions_puzzle::WatchOptions {
+            render: args.render.to_options(),
+            ..Default::default()
+        });
+    }
+
+    #[cfg(feature = "gui")]
+    if args.control_panel {
+        return match &args.record_session {
+            Some(path) => sim.record_control_panel(Some(seed))?.save(path),
+            None => sim.control_panel(),
+        };
+    }
+
+    #[cfg(feature = "serve")]
+    if let Some(addr) = &args.serve {
+        let options = particle_interactions_puzzle::ServeOptions {
+            event_log: args.event_log.clone(),
+            output_dir: args.serve_output_dir.clone(),
+            ..Default::default()
+        };
+        sim.serve(addr, &options)?;
+        return Ok(());
+    }
+
+    if args.order.is_some() {
+        sim = sim.with_order_history(Some(OrderHistoryLength::Full))?;
+    }
+
+    let mut trajectory = match &args.trajectory {
+        Some(path) => {
+            let mut writer =
+                CsvTrajectoryWriter::new(create(path)?, args.trajectory_stride.unwrap_or(1))?;
+            writer.write_frame(&sim)?;
+            Some(writer)
+        }
+        None => None,
+    };
+
+    let mut observables = args
+        .observables
+        .iter()
+        .map(|spec| {
+            ObservableWriter::new(
+                spec.clone(),
+                args.simulation
+                    .boundary_side_length
+                    .unwrap_or(DEFAULT_BOUNDARY_SIDE_LENGTH.0),
+                args.simulation
+                    .particle_distance_threshold
+                    .unwrap_or(DEFAULT_PARTICLE_DISTANCE_THRESHOLD.0),
+            )
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    for observable in &mut observables {
+        observable.observe(0, &sim)?;
+    }
+
+    for step in 1..=args.num_steps.unwrap_or(0) {
+        sim.run_for(1)?;
+
+        if let Some(trajectory) = &mut trajectory {
+            trajectory.write_step(&sim)?;
+        }
+        for observable in &mut observables {
+            observable.observe(step, &sim)?;
+        }
+    }
+
+    let stats = sim.stats();
+    tracing::info!(
+        steps = stats.steps,
+        steps_per_second = stats.steps_per_second,
+        mean_neighbors_per_particle = stats.mean_neighbors_per_particle,
+        neighbor_search_per_step = ?stats.neighbor_search_per_step,
+        alignment_per_step = ?stats.alignment_per_step,
+        integration_per_step = ?stats.integration_per_step,
+        "recorded steps done"
+    );
+
+    if let (Some(path), Some(trajectory)) = (&args.trajectory, trajectory) {
+        trajectory
+            .into_inner()
+            .with_context(|| format!("could not write trajectory `{}`", path.display()))?;
+    }
+
+    for observable in observables {
+        observable.finish()?;
+    }
+
+    if let (Some(path), Some(history)) = (&args.order, sim.order_history()) {
+        write_order(path, history.iter())
+            .with_context(|| format!("could not write order parameter `{}`", path.display()))?;
+    }
+
+    if !args.stationary.unwrap_or(true) {
+        return Ok(());
+    }
+
+    let options = StationaryOrderOptions {
+        cancellation: None,
+        max_steps: args.max_steps,
+        time_budget: args
+            .time_budget
+            .map(|seconds| {
+                Duration::try_from_secs_f64(seconds)
+                    .map_err(|_| anyhow!("`{}` is not a valid time budget", seconds))
+            })
+            .transpose()?,
+    };
+    let estimate = sim.compute_stationary_order_estimate(&options)?;
+
+    println!("iterations: {}", estimate.iterations);
+    println!("converged: {}", estimate.is_converged());
+    println!("stationary order parameter: {}", estimate.value);
+
+    if let Some(path) = &args.result {
+        let result = json!({
+            "seed": seed,
+            "iterations": estimate.iterations,
+            "converged": estimate.is_converged(),
+            "stationary_order_parameter": estimate.value,
+        });
+
+        write_result(path, &result)
+            .with_context(|| format!("could not write result `{}`", path.display()))?;
+    }
+
+    Ok(())
+}
+
+pub fn create(path: &Path) -> anyhow::Result<BufWriter<File>> {
+    let file =
+        File::create(path).with_context(|| format!("could not create `{}`", path.display()))?;
+
+    Ok(BufWriter::new(file))
+}
+
+/// Write the order parameter history as a `t,order` table
+fn write_order(path: &Path, history: impl Iterator<Item = (Float, Float)>) -> anyhow::Result<()> {
+    let mut writer = create(path)?;
+
+    writeln!(writer, "t,order")?;
+    for (time, order) in history {
+        writeln!(writer, "{time},{order}")?;
+    }
+
+    writer.flush()?;
+
+    Ok(())
+}
+
+fn write_result(path: &Path, result: &Value) -> anyhow::Result<()> {
+    let mut writer = create(path)?;
+
+    serde_json::to_writer_pretty(&mut writer, result)?;
+    writeln!(writer)?;
+    writer.flush()?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::*;
+    use crate::{Cli, Command};
+
+    /// Parse a `run` command line, without the leading `pip-sim run`
+    fn parse(args: &[&str]) -> RunArgs {
+        let args = ["pip-sim", "run"].iter().chain(args);
+        match Cli::try_parse_from(args).unwrap().command {
+            Command::Run(args) => args,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn flags_take_precedence_over_the_config() {
+        let dir = std::env::temp_dir().join(format!("pip-sim-run-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let config = dir.join("config.json");
+        let trajectory = dir.join("trajectory.csv");
+        let config_json = json!({
+            "num_particles": 3,
+            "noise": 0.3,
+            "num_steps": 5,
+            "stationary": false,
+            "trajectory": trajectory,
+        });
+        std::fs::write(&config, config_json.to_string()).unwrap();
+
+        let config = config.to_str().unwrap();
+        let args = parse(&["--config", config, "--num-steps", "2", "--seed", "1"]);
+        let merged = parse(&["--config", config, "--num-steps", "2"])
+            .merged_with_config()
+            .unwrap();
+        assert_eq!(merged.num_steps, Some(2));
+        assert_eq!(merged.simulation.noise, Some(0.3));
+        assert_eq!(merged.stationary, Some(false));
+
+        // The starting state and both steps, for each of the three particles
+        run(args).unwrap();
+        let csv = std::fs::read_to_string(&trajectory).unwrap();
+        assert_eq!(csv.lines().count(), 1 + 3 * 3);
+
+        std::fs::write(dir.join("bad.json"), r#"{"nosie": 0.3}"#).unwrap();
+        let bad = dir.join("bad.json");
+        let args = parse(&["--config", bad.to_str().unwrap()]);
+        assert!(args.merged_with_config().is_err());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,107 @@
+use clap::Args;
+use particle_interactions_puzzle::{
+    DomainBoundaryLength, Float, Noise, ParticleDistanceThreshold, RelativeTime, Simulation, Speed,
+    seed_rng,
+};
+use serde_json::{Map, Value};
+
+use crate::config::{config_count, config_float};
+
+/// Keys accepted in a config file for the simulation itself, each matching the flag of the same
+/// name
+pub const SIMULATION_CONFIG_KEYS: [&str; 7] = [
+    "num_particles",
+    "boundary_side_length",
+    "noise",
+    "speed",
+    "timestep",
+    "particle_distance_threshold",
+    "seed",
+];
+
+/// The simulation to set up, shared by every subcommand that runs a single one
+#[derive(Args, Clone, Debug, Default)]
+pub struct SimulationArgs {
+    /// Number of particles [default: 125]
+    #[arg(long)]
+    pub num_particles: Option<usize>,
+
+    /// Side length of the periodic square domain [default: 5]
+    #[arg(long)]
+    pub boundary_side_length: Option<Float>,
+
+    /// Amplitude of the heading noise [default: 0.01]
+    #[arg(long)]
+    pub noise: Option<Float>,
+
+    /// Particle speed [default: 1]
+    #[arg(long)]
+    pub speed: Option<Float>,
+
+    /// Timestep [default: 0.25]
+    #[arg(long)]
+    pub timestep: Option<Float>,
+
+    /// Distance within which particles align [default: 1]
+    #[arg(long)]
+    pub particle_distance_threshold: Option<Float>,
+
+    /// Seed for the random number generator, drawn at random (and printed) if not given
+    #[arg(long)]
+    pub seed: Option<u64>,
+}
+
+impl SimulationArgs {
+    /// Fill in every setting not given as a flag from a config file
+    pub fn merged_with_config(self, config: &Map<String, Value>) -> anyhow::Result<Self> {
+        Ok(Self {
+            num_particles: self
+                .num_particles
+                .or(config_count(config, "num_particles")?),
+            boundary_side_length: self
+                .boundary_side_length
+                .or(config_float(config, "boundary_side_length")?),
+            noise: self.noise.or(config_float(config, "noise")?),
+            speed: self.speed.or(config_float(config, "speed")?),
+            timestep: self.timestep.or(config_float(config, "timestep")?),
+            particle_distance_threshold: self
+                .particle_distance_threshold
+                .or(config_float(config, "particle_distance_threshold")?),
+            seed: self
+                .seed
+                .or(config_count(config, "seed")?.map(|seed| seed as u64)),
+        })
+    }
+
+    /// Seed the random number generator (printing the seed) and build the simulation, returning
+    /// the seed with it
+    pub fn build(&self) -> anyhow::Result<(u64, Simulation)> {
+        let seed = self.seed.unwrap_or_else(rand::random::<u64>);
+        seed_rng(seed);
+        println!("seed: {seed}");
+
+        let mut builder = Simulation::builder();
+        if let Some(num_particles) = self.num_particles {
+            builder = builder.num_particles(num_particles);
+        }
+        if let Some(boundary_side_length) = self.boundary_side_length {
+            builder = builder.boundary_side_length(DomainBoundaryLength(boundary_side_length));
+        }
+        if let Some(noise) = self.noise {
+            builder = builder.noise(Noise(noise));
+        }
+        if let Some(speed) = self.speed {
+            builder = builder.speed(Speed(speed));
+        }
+        if let Some(timestep) = self.timestep {
+            builder = builder.timestep(RelativeTime(timestep));
+        }
+        if let Some(particle_distance_threshold) = self.particle_distance_threshold {
+            builder = builder.particle_distance_threshold(ParticleDistanceThreshold(
+                particle_distance_threshold,
+            ));
+        }
+
+        Ok((seed, builder.build()?))
+    }
+}
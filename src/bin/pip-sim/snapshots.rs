@@ -0,0 +1,248 @@
+use std::{
+    fs::{self, File},
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use anyhow::{Context, anyhow, bail};
+use clap::Args;
+use particle_interactions_puzzle::{AbsoluteTime, Float};
+use serde_json::{Value, json};
+
+use crate::{
+    config::{config_count, config_floats, config_object, config_parsed, config_path, read_config},
+    render::RenderArgs,
+    setup::{SIMULATION_CONFIG_KEYS, SimulationArgs},
+};
+
+/// Keys accepted in a `snapshots` config file besides the simulation's own, each matching the
+/// flag of the same name (with `times` as an array of numbers), plus a `render` object styling
+/// the frames
+const CONFIG_KEYS: [&str; 5] = ["times", "output_dir", "format", "size", "render"];
+
+/// Width and height of each frame in pixels, unless given
+const DEFAULT_SIZE: u32 = 512;
+
+/// Image format of the rendered frames
+#[derive(Copy, Clone, Debug, Default)]
+enum ImageFormat {
+    #[default]
+    Png,
+    Svg,
+}
+
+impl ImageFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            Self::Png => "png",
+            Self::Svg => "svg",
+        }
+    }
+}
+
+impl FromStr for ImageFormat {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        match text {
+            "png" => Ok(Self::Png),
+            "svg" => Ok(Self::Svg),
+            _ => Err(format!("expected `png` or `svg`, got `{text}`")),
+        }
+    }
+}
+
+#[derive(Args)]
+pub struct SnapshotsArgs {
+    /// JSON config file holding an object with any of the settings below, keyed by their snake
+    /// case names (e.g. `{"noise": 0.5, "times": [0, 10, 100]}`). Flags take precedence over it.
+    #[arg(long)]
+    config: Option<PathBuf>,
+
+    #[command(flatten)]
+    simulation: SimulationArgs,
+
+    /// Simulated times to render a frame at, separated by commas (e.g. `0,10,100,1000`)
+    #[arg(long, value_delimiter = ',')]
+    times: Option<Vec<Float>>,
+
+    /// Directory to write the frames and their `index.json` into, created if missing
+    #[arg(long)]
+    output_dir: Option<PathBuf>,
+
+    /// Image format of the frames: `png` or `svg` [default: png]
+    #[arg(long)]
+    format: Option<ImageFormat>,
+
+    /// Width and height of each frame in pixels [default: 512]
+    #[arg(long)]
+    size: Option<u32>,
+
+    #[command(flatten)]
+    render: RenderArgs,
+}
+
+impl SnapshotsArgs {
+    /// Fill in every setting not given as a flag from the config file, if any
+    fn merged_with_config(self) -> anyhow::Result<Self> {
+        let Some(path) = &self.config else {
+            return Ok(self);
+        };
+
+        let keys: Vec<&str> = SIMULATION_CONFIG_KEYS
+            .into_iter()
+            .chain(CONFIG_KEYS)
+            .collect();
+        let config = read_config(path, &keys)?;
+
+        Ok(Self {
+            simulation: self.simulation.merged_with_config(&config)?,
+            times: self.times.or(config_floats(&config, "times")?),
+            output_dir: self.output_dir.or(config_path(&config, "output_dir")?),
+            format: self.format.or(config_parsed(&config, "format")?),
+            size: self
+                .size
+                .or(config_count(&config, "size")?.map(|size| size as u32)),
+            render: match config_object(&config, "render")? {
+                Some(render) => self.render.merged_with_config(render)?,
+                None => self.render,
+            },
+            ..self
+        })
+    }
+}
+
+/// Run one simulation, rendering a frame each time it reaches one of the requested times, and
+/// write an index describing the frames
+pub fn snapshots(args: SnapshotsArgs) -> anyhow::Result<()> {
+    let args = args.merged_with_config()?;
+
+    let mut times = args
+        .times
+        .ok_or_else(|| anyhow!("no snapshot times given, with `--times` or in the config"))?;
+    if let Some(time) = times.iter().find(|time| !time.is_finite() || **time < 0.0) {
+        bail!(
+            "snapshot times must be finite and non-negative, got `{}`",
+            time
+        );
+    }
+    times.sort_by(Float::total_cmp);
+
+    let output_dir = args.output_dir.ok_or_else(|| {
+        anyhow!("no output directory given, with `--output-dir` or in the config")
+    })?;
+    fs::create_dir_all(&output_dir)
+        .with_context(|| format!("could not create `{}`", output_dir.display()))?;
+
+    let format = args.format.unwrap_or_default();
+    let size = args.size.unwrap_or(DEFAULT_SIZE);
+    let options = args.render.to_options();
+
+    let (seed, mut sim) = args.simulation.build()?;
+
+    let mut frames = Vec::with_capacity(times.len());
+    let mut step = 0;
+    for (frame_idx, &time) in times.iter().enumerate() {
+        step += sim.run_until(AbsoluteTime(time))?;
+
+        let file_name = format!("frame_{frame_idx:04}.{}", format.extension());
+        let path = output_dir.join(&file_name);
+        let image = match format {
+            ImageFormat::Png => sim.to_png(size, &options)?,
+            ImageFormat::Svg => sim.to_svg(size, &options)?.into_bytes(),
+        };
+        fs::write(&path, image)
+            .with_context(|| format!("could not write frame `{}`", path.display()))?;
+
+        frames.push(json!({
+            "file": file_name,
+            "requested_time": time,
+            "time": sim.current_time().0,
+            "step": step,
+        }));
+    }
+
+    let num_frames = frames.len();
+    let index = json!({
+        "seed": seed,
+        "size": size,
+        "render": {
+            "color_by": options.coloring.to_string(),
+            "glyph": options.glyph.to_string(),
+            "color": options.color.to_string(),
+            "density_overlay": options.density_overlay,
+        },
+        "frames": frames,
+    });
+
+    let index_path = output_dir.join("index.json");
+    write_index(&index_path, &index)
+        .with_context(|| format!("could not write index `{}`", index_path.display()))?;
+
+    println!("frames: {num_frames}");
+
+    Ok(())
+}
+
+fn write_index(path: &Path, index: &Value) -> anyhow::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    serde_json::to_writer_pretty(&mut writer, index)?;
+    writeln!(writer)?;
+    writer.flush()?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::*;
+    use crate::{Cli, Command};
+
+    /// Parse a `snapshots` command line, without the leading `pip-sim snapshots`
+    fn parse(args: &[&str]) -> SnapshotsArgs {
+        let args = ["pip-sim", "snapshots"].iter().chain(args);
+        match Cli::try_parse_from(args).unwrap().command {
+            Command::Snapshots(args) => args,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn frames_are_rendered_in_time_order_with_an_index() {
+        let dir = std::env::temp_dir().join(format!("pip-sim-snapshots-{}", std::process::id()));
+        let output_dir = dir.to_str().unwrap();
+
+        snapshots(parse(&[
+            "--times",
+            "1,0",
+            "--output-dir",
+            output_dir,
+            "--format",
+            "svg",
+            "--num-particles",
+            "5",
+            "--seed",
+            "1",
+        ]))
+        .unwrap();
+
+        let index: Value =
+            serde_json::from_str(&fs::read_to_string(dir.join("index.json")).unwrap()).unwrap();
+        assert_eq!(index["seed"], 1);
+        let frames = index["frames"].as_array().unwrap();
+        assert_eq!(frames[0]["step"], 0);
+        assert_eq!(frames[1]["file"], "frame_0001.svg");
+        assert_eq!(frames[1]["time"], 1.0);
+        assert_eq!(frames[1]["step"], 4);
+        assert!(dir.join("frame_0001.svg").exists());
+
+        let negative_time = parse(&["--times=-1", "--output-dir", output_dir]);
+        assert!(snapshots(negative_time).is_err());
+        assert!(snapshots(parse(&["--output-dir", output_dir])).is_err());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
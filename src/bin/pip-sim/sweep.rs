@@ -0,0 +1,340 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::PathBuf,
+    str::FromStr,
+    time::Duration,
+};
+
+use anyhow::{Context, anyhow};
+use clap::Args;
+use particle_interactions_puzzle::{
+    DEFAULT_BOUNDARY_SIDE_LENGTH, DEFAULT_NOISE, DEFAULT_NUM_PARTICLES,
+    DEFAULT_PARTICLE_DISTANCE_THRESHOLD, DEFAULT_SPEED, DEFAULT_TIMESTEP, DomainBoundaryLength,
+    Float, Noise, ParticleDistanceThreshold, RelativeTime, Speed, StationaryOrderOptions,
+    SweepOptions, SweepPoint, run_sweep,
+};
+
+/// Evenly spaced values, given as `value` or `start:stop:count` (both ends included)
+#[derive(Clone, Debug)]
+pub struct Range(pub Vec<Float>);
+
+impl FromStr for Range {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let float = |field: &str| {
+            field
+                .trim()
+                .parse::<Float>()
+                .map_err(|_| format!("`{field}` is not a number"))
+        };
+
+        let fields: Vec<&str> = text.split(':').collect();
+        let values = match fields[..] {
+            [value] => vec![float(value)?],
+            [start, stop, count] => {
+                let (start, stop) = (float(start)?, float(stop)?);
+                let count: usize = count
+                    .trim()
+                    .parse()
+                    .map_err(|_| format!("`{count}` is not a count"))?;
+
+                match count {
+                    0 => return Err("a range needs at least one value".to_string()),
+                    1 => vec![start],
+                    _ => (0..count)
+                        .map(|idx| start + (stop - start) * idx as Float / (count - 1) as Float)
+                        .collect(),
+                }
+            }
+            _ => {
+                return Err(format!(
+                    "expected `value` or `start:stop:count`, got `{text}`"
+                ));
+            }
+        };
+
+        Ok(Self(values))
+    }
+}
+
+/// The noise, density, and speed values to combine, shared by the subcommands that sweep them
+#[derive(Args)]
+pub struct GridArgs {
+    /// Noise amplitudes to sweep, as `value` or `start:stop:count` [default: 0.01]
+    #[arg(long)]
+    noise: Option<Range>,
+
+    /// Particle densities (particles per unit area) to sweep, as `value` or `start:stop:count`,
+    /// each rounded to a whole number of particles in the domain [default: 5 (125 particles)]
+    #[arg(long)]
+    density: Option<Range>,
+
+    /// Particle speeds to sweep, as `value` or `start:stop:count` [default: 1]
+    #[arg(long)]
+    speed: Option<Range>,
+}
+
+impl GridArgs {
+    /// Every combination of the swept values in a domain of the given size, varying speed
+    /// fastest and noise slowest
+    pub fn combinations(&self, boundary_side_length: DomainBoundaryLength) -> Vec<Combination> {
+        let area = boundary_side_length.0 * boundary_side_length.0;
+
+        let noises = self
+            .noise
+            .as_ref()
+            .map_or(vec![DEFAULT_NOISE.0], |range| range.0.clone());
+        let densities = self
+            .density
+            .as_ref()
+            .map_or(vec![DEFAULT_NUM_PARTICLES as Float / area], |range| {
+                range.0.clone()
+            });
+        let speeds = self
+            .speed
+            .as_ref()
+            .map_or(vec![DEFAULT_SPEED.0], |range| range.0.clone());
+
+        noises
+            .iter()
+            .flat_map(|&noise| {
+                let speeds = &speeds;
+                densities.iter().flat_map(move |&density| {
+                    speeds.iter().map(move |&speed| Combination {
+                        noise,
+                        density,
+                        speed,
+                        num_particles: (density * area).round().max(0.0) as usize,
+                    })
+                })
+            })
+            .collect()
+    }
+}
+
+#[derive(Args)]
+pub struct SweepArgs {
+    #[command(flatten)]
+    grid: GridArgs,
+
+    /// Side length of the periodic square domain [default: 5]
+    #[arg(long)]
+    boundary_side_length: Option<Float>,
+
+    /// Timestep [default: 0.25]
+    #[arg(long)]
+    timestep: Option<Float>,
+
+    /// Distance within which particles align [default: 1]
+    #[arg(long)]
+    particle_distance_threshold: Option<Float>,
+
+    /// Seed for the random number generators; the run at row `i` is seeded with `seed + i`, so
+    /// results don't depend on the number of threads or on resuming
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Number of runs to compute at once [default: 1]
+    #[arg(long)]
+    num_threads: Option<usize>,
+
+    /// Give up on converging each stationary order parameter after this many steps
+    #[arg(long)]
+    max_steps: Option<usize>,
+
+    /// Give up on converging each stationary order parameter after this many seconds
+    #[arg(long)]
+    time_budget: Option<f64>,
+
+    /// Record each point to this CSV file as soon as it finishes, skipping the points it already
+    /// records, so rerunning an interrupted sweep with the same file resumes where it stopped
+    #[arg(long)]
+    results: Option<PathBuf>,
+
+    /// Write the results table here instead of to standard output
+    #[arg(long)]
+    output: Option<PathBuf>,
+}
+
+/// One combination of swept parameters
+#[derive(Copy, Clone, Debug)]
+pub struct Combination {
+    pub noise: Float,
+    pub density: Float,
+    pub speed: Float,
+    pub num_particles: usize,
+}
+
+/// The stationary order parameter of one combination
+#[derive(Copy, Clone, Debug)]
+pub struct Row {
+    pub value: Float,
+    pub converged: bool,
+    pub iterations: usize,
+}
+
+/// Compute the stationary order parameter at every combination of the swept parameters and
+/// write them as a tidy CSV table, one row per combination
+pub fn sweep(args: SweepArgs) -> anyhow::Result<()> {
+    let boundary_side_length = args
+        .boundary_side_length
+        .map_or(DEFAULT_BOUNDARY_SIDE_LENGTH, DomainBoundaryLength);
+    let timestep = args.timestep.map_or(DEFAULT_TIMESTEP, RelativeTime);
+    let particle_distance_threshold = args.particle_distance_threshold.map_or(
+        DEFAULT_PARTICLE_DISTANCE_THRESHOLD,
+        ParticleDistanceThreshold,
+    );
+    let combinations = args.grid.combinations(boundary_side_length);
+
+    let points: Vec<SweepPoint> = combinations
+        .iter()
+        .map(|combination| SweepPoint {
+            num_particles: combination.num_particles,
+            noise: Noise(combination.noise),
+            speed: Speed(combination.speed),
+            particle_distance_threshold,
+        })
+        .collect();
+
+    let options = SweepOptions {
+        stationary_order: StationaryOrderOptions {
+            cancellation: None,
+            max_steps: args.max_steps,
+            time_budget: args
+                .time_budget
+                .map(|seconds| {
+                    Duration::try_from_secs_f64(seconds)
+                        .map_err(|_| anyhow!("`{}` is not a valid time budget", seconds))
+                })
+                .transpose()?,
+        },
+        seed: args.seed,
+        num_threads: args.num_threads.unwrap_or(1),
+    };
+
+    let report = run_sweep(
+        &points,
+        boundary_side_length,
+        timestep,
+        &options,
+        args.results.as_deref(),
+    )?;
+
+    let num_recovered = report.results.len() - report.num_computed;
+    if num_recovered > 0 {
+        eprintln!(
+            "recovered {num_recovered} of {} points from earlier runs",
+            points.len()
+        );
+    }
+
+    let mut rows = vec![None; combinations.len()];
+    for result in &report.results {
+        rows[result.index] = Some(Row {
+            value: result.value,
+            converged: result.converged,
+            iterations: result.iterations,
+        });
+    }
+
+    let writer: Box<dyn Write> = match &args.output {
+        Some(path) => {
+            Box::new(BufWriter::new(File::create(path).with_context(|| {
+                format!("could not create `{}`", path.display())
+            })?))
+        }
+        None => Box::new(std::io::stdout().lock()),
+    };
+
+    write_table(writer, &combinations, &rows).context("could not write sweep results")
+}
+
+/// Write a tidy CSV table with a row per combination, skipping those without a result
+pub fn write_table(
+    mut writer: impl Write,
+    combinations: &[Combination],
+    rows: &[Option<Row>],
+) -> std::io::Result<()> {
+    writeln!(
+        writer,
+        "noise,density,speed,num_particles,stationary_order_parameter,converged,iterations"
+    )?;
+
+    for (combination, row) in combinations.iter().zip(rows) {
+        let Some(row) = row else {
+            continue;
+        };
+
+        writeln!(
+            writer,
+            "{},{},{},{},{},{},{}",
+            combination.noise,
+            combination.density,
+            combination.speed,
+            combination.num_particles,
+            row.value,
+            row.converged,
+            row.iterations
+        )?;
+    }
+
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ranges_include_both_ends() {
+        let range = |text: &str| text.parse::<Range>().map(|range| range.0);
+
+        assert_eq!(range("0.5").unwrap(), [0.5]);
+        assert_eq!(range("0:1:5").unwrap(), [0.0, 0.25, 0.5, 0.75, 1.0]);
+        assert_eq!(range(" 2 : 3 : 1").unwrap(), [2.0]);
+        assert!(range("0:1:0").is_err());
+        assert!(range("0:1").is_err());
+        assert!(range("zero").is_err());
+    }
+
+    #[test]
+    fn combinations_vary_speed_fastest_and_round_particle_counts() {
+        let grid = GridArgs {
+            noise: Some(Range(vec![0.1, 0.2])),
+            density: Some(Range(vec![0.5])),
+            speed: Some(Range(vec![1.0, 2.0])),
+        };
+
+        let combinations = grid.combinations(DomainBoundaryLength(3.0));
+        let values: Vec<_> = combinations
+            .iter()
+            .map(|combination| {
+                (
+                    combination.noise,
+                    combination.speed,
+                    combination.num_particles,
+                )
+            })
+            .collect();
+        assert_eq!(
+            values,
+            [(0.1, 1.0, 5), (0.1, 2.0, 5), (0.2, 1.0, 5), (0.2, 2.0, 5)]
+        );
+
+        // Rows without a result are left out of the table
+        let row = Row {
+            value: 0.75,
+            converged: true,
+            iterations: 40,
+        };
+        let mut table = Vec::new();
+        write_table(&mut table, &combinations[..2], &[None, Some(row)]).unwrap();
+        assert_eq!(
+            String::from_utf8(table).unwrap(),
+            "noise,density,speed,num_particles,stationary_order_parameter,converged,iterations\n\
+             0.1,0.5,2,5,0.75,true,40\n"
+        );
+    }
+}
@@ -0,0 +1,155 @@
+use crate::{
+    Simulation,
+    error::SimulationError,
+    particle::InitialCondition,
+    types::{DomainBoundaryLength, Noise, ParticleDistanceThreshold, RelativeTime, Speed},
+};
+
+/// Particle count of the project's reference setup
+pub const DEFAULT_NUM_PARTICLES: usize = 125;
+
+/// Domain side length of the project's reference setup
+pub const DEFAULT_BOUNDARY_SIDE_LENGTH: DomainBoundaryLength = DomainBoundaryLength(5.0);
+
+/// Noise amplitude of the project's reference setup, which orders
+pub const DEFAULT_NOISE: Noise = Noise(0.01);
+
+/// Particle speed of the project's reference setup
+pub const DEFAULT_SPEED: Speed = Speed(1.0);
+
+/// Timestep of the project's reference setup
+pub const DEFAULT_TIMESTEP: RelativeTime = RelativeTime(0.25);
+
+/// Interaction radius of the project's reference setup
+pub const DEFAULT_PARTICLE_DISTANCE_THRESHOLD: ParticleDistanceThreshold =
+    ParticleDistanceThreshold(1.0);
+
+/// Builds a [`Simulation`] from named settings, starting from the project's reference setup
+///
+/// # Notes
+/// Every setting is checked by [`SimulationBuilder::build`], so a bad value is reported by name
+/// rather than as a confusing failure later on.
+#[derive(Copy, Clone, Debug)]
+pub struct SimulationBuilder {
+    num_particles: usize,
+    boundary_side_length: DomainBoundaryLength,
+    noise: Noise,
+    speed: Speed,
+    timestep: RelativeTime,
+    particle_distance_threshold: ParticleDistanceThreshold,
+    initial_condition: InitialCondition,
+}
+
+impl Default for SimulationBuilder {
+    fn default() -> Self {
+        Self {
+            num_particles: DEFAULT_NUM_PARTICLES,
+            boundary_side_length: DEFAULT_BOUNDARY_SIDE_LENGTH,
+            noise: DEFAULT_NOISE,
+            speed: DEFAULT_SPEED,
+            timestep: DEFAULT_TIMESTEP,
+            particle_distance_threshold: DEFAULT_PARTICLE_DISTANCE_THRESHOLD,
+            initial_condition: InitialCondition::UniformRandom,
+        }
+    }
+}
+
+impl SimulationBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn num_particles(self, num_particles: usize) -> Self {
+        Self {
+            num_particles,
+            ..self
+        }
+    }
+
+    pub fn boundary_side_length(self, boundary_side_length: DomainBoundaryLength) -> Self {
+        Self {
+            boundary_side_length,
+            ..self
+        }
+    }
+
+    pub fn noise(self, noise: Noise) -> Self {
+        Self { noise, ..self }
+    }
+
+    pub fn speed(self, speed: Speed) -> Self {
+        Self { speed, ..self }
+    }
+
+    pub fn timestep(self, timestep: RelativeTime) -> Self {
+        Self { timestep, ..self }
+    }
+
+    pub fn particle_distance_threshold(
+        self,
+        particle_distance_threshold: ParticleDistanceThreshold,
+    ) -> Self {
+        Self {
+            particle_distance_threshold,
+            ..self
+        }
+    }
+
+    pub fn initial_condition(self, initial_condition: InitialCondition) -> Self {
+        Self {
+            initial_condition,
+            ..self
+        }
+    }
+
+    /// Check every setting, then instantiate the simulation
+    pub fn build(self) -> Result<Simulation, SimulationError> {
+        Simulation::from_initial_condition(
+            self.num_particles,
+            self.initial_condition,
+            self.boundary_side_length,
+            self.noise,
+            self.speed,
+            self.timestep,
+            self.particle_distance_threshold,
+        )
+    }
+}
+
+impl Simulation {
+    /// Start building a simulation from named settings
+    pub fn builder() -> SimulationBuilder {
+        SimulationBuilder::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builder_starts_from_the_reference_setup() {
+        let sim = Simulation::builder().build().unwrap();
+        assert_eq!(sim.num_particles(), DEFAULT_NUM_PARTICLES);
+        assert_eq!(sim.params.noise.0, DEFAULT_NOISE.0);
+        assert_eq!(sim.params.timestep.0, DEFAULT_TIMESTEP.0);
+
+        let sim = Simulation::builder()
+            .num_particles(4)
+            .noise(Noise(0.3))
+            .initial_condition(InitialCondition::SquareLattice)
+            .build()
+            .unwrap();
+        assert_eq!(sim.num_particles(), 4);
+        assert_eq!(sim.params.noise.0, 0.3);
+        assert_eq!(sim.params.speed.0, DEFAULT_SPEED.0);
+    }
+
+    #[test]
+    fn builder_reports_bad_settings() {
+        assert!(matches!(
+            Simulation::builder().timestep(RelativeTime(-1.0)).build(),
+            Err(SimulationError::InvalidParameter(_))
+        ));
+    }
+}
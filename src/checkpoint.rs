@@ -0,0 +1,291 @@
+use std::{
+    ffi::OsString,
+    fs::{self, File},
+    io::{BufReader, BufWriter, Read, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use anyhow::{Context, bail};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    Simulation,
+    error::{SimulationError, invalid_parameter},
+    observer::StepObserver,
+    random::{self, RngState},
+};
+
+/// Identifies a checkpoint file and its layout, bumped whenever the layout changes
+const CHECKPOINT_MAGIC: &[u8; 8] = b"PIPCKPT1";
+
+/// Identifies an in-memory snapshot from [`Simulation::to_snapshot_bytes`]
+const SNAPSHOT_MAGIC: &[u8; 8] = b"PIPSNAP1";
+
+/// What a checkpoint file holds after its magic bytes
+#[derive(Serialize)]
+struct CheckpointRef<'a> {
+    rng_state: RngState,
+    simulation: &'a Simulation,
+}
+
+#[derive(Deserialize)]
+struct Checkpoint {
+    rng_state: RngState,
+    simulation: Simulation,
+}
+
+impl Simulation {
+    /// Save the full state, plus the current thread's random state, to a binary checkpoint file
+    ///
+    /// # Notes
+    /// The checkpoint is written next to `path` first and then moved over it, so a crash
+    /// mid-write leaves the previous checkpoint intact. Observers aren't saved, and closure-based
+    /// settings (function fields, noise schedules, and scalar rules) fail to save.
+    pub fn save_checkpoint(&self, path: impl AsRef<Path>) -> anyhow::Result<()> {
+        let path = path.as_ref();
+        let mut partial_path = OsString::from(path);
+        partial_path.push(".partial");
+        let partial_path = PathBuf::from(partial_path);
+
+        let file = File::create(&partial_path)
+            .with_context(|| format!("could not create `{}`", partial_path.display()))?;
+        self.write_checkpoint(BufWriter::new(file))
+            .with_context(|| format!("could not write checkpoint `{}`", path.display()))?;
+
+        fs::rename(&partial_path, path)
+            .with_context(|| format!("could not move checkpoint into `{}`", path.display()))
+    }
+
+    /// Load a checkpoint saved by [`Simulation::save_checkpoint`], also restoring the current
+    /// thread's random state so the run resumes exactly where it left off
+    ///
+    /// # Notes
+    /// Observers have to be registered again, and checkpoints are only readable by the version of
+    /// this crate (and floating point precision) that wrote them.
+    pub fn load_checkpoint(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let file = File::open(path)
+            .with_context(|| format!("could not open checkpoint `{}`", path.display()))?;
+        let checkpoint = Self::read_checkpoint(BufReader::new(file))
+            .with_context(|| format!("could not read checkpoint `{}`", path.display()))?;
+
+        random::restore_rng_state(&checkpoint.rng_state);
+
+        Ok(checkpoint.simulation)
+    }
+
+    /// Checkpoint to `path` every `num_steps` steps taken in place from here on, returning the
+    /// observer doing it
+    ///
+    /// # Notes
+    /// Each checkpoint replaces the last, so resuming with [`Simulation::load_checkpoint`] loses
+    /// at most `num_steps` steps. Observers can't fail a run, so a failed save is kept on the
+    /// returned observer instead; check [`AutoCheckpoint::take_error`] now and then.
+    pub fn auto_checkpoint_every(
+        &mut self,
+        num_steps: usize,
+        path: impl Into<PathBuf>,
+    ) -> Result<Arc<Mutex<AutoCheckpoint>>, SimulationError> {
+        let checkpointer = Arc::new(Mutex::new(AutoCheckpoint::new(num_steps, path)?));
+        self.add_observer(checkpointer.clone());
+
+        Ok(checkpointer)
+    }
+
+    /// Serialize the full state to bytes, e.g. for pickling, leaving out the observers and the
+    /// thread's random state
+    ///
+    /// # Notes
+    /// Fails if the simulation holds a closure-based setting (a function field, noise schedule,
+    /// or scalar rule), rather than silently dropping it.
+    pub(crate) fn to_snapshot_bytes(&self) -> bincode::Result<Vec<u8>> {
+        let mut bytes = SNAPSHOT_MAGIC.to_vec();
+        bincode::serialize_into(&mut bytes, self)?;
+
+        Ok(bytes)
+    }
+
+    /// Restore a snapshot from [`Simulation::to_snapshot_bytes`]
+    pub(crate) fn from_snapshot_bytes(bytes: &[u8]) -> anyhow::Result<Self> {
+        let Some(snapshot) = bytes.strip_prefix(SNAPSHOT_MAGIC) else {
+            bail!("bytes are not a simulation snapshot, or are from an incompatible version");
+        };
+
+        Ok(bincode::deserialize(snapshot)?)
+    }
+
+    fn write_checkpoint(&self, mut writer: impl Write) -> anyhow::Result<()> {
+        writer.write_all(CHECKPOINT_MAGIC)?;
+
+        let checkpoint = CheckpointRef {
+            rng_state: random::rng_state(),
+            simulation: self,
+        };
+        bincode::serialize_into(&mut writer, &checkpoint)?;
+
+        Ok(writer.flush()?)
+    }
+
+    fn read_checkpoint(mut reader: impl Read) -> anyhow::Result<Checkpoint> {
+        let mut magic = [0; CHECKPOINT_MAGIC.len()];
+        reader
+            .read_exact(&mut magic)
+            .context("file is too short to be a checkpoint")?;
+
+        if &magic != CHECKPOINT_MAGIC {
+            bail!("file is not a checkpoint, or is from an incompatible version");
+        }
+
+        Ok(bincode::deserialize_from(reader)?)
+    }
+}
+
+/// Saves a checkpoint every few steps, as a [`StepObserver`] or through
+/// [`Simulation::auto_checkpoint_every`]
+#[derive(Debug)]
+pub struct AutoCheckpoint {
+    path: PathBuf,
+    stride: usize,
+    num_steps: usize,
+    num_saved: usize,
+    error: Option<anyhow::Error>,
+}
+
+impl AutoCheckpoint {
+    /// Save to `path` every `stride`-th step observed
+    pub fn new(stride: usize, path: impl Into<PathBuf>) -> Result<Self, SimulationError> {
+        if stride == 0 {
+            invalid_parameter!("checkpoint interval must be at least 1 step");
+        }
+
+        Ok(Self {
+            path: path.into(),
+            stride,
+            num_steps: 0,
+            num_saved: 0,
+            error: None,
+        })
+    }
+
+    /// Where the checkpoints are saved
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Number of checkpoints saved so far
+    pub fn num_saved(&self) -> usize {
+        self.num_saved
+    }
+
+    /// Take the most recent save failure, if any since the last call
+    pub fn take_error(&mut self) -> Option<anyhow::Error> {
+        self.error.take()
+    }
+}
+
+impl StepObserver for AutoCheckpoint {
+    fn on_step(&mut self, sim: &Simulation) {
+        self.num_steps += 1;
+
+        if !self.num_steps.is_multiple_of(self.stride) {
+            return;
+        }
+
+        match sim.save_checkpoint(&self.path) {
+            Ok(()) => self.num_saved += 1,
+            Err(error) => self.error = Some(error),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        NoiseModel, ScalarRule, UpdateOrder, UpdateRule,
+        types::{
+            DomainBoundaryLength, Float, Noise, ParticleDistanceThreshold, RelativeTime, Speed,
+        },
+    };
+
+    fn simulation() -> Simulation {
+        Simulation::new(
+            10,
+            DomainBoundaryLength(5.0),
+            Noise(0.5),
+            Speed(0.1),
+            RelativeTime(1.0),
+            ParticleDistanceThreshold(1.0),
+        )
+        .unwrap()
+        .with_update_rule(UpdateRule::BackwardVicsek)
+        .unwrap()
+        .with_update_order(UpdateOrder::RandomSequential)
+        .with_noise_model(NoiseModel::Scalar)
+    }
+
+    /// Run a few seeded steps and report where every particle ended up
+    fn seeded_run(mut sim: Simulation) -> Vec<(Float, Float, Float)> {
+        random::seed_rng(5);
+        sim.run_for(3).unwrap();
+
+        sim.particles
+            .iter()
+            .map(|particle| (particle.pos_x, particle.pos_y, particle.theta))
+            .collect()
+    }
+
+    #[test]
+    fn snapshots_keep_every_setting() {
+        let sim = simulation();
+        let restored = Simulation::from_snapshot_bytes(&sim.to_snapshot_bytes().unwrap()).unwrap();
+        assert_eq!(seeded_run(restored), seeded_run(sim));
+
+        assert!(Simulation::from_snapshot_bytes(b"not a snapshot").is_err());
+
+        let sim = simulation().with_scalar_rule(Some(ScalarRule::new(|_, _, _, _| {})));
+        assert!(sim.to_snapshot_bytes().is_err());
+    }
+
+    #[test]
+    fn checkpoints_resume_runs_exactly() {
+        let path = std::env::temp_dir().join(format!("checkpoint-{}.bin", std::process::id()));
+        let mut sim = simulation();
+        random::seed_rng(9);
+        sim.run_for(2).unwrap();
+        sim.save_checkpoint(&path).unwrap();
+
+        // Loading restores the random state the save was made with, so the draws match too
+        sim.run_for(3).unwrap();
+        let mut resumed = Simulation::load_checkpoint(&path).unwrap();
+        resumed.run_for(3).unwrap();
+        for (a, b) in sim.particles.iter().zip(resumed.particles.iter()) {
+            assert_eq!((a.pos_x, a.pos_y, a.theta), (b.pos_x, b.pos_y, b.theta));
+        }
+        assert_eq!(resumed.current_time.0, 5.0);
+
+        std::fs::write(&path, b"PIPCKPT0").unwrap();
+        assert!(Simulation::load_checkpoint(&path).is_err());
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn auto_checkpoints_save_every_few_steps() {
+        let path = std::env::temp_dir().join(format!("auto-checkpoint-{}.bin", std::process::id()));
+        let mut sim = simulation();
+        let checkpointer = sim.auto_checkpoint_every(2, &path).unwrap();
+        sim.run_for(5).unwrap();
+
+        let mut checkpointer = checkpointer.lock().unwrap();
+        assert_eq!(checkpointer.num_saved(), 2);
+        assert!(checkpointer.take_error().is_none());
+        assert_eq!(
+            Simulation::load_checkpoint(&path).unwrap().current_time.0,
+            4.0
+        );
+
+        assert!(AutoCheckpoint::new(0, &path).is_err());
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,462 @@
+use anyhow::bail;
+
+use crate::{
+    Simulation,
+    selection::ParticleSelection,
+    tracking::TrackingData,
+    types::{DomainBoundaryLength, Float},
+};
+
+/// Controls how summary statistics are binned
+#[derive(Copy, Clone, Debug)]
+pub struct ComparisonOptions {
+    /// Number of distance bins for the velocity correlation C(r)
+    pub correlation_bins: usize,
+
+    /// Largest pair distance included in C(r)
+    pub correlation_max_distance: Float,
+
+    /// Particles closer than this belong to the same cluster
+    pub cluster_distance: Float,
+
+    /// Clusters at least this large share the last bin of the cluster-size distribution
+    pub max_cluster_size: usize,
+}
+
+impl ComparisonOptions {
+    /// Create options with a default binning
+    pub fn new(correlation_max_distance: Float, cluster_distance: Float) -> Self {
+        Self {
+            correlation_bins: 20,
+            correlation_max_distance,
+            cluster_distance,
+            max_cluster_size: 50,
+        }
+    }
+}
+
+/// Relative importance of each statistic in the discrepancy score
+#[derive(Copy, Clone, Debug)]
+pub struct DiscrepancyWeights {
+    pub order: Float,
+    pub correlation: Float,
+    pub cluster_sizes: Float,
+}
+
+impl Default for DiscrepancyWeights {
+    fn default() -> Self {
+        Self {
+            order: 1.0,
+            correlation: 1.0,
+            cluster_sizes: 1.0,
+        }
+    }
+}
+
+/// Summary statistics of a run, simulated or experimental, for comparing the two
+///
+/// # Notes
+/// C(r) is the mean of cos(θ_i - θ_j) over pairs at distance r, and the cluster-size distribution
+/// is the fraction of particles in clusters of each size. Both are averaged over every frame, and
+/// distances are measured with the periodic boundary condition.
+#[derive(Clone, Debug)]
+pub struct SummaryStatistics {
+    /// Instantaneous order parameter at each frame
+    pub order_curve: Vec<Float>,
+
+    /// Velocity correlation C(r) per distance bin, 0 where a bin saw no pairs
+    pub velocity_correlation: Vec<Float>,
+
+    /// Fraction of particles in clusters of size 1, 2, ..., up to the maximum cluster size
+    pub cluster_size_distribution: Vec<Float>,
+}
+
+impl SummaryStatistics {
+    /// Step a simulation, collecting statistics from its current state and each of `num_steps`
+    /// steps after it
+    pub fn from_simulation(
+        sim: &Simulation,
+        num_steps: usize,
+        options: &ComparisonOptions,
+    ) -> anyhow::Result<Self> {
+        Self::from_simulation_selection(sim, num_steps, options, &ParticleSelection::All)
+    }
+
+    /// Like [`SummaryStatistics::from_simulation`], but only over the selected particles, as if
+    /// the rest weren't there
+    pub fn from_simulation_selection(
+        sim: &Simulation,
+        num_steps: usize,
+        options: &ComparisonOptions,
+        selection: &ParticleSelection,
+    ) -> anyhow::Result<Self> {
+        let mut accumulator = StatisticsAccumulator::new(options)?;
+
+        let mut sim = sim.clone();
+        accumulator.push(
+            &Self::simulation_frame(&sim, selection),
+            sim.params.boundary_side_length,
+        );
+
+        for _ in 0..num_steps {
+            sim = sim.to_timestepped();
+            accumulator.push(
+                &Self::simulation_frame(&sim, selection),
+                sim.params.boundary_side_length,
+            );
+        }
+
+        Ok(accumulator.finish())
+    }
+
+    /// Collect statistics from every frame of experimental tracking data, estimating headings from
+    /// displacement
+    pub fn from_tracking(
+        tracking: &TrackingData,
+        boundary_side_length: DomainBoundaryLength,
+        options: &ComparisonOptions,
+    ) -> anyhow::Result<Self> {
+        let mut accumulator = StatisticsAccumulator::new(options)?;
+
+        for frame in tracking.frames() {
+            let frame: Vec<_> = tracking
+                .to_headed_frame(frame, true)?
+                .into_iter()
+                .map(|point| (point.x, point.y, point.heading.unwrap_or_default()))
+                .collect();
+
+            accumulator.push(&frame, boundary_side_length);
+        }
+
+        Ok(accumulator.finish())
+    }
+
+    /// Score how far these statistics are from a reference, where 0 is a perfect match
+    ///
+    /// # Notes
+    /// The score is a weighted sum of the RMS difference of each statistic. Order curves of
+    /// different lengths are compared over their common prefix.
+    pub fn discrepancy(&self, reference: &Self, weights: &DiscrepancyWeights) -> Float {
+        weights.order * rms_difference(&self.order_curve, &reference.order_curve)
+            + weights.correlation
+                * rms_difference(&self.velocity_correlation, &reference.velocity_correlation)
+            + weights.cluster_sizes
+                * rms_difference(
+                    &self.cluster_size_distribution,
+                    &reference.cluster_size_distribution,
+                )
+    }
+
+    fn simulation_frame(
+        sim: &Simulation,
+        selection: &ParticleSelection,
+    ) -> Vec<(Float, Float, Float)> {
+        sim.particles
+            .iter()
+            .filter(|particle| selection.contains(particle))
+            .map(|particle| (particle.pos_x, particle.pos_y, particle.theta))
+            .collect()
+    }
+}
+
+impl Simulation {
+    /// Label each particle with the cluster it belongs to, where particles closer than
+    /// `cluster_distance` (directly or through a chain of others) share a cluster. Labels count
+    /// up from 0 in order of each cluster's first particle.
+    pub fn cluster_labels(&self, cluster_distance: Float) -> Vec<usize> {
+        self.selected_cluster_labels(cluster_distance, &ParticleSelection::All)
+            .into_iter()
+            .flatten()
+            .collect()
+    }
+
+    /// Like [`Simulation::cluster_labels`], but clustering only the selected particles. Every
+    /// other particle is labelled `None`.
+    pub fn selected_cluster_labels(
+        &self,
+        cluster_distance: Float,
+        selection: &ParticleSelection,
+    ) -> Vec<Option<usize>> {
+        let frame = SummaryStatistics::simulation_frame(self, selection);
+        let boundary_side_length = self.params.boundary_side_length;
+
+        let mut cluster_roots: Vec<usize> = (0..frame.len()).collect();
+        for (i, &(x_i, y_i, _)) in frame.iter().enumerate() {
+            for (j, &(x_j, y_j, _)) in frame.iter().enumerate().skip(i + 1) {
+                let dx = periodic_delta(x_i, x_j, boundary_side_length);
+                let dy = periodic_delta(y_i, y_j, boundary_side_length);
+
+                if (dx * dx + dy * dy).sqrt() < cluster_distance {
+                    let root_i = find_root(&mut cluster_roots, i);
+                    let root_j = find_root(&mut cluster_roots, j);
+                    cluster_roots[root_i] = root_j;
+                }
+            }
+        }
+
+        // Relabel the roots densely
+        let mut labels_by_root = vec![None; frame.len()];
+        let mut num_labels = 0;
+
+        let mut labels = (0..frame.len()).map(|i| {
+            let root = find_root(&mut cluster_roots, i);
+            *labels_by_root[root].get_or_insert_with(|| {
+                num_labels += 1;
+                num_labels - 1
+            })
+        });
+
+        // The frame holds the selected particles in order, so hand their labels out in turn
+        self.particles
+            .iter()
+            .map(|particle| match selection.contains(particle) {
+                true => labels.next(),
+                false => None,
+            })
+            .collect()
+    }
+
+    /// The instantaneous order parameter over the selected particles, or 0 if none are selected
+    pub fn order_parameter(&self, selection: &ParticleSelection) -> Float {
+        let (sum_cos, sum_sin, count) = self
+            .particles
+            .iter()
+            .filter(|particle| selection.contains(particle))
+            .fold((0.0, 0.0, 0), |(sum_cos, sum_sin, count), particle| {
+                (
+                    sum_cos + particle.theta.cos(),
+                    sum_sin + particle.theta.sin(),
+                    count + 1,
+                )
+            });
+
+        match count {
+            0 => 0.0,
+            count => (sum_cos * sum_cos + sum_sin * sum_sin).sqrt() / count as Float,
+        }
+    }
+}
+
+/// Root-mean-square difference over the common prefix of two series
+fn rms_difference(a: &[Float], b: &[Float]) -> Float {
+    let len = a.len().min(b.len());
+
+    if len == 0 {
+        return 0.0;
+    }
+
+    let sum_squares: Float = a.iter().zip(b).map(|(a, b)| (a - b) * (a - b)).sum();
+
+    (sum_squares / len as Float).sqrt()
+}
+
+/// Running sums for each statistic, accumulated frame by frame
+struct StatisticsAccumulator {
+    options: ComparisonOptions,
+    order_curve: Vec<Float>,
+    correlation_sums: Vec<Float>,
+    correlation_counts: Vec<usize>,
+    cluster_size_sums: Vec<Float>,
+    num_frames: usize,
+}
+
+impl StatisticsAccumulator {
+    fn new(options: &ComparisonOptions) -> anyhow::Result<Self> {
+        if options.correlation_bins == 0 || options.max_cluster_size == 0 {
+            bail!("comparison statistics need at least one correlation bin and cluster size");
+        }
+
+        if options.correlation_max_distance.is_nan() || options.correlation_max_distance <= 0.0 {
+            bail!(
+                "correlation max distance must be positive, got `{}`",
+                options.correlation_max_distance
+            );
+        }
+
+        Ok(Self {
+            options: *options,
+            order_curve: Vec::new(),
+            correlation_sums: vec![0.0; options.correlation_bins],
+            correlation_counts: vec![0; options.correlation_bins],
+            cluster_size_sums: vec![0.0; options.max_cluster_size],
+            num_frames: 0,
+        })
+    }
+
+    /// Add a frame of `(x, y, heading)` states
+    fn push(
+        &mut self,
+        frame: &[(Float, Float, Float)],
+        boundary_side_length: DomainBoundaryLength,
+    ) {
+        if frame.is_empty() {
+            return;
+        }
+
+        let (sum_cos, sum_sin) = frame
+            .iter()
+            .fold((0.0, 0.0), |(sum_cos, sum_sin), &(_, _, theta)| {
+                (sum_cos + theta.cos(), sum_sin + theta.sin())
+            });
+        self.order_curve
+            .push((sum_cos * sum_cos + sum_sin * sum_sin).sqrt() / frame.len() as Float);
+
+        // Track clusters with a union-find over the same pairs used for C(r)
+        let mut cluster_roots: Vec<usize> = (0..frame.len()).collect();
+        let bin_width =
+            self.options.correlation_max_distance / self.options.correlation_bins as Float;
+
+        for (i, &(x_i, y_i, theta_i)) in frame.iter().enumerate() {
+            for (j, &(x_j, y_j, theta_j)) in frame.iter().enumerate().skip(i + 1) {
+                let dx = periodic_delta(x_i, x_j, boundary_side_length);
+                let dy = periodic_delta(y_i, y_j, boundary_side_length);
+                let distance = (dx * dx + dy * dy).sqrt();
+
+                if distance < self.options.correlation_max_distance {
+                    let bin =
+                        ((distance / bin_width) as usize).min(self.options.correlation_bins - 1);
+                    self.correlation_sums[bin] += (theta_i - theta_j).cos();
+                    self.correlation_counts[bin] += 1;
+                }
+
+                if distance < self.options.cluster_distance {
+                    let root_i = find_root(&mut cluster_roots, i);
+                    let root_j = find_root(&mut cluster_roots, j);
+                    cluster_roots[root_i] = root_j;
+                }
+            }
+        }
+
+        let mut cluster_sizes = vec![0; frame.len()];
+        for i in 0..frame.len() {
+            cluster_sizes[find_root(&mut cluster_roots, i)] += 1;
+        }
+
+        // Each cluster of size s holds s particles
+        for size in cluster_sizes.into_iter().filter(|&size| size > 0) {
+            let bin = (size - 1).min(self.options.max_cluster_size - 1);
+            self.cluster_size_sums[bin] += size as Float / frame.len() as Float;
+        }
+
+        self.num_frames += 1;
+    }
+
+    fn finish(self) -> SummaryStatistics {
+        let velocity_correlation = self
+            .correlation_sums
+            .iter()
+            .zip(&self.correlation_counts)
+            .map(|(&sum, &count)| match count {
+                0 => 0.0,
+                count => sum / count as Float,
+            })
+            .collect();
+
+        let num_frames = self.num_frames.max(1) as Float;
+        let cluster_size_distribution = self
+            .cluster_size_sums
+            .iter()
+            .map(|sum| sum / num_frames)
+            .collect();
+
+        SummaryStatistics {
+            order_curve: self.order_curve,
+            velocity_correlation,
+            cluster_size_distribution,
+        }
+    }
+}
+
+/// Shortest distance along one axis for a periodic BC
+fn periodic_delta(x1: Float, x2: Float, boundary_side_length: DomainBoundaryLength) -> Float {
+    let dx = (x1 - x2).rem_euclid(boundary_side_length.0);
+    dx.min(boundary_side_length.0 - dx)
+}
+
+/// Find a cluster's root, halving the path along the way
+fn find_root(roots: &mut [usize], mut idx: usize) -> usize {
+    while roots[idx] != idx {
+        roots[idx] = roots[roots[idx]];
+        idx = roots[idx];
+    }
+
+    idx
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Noise, PI, ParticleDistanceThreshold, RelativeTime, Speed};
+
+    fn aligned(positions: &[(Float, Float)]) -> Simulation {
+        Simulation::with_particles(
+            positions,
+            &vec![0.7; positions.len()],
+            DomainBoundaryLength(5.0),
+            Noise(0.0),
+            Speed(0.01),
+            RelativeTime(1.0),
+            ParticleDistanceThreshold(1.0),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn aligned_clusters_have_full_correlation_and_order() {
+        let sim = aligned(&[(1.0, 1.0), (1.2, 1.0), (4.0, 4.0)]);
+
+        assert_eq!(sim.cluster_labels(0.5), [0, 0, 1]);
+
+        let options = ComparisonOptions::new(1.0, 0.5);
+        let statistics = SummaryStatistics::from_simulation(&sim, 2, &options).unwrap();
+        assert_eq!(statistics.order_curve.len(), 3);
+        assert!(
+            statistics
+                .order_curve
+                .iter()
+                .all(|order| (order - 1.0).abs() < 1e-9)
+        );
+
+        // Only the bin holding the one close pair sees anything
+        let filled_bin = (0.2 / 1.0 * options.correlation_bins as Float) as usize;
+        assert!((statistics.velocity_correlation[filled_bin] - 1.0).abs() < 1e-9);
+
+        // A third of the particles are alone, and two thirds in a pair
+        assert!((statistics.cluster_size_distribution[0] - 1.0 / 3.0).abs() < 1e-9);
+        assert!((statistics.cluster_size_distribution[1] - 2.0 / 3.0).abs() < 1e-9);
+
+        let weights = DiscrepancyWeights::default();
+        assert_eq!(statistics.discrepancy(&statistics, &weights), 0.0);
+    }
+
+    #[test]
+    fn observables_cover_only_the_selected_particles() {
+        let sim = Simulation::with_particles(
+            &[(1.0, 1.0), (1.2, 1.0), (1.4, 1.0)],
+            &[0.0, PI, 0.0],
+            DomainBoundaryLength(5.0),
+            Noise(0.0),
+            Speed(0.01),
+            RelativeTime(1.0),
+            ParticleDistanceThreshold(1.0),
+        )
+        .unwrap();
+        let ends = ParticleSelection::Ids([0, 2].into());
+
+        assert!(sim.order_parameter(&ParticleSelection::All) < 0.5);
+        assert!((sim.order_parameter(&ends) - 1.0).abs() < 1e-9);
+
+        // Without the middle particle the ends no longer chain into one cluster
+        assert_eq!(
+            sim.selected_cluster_labels(0.3, &ends),
+            [Some(0), None, Some(1)]
+        );
+
+        let sim = sim.to_tagged(1);
+        assert_eq!(sim.order_parameter(&ParticleSelection::Tag(0)), 0.0);
+        assert_eq!(
+            sim.order_parameter(&ParticleSelection::Tag(1)),
+            sim.order_parameter(&ParticleSelection::All)
+        );
+    }
+}
@@ -0,0 +1,107 @@
+use std::{
+    fmt::Debug,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+
+/// A cheaply-cloneable flag for stopping long computations early
+///
+/// # Notes
+/// Cancelling a clone cancels every other clone, so one can be handed to a computation while
+/// another is kept (e.g. in a signal handler or another thread) to cancel it.
+#[derive(Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+
+    /// An optional external check, e.g. for pending interrupts in an embedding interpreter
+    poll: Option<Arc<dyn Fn() -> bool + Send + Sync>>,
+}
+
+impl CancellationToken {
+    /// Create a new, uncancelled token
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a token that also counts as cancelled once `poll` returns true
+    pub fn with_poll(poll: impl Fn() -> bool + Send + Sync + 'static) -> Self {
+        Self {
+            cancelled: Arc::default(),
+            poll: Some(Arc::new(poll)),
+        }
+    }
+
+    /// Request cancellation
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// Check whether cancellation has been requested
+    pub fn is_cancelled(&self) -> bool {
+        if self.cancelled.load(Ordering::Relaxed) {
+            return true;
+        }
+
+        // Latch the poll result so it only needs to fire once
+        if self.poll.as_ref().is_some_and(|poll| poll()) {
+            self.cancel();
+            return true;
+        }
+
+        false
+    }
+}
+
+impl Debug for CancellationToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CancellationToken")
+            .field("cancelled", &self.cancelled.load(Ordering::Relaxed))
+            .field("poll", &self.poll.is_some())
+            .finish()
+    }
+}
+
+/// Why a long computation stopped
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum StopReason {
+    /// The convergence criteria were met
+    Converged,
+
+    /// The computation was cancelled and the result is partial
+    Cancelled,
+
+    /// The step/iteration budget ran out before convergence
+    StepBudgetExhausted,
+
+    /// The wall-clock budget ran out before convergence
+    TimeBudgetExhausted,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cancelling_a_clone_cancels_every_clone() {
+        let token = CancellationToken::new();
+        let clone = token.clone();
+        assert!(!token.is_cancelled());
+
+        clone.cancel();
+        assert!(token.is_cancelled());
+    }
+
+    #[test]
+    fn poll_result_is_latched() {
+        let polls = Arc::new(AtomicBool::new(true));
+        let token = CancellationToken::with_poll({
+            let polls = polls.clone();
+            move || polls.swap(false, Ordering::Relaxed)
+        });
+
+        assert!(token.is_cancelled());
+        assert!(token.is_cancelled());
+    }
+}
@@ -0,0 +1,192 @@
+use anyhow::bail;
+
+use crate::{
+    Simulation,
+    random::seed_rng,
+    simulation::{StationaryOrderEstimate, StationaryOrderOptions},
+    trigger::Observable,
+    types::{Float, RelativeTime},
+};
+
+/// Controls for a timestep convergence study
+#[derive(Clone, Debug)]
+pub struct TimestepConvergenceOptions {
+    /// Number of timesteps tried, halving each time: Δt, Δt/2, Δt/4, ...
+    pub num_levels: usize,
+
+    /// Simulated time each level runs for when averaging the observables, the same at every level
+    pub duration: Float,
+
+    /// Seed reset before every level, so each starts from the same random state
+    pub seed: u64,
+
+    /// Extra observables to compare, by name, each averaged over the second half of the run
+    pub observables: Vec<(String, Observable)>,
+
+    /// Controls for each level's stationary order parameter
+    pub stationary_order: StationaryOrderOptions,
+}
+
+impl Default for TimestepConvergenceOptions {
+    fn default() -> Self {
+        Self {
+            num_levels: 4,
+            duration: 50.0,
+            seed: 0,
+            observables: Vec::new(),
+            stationary_order: StationaryOrderOptions::default(),
+        }
+    }
+}
+
+/// The results at one timestep of a convergence study
+#[derive(Copy, Clone, Debug)]
+pub struct TimestepLevel {
+    pub timestep: RelativeTime,
+
+    pub stationary_order: StationaryOrderEstimate,
+
+    /// The order parameter averaged over the second half of the run
+    pub mean_order: Float,
+}
+
+/// The outcome of a timestep convergence study, finest timestep last
+#[derive(Clone, Debug)]
+pub struct TimestepConvergenceReport {
+    pub levels: Vec<TimestepLevel>,
+
+    /// The names of the extra observables, in the order given
+    pub observable_names: Vec<String>,
+
+    /// Each level's averaged value of every extra observable, in level order
+    pub observables: Vec<Vec<Float>>,
+}
+
+impl TimestepConvergenceReport {
+    /// How much the stationary order parameter changed with each halving of the timestep
+    pub fn stationary_order_changes(&self) -> Vec<Float> {
+        self.levels
+            .windows(2)
+            .map(|pair| (pair[1].stationary_order.value - pair[0].stationary_order.value).abs())
+            .collect()
+    }
+}
+
+impl Simulation {
+    /// Rerun the current state at successively halved timesteps with the same seed, and compare
+    /// the stationary order parameter and observables between them
+    ///
+    /// # Notes
+    /// Results that keep shifting as the timestep shrinks are discretization artifacts. The
+    /// Vicsek update perturbs headings once per step, so without heading relaxation the order
+    /// is expected to depend on Δt at any size; this makes that dependence visible rather than
+    /// correcting for it. Runs start from the same particles, but the random draws only line up
+    /// step for step, not in simulated time.
+    pub fn timestep_convergence(
+        &self,
+        options: &TimestepConvergenceOptions,
+    ) -> anyhow::Result<TimestepConvergenceReport> {
+        if options.num_levels == 0 {
+            bail!("a timestep convergence study needs at least one level");
+        }
+
+        if !options.duration.is_finite() || options.duration <= 0.0 {
+            bail!(
+                "convergence run duration must be positive and finite, got `{}`",
+                options.duration
+            );
+        }
+
+        let mut report = TimestepConvergenceReport {
+            levels: Vec::with_capacity(options.num_levels),
+            observable_names: options
+                .observables
+                .iter()
+                .map(|(name, _)| name.clone())
+                .collect(),
+            observables: Vec::with_capacity(options.num_levels),
+        };
+
+        for level in 0..options.num_levels {
+            let timestep = RelativeTime(self.params.timestep.0 / (2.0 as Float).powi(level as i32));
+            let start = self.clone().with_timestep(timestep)?;
+
+            seed_rng(options.seed);
+            let stationary_order =
+                start.compute_stationary_order_estimate(&options.stationary_order)?;
+
+            // Average over the second half, to skip the transient
+            seed_rng(options.seed);
+            let num_steps = ((options.duration / timestep.0).round() as usize).max(2);
+            let num_averaged = num_steps - num_steps / 2;
+            let mut order_sum = 0.0;
+            let mut observable_sums = vec![0.0; options.observables.len()];
+
+            for sim in start.iter_steps().take(num_steps).skip(num_steps / 2) {
+                order_sum += sim.instantaneous_order.0;
+                for (sum, (_, observable)) in observable_sums.iter_mut().zip(&options.observables) {
+                    *sum += observable.evaluate(&sim);
+                }
+            }
+
+            report.levels.push(TimestepLevel {
+                timestep,
+                stationary_order,
+                mean_order: order_sum / num_averaged as Float,
+            });
+            report.observables.push(
+                observable_sums
+                    .into_iter()
+                    .map(|sum| sum / num_averaged as Float)
+                    .collect(),
+            );
+        }
+
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{DomainBoundaryLength, Noise, ParticleDistanceThreshold, Speed};
+
+    #[test]
+    fn levels_halve_the_timestep_over_the_same_duration() {
+        let sim = Simulation::new(
+            10,
+            DomainBoundaryLength(5.0),
+            Noise(0.1),
+            Speed(0.1),
+            RelativeTime(1.0),
+            ParticleDistanceThreshold(1.0),
+        )
+        .unwrap();
+        let options = TimestepConvergenceOptions {
+            num_levels: 3,
+            duration: 4.0,
+            observables: vec![("time".to_string(), Observable::Time)],
+            stationary_order: StationaryOrderOptions {
+                max_steps: Some(5),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let report = sim.timestep_convergence(&options).unwrap();
+        let timesteps: Vec<Float> = report.levels.iter().map(|level| level.timestep.0).collect();
+        assert_eq!(timesteps, [1.0, 0.5, 0.25]);
+        assert_eq!(report.stationary_order_changes().len(), 2);
+
+        // Each level averages the time over the second half of the same duration
+        assert_eq!(report.observable_names, ["time"]);
+        let mean_times: Vec<Float> = report.observables.iter().map(|values| values[0]).collect();
+        assert_eq!(mean_times, [3.5, 3.25, 3.125]);
+
+        let no_levels = TimestepConvergenceOptions {
+            num_levels: 0,
+            ..Default::default()
+        };
+        assert!(sim.timestep_convergence(&no_levels).is_err());
+    }
+}
@@ -0,0 +1,267 @@
+use crate::{
+    Simulation,
+    error::{SimulationError, invalid_parameter},
+    random,
+    scalars::ScalarRule,
+    selection::ParticleSelection,
+    types::Float,
+};
+
+/// Name of the per-particle scalar holding each particle's SIR state
+pub const SIR_STATE: &str = "sir_state";
+
+/// Where a particle is in an SIR contact process, stored in the [`SIR_STATE`] scalar as 0, 1,
+/// or 2
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SirState {
+    Susceptible,
+    Infected,
+    Recovered,
+}
+
+impl SirState {
+    fn from_value(value: Float) -> Self {
+        match value.round() as i64 {
+            1 => Self::Infected,
+            2 => Self::Recovered,
+            _ => Self::Susceptible,
+        }
+    }
+
+    fn to_value(self) -> Float {
+        match self {
+            Self::Susceptible => 0.0,
+            Self::Infected => 1.0,
+            Self::Recovered => 2.0,
+        }
+    }
+}
+
+/// An SIR-style contact process spreading between neighbors as the swarm moves
+///
+/// # Notes
+/// Each step a susceptible particle with `k` infected neighbors is infected with probability
+/// 1 - exp(-β k Δt), and an infected particle recovers with probability 1 - exp(-γ Δt).
+/// Neighbors are those the particle aligns with, so vision cones and per-particle radii apply.
+#[derive(Copy, Clone, Debug)]
+pub struct ContactProcess {
+    /// Rate β of infection per infected neighbor
+    pub transmission_rate: Float,
+
+    /// Rate γ at which infected particles recover, for good
+    pub recovery_rate: Float,
+}
+
+impl ContactProcess {
+    /// Express the process as an update rule for the [`SIR_STATE`] scalar
+    fn to_scalar_rule(self) -> ScalarRule {
+        ScalarRule::new(move |particle, neighbors, timestep, scalars| {
+            let state = SirState::from_value(particle.scalars.get(SIR_STATE));
+
+            let rate = match state {
+                SirState::Susceptible => {
+                    let num_infected = neighbors
+                        .iter()
+                        .filter(|neighbor| {
+                            SirState::from_value(neighbor.scalars.get(SIR_STATE))
+                                == SirState::Infected
+                        })
+                        .count();
+
+                    self.transmission_rate * num_infected as Float
+                }
+                SirState::Infected => self.recovery_rate,
+                SirState::Recovered => return,
+            };
+
+            let probability = 1.0 - (-rate * timestep.0).exp();
+            if probability > 0.0 && random::random::<Float>() < probability {
+                let next = match state {
+                    SirState::Susceptible => SirState::Infected,
+                    _ => SirState::Recovered,
+                };
+                scalars.set(SIR_STATE, next.to_value());
+            }
+        })
+    }
+}
+
+/// How many particles are in each SIR state
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct SirCounts {
+    pub susceptible: usize,
+    pub infected: usize,
+    pub recovered: usize,
+}
+
+/// The SIR counts over an epidemic, one entry per recorded step
+#[derive(Clone, Debug, Default)]
+pub struct EpidemicCurve {
+    pub times: Vec<Float>,
+    pub counts: Vec<SirCounts>,
+}
+
+impl EpidemicCurve {
+    /// The time and size of the largest number infected at once
+    pub fn peak(&self) -> Option<(Float, usize)> {
+        self.times
+            .iter()
+            .zip(&self.counts)
+            .map(|(&time, counts)| (time, counts.infected))
+            .reduce(|peak, entry| match entry.1 > peak.1 {
+                true => entry,
+                false => peak,
+            })
+    }
+
+    /// The fraction of particles ever infected by the end, i.e. recovered or still infected
+    pub fn final_size(&self) -> Option<Float> {
+        self.counts.last().map(|counts| {
+            let total = counts.susceptible + counts.infected + counts.recovered;
+            (counts.infected + counts.recovered) as Float / total as Float
+        })
+    }
+}
+
+impl Simulation {
+    /// Spread an SIR contact process between neighbors every step, or stop it with `None`
+    ///
+    /// # Notes
+    /// The process is the simulation's scalar rule, so it replaces any other rule. Particles
+    /// start out susceptible; seed the outbreak with [`Simulation::infect`].
+    pub fn with_contact_process(
+        self,
+        contact_process: Option<ContactProcess>,
+    ) -> Result<Self, SimulationError> {
+        if let Some(contact_process) = contact_process {
+            for (name, value) in [
+                ("transmission rate", contact_process.transmission_rate),
+                ("recovery rate", contact_process.recovery_rate),
+            ] {
+                if !value.is_finite() || value < 0.0 {
+                    invalid_parameter!("{} must be non-negative and finite, got `{}`", name, value);
+                }
+            }
+        }
+
+        Ok(self.with_scalar_rule(contact_process.map(ContactProcess::to_scalar_rule)))
+    }
+
+    /// Infect the selected particles, whatever their current SIR state
+    pub fn infect(&mut self, selection: &ParticleSelection) {
+        let states: Vec<Float> = self
+            .particles
+            .iter()
+            .map(|particle| match selection.contains(particle) {
+                true => SirState::Infected.to_value(),
+                false => particle.scalars.get(SIR_STATE),
+            })
+            .collect();
+
+        self.particles = self.particles.to_with_scalar(SIR_STATE, Some(&states));
+    }
+
+    /// Count the particles in each SIR state
+    pub fn sir_counts(&self) -> SirCounts {
+        self.particles
+            .iter()
+            .fold(SirCounts::default(), |mut counts, particle| {
+                match SirState::from_value(particle.scalars.get(SIR_STATE)) {
+                    SirState::Susceptible => counts.susceptible += 1,
+                    SirState::Infected => counts.infected += 1,
+                    SirState::Recovered => counts.recovered += 1,
+                }
+                counts
+            })
+    }
+
+    /// Step until nobody is infected or `max_steps` have been taken, recording the SIR counts
+    /// from the starting state onwards
+    pub fn run_epidemic(self, max_steps: usize) -> (Self, EpidemicCurve) {
+        let mut sim = self;
+        let mut curve = EpidemicCurve {
+            times: vec![sim.current_time.0],
+            counts: vec![sim.sir_counts()],
+        };
+
+        for _ in 0..max_steps {
+            if curve
+                .counts
+                .last()
+                .is_some_and(|counts| counts.infected == 0)
+            {
+                break;
+            }
+
+            sim = sim.to_timestepped();
+            curve.times.push(sim.current_time.0);
+            curve.counts.push(sim.sir_counts());
+        }
+
+        (sim, curve)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{
+        DomainBoundaryLength, Noise, ParticleDistanceThreshold, RelativeTime, Speed,
+    };
+
+    #[test]
+    fn infection_passes_between_neighbors_and_burns_out() {
+        let mut sim = Simulation::with_particles(
+            &[(1.0, 1.0), (1.5, 1.0), (4.0, 4.0)],
+            &[0.0, 0.0, 0.0],
+            DomainBoundaryLength(5.0),
+            Noise(0.0),
+            Speed(0.0),
+            RelativeTime(1.0),
+            ParticleDistanceThreshold(1.0),
+        )
+        .unwrap()
+        .with_contact_process(Some(ContactProcess {
+            transmission_rate: 1e3,
+            recovery_rate: 1e3,
+        }))
+        .unwrap();
+        sim.infect(&ParticleSelection::Ids([0].into()));
+
+        // The first particle infects its neighbor as it recovers, and the far one is never reached
+        let (_, curve) = sim.run_epidemic(10);
+        let counts = |susceptible, infected, recovered| SirCounts {
+            susceptible,
+            infected,
+            recovered,
+        };
+        assert_eq!(curve.times, [0.0, 1.0, 2.0]);
+        assert_eq!(
+            curve.counts,
+            [counts(2, 1, 0), counts(1, 1, 1), counts(1, 0, 2)]
+        );
+        assert_eq!(curve.peak(), Some((0.0, 1)));
+        assert!((curve.final_size().unwrap() - 2.0 / 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn contact_rates_must_be_non_negative() {
+        let sim = Simulation::new(
+            5,
+            DomainBoundaryLength(5.0),
+            Noise(0.1),
+            Speed(0.1),
+            RelativeTime(1.0),
+            ParticleDistanceThreshold(1.0),
+        )
+        .unwrap();
+
+        assert!(matches!(
+            sim.with_contact_process(Some(ContactProcess {
+                transmission_rate: -1.0,
+                recovery_rate: 0.1,
+            })),
+            Err(SimulationError::InvalidParameter(_))
+        ));
+    }
+}
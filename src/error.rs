@@ -0,0 +1,91 @@
+use std::path::PathBuf;
+
+use thiserror::Error;
+
+use crate::types::Float;
+
+/// Why a simulation couldn't be built or an analysis of it failed
+#[derive(Debug, Error)]
+pub enum SimulationError {
+    /// A parameter is out of range, e.g. a negative noise or an empty simulation
+    #[error("{0}")]
+    InvalidParameter(String),
+
+    /// An iterative computation ran out of steps before converging
+    #[error(
+        "max iterations (`{iterations}`) reached for {quantity} (last estimate `{value}`, \
+         residual `{residual}`)"
+    )]
+    ConvergenceFailure {
+        quantity: &'static str,
+        iterations: usize,
+        value: Float,
+        residual: Float,
+    },
+
+    /// The optimizer couldn't be set up, or failed while running
+    #[error("optimizer failed: {0}")]
+    OptimizerFailure(String),
+
+    /// The watchdog caught a step going wrong, e.g. a NaN position or a particle jumping across
+    /// the domain
+    #[error("numerical anomaly at time `{time}`: {reason} ({})", describe_dump(.dump_dir))]
+    NumericalAnomaly {
+        time: Float,
+        reason: String,
+
+        /// Where the frames and random state were dumped, unless writing them failed
+        dump_dir: Option<PathBuf>,
+    },
+}
+
+fn describe_dump(dump_dir: &Option<PathBuf>) -> String {
+    match dump_dir {
+        Some(dump_dir) => format!("state dumped to `{}`", dump_dir.display()),
+        None => "the state could not be dumped".to_string(),
+    }
+}
+
+/// Fail with an invalid parameter error, formatted like `bail!`
+macro_rules! invalid_parameter {
+    ($($arg:tt)*) => {
+        return Err($crate::error::SimulationError::InvalidParameter(format!($($arg)*)))
+    };
+}
+
+pub(crate) use invalid_parameter;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn errors_describe_what_went_wrong() {
+        let error = SimulationError::ConvergenceFailure {
+            quantity: "stationary order parameter",
+            iterations: 10,
+            value: 0.5,
+            residual: 0.1,
+        };
+        assert_eq!(
+            error.to_string(),
+            "max iterations (`10`) reached for stationary order parameter (last estimate `0.5`, \
+             residual `0.1`)"
+        );
+
+        let anomaly = |dump_dir| SimulationError::NumericalAnomaly {
+            time: 2.0,
+            reason: "NaN position".to_string(),
+            dump_dir,
+        };
+        assert_eq!(
+            anomaly(Some(PathBuf::from("dump"))).to_string(),
+            "numerical anomaly at time `2`: NaN position (state dumped to `dump`)"
+        );
+        assert!(
+            anomaly(None)
+                .to_string()
+                .ends_with("(the state could not be dumped)")
+        );
+    }
+}
@@ -0,0 +1,428 @@
+use std::io::{Read, Seek, SeekFrom, Write};
+
+use anyhow::{Context, bail};
+
+use crate::{
+    particle::Particle,
+    simulation::Simulation,
+    trajectory::{Trajectory, TrajectoryFrame},
+    types::{
+        AbsoluteTime, DomainBoundaryLength, Float, Noise, ParticleDistanceThreshold, RelativeTime,
+        Speed,
+    },
+};
+
+/// Identifies a compressed trajectory file
+const MAGIC: &[u8; 4] = b"PIPZ";
+
+const FORMAT_VERSION: u8 = 1;
+
+/// Bytes before the first frame: the magic, the version, and five parameters
+const HEADER_LEN: u64 = 4 + 1 + 5 * 8;
+
+/// The simulation parameters stored at the start of a compressed trajectory
+#[derive(Copy, Clone, Debug)]
+pub struct CompressedTrajectoryHeader {
+    pub boundary_side_length: DomainBoundaryLength,
+    pub noise: Noise,
+    pub speed: Speed,
+    pub timestep: RelativeTime,
+    pub particle_distance_threshold: ParticleDistanceThreshold,
+}
+
+impl CompressedTrajectoryHeader {
+    fn from_simulation(sim: &Simulation) -> Self {
+        Self {
+            boundary_side_length: sim.params.boundary_side_length,
+            noise: sim.params.noise,
+            speed: sim.params.speed,
+            timestep: sim.params.timestep,
+            particle_distance_threshold: sim.params.particle_distance_threshold,
+        }
+    }
+
+    /// Encode as little-endian bytes
+    // Note: `Float` may be 32-bit, but the format always stores 64-bit floats
+    #[allow(clippy::unnecessary_cast)]
+    fn write_to(&self, writer: &mut impl Write) -> std::io::Result<()> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&[FORMAT_VERSION])?;
+
+        for value in [
+            self.boundary_side_length.0,
+            self.noise.0,
+            self.speed.0,
+            self.timestep.0,
+            self.particle_distance_threshold.0,
+        ] {
+            writer.write_all(&(value as f64).to_le_bytes())?;
+        }
+
+        Ok(())
+    }
+
+    fn read_from(reader: &mut impl Read) -> anyhow::Result<Self> {
+        let mut magic = [0; 4];
+        reader
+            .read_exact(&mut magic)
+            .context("could not read compressed trajectory header")?;
+        if &magic != MAGIC {
+            bail!("not a compressed trajectory");
+        }
+
+        let mut version = [0; 1];
+        reader
+            .read_exact(&mut version)
+            .context("could not read compressed trajectory version")?;
+        if version[0] != FORMAT_VERSION {
+            bail!(
+                "unsupported compressed trajectory version `{}` (expected `{}`)",
+                version[0],
+                FORMAT_VERSION
+            );
+        }
+
+        let mut parameters = [0.0; 5];
+        for parameter in &mut parameters {
+            let mut bytes = [0; 8];
+            reader
+                .read_exact(&mut bytes)
+                .context("could not read compressed trajectory parameters")?;
+            *parameter = f64::from_le_bytes(bytes) as Float;
+        }
+        let [
+            boundary_side_length,
+            noise,
+            speed,
+            timestep,
+            particle_distance_threshold,
+        ] = parameters;
+
+        Ok(Self {
+            boundary_side_length: DomainBoundaryLength(boundary_side_length),
+            noise: Noise(noise),
+            speed: Speed(speed),
+            timestep: RelativeTime(timestep),
+            particle_distance_threshold: ParticleDistanceThreshold(particle_distance_threshold),
+        })
+    }
+}
+
+/// Streams full-precision frames to any writer (e.g. a buffered file), each compressed on its
+/// own with zstd so [`TrajectoryReader`] can jump straight to any of them
+///
+/// # Notes
+/// The file starts with the simulation parameters, followed by one length-prefixed compressed
+/// block per frame holding the time and each particle's ID, position, and heading as 64-bit
+/// values, byte-shuffled before compressing. The parameters are taken when the writer is
+/// created, so later changes (e.g. a domain resize) aren't reflected in the header.
+pub struct CompressedTrajectoryWriter<W: Write> {
+    writer: W,
+    level: i32,
+}
+
+impl<W: Write> CompressedTrajectoryWriter<W> {
+    /// Start a new trajectory of `sim`, compressing at zstd `level` (1 to 22, where 3 is a good
+    /// default)
+    pub fn new(mut writer: W, sim: &Simulation, level: i32) -> anyhow::Result<Self> {
+        if !zstd::compression_level_range().contains(&level) {
+            bail!(
+                "zstd compression level must be in `{:?}`, got `{}`",
+                zstd::compression_level_range(),
+                level
+            );
+        }
+
+        CompressedTrajectoryHeader::from_simulation(sim)
+            .write_to(&mut writer)
+            .context("could not write compressed trajectory header")?;
+
+        Ok(Self { writer, level })
+    }
+
+    /// Compress and append the current state of a simulation
+    // Note: `Float` may be 32-bit, but the format always stores 64-bit floats
+    #[allow(clippy::unnecessary_cast)]
+    pub fn write_frame(&mut self, sim: &Simulation) -> anyhow::Result<()> {
+        let num_particles = sim.particles.len();
+        let mut raw = Vec::with_capacity(16 + 32 * num_particles);
+        raw.extend((sim.current_time.0 as f64).to_le_bytes());
+        raw.extend((num_particles as u64).to_le_bytes());
+        raw.extend(
+            sim.particles
+                .iter()
+                .flat_map(|particle| (particle.stable_id as u64).to_le_bytes()),
+        );
+        let columns: [fn(&Particle) -> Float; 3] = [
+            |particle| particle.pos_x,
+            |particle| particle.pos_y,
+            |particle| particle.theta,
+        ];
+        for value in columns {
+            raw.extend(
+                sim.particles
+                    .iter()
+                    .flat_map(|particle| (value(particle) as f64).to_le_bytes()),
+            );
+        }
+
+        let compressed = zstd::bulk::compress(&shuffle_bytes(&raw), self.level)
+            .context("could not compress frame")?;
+
+        self.writer
+            .write_all(&(compressed.len() as u64).to_le_bytes())
+            .and_then(|_| self.writer.write_all(&compressed))
+            .context("could not write compressed frame")
+    }
+
+    /// Flush and hand back the underlying writer
+    pub fn into_inner(mut self) -> anyhow::Result<W> {
+        self.writer
+            .flush()
+            .context("could not flush compressed trajectory")?;
+
+        Ok(self.writer)
+    }
+}
+
+/// Reads a compressed trajectory back, frame by frame in any order
+///
+/// # Notes
+/// Opening the file scans the frame lengths (without decompressing anything) to index where each
+/// frame starts. A final frame cut short, e.g. by a crash while writing, is left out of the index.
+/// Frames read back number their `step` by their position in the file.
+pub struct TrajectoryReader<R: Read + Seek> {
+    reader: R,
+    header: CompressedTrajectoryHeader,
+
+    /// Where each frame's compressed block starts, and how long it is
+    frames: Vec<(u64, u64)>,
+}
+
+impl<R: Read + Seek> TrajectoryReader<R> {
+    /// Read the header and index the frames
+    pub fn new(mut reader: R) -> anyhow::Result<Self> {
+        reader
+            .seek(SeekFrom::Start(0))
+            .context("could not seek in compressed trajectory")?;
+        let header = CompressedTrajectoryHeader::read_from(&mut reader)?;
+
+        let file_len = reader
+            .seek(SeekFrom::End(0))
+            .context("could not seek in compressed trajectory")?;
+
+        let mut frames = Vec::new();
+        let mut position = HEADER_LEN;
+        while position + 8 <= file_len {
+            reader
+                .seek(SeekFrom::Start(position))
+                .context("could not seek in compressed trajectory")?;
+            let mut length = [0; 8];
+            reader
+                .read_exact(&mut length)
+                .context("could not read compressed frame length")?;
+            let length = u64::from_le_bytes(length);
+
+            let start = position + 8;
+            if length > file_len - start {
+                break;
+            }
+
+            frames.push((start, length));
+            position = start + length;
+        }
+
+        Ok(Self {
+            reader,
+            header,
+            frames,
+        })
+    }
+
+    /// The simulation parameters the trajectory was written with
+    pub fn header(&self) -> &CompressedTrajectoryHeader {
+        &self.header
+    }
+
+    /// Get the number of complete frames
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Check whether the trajectory holds no complete frames
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Decompress the `idx`th frame
+    pub fn frame(&mut self, idx: usize) -> anyhow::Result<TrajectoryFrame> {
+        let Some(&(start, length)) = self.frames.get(idx) else {
+            bail!(
+                "frame `{}` is out of range for a trajectory of `{}` frames",
+                idx,
+                self.frames.len()
+            );
+        };
+
+        let mut compressed = vec![0; length as usize];
+        self.reader
+            .seek(SeekFrom::Start(start))
+            .and_then(|_| self.reader.read_exact(&mut compressed))
+            .with_context(|| format!("could not read frame `{idx}`"))?;
+
+        let raw = zstd::decode_all(compressed.as_slice())
+            .with_context(|| format!("could not decompress frame `{idx}`"))?;
+        let raw = unshuffle_bytes(&raw);
+
+        decode_frame(&raw, idx).with_context(|| format!("frame `{idx}` is corrupt"))
+    }
+
+    /// Decompress every frame into a trajectory
+    pub fn read_trajectory(&mut self) -> anyhow::Result<Trajectory> {
+        let frames = (0..self.len())
+            .map(|idx| self.frame(idx))
+            .collect::<anyhow::Result<_>>()?;
+
+        Ok(Trajectory { frames })
+    }
+}
+
+/// Group the `n`th byte of every 8-byte word together, so the slowly varying high bytes (signs,
+/// exponents, leading digits) sit next to each other and compress far better
+fn shuffle_bytes(raw: &[u8]) -> Vec<u8> {
+    let num_words = raw.len() / 8;
+    let mut shuffled = vec![0; num_words * 8];
+
+    for (word_idx, word) in raw.chunks_exact(8).enumerate() {
+        for (byte_idx, &byte) in word.iter().enumerate() {
+            shuffled[byte_idx * num_words + word_idx] = byte;
+        }
+    }
+
+    shuffled
+}
+
+/// Undo [`shuffle_bytes`]
+fn unshuffle_bytes(shuffled: &[u8]) -> Vec<u8> {
+    let num_words = shuffled.len() / 8;
+    let mut raw = vec![0; num_words * 8];
+
+    for (byte_idx, plane) in shuffled.chunks_exact(num_words.max(1)).take(8).enumerate() {
+        for (word_idx, &byte) in plane.iter().enumerate() {
+            raw[word_idx * 8 + byte_idx] = byte;
+        }
+    }
+
+    raw
+}
+
+/// Unpack a decompressed frame
+fn decode_frame(raw: &[u8], step: usize) -> anyhow::Result<TrajectoryFrame> {
+    if raw.len() < 16 {
+        bail!("frame is only `{}` bytes long", raw.len());
+    }
+
+    let mut words = raw.chunks_exact(8).map(|word| {
+        let mut bytes = [0; 8];
+        bytes.copy_from_slice(word);
+        bytes
+    });
+
+    let time = f64::from_le_bytes(words.next().unwrap_or_default()) as Float;
+    let num_particles = u64::from_le_bytes(words.next().unwrap_or_default());
+    let expected_len = num_particles
+        .checked_mul(32)
+        .and_then(|len| len.checked_add(16));
+    if expected_len != Some(raw.len() as u64) {
+        bail!(
+            "frame claims `{}` particles but is `{}` bytes long",
+            num_particles,
+            raw.len()
+        );
+    }
+    let num_particles = num_particles as usize;
+
+    let id = words
+        .by_ref()
+        .take(num_particles)
+        .map(|word| u64::from_le_bytes(word) as usize)
+        .collect();
+    let mut column = || -> Vec<Float> {
+        words
+            .by_ref()
+            .take(num_particles)
+            .map(|word| f64::from_le_bytes(word) as Float)
+            .collect()
+    };
+    let x = column();
+    let y = column();
+    let theta = column();
+
+    Ok(TrajectoryFrame {
+        step,
+        time: AbsoluteTime(time),
+        id,
+        x,
+        y,
+        theta,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    #[test]
+    fn frames_read_back_exactly_in_any_order() {
+        let mut sim = Simulation::new(
+            4,
+            DomainBoundaryLength(5.0),
+            Noise(0.1),
+            Speed(0.1),
+            RelativeTime(1.0),
+            ParticleDistanceThreshold(1.0),
+        )
+        .unwrap();
+
+        let mut writer = CompressedTrajectoryWriter::new(Cursor::new(Vec::new()), &sim, 3).unwrap();
+        let mut expected = Vec::new();
+        for _ in 0..3 {
+            writer.write_frame(&sim).unwrap();
+            expected.push(
+                sim.particles
+                    .iter()
+                    .map(|p| (p.pos_x, p.pos_y, p.theta))
+                    .collect::<Vec<_>>(),
+            );
+            sim.run_for(1).unwrap();
+        }
+        let mut bytes = writer.into_inner().unwrap().into_inner();
+
+        let mut reader = TrajectoryReader::new(Cursor::new(bytes.clone())).unwrap();
+        assert_eq!(reader.len(), 3);
+        assert_eq!(reader.header().noise.0, 0.1);
+
+        let frame = reader.frame(2).unwrap();
+        assert_eq!((frame.step, frame.time.0), (2, 2.0));
+        let particles: Vec<_> = (0..frame.len())
+            .map(|idx| (frame.x[idx], frame.y[idx], frame.theta[idx]))
+            .collect();
+        assert_eq!(particles, expected[2]);
+        assert_eq!(reader.read_trajectory().unwrap().times(), [0.0, 1.0, 2.0]);
+        assert!(reader.frame(3).is_err());
+
+        // A frame cut short by a crash is left out
+        bytes.truncate(bytes.len() - 1);
+        assert_eq!(TrajectoryReader::new(Cursor::new(bytes)).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn shuffling_bytes_round_trips() {
+        let raw: Vec<u8> = (0..24).collect();
+        let shuffled = shuffle_bytes(&raw);
+        assert_eq!(&shuffled[..3], &[0, 8, 16]);
+        assert_eq!(unshuffle_bytes(&shuffled), raw);
+    }
+}
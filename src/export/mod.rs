@@ -0,0 +1,21 @@
+#[cfg(feature = "arrow")]
+mod arrow;
+#[cfg(feature = "zstd")]
+mod compressed;
+mod csv;
+#[cfg(feature = "parquet")]
+mod parquet;
+mod quantized;
+mod vtk;
+mod xyz;
+
+#[cfg(feature = "arrow")]
+pub use arrow::{ArrowFrameWriter, trajectory_schema};
+#[cfg(feature = "zstd")]
+pub use compressed::{CompressedTrajectoryHeader, CompressedTrajectoryWriter, TrajectoryReader};
+pub use csv::CsvTrajectoryWriter;
+#[cfg(feature = "parquet")]
+pub use parquet::ParquetTrajectoryWriter;
+pub use quantized::{QuantizedFrame, QuantizedTrajectoryWriter, read_quantized_trajectory};
+pub use vtk::VtkSeriesWriter;
+pub use xyz::XyzTrajectoryWriter;
@@ -0,0 +1,93 @@
+use std::io::Write;
+
+use anyhow::Context;
+use parquet::{arrow::ArrowWriter, basic::Compression, file::properties::WriterProperties};
+
+use crate::{export::arrow::trajectory_schema, simulation::Simulation};
+
+/// Streams the long-format trajectory table, one `t, id, x, y, theta` row per particle per
+/// frame, to a Parquet file with one row group per frame
+///
+/// # Notes
+/// `id` is the particle's stable ID. Floats are always stored as 64-bit, and columns are Snappy
+/// compressed. The file is only readable once [`ParquetTrajectoryWriter::finish`] writes its
+/// footer.
+pub struct ParquetTrajectoryWriter<W: Write + Send> {
+    writer: ArrowWriter<W>,
+}
+
+impl<W: Write + Send> ParquetTrajectoryWriter<W> {
+    /// Start a new table
+    pub fn new(writer: W) -> anyhow::Result<Self> {
+        let properties = WriterProperties::builder()
+            .set_compression(Compression::SNAPPY)
+            .build();
+        let writer = ArrowWriter::try_new(writer, trajectory_schema(), Some(properties))
+            .context("could not start Parquet trajectory")?;
+
+        Ok(Self { writer })
+    }
+
+    /// Append the current state of a simulation as its own row group
+    pub fn write_frame(&mut self, sim: &Simulation) -> anyhow::Result<()> {
+        self.writer
+            .write(&sim.to_record_batch()?)
+            .and_then(|_| self.writer.flush())
+            .context("could not write Parquet trajectory frame")
+    }
+
+    /// Write the footer and hand back the underlying writer
+    pub fn finish(self) -> anyhow::Result<W> {
+        self.writer
+            .into_inner()
+            .context("could not finish Parquet trajectory")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+    use super::*;
+    use crate::{
+        export::trajectory_schema,
+        types::{DomainBoundaryLength, Noise, ParticleDistanceThreshold, RelativeTime, Speed},
+    };
+
+    #[test]
+    fn frames_read_back_as_one_row_group_each() {
+        let path = std::env::temp_dir().join(format!("trajectory-{}.parquet", std::process::id()));
+        let mut sim = Simulation::new(
+            3,
+            DomainBoundaryLength(5.0),
+            Noise(0.1),
+            Speed(0.1),
+            RelativeTime(1.0),
+            ParticleDistanceThreshold(1.0),
+        )
+        .unwrap();
+
+        let mut writer = ParquetTrajectoryWriter::new(File::create(&path).unwrap()).unwrap();
+        writer.write_frame(&sim).unwrap();
+        sim.run_for(1).unwrap();
+        writer.write_frame(&sim).unwrap();
+        writer.finish().unwrap();
+
+        let reader = ParquetRecordBatchReaderBuilder::try_new(File::open(&path).unwrap()).unwrap();
+        assert_eq!(reader.metadata().num_row_groups(), 2);
+        assert_eq!(reader.schema(), &trajectory_schema());
+
+        // The second row group is exactly the second frame
+        let batches: Vec<_> = reader
+            .with_row_groups(vec![1])
+            .build()
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(batches, [sim.to_record_batch().unwrap()]);
+
+        let _ = std::fs::remove_file(&path);
+    }
+}
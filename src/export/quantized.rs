@@ -0,0 +1,274 @@
+use std::io::{ErrorKind, Read, Write};
+
+use anyhow::{Context, bail};
+
+use crate::{
+    simulation::Simulation,
+    types::{AbsoluteTime, DomainBoundaryLength, Float, PI},
+};
+
+/// Identifies a quantized trajectory stream
+const MAGIC: &[u8; 4] = b"PIPQ";
+
+const FORMAT_VERSION: u8 = 1;
+
+/// Number of distinct quantized levels for a 16-bit value
+const NUM_LEVELS: Float = 65536.0;
+
+/// A single frame with positions stored as 16-bit fixed point relative to the domain, and headings
+/// as 16-bit quantized angles
+///
+/// # Notes
+/// This is ~4x smaller than full 64-bit precision, at a resolution of L/65536 in position and
+/// 2π/65536 in heading, which is plenty for plotting and most post-processing.
+#[derive(Clone, Debug)]
+pub struct QuantizedFrame {
+    pub time: AbsoluteTime,
+    pub boundary_side_length: DomainBoundaryLength,
+    x: Vec<u16>,
+    y: Vec<u16>,
+    theta: Vec<u16>,
+}
+
+impl QuantizedFrame {
+    /// Quantize the current state of a simulation
+    pub fn from_simulation(sim: &Simulation) -> Self {
+        let boundary_side_length = sim.params.boundary_side_length;
+
+        let x = sim
+            .particles
+            .iter()
+            .map(|particle| quantize(particle.pos_x, boundary_side_length.0))
+            .collect();
+
+        let y = sim
+            .particles
+            .iter()
+            .map(|particle| quantize(particle.pos_y, boundary_side_length.0))
+            .collect();
+
+        let theta = sim
+            .particles
+            .iter()
+            .map(|particle| quantize(particle.theta, 2.0 * PI))
+            .collect();
+
+        Self {
+            time: sim.current_time,
+            boundary_side_length,
+            x,
+            y,
+            theta,
+        }
+    }
+
+    /// Get the number of particles in the frame
+    pub fn len(&self) -> usize {
+        self.x.len()
+    }
+
+    /// Check whether the frame holds no particles
+    pub fn is_empty(&self) -> bool {
+        self.x.is_empty()
+    }
+
+    /// Reconstruct x-positions
+    pub fn x(&self) -> Vec<Float> {
+        dequantize_all(&self.x, self.boundary_side_length.0)
+    }
+
+    /// Reconstruct y-positions
+    pub fn y(&self) -> Vec<Float> {
+        dequantize_all(&self.y, self.boundary_side_length.0)
+    }
+
+    /// Reconstruct headings, in [0, 2π)
+    pub fn theta(&self) -> Vec<Float> {
+        dequantize_all(&self.theta, 2.0 * PI)
+    }
+
+    /// Encode as little-endian bytes
+    // Note: `Float` may be 32-bit, but the format always stores 64-bit floats
+    #[allow(clippy::unnecessary_cast)]
+    fn write_to(&self, writer: &mut impl Write) -> std::io::Result<()> {
+        writer.write_all(&(self.time.0 as f64).to_le_bytes())?;
+        writer.write_all(&(self.boundary_side_length.0 as f64).to_le_bytes())?;
+        writer.write_all(&(self.len() as u64).to_le_bytes())?;
+
+        for values in [&self.x, &self.y, &self.theta] {
+            let bytes: Vec<u8> = values
+                .iter()
+                .flat_map(|value| value.to_le_bytes())
+                .collect();
+            writer.write_all(&bytes)?;
+        }
+
+        Ok(())
+    }
+
+    /// Decode from little-endian bytes, returning `None` at a clean end of stream
+    fn read_from(reader: &mut impl Read) -> anyhow::Result<Option<Self>> {
+        let mut time = [0; 8];
+        match reader.read_exact(&mut time) {
+            Err(error) if error.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+            result => result.context("could not read frame time")?,
+        }
+
+        let boundary_side_length = read_f64(reader).context("could not read frame domain size")?;
+        let num_particles = read_u64(reader).context("could not read frame particle count")?;
+        let num_particles = usize::try_from(num_particles)
+            .context("frame particle count does not fit in memory")?;
+
+        let mut read_values = || -> anyhow::Result<Vec<u16>> {
+            let mut bytes = vec![0; 2 * num_particles];
+            reader
+                .read_exact(&mut bytes)
+                .context("truncated frame data")?;
+
+            Ok(bytes
+                .chunks_exact(2)
+                .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+                .collect())
+        };
+
+        let x = read_values()?;
+        let y = read_values()?;
+        let theta = read_values()?;
+
+        Ok(Some(Self {
+            time: AbsoluteTime(f64::from_le_bytes(time) as Float),
+            boundary_side_length: DomainBoundaryLength(boundary_side_length as Float),
+            x,
+            y,
+            theta,
+        }))
+    }
+}
+
+/// Streams quantized frames to any writer (e.g. a buffered file)
+pub struct QuantizedTrajectoryWriter<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> QuantizedTrajectoryWriter<W> {
+    /// Start a new trajectory, writing the stream header
+    pub fn new(mut writer: W) -> anyhow::Result<Self> {
+        writer
+            .write_all(MAGIC)
+            .and_then(|_| writer.write_all(&[FORMAT_VERSION]))
+            .context("could not write quantized trajectory header")?;
+
+        Ok(Self { writer })
+    }
+
+    /// Quantize and append the current state of a simulation
+    pub fn write_frame(&mut self, sim: &Simulation) -> anyhow::Result<()> {
+        self.write_quantized_frame(&QuantizedFrame::from_simulation(sim))
+    }
+
+    /// Append an already quantized frame, e.g. one held back until it was known to be wanted
+    pub fn write_quantized_frame(&mut self, frame: &QuantizedFrame) -> anyhow::Result<()> {
+        frame
+            .write_to(&mut self.writer)
+            .context("could not write quantized frame")
+    }
+
+    /// Flush and hand back the underlying writer
+    pub fn into_inner(mut self) -> anyhow::Result<W> {
+        self.writer
+            .flush()
+            .context("could not flush quantized trajectory")?;
+
+        Ok(self.writer)
+    }
+}
+
+/// Read back every frame of a quantized trajectory
+pub fn read_quantized_trajectory(mut reader: impl Read) -> anyhow::Result<Vec<QuantizedFrame>> {
+    let mut magic = [0; 4];
+    reader
+        .read_exact(&mut magic)
+        .context("could not read quantized trajectory header")?;
+    if &magic != MAGIC {
+        bail!("not a quantized trajectory");
+    }
+
+    let mut version = [0; 1];
+    reader
+        .read_exact(&mut version)
+        .context("could not read quantized trajectory version")?;
+    if version[0] != FORMAT_VERSION {
+        bail!(
+            "unsupported quantized trajectory version `{}` (expected `{}`)",
+            version[0],
+            FORMAT_VERSION
+        );
+    }
+
+    std::iter::from_fn(|| QuantizedFrame::read_from(&mut reader).transpose()).collect()
+}
+
+/// Map a value in [0, period) onto 16 bits, wrapping the top edge back to 0
+#[inline]
+fn quantize(value: Float, period: Float) -> u16 {
+    let level = (value.rem_euclid(period) / period * NUM_LEVELS).round();
+
+    (level as u32 % NUM_LEVELS as u32) as u16
+}
+
+#[inline]
+fn dequantize_all(values: &[u16], period: Float) -> Vec<Float> {
+    values
+        .iter()
+        .map(|&value| value as Float / NUM_LEVELS * period)
+        .collect()
+}
+
+fn read_f64(reader: &mut impl Read) -> std::io::Result<f64> {
+    let mut bytes = [0; 8];
+    reader.read_exact(&mut bytes)?;
+
+    Ok(f64::from_le_bytes(bytes))
+}
+
+fn read_u64(reader: &mut impl Read) -> std::io::Result<u64> {
+    let mut bytes = [0; 8];
+    reader.read_exact(&mut bytes)?;
+
+    Ok(u64::from_le_bytes(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Noise, ParticleDistanceThreshold, RelativeTime, Speed};
+
+    #[test]
+    fn round_trip_stays_within_quantization_resolution() {
+        let sim = Simulation::new(
+            30,
+            DomainBoundaryLength(5.0),
+            Noise(0.5),
+            Speed(0.1),
+            RelativeTime(1.0),
+            ParticleDistanceThreshold(1.0),
+        )
+        .unwrap();
+
+        let mut writer = QuantizedTrajectoryWriter::new(Vec::new()).unwrap();
+        writer.write_frame(&sim).unwrap();
+        writer.write_frame(&sim).unwrap();
+        let bytes = writer.into_inner().unwrap();
+
+        let frames = read_quantized_trajectory(bytes.as_slice()).unwrap();
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].len(), 30);
+
+        let position_step = 5.0 / NUM_LEVELS;
+        for (x, particle) in frames[0].x().iter().zip(sim.particles.iter()) {
+            assert!((x - particle.pos_x).abs() <= position_step);
+        }
+
+        assert!(read_quantized_trajectory(&b"NOPE"[..]).is_err());
+    }
+}
@@ -0,0 +1,270 @@
+use std::{
+    fmt::Display,
+    fs::File,
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, anyhow};
+
+use crate::{simulation::SimulationData, types::Float};
+
+impl SimulationData {
+    /// Write the frame as a VTK XML PolyData (`.vtp`) file for ParaView
+    ///
+    /// # Notes
+    /// Each particle is a vertex at `(x, y, 0)` carrying its unit `heading` vector (the default
+    /// vectors, e.g. for glyphs), `theta`, `speed`, `phase`, `id`, `tag`, `leader`, and any named
+    /// scalars. The simulated `time` and the `instantaneous_order` are stored as field data.
+    pub fn write_vtk(&self, path: impl AsRef<Path>) -> anyhow::Result<()> {
+        let path = path.as_ref();
+        let file =
+            File::create(path).with_context(|| format!("could not create `{}`", path.display()))?;
+        let mut writer = BufWriter::new(file);
+
+        self.write_vtp(&mut writer)
+            .and_then(|_| writer.flush())
+            .with_context(|| format!("could not write VTK file `{}`", path.display()))
+    }
+
+    fn write_vtp(&self, writer: &mut impl Write) -> std::io::Result<()> {
+        let num_points = self.x.len();
+
+        writeln!(writer, r#"<?xml version="1.0"?>"#)?;
+        writeln!(
+            writer,
+            r#"<VTKFile type="PolyData" version="0.1" byte_order="LittleEndian">"#
+        )?;
+        writeln!(writer, "<PolyData>")?;
+
+        writeln!(writer, "<FieldData>")?;
+        for (name, value) in [
+            ("time", self.time),
+            ("instantaneous_order", self.instantaneous_order),
+        ] {
+            writeln!(
+                writer,
+                r#"<DataArray type="Float64" Name="{name}" NumberOfTuples="1" format="ascii">{value}</DataArray>"#
+            )?;
+        }
+        writeln!(writer, "</FieldData>")?;
+
+        writeln!(
+            writer,
+            r#"<Piece NumberOfPoints="{num_points}" NumberOfVerts="{num_points}" NumberOfLines="0" NumberOfStrips="0" NumberOfPolys="0">"#
+        )?;
+
+        writeln!(writer, r#"<PointData Vectors="heading" Scalars="theta">"#)?;
+        let heading = self.u.iter().zip(&self.v).flat_map(|(&u, &v)| [u, v, 0.0]);
+        write_data_array(writer, "Float64", "heading", 3, heading)?;
+        write_data_array(writer, "Float64", "theta", 1, &self.theta)?;
+        write_data_array(writer, "Float64", "speed", 1, &self.speed)?;
+        write_data_array(writer, "Float64", "phase", 1, &self.phase)?;
+        write_data_array(writer, "Int64", "id", 1, &self.id)?;
+        write_data_array(writer, "Int64", "tag", 1, &self.tag)?;
+        let leader = self.leader.iter().map(|&leader| u8::from(leader));
+        write_data_array(writer, "UInt8", "leader", 1, leader)?;
+        for (name, values) in &self.scalars {
+            write_data_array(writer, "Float64", &escape_xml(name), 1, values)?;
+        }
+        writeln!(writer, "</PointData>")?;
+
+        writeln!(writer, "<Points>")?;
+        let points = self.x.iter().zip(&self.y).flat_map(|(&x, &y)| [x, y, 0.0]);
+        write_data_array(writer, "Float64", "points", 3, points)?;
+        writeln!(writer, "</Points>")?;
+
+        // One single-point vertex cell per particle, so ParaView renders them as points
+        writeln!(writer, "<Verts>")?;
+        write_data_array(writer, "Int64", "connectivity", 1, 0..num_points)?;
+        write_data_array(writer, "Int64", "offsets", 1, 1..=num_points)?;
+        writeln!(writer, "</Verts>")?;
+
+        writeln!(writer, "</Piece>")?;
+        writeln!(writer, "</PolyData>")?;
+        writeln!(writer, "</VTKFile>")
+    }
+}
+
+/// Writes a series of frames as `.vtp` files plus a ParaView `.pvd` collection indexing them by
+/// time, so the series plays back as an animation
+///
+/// # Notes
+/// Frames go next to the collection file, named after it, e.g. `run.pvd` indexes
+/// `run_000000.vtp`, `run_000001.vtp`, .... The collection is only written by
+/// [`VtkSeriesWriter::finish`].
+#[derive(Clone, Debug)]
+pub struct VtkSeriesWriter {
+    collection_path: PathBuf,
+    frame_prefix: String,
+    frames: Vec<(Float, String)>,
+}
+
+impl VtkSeriesWriter {
+    /// Start a series indexed by the `.pvd` file at `collection_path`
+    pub fn new(collection_path: impl Into<PathBuf>) -> anyhow::Result<Self> {
+        let collection_path = collection_path.into();
+        let frame_prefix = collection_path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .ok_or_else(|| {
+                anyhow!(
+                    "`{}` has no usable file name for a VTK series",
+                    collection_path.display()
+                )
+            })?
+            .to_string();
+
+        Ok(Self {
+            collection_path,
+            frame_prefix,
+            frames: Vec::new(),
+        })
+    }
+
+    /// Get the number of frames written so far
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Check whether no frames were written yet
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Write the next frame's `.vtp` file
+    pub fn write_frame(&mut self, data: &SimulationData) -> anyhow::Result<()> {
+        let file_name = format!("{}_{:06}.vtp", self.frame_prefix, self.frames.len());
+        data.write_vtk(self.collection_path.with_file_name(&file_name))?;
+        self.frames.push((data.time, file_name));
+
+        Ok(())
+    }
+
+    /// Write the `.pvd` collection indexing every frame written
+    pub fn finish(self) -> anyhow::Result<()> {
+        let path = &self.collection_path;
+        let file =
+            File::create(path).with_context(|| format!("could not create `{}`", path.display()))?;
+        let mut writer = BufWriter::new(file);
+
+        self.write_pvd(&mut writer)
+            .and_then(|_| writer.flush())
+            .with_context(|| format!("could not write VTK collection `{}`", path.display()))
+    }
+
+    fn write_pvd(&self, writer: &mut impl Write) -> std::io::Result<()> {
+        writeln!(writer, r#"<?xml version="1.0"?>"#)?;
+        writeln!(
+            writer,
+            r#"<VTKFile type="Collection" version="0.1" byte_order="LittleEndian">"#
+        )?;
+        writeln!(writer, "<Collection>")?;
+        for (time, file_name) in &self.frames {
+            writeln!(
+                writer,
+                r#"<DataSet timestep="{}" group="" part="0" file="{}"/>"#,
+                time,
+                escape_xml(file_name)
+            )?;
+        }
+        writeln!(writer, "</Collection>")?;
+        writeln!(writer, "</VTKFile>")
+    }
+}
+
+/// Write one inline ASCII `DataArray` element
+// Note: `Float` may be 32-bit, but it always reads back fine as `Float64`
+fn write_data_array<T: Display>(
+    writer: &mut impl Write,
+    kind: &str,
+    name: &str,
+    num_components: usize,
+    values: impl IntoIterator<Item = T>,
+) -> std::io::Result<()> {
+    write!(
+        writer,
+        r#"<DataArray type="{kind}" Name="{name}" NumberOfComponents="{num_components}" format="ascii">"#
+    )?;
+    for (idx, value) in values.into_iter().enumerate() {
+        if idx > 0 {
+            write!(writer, " ")?;
+        }
+        write!(writer, "{value}")?;
+    }
+    writeln!(writer, "</DataArray>")
+}
+
+/// Escape text for use in an XML attribute
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        simulation::Simulation,
+        types::{DomainBoundaryLength, Noise, ParticleDistanceThreshold, RelativeTime, Speed},
+    };
+
+    fn data() -> SimulationData {
+        let mut sim = Simulation::with_particles(
+            &[(1.0, 1.5), (2.5, 3.0)],
+            &[0.0, 0.5],
+            DomainBoundaryLength(5.0),
+            Noise(0.0),
+            Speed(0.0),
+            RelativeTime(1.0),
+            ParticleDistanceThreshold(1.0),
+        )
+        .unwrap();
+        sim.set_scalar("a<b", &[1.0, 2.0]).unwrap();
+
+        SimulationData::from(&sim)
+    }
+
+    #[test]
+    fn frames_hold_a_vertex_per_particle() {
+        let mut vtp = Vec::new();
+        data().write_vtp(&mut vtp).unwrap();
+        let vtp = String::from_utf8(vtp).unwrap();
+
+        assert!(vtp.contains(r#"NumberOfPoints="2" NumberOfVerts="2""#));
+        assert!(
+            vtp.contains(r#"Name="points" NumberOfComponents="3" format="ascii">1 1.5 0 2.5 3 0<"#)
+        );
+        assert!(vtp.contains(r#"Name="connectivity" NumberOfComponents="1" format="ascii">0 1<"#));
+        assert!(vtp.contains(r#"Name="offsets" NumberOfComponents="1" format="ascii">1 2<"#));
+        assert!(vtp.contains(r#"Name="a&lt;b" NumberOfComponents="1" format="ascii">1 2<"#));
+    }
+
+    #[test]
+    fn series_index_their_frames_by_time() {
+        let dir = std::env::temp_dir().join(format!("vtk-series-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let mut series = VtkSeriesWriter::new(dir.join("run.pvd")).unwrap();
+        series.write_frame(&data()).unwrap();
+        series
+            .write_frame(&SimulationData {
+                time: 2.5,
+                ..data()
+            })
+            .unwrap();
+        assert_eq!(series.len(), 2);
+        series.finish().unwrap();
+
+        let pvd = std::fs::read_to_string(dir.join("run.pvd")).unwrap();
+        assert!(pvd.contains(r#"<DataSet timestep="0" group="" part="0" file="run_000000.vtp"/>"#));
+        assert!(
+            pvd.contains(r#"<DataSet timestep="2.5" group="" part="0" file="run_000001.vtp"/>"#)
+        );
+        assert!(dir.join("run_000001.vtp").exists());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,107 @@
+use std::io::Write;
+
+use anyhow::Context;
+
+use crate::simulation::Simulation;
+
+/// Streams frames in the extended XYZ format, one after another in a single file, for molecular
+/// visualization tools such as OVITO and VMD
+///
+/// # Notes
+/// Each particle is an `X` atom with its position `pos` (at z = 0), unit heading `orientation`,
+/// stable `id`, and `tag`. The comment line carries the periodic domain as the `Lattice`, with
+/// the z axis non-periodic, and the simulated `Time`.
+pub struct XyzTrajectoryWriter<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> XyzTrajectoryWriter<W> {
+    /// Start a new trajectory
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+
+    /// Append the current state of a simulation as the next frame
+    pub fn write_frame(&mut self, sim: &Simulation) -> anyhow::Result<()> {
+        self.write_xyz_frame(sim)
+            .context("could not write extended XYZ frame")
+    }
+
+    fn write_xyz_frame(&mut self, sim: &Simulation) -> std::io::Result<()> {
+        let length = sim.params.boundary_side_length.0;
+
+        writeln!(self.writer, "{}", sim.particles.len())?;
+        writeln!(
+            self.writer,
+            r#"Lattice="{length} 0 0 0 {length} 0 0 0 1" Properties=species:S:1:pos:R:3:orientation:R:3:id:I:1:tag:I:1 Time={} pbc="T T F""#,
+            sim.current_time.0
+        )?;
+
+        for particle in sim.particles.iter() {
+            writeln!(
+                self.writer,
+                "X {} {} 0 {} {} 0 {} {}",
+                particle.pos_x,
+                particle.pos_y,
+                particle.theta.cos(),
+                particle.theta.sin(),
+                particle.stable_id,
+                particle.tag
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Flush and hand back the underlying writer
+    pub fn into_inner(mut self) -> anyhow::Result<W> {
+        self.writer
+            .flush()
+            .context("could not flush extended XYZ trajectory")?;
+
+        Ok(self.writer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{
+        DomainBoundaryLength, Float, Noise, ParticleDistanceThreshold, RelativeTime, Speed,
+    };
+
+    #[test]
+    fn frames_carry_the_domain_time_and_particles() {
+        let sim = Simulation::with_particles(
+            &[(1.0, 1.5), (2.5, 3.0)],
+            &[0.0, 0.5],
+            DomainBoundaryLength(5.0),
+            Noise(0.0),
+            Speed(0.0),
+            RelativeTime(1.0),
+            ParticleDistanceThreshold(1.0),
+        )
+        .unwrap();
+
+        let mut writer = XyzTrajectoryWriter::new(Vec::new());
+        writer.write_frame(&sim).unwrap();
+        writer.write_frame(&sim).unwrap();
+        let xyz = String::from_utf8(writer.into_inner().unwrap()).unwrap();
+        let lines: Vec<&str> = xyz.lines().collect();
+
+        assert_eq!(lines.len(), 8);
+        assert_eq!(lines[0], "2");
+        assert!(lines[1].starts_with(r#"Lattice="5 0 0 0 5 0 0 0 1""#));
+        assert!(lines[1].ends_with(r#"Time=0 pbc="T T F""#));
+        assert_eq!(lines[2], "X 1 1.5 0 1 0 0 0 0");
+        assert_eq!(
+            lines[3],
+            format!(
+                "X 2.5 3 0 {} {} 0 1 0",
+                (0.5 as Float).cos(),
+                (0.5 as Float).sin()
+            )
+        );
+        assert_eq!(lines[4], "2");
+    }
+}
@@ -0,0 +1,361 @@
+use std::{fmt::Debug, sync::Arc};
+
+use crate::{
+    error::{SimulationError, invalid_parameter},
+    types::{DomainBoundaryLength, Float, PI},
+};
+
+/// Points across the domain a function field is sampled at when it's checked, since it can't be
+/// checked everywhere
+const FUNCTION_CHECK_POINTS_PER_SIDE: usize = 32;
+
+/// A non-negative quantity that varies over the domain, such as a noise amplitude (to send a
+/// flock through noisy regions) or a speed (to slow particles down in rough terrain)
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ScalarField {
+    /// Value as a function of position `(x, y)`, which fails to serialize. Setters only check it
+    /// at the centers of a 32 by 32 grid of cells over the domain.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    Function(Arc<dyn Fn(Float, Float) -> Float + Send + Sync>),
+
+    /// Value per cell of a square grid laid over the domain, stored row by row from
+    /// `y = 0` upwards
+    Grid {
+        cells_per_side: usize,
+        values: Arc<[Float]>,
+    },
+}
+
+impl ScalarField {
+    /// Create a field from a function of position `(x, y)`
+    pub fn function(field: impl Fn(Float, Float) -> Float + Send + Sync + 'static) -> Self {
+        Self::Function(Arc::new(field))
+    }
+
+    /// Create a gridded field from rows of cells, starting at `y = 0`
+    pub fn grid(rows: Vec<Vec<Float>>) -> Result<Self, SimulationError> {
+        let cells_per_side = rows.len();
+
+        if cells_per_side == 0 {
+            invalid_parameter!("a field grid needs at least one cell");
+        }
+
+        if let Some(row) = rows.iter().position(|row| row.len() != cells_per_side) {
+            invalid_parameter!(
+                "field grid must be square, but row `{}` has `{}` cells instead of `{}`",
+                row,
+                rows[row].len(),
+                cells_per_side
+            );
+        }
+
+        let values: Arc<[Float]> = rows.into_iter().flatten().collect();
+        check_values(values.iter().copied())?;
+
+        Ok(Self::Grid {
+            cells_per_side,
+            values,
+        })
+    }
+
+    /// Check the field only holds non-negative, finite values over a domain of the given size
+    ///
+    /// # Notes
+    /// A function field is sampled at the centers of a 32 by 32 grid of cells over the domain, so
+    /// it can still misbehave between them.
+    pub(crate) fn validate(
+        &self,
+        boundary_side_length: DomainBoundaryLength,
+    ) -> Result<(), SimulationError> {
+        match self {
+            Self::Function(_) => {
+                let cell_size = boundary_side_length.0 / FUNCTION_CHECK_POINTS_PER_SIDE as Float;
+                let center = |idx: usize| (idx as Float + 0.5) * cell_size;
+
+                check_values((0..FUNCTION_CHECK_POINTS_PER_SIDE.pow(2)).map(|idx| {
+                    self.evaluate(
+                        center(idx % FUNCTION_CHECK_POINTS_PER_SIDE),
+                        center(idx / FUNCTION_CHECK_POINTS_PER_SIDE),
+                        boundary_side_length,
+                    )
+                }))
+            }
+            Self::Grid {
+                cells_per_side,
+                values,
+            } => {
+                if *cells_per_side == 0 || values.len() != cells_per_side.pow(2) {
+                    invalid_parameter!(
+                        "field grid must hold `{}` squared cells, got `{}`",
+                        cells_per_side,
+                        values.len()
+                    );
+                }
+
+                check_values(values.iter().copied())
+            }
+        }
+    }
+
+    /// Look up the value at a position inside the domain
+    #[inline]
+    pub(crate) fn evaluate(
+        &self,
+        pos_x: Float,
+        pos_y: Float,
+        boundary_side_length: DomainBoundaryLength,
+    ) -> Float {
+        match self {
+            Self::Function(field) => field(pos_x, pos_y),
+            Self::Grid {
+                cells_per_side,
+                values,
+            } => {
+                // Clamp so positions exactly on the far boundary land in the last cell
+                let cell = |pos: Float| {
+                    ((pos / boundary_side_length.0 * *cells_per_side as Float) as usize)
+                        .min(cells_per_side - 1)
+                };
+
+                values[cell(pos_y) * cells_per_side + cell(pos_x)]
+            }
+        }
+    }
+}
+
+/// Reject any negative or non-finite field value
+fn check_values(mut values: impl Iterator<Item = Float>) -> Result<(), SimulationError> {
+    if let Some(value) = values.find(|value| !value.is_finite() || *value < 0.0) {
+        invalid_parameter!(
+            "field values must be non-negative and finite, got `{}`",
+            value
+        );
+    }
+
+    Ok(())
+}
+
+impl Debug for ScalarField {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Function(_) => f.write_str("ScalarField::Function"),
+            Self::Grid { cells_per_side, .. } => f
+                .debug_struct("ScalarField::Grid")
+                .field("cells_per_side", cells_per_side)
+                .finish(),
+        }
+    }
+}
+
+/// A background flow that carries particles along, on top of their self-propulsion
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FlowField {
+    /// The same velocity `(u, v)` everywhere
+    Uniform { u: Float, v: Float },
+
+    /// Simple shear along x, u = rate * (y - L/2), centered so the domain's middle is at rest
+    ///
+    /// # Notes
+    /// The flow jumps across the y boundary, since the periodic wrap doesn't shift particles the
+    /// way Lees-Edwards boundaries would.
+    Shear { rate: Float },
+
+    /// A grid of counter-rotating vortices with one period across the domain, which respects the
+    /// periodic boundaries:
+    /// u = A sin(2πx/L) cos(2πy/L), v = -A cos(2πx/L) sin(2πy/L)
+    TaylorGreen { amplitude: Float },
+
+    /// Flow velocity `(u, v)` as a function of position `(x, y)`, which fails to serialize
+    #[cfg_attr(feature = "serde", serde(skip))]
+    Function(Arc<dyn Fn(Float, Float) -> (Float, Float) + Send + Sync>),
+}
+
+impl FlowField {
+    /// Create a flow from a function of position `(x, y)` returning the velocity `(u, v)`
+    pub fn function(flow: impl Fn(Float, Float) -> (Float, Float) + Send + Sync + 'static) -> Self {
+        Self::Function(Arc::new(flow))
+    }
+
+    /// Look up the flow velocity at a position inside the domain
+    #[inline]
+    pub(crate) fn evaluate(
+        &self,
+        pos_x: Float,
+        pos_y: Float,
+        boundary_side_length: DomainBoundaryLength,
+    ) -> (Float, Float) {
+        match self {
+            Self::Uniform { u, v } => (*u, *v),
+            Self::Shear { rate } => (rate * (pos_y - 0.5 * boundary_side_length.0), 0.0),
+            Self::TaylorGreen { amplitude } => {
+                let kx = 2.0 * PI * pos_x / boundary_side_length.0;
+                let ky = 2.0 * PI * pos_y / boundary_side_length.0;
+
+                (
+                    amplitude * kx.sin() * ky.cos(),
+                    -amplitude * kx.cos() * ky.sin(),
+                )
+            }
+            Self::Function(flow) => flow(pos_x, pos_y),
+        }
+    }
+}
+
+impl Debug for FlowField {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Uniform { u, v } => f
+                .debug_struct("FlowField::Uniform")
+                .field("u", u)
+                .field("v", v)
+                .finish(),
+            Self::Shear { rate } => f
+                .debug_struct("FlowField::Shear")
+                .field("rate", rate)
+                .finish(),
+            Self::TaylorGreen { amplitude } => f
+                .debug_struct("FlowField::TaylorGreen")
+                .field("amplitude", amplitude)
+                .finish(),
+            Self::Function(_) => f.write_str("FlowField::Function"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        simulation::Simulation,
+        types::{Noise, ParticleDistanceThreshold, RelativeTime, Speed},
+    };
+
+    fn sim() -> Simulation {
+        Simulation::new(
+            10,
+            DomainBoundaryLength(5.0),
+            Noise(0.1),
+            Speed(0.1),
+            RelativeTime(1.0),
+            ParticleDistanceThreshold(1.0),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn grid_rejects_negative_and_non_finite_cells() {
+        for bad in [-0.5, Float::NAN, Float::INFINITY] {
+            assert!(matches!(
+                ScalarField::grid(vec![vec![1.0, bad], vec![1.0, 1.0]]),
+                Err(SimulationError::InvalidParameter(_))
+            ));
+        }
+        assert!(matches!(
+            ScalarField::grid(vec![vec![1.0, 1.0]]),
+            Err(SimulationError::InvalidParameter(_))
+        ));
+        assert!(ScalarField::grid(vec![vec![0.0, 1.0], vec![2.0, 3.0]]).is_ok());
+    }
+
+    #[test]
+    fn field_setters_reject_bad_values() {
+        let negative_function = ScalarField::function(|x, _| 1.0 - x);
+        assert!(sim().with_noise_field(Some(negative_function)).is_err());
+
+        let nan_grid = ScalarField::Grid {
+            cells_per_side: 1,
+            values: Arc::from([Float::NAN]),
+        };
+        assert!(sim().with_speed_field(Some(nan_grid)).is_err());
+
+        let good_function = ScalarField::function(|x, y| x + y);
+        assert!(sim().with_speed_field(Some(good_function)).is_ok());
+        assert!(sim().with_noise_field(None).is_ok());
+    }
+
+    #[test]
+    fn grid_cells_count_rows_from_the_bottom() {
+        let field = ScalarField::grid(vec![vec![0.0, 1.0], vec![2.0, 3.0]]).unwrap();
+        let length = DomainBoundaryLength(4.0);
+
+        assert_eq!(field.evaluate(1.0, 1.0, length), 0.0);
+        assert_eq!(field.evaluate(3.0, 1.0, length), 1.0);
+        assert_eq!(field.evaluate(1.0, 3.0, length), 2.0);
+
+        // The far boundary falls in the last cell
+        assert_eq!(field.evaluate(4.0, 4.0, length), 3.0);
+    }
+
+    #[test]
+    fn noise_field_replaces_the_simulation_noise() {
+        let mut sim = Simulation::with_particles(
+            &[(1.0, 1.0), (1.2, 1.0)],
+            &[0.3, 1.1],
+            DomainBoundaryLength(5.0),
+            Noise(3.0),
+            Speed(0.01),
+            RelativeTime(1.0),
+            ParticleDistanceThreshold(1.0),
+        )
+        .unwrap()
+        .with_noise_field(Some(ScalarField::grid(vec![vec![0.0]]).unwrap()))
+        .unwrap();
+        sim.run_for(1).unwrap();
+
+        // Without noise, each particle takes the other's heading
+        let thetas: Vec<Float> = sim.particles.iter().map(|p| p.theta).collect();
+        assert!((thetas[0] - 1.1).abs() < 1e-5 && (thetas[1] - 0.3).abs() < 1e-5);
+    }
+
+    fn single_particle(speed: Speed) -> Simulation {
+        Simulation::with_particles(
+            &[(2.0, 2.0)],
+            &[0.0],
+            DomainBoundaryLength(5.0),
+            Noise(0.0),
+            speed,
+            RelativeTime(1.0),
+            ParticleDistanceThreshold(1.0),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn speed_field_replaces_the_simulation_speed() {
+        let mut sim = single_particle(Speed(0.1))
+            .with_speed_field(Some(ScalarField::function(|x, _| 0.1 * x)))
+            .unwrap();
+        sim.run_for(1).unwrap();
+
+        // Heading along x at the field's 0.2 from x = 2
+        let particle = sim.particles.iter().next().unwrap();
+        assert!((particle.pos_x - 2.2).abs() < 1e-5);
+        assert!((particle.pos_y - 2.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn flows_carry_particles_along() {
+        let length = DomainBoundaryLength(4.0);
+
+        let (u, v) = FlowField::Shear { rate: 0.5 }.evaluate(1.0, 3.0, length);
+        assert_eq!((u, v), (0.5, 0.0));
+        assert_eq!(
+            FlowField::Shear { rate: 0.5 }.evaluate(1.0, 2.0, length).0,
+            0.0
+        );
+
+        // A quarter of the way along the bottom edge, the flow runs along x at full strength
+        let (u, v) = FlowField::TaylorGreen { amplitude: 1.0 }.evaluate(1.0, 0.0, length);
+        assert!((u - 1.0).abs() < 1e-9 && v.abs() < 1e-9);
+
+        let mut sim = single_particle(Speed(0.0))
+            .with_flow_field(Some(FlowField::Uniform { u: 0.3, v: -0.1 }));
+        sim.run_for(2).unwrap();
+
+        let particle = sim.particles.iter().next().unwrap();
+        assert!((particle.pos_x - 2.6).abs() < 1e-5);
+        assert!((particle.pos_y - 1.8).abs() < 1e-5);
+    }
+}
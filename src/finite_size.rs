@@ -0,0 +1,237 @@
+use anyhow::bail;
+
+use crate::{
+    Simulation,
+    control::StopReason,
+    particle::{InitialCondition, Particles},
+    simulation::{SimulationParameters, StationaryOrderOptions},
+    types::{DomainBoundaryLength, Float},
+};
+
+/// Controls for a finite-size extrapolation
+#[derive(Clone, Debug)]
+pub struct FiniteSizeOptions {
+    /// Particle counts to run, at least two different ones
+    pub sizes: Vec<usize>,
+
+    /// Independent runs per size, each from a fresh random layout
+    pub num_runs: usize,
+
+    /// Controls for each run's stationary order parameter
+    pub stationary_order: StationaryOrderOptions,
+}
+
+impl FiniteSizeOptions {
+    /// Run each size a few times with the default stationary order settings
+    pub fn new(sizes: Vec<usize>) -> Self {
+        Self {
+            sizes,
+            num_runs: 3,
+            stationary_order: StationaryOrderOptions::default(),
+        }
+    }
+}
+
+/// The stationary order parameters measured at one system size
+#[derive(Clone, Debug)]
+pub struct FiniteSizePoint {
+    pub num_particles: usize,
+
+    /// Side length keeping the density fixed
+    pub boundary_side_length: DomainBoundaryLength,
+
+    /// Each run's stationary order parameter, converged or not
+    pub values: Vec<Float>,
+}
+
+/// The stationary order parameter extrapolated to an infinite system
+#[derive(Clone, Debug)]
+pub struct FiniteSizeReport {
+    pub points: Vec<FiniteSizePoint>,
+
+    /// The fitted value at 1/N = 0
+    pub extrapolated: Float,
+
+    /// Standard error of the extrapolated value, from the scatter about the fit
+    pub extrapolated_std_error: Float,
+
+    /// The fitted change in order per unit of 1/N
+    pub slope: Float,
+}
+
+impl Simulation {
+    /// Run this configuration at increasing particle counts with the density held fixed, fit the
+    /// stationary order parameter against 1/N, and extrapolate to an infinite system
+    ///
+    /// # Notes
+    /// Every run uses this simulation's parameters, with the domain resized to the density, from a
+    /// uniformly random layout. Schedules, per-particle settings, and observers aren't carried
+    /// over. The fit is ordinary least squares over every run, so the error needs at least three
+    /// runs in all.
+    pub fn extrapolate_system_size(
+        &self,
+        options: &FiniteSizeOptions,
+    ) -> anyhow::Result<FiniteSizeReport> {
+        if options.num_runs == 0 {
+            bail!("finite-size extrapolation needs at least one run per size");
+        }
+
+        if options.sizes.contains(&0) {
+            bail!("every system size must have at least one particle");
+        }
+
+        let distinct_sizes = options
+            .sizes
+            .iter()
+            .filter(|&&size| size != options.sizes[0]);
+        if distinct_sizes.count() == 0 || options.sizes.len() * options.num_runs < 3 {
+            bail!("finite-size extrapolation needs at least two sizes and three runs in all");
+        }
+
+        let density = self.number_density();
+        let mut points = Vec::with_capacity(options.sizes.len());
+
+        for &num_particles in &options.sizes {
+            let boundary_side_length =
+                DomainBoundaryLength((num_particles as Float / density).sqrt());
+
+            let values = (0..options.num_runs)
+                .map(|_| {
+                    let sim = self.to_resampled(num_particles, boundary_side_length)?;
+                    let estimate =
+                        sim.compute_stationary_order_estimate(&options.stationary_order)?;
+
+                    // A cut-short estimate would quietly skew the fit
+                    if estimate.stop_reason == StopReason::Cancelled {
+                        bail!("finite-size extrapolation was cancelled");
+                    }
+
+                    Ok(estimate.value)
+                })
+                .collect::<anyhow::Result<_>>()?;
+
+            points.push(FiniteSizePoint {
+                num_particles,
+                boundary_side_length,
+                values,
+            });
+        }
+
+        // Least squares of the order against 1/N...
+        let samples: Vec<(Float, Float)> = points
+            .iter()
+            .flat_map(|point| {
+                let inverse_size = 1.0 / point.num_particles as Float;
+                point.values.iter().map(move |&value| (inverse_size, value))
+            })
+            .collect();
+        let num_samples = samples.len() as Float;
+        let mean_x = samples.iter().map(|(x, _)| x).sum::<Float>() / num_samples;
+        let mean_y = samples.iter().map(|(_, y)| y).sum::<Float>() / num_samples;
+        let sxx = samples
+            .iter()
+            .map(|(x, _)| (x - mean_x).powi(2))
+            .sum::<Float>();
+        let sxy = samples
+            .iter()
+            .map(|(x, y)| (x - mean_x) * (y - mean_y))
+            .sum::<Float>();
+
+        let slope = sxy / sxx;
+        let extrapolated = mean_y - slope * mean_x;
+
+        // ...then the intercept's standard error from the residuals.
+        let residual_variance = samples
+            .iter()
+            .map(|(x, y)| (y - extrapolated - slope * x).powi(2))
+            .sum::<Float>()
+            / (num_samples - 2.0).max(1.0);
+        let extrapolated_std_error =
+            (residual_variance * (1.0 / num_samples + mean_x.powi(2) / sxx)).sqrt();
+
+        Ok(FiniteSizeReport {
+            points,
+            extrapolated,
+            extrapolated_std_error,
+            slope,
+        })
+    }
+
+    /// A fresh, uniformly random simulation of `num_particles` with this one's parameters, in a
+    /// domain of the given size
+    fn to_resampled(
+        &self,
+        num_particles: usize,
+        boundary_side_length: DomainBoundaryLength,
+    ) -> anyhow::Result<Self> {
+        let sim = Self::from_initial_particles(
+            Particles::new(
+                num_particles,
+                boundary_side_length,
+                InitialCondition::UniformRandom,
+            ),
+            boundary_side_length,
+            self.params.noise,
+            self.params.speed,
+            self.params.timestep,
+            self.params.particle_distance_threshold,
+        )?;
+
+        let params = SimulationParameters {
+            boundary_side_length,
+            ..self.params.clone()
+        };
+
+        Ok(Self { params, ..sim })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Noise, ParticleDistanceThreshold, RelativeTime, Speed};
+
+    #[test]
+    fn sizes_keep_the_density_and_fit_a_line() {
+        let sim = Simulation::new(
+            4,
+            DomainBoundaryLength(2.0),
+            Noise(0.1),
+            Speed(0.1),
+            RelativeTime(1.0),
+            ParticleDistanceThreshold(1.0),
+        )
+        .unwrap();
+        let options = FiniteSizeOptions {
+            num_runs: 2,
+            stationary_order: StationaryOrderOptions {
+                max_steps: Some(5),
+                ..Default::default()
+            },
+            ..FiniteSizeOptions::new(vec![4, 16])
+        };
+
+        let report = sim.extrapolate_system_size(&options).unwrap();
+        let sides: Vec<Float> = report
+            .points
+            .iter()
+            .map(|point| point.boundary_side_length.0)
+            .collect();
+        assert_eq!(sides, [2.0, 4.0]);
+        assert!(report.points.iter().all(|point| point.values.len() == 2));
+        assert!(report.extrapolated.is_finite() && report.slope.is_finite());
+
+        // The intercept sits on the line through the mean of each size's runs
+        let means: Vec<Float> = report
+            .points
+            .iter()
+            .map(|point| point.values.iter().sum::<Float>() / 2.0)
+            .collect();
+        let slope = (means[1] - means[0]) / (1.0 / 16.0 - 1.0 / 4.0);
+        assert!((report.slope - slope).abs() < 1e-9);
+        assert!((report.extrapolated - (means[1] - slope / 16.0)).abs() < 1e-9);
+
+        let one_size = FiniteSizeOptions::new(vec![4, 4]);
+        assert!(sim.extrapolate_system_size(&one_size).is_err());
+    }
+}
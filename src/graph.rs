@@ -0,0 +1,178 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Write,
+};
+
+use crate::{
+    error::{SimulationError, invalid_parameter},
+    simulation::Simulation,
+    types::{Float, ParticleDistanceThreshold},
+};
+
+/// Who aligns with whom at one instant, as a directed graph over the particles
+///
+/// # Notes
+/// An edge (i, j) means particle i aligns with particle j. With per-particle interaction radii a
+/// long-sighted particle can follow a short-sighted one that doesn't see it, so edges needn't come
+/// in pairs.
+#[derive(Clone, Debug)]
+pub struct InteractionGraph {
+    /// Every particle's ID, as reported in `SimulationData`
+    pub ids: Vec<usize>,
+
+    /// (particle, neighbor) pairs of IDs, in particle order
+    pub edges: Vec<(usize, usize)>,
+}
+
+impl InteractionGraph {
+    /// How many neighbors each particle aligns with, in the order of `ids`
+    pub fn out_degrees(&self) -> Vec<usize> {
+        self.degrees(|&(from, _)| from)
+    }
+
+    /// How many particles align with each particle, in the order of `ids`
+    pub fn in_degrees(&self) -> Vec<usize> {
+        self.degrees(|&(_, to)| to)
+    }
+
+    /// The fraction of edges whose reverse is also an edge, which is 1 when every particle shares
+    /// the same radius. NaN without any edges.
+    pub fn reciprocity(&self) -> Float {
+        let edges: HashSet<_> = self.edges.iter().copied().collect();
+        let num_reciprocated = self
+            .edges
+            .iter()
+            .filter(|&&(from, to)| edges.contains(&(to, from)))
+            .count();
+
+        num_reciprocated as Float / self.edges.len() as Float
+    }
+
+    /// Write the graph in Graphviz DOT format, with particles as nodes named by their IDs
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph interactions {\n");
+
+        // Writing to a String can't fail
+        for id in &self.ids {
+            let _ = writeln!(dot, "    {id};");
+        }
+        for (from, to) in &self.edges {
+            let _ = writeln!(dot, "    {from} -> {to};");
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+
+    fn degrees(&self, endpoint: impl Fn(&(usize, usize)) -> usize) -> Vec<usize> {
+        let positions: HashMap<usize, usize> = self
+            .ids
+            .iter()
+            .enumerate()
+            .map(|(position, &id)| (id, position))
+            .collect();
+
+        let mut degrees = vec![0; self.ids.len()];
+        for edge in &self.edges {
+            degrees[positions[&endpoint(edge)]] += 1;
+        }
+
+        degrees
+    }
+}
+
+impl Simulation {
+    /// Give each particle its own interaction radius, in particle order, so it aligns with the
+    /// neighbors within its own radius rather than the simulation's threshold
+    ///
+    /// # Notes
+    /// This only affects the Vicsek update rules, and makes the interaction graph directed. See
+    /// [`Simulation::interaction_graph`].
+    pub fn set_interaction_radii(
+        &mut self,
+        radii: &[ParticleDistanceThreshold],
+    ) -> Result<(), SimulationError> {
+        if radii.len() != self.particles.len() {
+            invalid_parameter!(
+                "got `{}` interaction radii for `{}` particles",
+                radii.len(),
+                self.particles.len()
+            );
+        }
+
+        if let Some(radius) = radii
+            .iter()
+            .find(|radius| radius.0.is_nan() || radius.0 <= 0.0)
+        {
+            invalid_parameter!("interaction radii must be positive, got `{}`", radius.0);
+        }
+
+        self.particles = self.particles.to_with_interaction_radii(Some(radii));
+
+        Ok(())
+    }
+
+    /// Revert every particle to the simulation's distance threshold
+    pub fn clear_interaction_radii(&mut self) {
+        self.particles = self.particles.to_with_interaction_radii(None);
+    }
+
+    /// Snapshot who aligns with whom under the Vicsek rules, honoring interaction radii, the
+    /// vision cone, and occlusion
+    pub fn interaction_graph(&self) -> InteractionGraph {
+        InteractionGraph {
+            ids: self
+                .particles
+                .iter()
+                .map(|particle| particle.stable_id)
+                .collect(),
+            edges: self.particles.compute_interaction_edges(&self.params),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{DomainBoundaryLength, Noise, RelativeTime, Speed};
+
+    #[test]
+    fn longer_radii_make_one_way_edges() {
+        let mut sim = Simulation::with_particles(
+            &[(1.0, 1.0), (1.5, 1.0), (3.0, 3.0)],
+            &[0.0, 0.0, 0.0],
+            DomainBoundaryLength(5.0),
+            Noise(0.0),
+            Speed(0.01),
+            RelativeTime(1.0),
+            ParticleDistanceThreshold(1.0),
+        )
+        .unwrap();
+
+        let graph = sim.interaction_graph();
+        assert_eq!(graph.edges, [(0, 1), (1, 0)]);
+        assert_eq!(graph.reciprocity(), 1.0);
+
+        // Only the first particle sees as far as its neighbor
+        sim.set_interaction_radii(&[
+            ParticleDistanceThreshold(1.0),
+            ParticleDistanceThreshold(0.2),
+            ParticleDistanceThreshold(1.0),
+        ])
+        .unwrap();
+        let graph = sim.interaction_graph();
+        assert_eq!(graph.edges, [(0, 1)]);
+        assert_eq!(graph.reciprocity(), 0.0);
+        assert_eq!(graph.out_degrees(), [1, 0, 0]);
+        assert_eq!(graph.in_degrees(), [0, 1, 0]);
+        assert_eq!(
+            graph.to_dot(),
+            "digraph interactions {\n    0;\n    1;\n    2;\n    0 -> 1;\n}\n"
+        );
+
+        assert!(matches!(
+            sim.set_interaction_radii(&[ParticleDistanceThreshold(1.0)]),
+            Err(SimulationError::InvalidParameter(_))
+        ));
+    }
+}
@@ -0,0 +1,294 @@
+use std::collections::VecDeque;
+
+use anyhow::anyhow;
+use eframe::egui;
+use egui_plot::{Line, Plot};
+
+use crate::{
+    session::{ParameterChange, Session, SessionRecorder},
+    simulation::Simulation,
+    types::{Float, Noise, PI, ParticleDistanceThreshold, Speed},
+};
+
+/// Most recent instantaneous orders kept for the plot
+const ORDER_PLOT_LENGTH: usize = 2000;
+
+/// Upper end of the speed slider
+const MAX_SLIDER_SPEED: Float = 5.0;
+
+/// Length of each particle arrow, as a fraction of the domain view
+const ARROW_LENGTH: f32 = 0.03;
+
+impl Simulation {
+    /// Open an interactive window that steps the simulation in place while it's open, with
+    /// sliders for the noise, speed, and interaction radius that change it live, and a plot of
+    /// the instantaneous order
+    ///
+    /// # Notes
+    /// The panel can also pause, take single steps, and set how many steps are taken per redraw.
+    /// Parameters changed from the panel stick once the window is closed. Some platforms only
+    /// allow windows on the main thread.
+    pub fn control_panel(&mut self) -> anyhow::Result<()> {
+        self.run_control_panel(None)
+    }
+
+    /// Open the control panel like [`Simulation::control_panel`], recording every parameter change
+    /// made from it along with the steps taken, and return the session once the window is closed
+    /// so it can be saved and replayed with [`Simulation::replay_session`]
+    ///
+    /// # Notes
+    /// `seed` is only kept for reference; the session is reproduced from the random state at the
+    /// start.
+    pub fn record_control_panel(&mut self, seed: Option<u64>) -> anyhow::Result<Session> {
+        let mut recorder = SessionRecorder::start(self, seed);
+        self.run_control_panel(Some(&mut recorder))?;
+
+        Ok(recorder.finish())
+    }
+
+    fn run_control_panel(&mut self, recorder: Option<&mut SessionRecorder>) -> anyhow::Result<()> {
+        let options = eframe::NativeOptions {
+            viewport: egui::ViewportBuilder::default().with_inner_size([1000.0, 700.0]),
+            ..Default::default()
+        };
+
+        eframe::run_native(
+            "pip-sim control panel",
+            options,
+            Box::new(|_| Ok(Box::new(ControlPanel::new(self, recorder)))),
+        )
+        .map_err(|error| anyhow!("could not run control panel: {}", error))
+    }
+}
+
+/// State of the window opened by [`Simulation::control_panel`]
+struct ControlPanel<'a> {
+    sim: &'a mut Simulation,
+    paused: bool,
+    steps_per_frame: usize,
+    orders: VecDeque<[f64; 2]>,
+
+    /// Why the last parameter change was rejected, if it was
+    error: Option<String>,
+
+    /// Where steps and parameter changes are recorded, if they are
+    recorder: Option<&'a mut SessionRecorder>,
+}
+
+impl<'a> ControlPanel<'a> {
+    fn new(sim: &'a mut Simulation, recorder: Option<&'a mut SessionRecorder>) -> Self {
+        let mut panel = Self {
+            sim,
+            paused: false,
+            steps_per_frame: 1,
+            orders: VecDeque::with_capacity(ORDER_PLOT_LENGTH),
+            error: None,
+            recorder,
+        };
+        panel.record_order();
+
+        panel
+    }
+
+    // Note: `Float` may be 32-bit, but the plot takes 64-bit floats
+    #[allow(clippy::unnecessary_cast)]
+    fn record_order(&mut self) {
+        if self.orders.len() >= ORDER_PLOT_LENGTH {
+            self.orders.pop_front();
+        }
+        self.orders.push_back([
+            self.sim.current_time.0 as f64,
+            self.sim.instantaneous_order.0 as f64,
+        ]);
+    }
+
+    fn step(&mut self, num_steps: usize) {
+        for _ in 0..num_steps {
+            if let Err(error) = self.sim.run_for(1) {
+                self.error = Some(error.to_string());
+                self.paused = true;
+                return;
+            }
+            if let Some(recorder) = &mut self.recorder {
+                recorder.record_steps(1);
+            }
+            self.record_order();
+        }
+    }
+
+    /// Apply a parameter change through its validating setter, keeping the old parameters if it's
+    /// rejected
+    fn update_sim(&mut self, change: ParameterChange) {
+        match self.sim.apply_change(&change) {
+            Ok(()) => {
+                if let Some(recorder) = &mut self.recorder {
+                    recorder.record_change(self.sim, change);
+                }
+                self.error = None;
+            }
+            Err(error) => self.error = Some(error.to_string()),
+        }
+    }
+
+    fn controls(&mut self, ui: &mut egui::Ui) {
+        ui.heading("Parameters");
+
+        let mut noise = self.sim.params.noise.0;
+        if ui
+            .add(egui::Slider::new(&mut noise, 0.0..=2.0 * PI).text("noise"))
+            .changed()
+        {
+            self.update_sim(ParameterChange::Noise(Noise(noise)));
+        }
+
+        let mut speed = self.sim.params.speed.0;
+        if ui
+            .add(egui::Slider::new(&mut speed, 0.0..=MAX_SLIDER_SPEED).text("speed"))
+            .changed()
+        {
+            self.update_sim(ParameterChange::Speed(Speed(speed)));
+        }
+
+        let mut threshold = self.sim.params.particle_distance_threshold.0;
+        let max_threshold = 0.5 * self.sim.params.boundary_side_length.0;
+        if ui
+            .add(egui::Slider::new(&mut threshold, 0.01..=max_threshold).text("interaction radius"))
+            .changed()
+        {
+            self.update_sim(ParameterChange::ParticleDistanceThreshold(
+                ParticleDistanceThreshold(threshold),
+            ));
+        }
+
+        ui.separator();
+        ui.heading("Stepping");
+
+        ui.add(egui::Slider::new(&mut self.steps_per_frame, 1..=50).text("steps per frame"));
+        ui.horizontal(|ui| {
+            if ui
+                .button(if self.paused { "Resume" } else { "Pause" })
+                .clicked()
+            {
+                self.paused = !self.paused;
+            }
+            if ui
+                .add_enabled(self.paused, egui::Button::new("Step"))
+                .clicked()
+            {
+                self.step(1);
+            }
+        });
+
+        ui.separator();
+        ui.label(format!("time: {:.2}", self.sim.current_time.0));
+        ui.label(format!("particles: {}", self.sim.particles.len()));
+        ui.label(format!(
+            "instantaneous order: {:.3}",
+            self.sim.instantaneous_order.0
+        ));
+        if self.recorder.is_some() {
+            ui.label("recording session");
+        }
+
+        if let Some(error) = &self.error {
+            ui.separator();
+            ui.colored_label(egui::Color32::RED, error);
+        }
+    }
+
+    /// Draw each particle as an arrow along its heading, in a square fitted to the space left
+    fn particles(&self, ui: &mut egui::Ui) {
+        let available = ui.available_size();
+        let side = available.x.min(available.y);
+        let (response, painter) = ui.allocate_painter(egui::vec2(side, side), egui::Sense::hover());
+        let rect = response.rect;
+
+        painter.rect_filled(rect, 0.0, egui::Color32::WHITE);
+
+        let scale = side / self.sim.params.boundary_side_length.0 as f32;
+        let arrow_length = ARROW_LENGTH * side;
+        let stroke = egui::Stroke::new(1.0, egui::Color32::BLACK);
+
+        for particle in self.sim.particles.iter() {
+            // y points up in the simulation but down on screen
+            let center = egui::pos2(
+                rect.left() + particle.pos_x as f32 * scale,
+                rect.bottom() - particle.pos_y as f32 * scale,
+            );
+            let heading = egui::vec2(particle.theta.cos() as f32, -particle.theta.sin() as f32)
+                * arrow_length;
+
+            painter.arrow(center - 0.5 * heading, heading, stroke);
+        }
+    }
+
+    fn order_plot(&self, ui: &mut egui::Ui) {
+        Plot::new("instantaneous_order")
+            .height(ui.available_height())
+            .include_y(0.0)
+            .include_y(1.0)
+            .x_axis_label("time")
+            .y_axis_label("instantaneous order")
+            .show(ui, |plot_ui| {
+                plot_ui.line(Line::new(
+                    "instantaneous order",
+                    self.orders.iter().copied().collect::<Vec<_>>(),
+                ));
+            });
+    }
+}
+
+impl eframe::App for ControlPanel<'_> {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        if !self.paused {
+            self.step(self.steps_per_frame);
+        }
+
+        egui::SidePanel::left("controls")
+            .resizable(false)
+            .show(ctx, |ui| self.controls(ui));
+        egui::TopBottomPanel::bottom("order")
+            .exact_height(200.0)
+            .show(ctx, |ui| self.order_plot(ui));
+        egui::CentralPanel::default().show(ctx, |ui| self.particles(ui));
+
+        // Keep stepping even without input
+        if !self.paused {
+            ctx.request_repaint();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{DomainBoundaryLength, RelativeTime};
+
+    #[test]
+    fn panel_steps_and_accepted_changes_are_recorded() {
+        let mut sim = Simulation::new(
+            3,
+            DomainBoundaryLength(5.0),
+            Noise(0.1),
+            Speed(0.1),
+            RelativeTime(1.0),
+            ParticleDistanceThreshold(1.0),
+        )
+        .unwrap();
+        let mut recorder = SessionRecorder::start(&sim, None);
+
+        let mut panel = ControlPanel::new(&mut sim, Some(&mut recorder));
+        panel.step(2);
+        panel.update_sim(ParameterChange::Noise(Noise(0.3)));
+        panel.update_sim(ParameterChange::Speed(Speed(-1.0)));
+        assert!(panel.error.is_some());
+        panel.step(1);
+        assert_eq!(panel.orders.len(), 4);
+
+        let session = recorder.finish();
+        assert_eq!(session.num_steps, 3);
+        assert_eq!(session.changes.len(), 1);
+        assert_eq!((session.changes[0].step, session.changes[0].time), (2, 2.0));
+        assert_eq!((sim.params.noise.0, sim.params.speed.0), (0.3, 0.1));
+    }
+}
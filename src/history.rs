@@ -0,0 +1,194 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+
+use crate::{
+    Simulation,
+    error::{SimulationError, invalid_parameter},
+    types::{AbsoluteTime, Float},
+};
+
+/// How much of the order parameter's history to keep
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OrderHistoryLength {
+    /// Every step since the history was started
+    Full,
+
+    /// Only the most recent entries, dropping the oldest as new ones come in
+    Last(usize),
+}
+
+/// The instantaneous order parameter over time, from [`Simulation::order_history`]
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OrderHistory {
+    length: OrderHistoryLength,
+    times: VecDeque<Float>,
+    orders: VecDeque<Float>,
+}
+
+impl OrderHistory {
+    fn new(length: OrderHistoryLength) -> Self {
+        Self {
+            length,
+            times: VecDeque::new(),
+            orders: VecDeque::new(),
+        }
+    }
+
+    /// Get the number of entries kept
+    pub fn len(&self) -> usize {
+        self.times.len()
+    }
+
+    /// Check whether the history holds no entries
+    pub fn is_empty(&self) -> bool {
+        self.times.is_empty()
+    }
+
+    /// How much of the history is kept
+    pub fn length(&self) -> OrderHistoryLength {
+        self.length
+    }
+
+    /// The simulated time of each entry, oldest first
+    pub fn times(&self) -> Vec<Float> {
+        self.times.iter().copied().collect()
+    }
+
+    /// The instantaneous order parameter of each entry, oldest first
+    pub fn orders(&self) -> Vec<Float> {
+        self.orders.iter().copied().collect()
+    }
+
+    /// Iterate over `(time, order)` entries, oldest first
+    pub fn iter(&self) -> impl Iterator<Item = (Float, Float)> {
+        self.times.iter().copied().zip(self.orders.iter().copied())
+    }
+
+    /// Append an entry, first dropping any at or after `time`, which a rewound branch left
+    /// behind
+    pub(crate) fn push(&mut self, time: AbsoluteTime, order: Float) {
+        while self.times.back().is_some_and(|&last| last >= time.0) {
+            self.times.pop_back();
+            self.orders.pop_back();
+        }
+
+        if let OrderHistoryLength::Last(capacity) = self.length
+            && self.times.len() == capacity
+        {
+            self.times.pop_front();
+            self.orders.pop_front();
+        }
+
+        self.times.push_back(time.0);
+        self.orders.push_back(order);
+    }
+}
+
+impl Simulation {
+    /// Start keeping the instantaneous order parameter after every step from here on, starting
+    /// with the current value, or stop keeping it with `None`
+    ///
+    /// # Notes
+    /// Like the noise recording, the history is shared by every state stepped from this one, so
+    /// it follows whichever copy was stepped last: entries from a later time are dropped when an
+    /// earlier state steps again. Running the stationary order parameter computations from the
+    /// current state therefore leaves their run in the history until this simulation steps.
+    pub fn with_order_history(
+        self,
+        length: Option<OrderHistoryLength>,
+    ) -> Result<Self, SimulationError> {
+        if let Some(OrderHistoryLength::Last(0)) = length {
+            invalid_parameter!("order history must keep at least one entry");
+        }
+
+        let order_history = length.map(|length| {
+            let mut history = OrderHistory::new(length);
+            history.push(self.current_time, self.instantaneous_order.0);
+            Arc::new(Mutex::new(history))
+        });
+
+        Ok(Self {
+            order_history,
+            ..self
+        })
+    }
+
+    /// Get the order parameter history kept so far, if keeping one
+    pub fn order_history(&self) -> Option<OrderHistory> {
+        self.order_history.as_ref().map(|history| {
+            history
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .clone()
+        })
+    }
+
+    /// Append the current order parameter to the history, if keeping one
+    pub(crate) fn record_order_history(&self) {
+        if let Some(history) = &self.order_history {
+            history
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .push(self.current_time, self.instantaneous_order.0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{
+        DomainBoundaryLength, Noise, ParticleDistanceThreshold, RelativeTime, Speed,
+    };
+
+    fn simulation() -> Simulation {
+        Simulation::new(
+            3,
+            DomainBoundaryLength(5.0),
+            Noise(0.1),
+            Speed(0.1),
+            RelativeTime(1.0),
+            ParticleDistanceThreshold(1.0),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn bounded_histories_keep_the_latest_entries() {
+        let mut sim = simulation()
+            .with_order_history(Some(OrderHistoryLength::Last(3)))
+            .unwrap();
+        assert_eq!(sim.order_history().unwrap().times(), [0.0]);
+
+        sim.run_for(5).unwrap();
+        let history = sim.order_history().unwrap();
+        assert_eq!(history.times(), [3.0, 4.0, 5.0]);
+        assert_eq!(history.orders().last(), Some(&sim.instantaneous_order.0));
+
+        assert!(simulation().order_history().is_none());
+        assert!(
+            simulation()
+                .with_order_history(Some(OrderHistoryLength::Last(0)))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn stepping_an_earlier_state_drops_the_later_entries() {
+        let mut sim = simulation()
+            .with_order_history(Some(OrderHistoryLength::Full))
+            .unwrap();
+        sim.run_for(2).unwrap();
+
+        let mut earlier = sim.clone();
+        sim.run_for(3).unwrap();
+        assert_eq!(sim.order_history().unwrap().len(), 6);
+
+        earlier.run_for(1).unwrap();
+        assert_eq!(sim.order_history().unwrap().times(), [0.0, 1.0, 2.0, 3.0]);
+    }
+}
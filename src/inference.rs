@@ -0,0 +1,524 @@
+use anyhow::{Context, bail};
+
+use crate::{
+    Simulation,
+    compare::{ComparisonOptions, DiscrepancyWeights, SummaryStatistics},
+    control::{CancellationToken, StopReason},
+    math::sample_standard_normal,
+    random,
+    types::{DomainBoundaryLength, Float, Noise, ParticleDistanceThreshold, RelativeTime, Speed},
+};
+
+/// A uniform prior over the inferred parameters, given as inclusive `(min, max)` bounds
+#[derive(Copy, Clone, Debug)]
+pub struct Prior {
+    pub noise: (Float, Float),
+    pub speed: (Float, Float),
+    pub particle_distance_threshold: (Float, Float),
+}
+
+impl Prior {
+    fn bounds(&self) -> [(Float, Float); 3] {
+        [self.noise, self.speed, self.particle_distance_threshold]
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        for (name, (min, max)) in ["noise", "speed", "particle distance threshold"]
+            .into_iter()
+            .zip(self.bounds())
+        {
+            if !min.is_finite() || !max.is_finite() || min < 0.0 || max < min {
+                bail!("prior bounds for {name} must satisfy 0 <= min <= max, got `{min}`, `{max}`");
+            }
+        }
+
+        if self.particle_distance_threshold.1 <= 0.0 {
+            bail!("prior for the particle distance threshold must allow positive values");
+        }
+
+        Ok(())
+    }
+
+    fn contains(&self, params: &[Float; 3]) -> bool {
+        self.bounds()
+            .iter()
+            .zip(params)
+            .all(|(&(min, max), value)| (min..=max).contains(value))
+    }
+
+    fn sample(&self) -> [Float; 3] {
+        self.bounds()
+            .map(|(min, max)| min + (max - min) * random::random::<Float>())
+    }
+}
+
+/// Controls for approximate Bayesian computation
+#[derive(Clone, Debug)]
+pub struct AbcOptions {
+    /// Steps simulated for each parameter draw
+    pub num_steps: usize,
+
+    /// Number of posterior samples kept
+    pub population_size: usize,
+
+    /// Total number of simulations allowed across all generations
+    pub num_simulations: usize,
+
+    /// Number of SMC generations after the initial draw from the prior
+    pub num_generations: usize,
+
+    /// Checked between simulations; when cancelled the last complete population is returned
+    pub cancellation: Option<CancellationToken>,
+}
+
+impl Default for AbcOptions {
+    fn default() -> Self {
+        Self {
+            num_steps: 100,
+            population_size: 100,
+            num_simulations: 5000,
+            num_generations: 5,
+            cancellation: None,
+        }
+    }
+}
+
+/// One accepted parameter set
+#[derive(Copy, Clone, Debug)]
+pub struct PosteriorSample {
+    pub noise: Noise,
+    pub speed: Speed,
+    pub particle_distance_threshold: ParticleDistanceThreshold,
+
+    /// Discrepancy between the simulated and reference statistics
+    pub distance: Float,
+
+    /// Importance weight, normalized over the population
+    pub weight: Float,
+}
+
+/// Samples approximating the posterior over the inferred parameters
+#[derive(Clone, Debug)]
+pub struct AbcPosterior {
+    pub samples: Vec<PosteriorSample>,
+
+    /// Largest discrepancy accepted into the population
+    pub tolerance: Float,
+
+    /// Number of simulations run
+    pub num_simulations: usize,
+
+    pub stop_reason: StopReason,
+}
+
+/// Everything needed to simulate a parameter draw and score it against the reference
+struct AbcModel<'a> {
+    reference: &'a SummaryStatistics,
+    num_particles: usize,
+    boundary_side_length: DomainBoundaryLength,
+    timestep: RelativeTime,
+    comparison: &'a ComparisonOptions,
+    num_steps: usize,
+}
+
+impl AbcModel<'_> {
+    fn distance(&self, params: &[Float; 3]) -> anyhow::Result<Float> {
+        let sim = Simulation::new(
+            self.num_particles,
+            self.boundary_side_length,
+            Noise(params[0]),
+            Speed(params[1]),
+            self.timestep,
+            ParticleDistanceThreshold(params[2]),
+        )
+        .context("could not instantiate simulation for ABC")?;
+
+        let statistics = SummaryStatistics::from_simulation(&sim, self.num_steps, self.comparison)?;
+
+        Ok(statistics.discrepancy(self.reference, &DiscrepancyWeights::default()))
+    }
+}
+
+/// Infer (noise, speed, threshold) by ABC rejection: simulate `num_simulations` draws from the
+/// prior and keep the `population_size` closest to the reference statistics
+pub fn abc_rejection(
+    reference: &SummaryStatistics,
+    num_particles: usize,
+    boundary_side_length: DomainBoundaryLength,
+    timestep: RelativeTime,
+    prior: &Prior,
+    comparison: &ComparisonOptions,
+    options: &AbcOptions,
+) -> anyhow::Result<AbcPosterior> {
+    prior.validate()?;
+
+    if options.population_size == 0 || options.num_simulations < options.population_size {
+        bail!("ABC needs a population of at least one, and at least as many simulations");
+    }
+
+    let model = AbcModel {
+        reference,
+        num_particles,
+        boundary_side_length,
+        timestep,
+        comparison,
+        num_steps: options.num_steps,
+    };
+
+    let mut draws = Vec::with_capacity(options.num_simulations);
+    let mut stop_reason = StopReason::Converged;
+
+    for _ in 0..options.num_simulations {
+        if is_cancelled(options) {
+            stop_reason = StopReason::Cancelled;
+            break;
+        }
+
+        let params = prior.sample();
+        draws.push((params, model.distance(&params)?));
+    }
+
+    let num_simulations = draws.len();
+
+    // Keep the closest draws
+    draws.sort_by(|(_, a), (_, b)| a.total_cmp(b));
+    draws.truncate(options.population_size);
+
+    let tolerance = draws
+        .last()
+        .map_or(Float::INFINITY, |&(_, distance)| distance);
+    let weight = 1.0 / draws.len().max(1) as Float;
+
+    Ok(AbcPosterior {
+        samples: draws
+            .into_iter()
+            .map(|(params, distance)| to_sample(params, distance, weight))
+            .collect(),
+        tolerance,
+        num_simulations,
+        stop_reason,
+    })
+}
+
+/// Infer (noise, speed, threshold) by ABC sequential Monte Carlo (population Monte Carlo)
+///
+/// # Notes
+/// The first population is drawn from the prior. Each later generation lowers the tolerance to
+/// the median distance of the previous one, and proposes by perturbing weighted samples of it with
+/// a Gaussian kernel of twice its weighted variance. If the simulation budget runs out mid-way,
+/// the last complete population is returned.
+pub fn abc_smc(
+    reference: &SummaryStatistics,
+    num_particles: usize,
+    boundary_side_length: DomainBoundaryLength,
+    timestep: RelativeTime,
+    prior: &Prior,
+    comparison: &ComparisonOptions,
+    options: &AbcOptions,
+) -> anyhow::Result<AbcPosterior> {
+    prior.validate()?;
+
+    if options.population_size == 0 || options.num_simulations < options.population_size {
+        bail!("ABC needs a population of at least one, and at least as many simulations");
+    }
+
+    let model = AbcModel {
+        reference,
+        num_particles,
+        boundary_side_length,
+        timestep,
+        comparison,
+        num_steps: options.num_steps,
+    };
+
+    // The first generation accepts every draw from the prior
+    let mut population = Vec::with_capacity(options.population_size);
+    for _ in 0..options.population_size {
+        let params = prior.sample();
+        population.push((params, model.distance(&params)?, 1.0));
+    }
+    normalize_weights(&mut population);
+
+    let mut num_simulations = options.population_size;
+    let mut tolerance = Float::INFINITY;
+
+    for _ in 0..options.num_generations {
+        let mut distances: Vec<Float> = population
+            .iter()
+            .map(|&(_, distance, _)| distance)
+            .collect();
+        distances.sort_by(Float::total_cmp);
+        let next_tolerance = distances[distances.len() / 2];
+
+        let kernel_std = kernel_std(&population);
+
+        let mut next_population = Vec::with_capacity(options.population_size);
+        while next_population.len() < options.population_size {
+            if is_cancelled(options) {
+                return Ok(to_posterior(
+                    population,
+                    tolerance,
+                    num_simulations,
+                    StopReason::Cancelled,
+                ));
+            }
+
+            if num_simulations >= options.num_simulations {
+                return Ok(to_posterior(
+                    population,
+                    tolerance,
+                    num_simulations,
+                    StopReason::StepBudgetExhausted,
+                ));
+            }
+
+            // Perturb a weighted pick from the previous population, staying within the prior
+            let (ancestor, _, _) = population[sample_weighted(&population)];
+            let mut params = ancestor;
+            for (value, std) in params.iter_mut().zip(kernel_std) {
+                *value += std * sample_standard_normal();
+            }
+
+            if !prior.contains(&params) || params[2] == 0.0 {
+                continue;
+            }
+
+            num_simulations += 1;
+            let distance = model.distance(&params)?;
+
+            if distance <= next_tolerance {
+                // The prior is uniform, so the weight is just the inverse proposal density
+                let proposal_density: Float = population
+                    .iter()
+                    .map(|(previous, _, weight)| {
+                        weight * gaussian_kernel_density(&params, previous, &kernel_std)
+                    })
+                    .sum();
+
+                next_population.push((params, distance, 1.0 / proposal_density));
+            }
+        }
+
+        normalize_weights(&mut next_population);
+        population = next_population;
+        tolerance = next_tolerance;
+    }
+
+    Ok(to_posterior(
+        population,
+        tolerance,
+        num_simulations,
+        StopReason::Converged,
+    ))
+}
+
+type WeightedDraw = ([Float; 3], Float, Float);
+
+fn is_cancelled(options: &AbcOptions) -> bool {
+    options
+        .cancellation
+        .as_ref()
+        .is_some_and(CancellationToken::is_cancelled)
+}
+
+fn to_sample(params: [Float; 3], distance: Float, weight: Float) -> PosteriorSample {
+    PosteriorSample {
+        noise: Noise(params[0]),
+        speed: Speed(params[1]),
+        particle_distance_threshold: ParticleDistanceThreshold(params[2]),
+        distance,
+        weight,
+    }
+}
+
+fn to_posterior(
+    population: Vec<WeightedDraw>,
+    tolerance: Float,
+    num_simulations: usize,
+    stop_reason: StopReason,
+) -> AbcPosterior {
+    AbcPosterior {
+        samples: population
+            .into_iter()
+            .map(|(params, distance, weight)| to_sample(params, distance, weight))
+            .collect(),
+        tolerance,
+        num_simulations,
+        stop_reason,
+    }
+}
+
+fn normalize_weights(population: &mut [WeightedDraw]) {
+    let total: Float = population.iter().map(|&(_, _, weight)| weight).sum();
+
+    for (_, _, weight) in population.iter_mut() {
+        *weight /= total;
+    }
+}
+
+/// Per-parameter kernel width: the square root of twice the population's weighted variance
+fn kernel_std(population: &[WeightedDraw]) -> [Float; 3] {
+    std::array::from_fn(|dim| {
+        let mean: Float = population
+            .iter()
+            .map(|(params, _, weight)| weight * params[dim])
+            .sum();
+        let variance: Float = population
+            .iter()
+            .map(|(params, _, weight)| weight * (params[dim] - mean).powi(2))
+            .sum();
+
+        (2.0 * variance).sqrt()
+    })
+}
+
+/// Pick an index with probability proportional to its (normalized) weight
+fn sample_weighted(population: &[WeightedDraw]) -> usize {
+    let mut remaining = random::random::<Float>();
+
+    for (idx, &(_, _, weight)) in population.iter().enumerate() {
+        remaining -= weight;
+        if remaining <= 0.0 {
+            return idx;
+        }
+    }
+
+    population.len() - 1
+}
+
+/// Unnormalized density of an axis-aligned Gaussian kernel. Parameters with zero spread only
+/// match exactly.
+fn gaussian_kernel_density(x: &[Float; 3], center: &[Float; 3], std: &[Float; 3]) -> Float {
+    x.iter()
+        .zip(center)
+        .zip(std)
+        .map(|((x, center), std)| match *std > 0.0 {
+            true => (-(x - center).powi(2) / (2.0 * std * std)).exp() / std,
+            false => Float::from(x == center),
+        })
+        .product()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reference(comparison: &ComparisonOptions) -> SummaryStatistics {
+        random::seed_rng(7);
+        let sim = Simulation::new(
+            20,
+            DomainBoundaryLength(5.0),
+            Noise(0.2),
+            Speed(0.1),
+            RelativeTime(1.0),
+            ParticleDistanceThreshold(1.0),
+        )
+        .unwrap();
+
+        SummaryStatistics::from_simulation(&sim, 5, comparison).unwrap()
+    }
+
+    fn prior() -> Prior {
+        Prior {
+            noise: (0.0, 1.0),
+            speed: (0.05, 0.2),
+            particle_distance_threshold: (0.5, 1.5),
+        }
+    }
+
+    fn options() -> AbcOptions {
+        AbcOptions {
+            num_steps: 5,
+            population_size: 4,
+            num_simulations: 12,
+            num_generations: 2,
+            cancellation: None,
+        }
+    }
+
+    #[test]
+    fn rejection_keeps_the_closest_draws_within_the_prior() {
+        let comparison = ComparisonOptions::new(2.0, 0.5);
+        let reference = reference(&comparison);
+        let prior = prior();
+        let posterior = abc_rejection(
+            &reference,
+            20,
+            DomainBoundaryLength(5.0),
+            RelativeTime(1.0),
+            &prior,
+            &comparison,
+            &options(),
+        )
+        .unwrap();
+
+        assert_eq!(posterior.num_simulations, 12);
+        assert_eq!(posterior.samples.len(), 4);
+        assert!(matches!(posterior.stop_reason, StopReason::Converged));
+
+        let distances: Vec<Float> = posterior.samples.iter().map(|s| s.distance).collect();
+        assert!(distances.is_sorted());
+        assert_eq!(posterior.tolerance, distances[3]);
+
+        for sample in &posterior.samples {
+            let params = [
+                sample.noise.0,
+                sample.speed.0,
+                sample.particle_distance_threshold.0,
+            ];
+            assert!(prior.contains(&params));
+            assert_eq!(sample.weight, 0.25);
+        }
+    }
+
+    #[test]
+    fn smc_stays_within_its_simulation_budget() {
+        let comparison = ComparisonOptions::new(2.0, 0.5);
+        let reference = reference(&comparison);
+        let posterior = abc_smc(
+            &reference,
+            20,
+            DomainBoundaryLength(5.0),
+            RelativeTime(1.0),
+            &prior(),
+            &comparison,
+            &options(),
+        )
+        .unwrap();
+
+        assert!(posterior.num_simulations <= 12);
+        assert_eq!(posterior.samples.len(), 4);
+
+        let total_weight: Float = posterior.samples.iter().map(|s| s.weight).sum();
+        assert!((total_weight - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn invalid_priors_and_budgets_are_rejected() {
+        let comparison = ComparisonOptions::new(2.0, 0.5);
+        let reference = reference(&comparison);
+        let run = |prior: &Prior, options: &AbcOptions| {
+            abc_rejection(
+                &reference,
+                20,
+                DomainBoundaryLength(5.0),
+                RelativeTime(1.0),
+                prior,
+                &comparison,
+                options,
+            )
+        };
+
+        let inverted = Prior {
+            noise: (1.0, 0.0),
+            ..prior()
+        };
+        assert!(run(&inverted, &options()).is_err());
+
+        let too_few = AbcOptions {
+            num_simulations: 2,
+            ..options()
+        };
+        assert!(run(&prior(), &too_few).is_err());
+    }
+}
@@ -1,34 +1,212 @@
-use pyo3::prelude::*;
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{BufReader, BufWriter},
+    path::PathBuf,
+    sync::Arc,
+    time::Duration,
+};
+
+use anyhow::Context;
+use numpy::{PyArray1, PyReadonlyArray1};
+use pyo3::{
+    exceptions::{PyIndexError, PyKeyboardInterrupt, PyRuntimeError, PyValueError},
+    prelude::*,
+    types::{PyBytes, PyDict},
+};
 
+#[cfg(feature = "animation")]
+mod animation;
+mod bands;
+#[cfg(feature = "bench")]
+pub mod bench;
+mod builder;
+#[cfg(feature = "checkpoint")]
+mod checkpoint;
+mod compare;
+mod control;
+mod convergence;
+mod epidemic;
+mod error;
+mod export;
+mod field;
+mod finite_size;
+mod graph;
+#[cfg(feature = "gui")]
+mod gui;
+mod history;
+mod inference;
 mod math;
+mod memory;
+mod neighbors;
+mod noise;
+mod observer;
 mod optimize;
 mod particle;
+mod perf;
+mod random;
+mod render;
+mod scalars;
+mod schedule;
+mod selection;
+mod sensitivity;
+#[cfg(feature = "serve")]
+mod serve;
+mod session;
+mod significance;
 mod simulation;
+mod state_file;
+mod sweep;
+mod tracking;
+mod trajectory;
+mod trigger;
 mod types;
+mod verification;
+#[cfg(feature = "viz")]
+mod viz;
+mod watchdog;
 
 // Exports for pure Rust use
-pub use optimize::optimize_for_critical_noise;
-pub use simulation::{Simulation, SimulationData};
+pub use bands::{
+    BandCollisionOptions, BandCollisionOutcome, BandCollisionResult, LEFTWARD_BAND_TAG,
+    RIGHTWARD_BAND_TAG, run_band_collisions,
+};
+pub use builder::{
+    DEFAULT_BOUNDARY_SIDE_LENGTH, DEFAULT_NOISE, DEFAULT_NUM_PARTICLES,
+    DEFAULT_PARTICLE_DISTANCE_THRESHOLD, DEFAULT_SPEED, DEFAULT_TIMESTEP, SimulationBuilder,
+};
+#[cfg(feature = "checkpoint")]
+pub use checkpoint::AutoCheckpoint;
+pub use compare::{ComparisonOptions, DiscrepancyWeights, SummaryStatistics};
+pub use control::{CancellationToken, StopReason};
+pub use convergence::{TimestepConvergenceOptions, TimestepConvergenceReport, TimestepLevel};
+pub use epidemic::{ContactProcess, EpidemicCurve, SIR_STATE, SirCounts, SirState};
+pub use error::SimulationError;
+#[cfg(feature = "parquet")]
+pub use export::ParquetTrajectoryWriter;
+#[cfg(feature = "arrow")]
+pub use export::{ArrowFrameWriter, trajectory_schema};
+#[cfg(feature = "zstd")]
+pub use export::{CompressedTrajectoryHeader, CompressedTrajectoryWriter, TrajectoryReader};
+pub use export::{
+    CsvTrajectoryWriter, QuantizedFrame, QuantizedTrajectoryWriter, VtkSeriesWriter,
+    XyzTrajectoryWriter, read_quantized_trajectory,
+};
+pub use field::{FlowField, ScalarField};
+pub use finite_size::{FiniteSizeOptions, FiniteSizePoint, FiniteSizeReport};
+pub use graph::InteractionGraph;
+pub use history::{OrderHistory, OrderHistoryLength};
+pub use inference::{AbcOptions, AbcPosterior, PosteriorSample, Prior, abc_rejection, abc_smc};
+pub use memory::{CapacityPlan, MemoryFootprint, plan_capacity};
+pub use neighbors::{distance_threshold_for_neighbors, mean_neighbors_for_distance_threshold};
+pub use noise::NoiseStream;
+pub use observer::StepObserver;
+pub use optimize::{
+    Calibration, CriticalNoiseOptimum, OptimizerOptions, calibrate_to_statistics,
+    optimize_for_critical_noise, optimize_for_critical_noise_with,
+};
+pub use particle::{
+    BirthDeath, CouzinZones, EnergyBudget, InitialCondition, LeaderHeading, NeighborWeighting,
+    NoiseModel, Repulsion, SpeedDistribution, UpdateOrder, UpdateRule,
+};
+pub use perf::{PerformanceCounters, SimulationStats};
+pub use random::{RngState, restore_rng_state, rng_state, seed_rng};
+pub use render::{Colormap, Glyph, ParticleColoring, RenderOptions, Rgb};
+pub use scalars::{ParticleScalars, ParticleView, ScalarRule};
+pub use schedule::{DomainResizeSchedule, NoiseSchedule, Schedule};
+pub use selection::ParticleSelection;
+pub use sensitivity::{
+    ParameterIndices, ParameterRanges, SamplingScheme, SensitivityOptions, SensitivityReport,
+    analyze_sensitivity,
+};
+#[cfg(feature = "serve")]
+pub use serve::{FrameFormat, ServeOptions};
+pub use session::{ParameterChange, Session, SessionChange, SessionRecorder};
+pub use significance::{
+    MeasurementSummary, SignificanceOptions, SignificanceTest, Verdict, compare_measurements,
+};
+pub use simulation::{
+    Region, Simulation, SimulationData, StationaryOrderEstimate, StationaryOrderOptions, Steps,
+};
+pub use sweep::{SweepOptions, SweepPoint, SweepReport, SweepResult, run_sweep};
+pub use tracking::{TrackedPoint, TrackingData};
+pub use trajectory::{Trajectory, TrajectoryFrame, TrajectoryRecorder};
+pub use trigger::{
+    AdaptiveRecording, Crossing, Observable, Trigger, TriggerAction, TriggerEvent, TriggerRunReport,
+};
 pub use types::{
-    AbsoluteTime, DomainBoundaryLength, Float, Noise, ParticleDistanceThreshold, RelativeTime,
-    Speed,
+    AbsoluteTime, Angle, DomainBoundaryLength, Float, Noise, ParticleDistanceThreshold, Quantity,
+    RelativeTime, Speed,
 };
+pub use verification::{VerificationCheck, VerificationOptions, VerificationReport};
+#[cfg(feature = "viz")]
+pub use viz::WatchOptions;
+pub use watchdog::{StepStatistics, Watchdog, read_rng_state};
 
 #[pymodule]
 fn particle_interactions_puzzle(m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_class::<PySimulation>()?;
+    m.add_class::<PyRenderOptions>()?;
     m.add_function(wrap_pyfunction!(py_optimize_for_critical_noise, m)?)?;
+    m.add_function(wrap_pyfunction!(py_plan_capacity, m)?)?;
+    m.add_function(wrap_pyfunction!(py_compare_measurements, m)?)?;
+    m.add_function(wrap_pyfunction!(py_distance_threshold_for_neighbors, m)?)?;
+    m.add_function(wrap_pyfunction!(
+        py_mean_neighbors_for_distance_threshold,
+        m
+    )?)?;
+    m.add_class::<PySteps>()?;
+    m.add_class::<PyTrajectory>()?;
+    m.add_class::<PyQuantizedTrajectoryWriter>()?;
+    m.add_class::<PyCsvTrajectoryWriter>()?;
+    m.add_class::<PyVtkSeriesWriter>()?;
+    m.add_class::<PyXyzTrajectoryWriter>()?;
+    #[cfg(feature = "parquet")]
+    m.add_class::<PyParquetTrajectoryWriter>()?;
+    #[cfg(feature = "arrow")]
+    m.add_class::<PyArrowFrameWriter>()?;
+    #[cfg(feature = "zstd")]
+    m.add_class::<PyCompressedTrajectoryWriter>()?;
+    #[cfg(feature = "zstd")]
+    m.add_class::<PyTrajectoryReader>()?;
+    m.add_function(wrap_pyfunction!(py_read_quantized_trajectory, m)?)?;
+    m.add_function(wrap_pyfunction!(py_run_worker, m)?)?;
+    m.add_function(wrap_pyfunction!(py_run_sweep, m)?)?;
+    m.add_function(wrap_pyfunction!(py_run_band_collisions, m)?)?;
 
     Ok(())
 }
 
-#[pyclass(name = "Simulation")]
+/// Width and height of the snapshot shown in notebooks, in pixels
+const NOTEBOOK_RENDER_SIZE: u32 = 400;
+
+#[pyclass(name = "Simulation", module = "particle_interactions_puzzle")]
 struct PySimulation(Simulation);
 
 #[pymethods]
 impl PySimulation {
-    /// Construct a new particle Simulator
+    /// Construct a new particle Simulator. Every setting is optional, defaulting to the project's
+    /// reference setup (125 particles in a 5x5 domain, noise 0.01, speed 1, timestep 0.25, and
+    /// threshold 1), and is checked up front.
+    ///
+    /// Particles are laid out per `initial_condition`:
+    /// `"uniform"` (the default, all random), `"lattice"` (a square lattice), `"gaussian"` (a blob
+    /// of standard deviation `std_dev` at the center), `"ring"` (a circle of `radius` at the
+    /// center), or `"aligned"` (random positions, all sharing `heading`)
     #[new]
+    #[pyo3(signature = (
+        num_particles = DEFAULT_NUM_PARTICLES,
+        boundary_side_length = DEFAULT_BOUNDARY_SIDE_LENGTH.0,
+        noise = DEFAULT_NOISE.0,
+        speed = DEFAULT_SPEED.0,
+        timestep = DEFAULT_TIMESTEP.0,
+        particle_distance_threshold = DEFAULT_PARTICLE_DISTANCE_THRESHOLD.0,
+        initial_condition = "uniform",
+        std_dev = None,
+        radius = None,
+        heading = None,
+    ))]
+    #[allow(clippy::too_many_arguments)]
     fn new(
         num_particles: usize,
         boundary_side_length: Float,
@@ -36,20 +214,76 @@ impl PySimulation {
         speed: Float,
         timestep: Float,
         particle_distance_threshold: Float,
+        initial_condition: &str,
+        std_dev: Option<Float>,
+        radius: Option<Float>,
+        heading: Option<Float>,
     ) -> PyResult<Self> {
-        let boundary_side_length = DomainBoundaryLength(boundary_side_length);
-        let noise = Noise(noise);
-        let speed = Speed(speed);
-        let timestep = RelativeTime(timestep);
-        let particle_distance_threshold = ParticleDistanceThreshold(particle_distance_threshold);
+        let initial_condition = match (initial_condition, std_dev, radius, heading) {
+            ("uniform", None, None, None) => InitialCondition::UniformRandom,
+            ("lattice", None, None, None) => InitialCondition::SquareLattice,
+            ("gaussian", Some(std_dev), None, None) => InitialCondition::GaussianBlob { std_dev },
+            ("ring", None, Some(radius), None) => InitialCondition::Ring { radius },
+            ("aligned", None, None, Some(heading)) => InitialCondition::Aligned { heading },
+            ("uniform" | "lattice" | "gaussian" | "ring" | "aligned", ..) => {
+                return Err(anyhow::anyhow!(
+                    "wrong arguments for the `{}` initial condition: gaussian takes only \
+                     `std_dev`, ring only `radius`, aligned only `heading`, and the rest none",
+                    initial_condition
+                )
+                .into());
+            }
+            (kind, ..) => {
+                return Err(anyhow::anyhow!("unknown initial condition `{}`", kind).into());
+            }
+        };
 
-        Ok(Self(Simulation::new(
-            num_particles,
-            boundary_side_length,
-            noise,
-            speed,
-            timestep,
-            particle_distance_threshold,
+        Ok(Self(
+            Simulation::builder()
+                .num_particles(num_particles)
+                .boundary_side_length(DomainBoundaryLength(boundary_side_length))
+                .noise(Noise(noise))
+                .speed(Speed(speed))
+                .timestep(RelativeTime(timestep))
+                .particle_distance_threshold(ParticleDistanceThreshold(particle_distance_threshold))
+                .initial_condition(initial_condition)
+                .build()?,
+        ))
+    }
+
+    /// Construct a particle Simulator from prescribed positions and headings, given as equal-length
+    /// arrays in particle order
+    #[staticmethod]
+    #[allow(clippy::too_many_arguments)]
+    fn with_particles(
+        x: Vec<Float>,
+        y: Vec<Float>,
+        theta: Vec<Float>,
+        boundary_side_length: Float,
+        noise: Float,
+        speed: Float,
+        timestep: Float,
+        particle_distance_threshold: Float,
+    ) -> PyResult<Self> {
+        if x.len() != y.len() {
+            return Err(anyhow::anyhow!(
+                "got `{}` x coordinates but `{}` y coordinates",
+                x.len(),
+                y.len()
+            )
+            .into());
+        }
+
+        let positions: Vec<_> = x.into_iter().zip(y).collect();
+
+        Ok(Self(Simulation::with_particles(
+            &positions,
+            &theta,
+            DomainBoundaryLength(boundary_side_length),
+            Noise(noise),
+            Speed(speed),
+            RelativeTime(timestep),
+            ParticleDistanceThreshold(particle_distance_threshold),
         )?))
     }
 
@@ -58,75 +292,2892 @@ impl PySimulation {
         Self(self.0.to_timestepped())
     }
 
+    /// Step `num_steps` times in place, without a round trip to Python per step
+    fn run_for(&mut self, py: Python<'_>, num_steps: usize) -> PyResult<()> {
+        Ok(py.allow_threads(|| self.0.run_for(num_steps))?)
+    }
+
+    /// Step `n` times in place, returning the simulation itself for chaining
+    #[pyo3(signature = (n = 1))]
+    fn step(mut slf: PyRefMut<'_, Self>, n: usize) -> PyResult<PyRefMut<'_, Self>> {
+        let py = slf.py();
+        let sim = &mut slf.0;
+        py.allow_threads(|| sim.run_for(n))?;
+        Ok(slf)
+    }
+
+    /// Step in place until the simulated time reaches `time`, returning the simulation itself for
+    /// chaining
+    fn run_until(mut slf: PyRefMut<'_, Self>, time: Float) -> PyResult<PyRefMut<'_, Self>> {
+        let py = slf.py();
+        let sim = &mut slf.0;
+        py.allow_threads(|| sim.run_until(AbsoluteTime(time)))?;
+        Ok(slf)
+    }
+
+    /// Step `num_steps` times in place, recording positions and headings at the start and then
+    /// every `stride`-th step
+    #[pyo3(signature = (num_steps, stride = 1))]
+    fn record_trajectory(
+        &mut self,
+        py: Python<'_>,
+        num_steps: usize,
+        stride: usize,
+    ) -> PyResult<PyTrajectory> {
+        let trajectory = py.allow_threads(|| self.0.record_trajectory(num_steps, stride))?;
+
+        Ok(PyTrajectory(trajectory))
+    }
+
+    /// Resize the domain, optionally stretching particle positions along with it
+    #[pyo3(signature = (boundary_side_length, rescale_positions = true))]
+    fn to_resized(&self, boundary_side_length: Float, rescale_positions: bool) -> PyResult<Self> {
+        Ok(Self(self.0.to_resized(
+            DomainBoundaryLength(boundary_side_length),
+            rescale_positions,
+        )?))
+    }
+
+    /// Resize the domain over time, linearly interpolating between `(time, length)` keyframes
+    #[pyo3(signature = (times, boundary_side_lengths, rescale_positions = true))]
+    fn set_domain_schedule(
+        &mut self,
+        times: Vec<Float>,
+        boundary_side_lengths: Vec<Float>,
+        rescale_positions: bool,
+    ) -> PyResult<()> {
+        if times.len() != boundary_side_lengths.len() {
+            return Err(anyhow::anyhow!(
+                "got `{}` times but `{}` boundary side lengths",
+                times.len(),
+                boundary_side_lengths.len()
+            )
+            .into());
+        }
+
+        let keyframes = times
+            .into_iter()
+            .zip(boundary_side_lengths)
+            .map(|(time, length)| (AbsoluteTime(time), DomainBoundaryLength(length)))
+            .collect();
+        let domain_schedule =
+            DomainResizeSchedule::new(Schedule::new(keyframes)?, rescale_positions)?;
+
+        self.0.domain_schedule = Some(domain_schedule);
+
+        Ok(())
+    }
+
+    /// Vary the noise over time, linearly interpolating between `(time, noise)` keyframes
+    fn set_noise_schedule(&mut self, times: Vec<Float>, noises: Vec<Float>) -> PyResult<()> {
+        if times.len() != noises.len() {
+            return Err(anyhow::anyhow!(
+                "got `{}` times but `{}` noise amplitudes",
+                times.len(),
+                noises.len()
+            )
+            .into());
+        }
+
+        let keyframes = times
+            .into_iter()
+            .zip(noises)
+            .map(|(time, noise)| (AbsoluteTime(time), Noise(noise)))
+            .collect();
+
+        self.0.noise_schedule = Some(NoiseSchedule::piecewise(Schedule::new(keyframes)?)?);
+
+        Ok(())
+    }
+
+    /// Go back to the fixed noise parameter
+    fn clear_noise_schedule(&mut self) {
+        self.0.noise_schedule = None;
+    }
+
+    /// Change the noise amplitude, keeping the current particle state
+    fn with_noise(&self, noise: Float) -> PyResult<Self> {
+        Ok(Self(self.0.clone().with_noise(Noise(noise))?))
+    }
+
+    /// Change the particle speed, keeping the current particle state
+    fn with_speed(&self, speed: Float) -> PyResult<Self> {
+        Ok(Self(self.0.clone().with_speed(Speed(speed))?))
+    }
+
+    /// Change the timestep, keeping the current particle state
+    fn with_timestep(&self, timestep: Float) -> PyResult<Self> {
+        Ok(Self(self.0.clone().with_timestep(RelativeTime(timestep))?))
+    }
+
+    /// Change the interaction radius, keeping the current particle state
+    fn with_distance_threshold(&self, particle_distance_threshold: Float) -> PyResult<Self> {
+        Ok(Self(self.0.clone().with_distance_threshold(
+            ParticleDistanceThreshold(particle_distance_threshold),
+        )?))
+    }
+
+    /// Limit perception to a vision cone of `half_angle` radians either side of each particle's
+    /// heading, or restore the full circle with `None`
+    #[pyo3(signature = (half_angle = None))]
+    fn with_vision_cone(&self, half_angle: Option<Float>) -> PyResult<Self> {
+        Ok(Self(
+            self.0.clone().with_vision_cone(half_angle.map(Angle))?,
+        ))
+    }
+
+    /// Weight neighbors by distance when averaging their headings: one of `"uniform"`,
+    /// `"linear"`, or `"gaussian"` (which also needs a `width`)
+    #[pyo3(signature = (kind, width = None))]
+    fn with_neighbor_weighting(&self, kind: &str, width: Option<Float>) -> PyResult<Self> {
+        let neighbor_weighting = match (kind, width) {
+            ("uniform", None) => NeighborWeighting::Uniform,
+            ("linear", None) => NeighborWeighting::LinearDecay,
+            ("gaussian", Some(width)) => NeighborWeighting::Gaussian { width },
+            ("gaussian", None) => {
+                return Err(anyhow::anyhow!("gaussian neighbor weighting needs a `width`").into());
+            }
+            _ => {
+                return Err(anyhow::anyhow!(
+                    "unknown neighbor weighting `{}` (with width `{:?}`)",
+                    kind,
+                    width
+                )
+                .into());
+            }
+        };
+
+        Ok(Self(
+            self.0.clone().with_neighbor_weighting(neighbor_weighting)?,
+        ))
+    }
+
+    /// Switch the heading update rule: `"vicsek"`, `"backward_vicsek"` (moving along the new
+    /// heading instead of the old one), `"couzin"` (which also needs the
+    /// `repulsion_radius`, `orientation_radius`, and `attraction_radius` of its zones), or
+    /// `"active_brownian"` (which also needs a `rotational_diffusion` coefficient)
+    #[pyo3(signature = (
+        kind,
+        repulsion_radius = None,
+        orientation_radius = None,
+        attraction_radius = None,
+        rotational_diffusion = None,
+    ))]
+    fn with_update_rule(
+        &self,
+        kind: &str,
+        repulsion_radius: Option<Float>,
+        orientation_radius: Option<Float>,
+        attraction_radius: Option<Float>,
+        rotational_diffusion: Option<Float>,
+    ) -> PyResult<Self> {
+        let zones = (repulsion_radius, orientation_radius, attraction_radius);
+
+        let update_rule = match (kind, zones, rotational_diffusion) {
+            ("vicsek", (None, None, None), None) => UpdateRule::Vicsek,
+            ("backward_vicsek", (None, None, None), None) => UpdateRule::BackwardVicsek,
+            (
+                "couzin",
+                (Some(repulsion_radius), Some(orientation_radius), Some(attraction_radius)),
+                None,
+            ) => UpdateRule::Couzin(CouzinZones {
+                repulsion_radius,
+                orientation_radius,
+                attraction_radius,
+            }),
+            ("active_brownian", (None, None, None), Some(rotational_diffusion)) => {
+                UpdateRule::ActiveBrownian {
+                    rotational_diffusion,
+                }
+            }
+            ("vicsek" | "backward_vicsek" | "couzin" | "active_brownian", ..) => {
+                return Err(anyhow::anyhow!(
+                    "wrong arguments for the `{}` update rule: couzin takes only the zone radii, \
+                     active_brownian only `rotational_diffusion`, and the vicsek rules neither",
+                    kind
+                )
+                .into());
+            }
+            (kind, ..) => {
+                return Err(anyhow::anyhow!("unknown update rule `{}`", kind).into());
+            }
+        };
+
+        Ok(Self(self.0.clone().with_update_rule(update_rule)?))
+    }
+
+    /// Switch the noise model: `"vectorial"` (the default, a noise vector added before taking
+    /// the angle) or `"scalar"` (a uniform angle in [-η/2, η/2] added after averaging)
+    fn with_noise_model(&self, kind: &str) -> PyResult<Self> {
+        let noise_model = match kind {
+            "vectorial" => NoiseModel::Vectorial,
+            "scalar" => NoiseModel::Scalar,
+            kind => return Err(anyhow::anyhow!("unknown noise model `{}`", kind).into()),
+        };
+
+        Ok(Self(self.0.clone().with_noise_model(noise_model)))
+    }
+
+    /// Ignore neighbors hidden behind a closer neighbor within `tolerance` of the line of sight,
+    /// or see everyone in range again with `tolerance=None`
+    #[pyo3(signature = (tolerance = None))]
+    fn with_occlusion(&self, tolerance: Option<Float>) -> PyResult<Self> {
+        Ok(Self(self.0.clone().with_occlusion(tolerance)?))
+    }
+
+    /// Jitter positions each step with translational noise of diffusion coefficient
+    /// `translational_diffusion`, or turn it off with `None`
+    #[pyo3(signature = (translational_diffusion = None))]
+    fn with_translational_noise(&self, translational_diffusion: Option<Float>) -> PyResult<Self> {
+        Ok(Self(
+            self.0
+                .clone()
+                .with_translational_noise(translational_diffusion)?,
+        ))
+    }
+
+    /// Switch the update order: `"synchronous"` (the default, everyone updates at once) or
+    /// `"random_sequential"` (one at a time in random order, each seeing earlier updates)
+    fn with_update_order(&self, kind: &str) -> PyResult<Self> {
+        let update_order = match kind {
+            "synchronous" => UpdateOrder::Synchronous,
+            "random_sequential" => UpdateOrder::RandomSequential,
+            kind => return Err(anyhow::anyhow!("unknown update order `{}`", kind).into()),
+        };
+
+        Ok(Self(self.0.clone().with_update_order(update_order)))
+    }
+
+    /// Turn headings towards their target over a relaxation time `tau` instead of snapping to it
+    /// each step. Disable with `tau=None`.
+    #[pyo3(signature = (tau = None))]
+    fn with_heading_relaxation(&self, tau: Option<Float>) -> PyResult<Self> {
+        Ok(Self(
+            self.0
+                .clone()
+                .with_heading_relaxation(tau.map(RelativeTime))?,
+        ))
+    }
+
+    /// Push apart particles closer than `radius`, with a repulsion speed of `strength` between
+    /// coincident particles that decays to zero at the radius. Disable with `radius=None`.
+    #[pyo3(signature = (radius = None, strength = 1.0))]
+    fn with_repulsion(&self, radius: Option<Float>, strength: Float) -> PyResult<Self> {
+        let repulsion = radius.map(|radius| Repulsion { radius, strength });
+
+        Ok(Self(self.0.clone().with_repulsion(repulsion)?))
+    }
+
+    /// Start recording every noise draw from here on, for later replay
+    fn with_noise_recording(&self) -> Self {
+        Self(self.0.clone().with_noise_recording())
+    }
+
+    /// Get the noise draws recorded so far (starting phases first, then one list per step), if
+    /// recording
+    fn noise_recording(&self) -> Option<Vec<Vec<Float>>> {
+        self.0
+            .noise_recording()
+            .map(|stream| stream.iter().map(|phases| phases.to_vec()).collect())
+    }
+
+    /// Check every step taken in place for NaNs and, if limits are given, particles moving too far
+    /// or the order parameter jumping too much in one step. On an anomaly the states before and
+    /// after the step and the random state are written to `dump_dir`, and a RuntimeError is
+    /// raised. Stop checking with the default `dump_dir=None`.
+    #[pyo3(signature = (dump_dir = None, max_displacement = None, max_order_jump = None))]
+    fn with_watchdog(
+        &self,
+        dump_dir: Option<PathBuf>,
+        max_displacement: Option<Float>,
+        max_order_jump: Option<Float>,
+    ) -> PyResult<Self> {
+        let watchdog = dump_dir.map(|dump_dir| Watchdog {
+            dump_dir,
+            max_displacement,
+            max_order_jump,
+        });
+
+        Ok(Self(self.0.clone().with_watchdog(watchdog)?))
+    }
+
+    /// Start keeping the instantaneous order parameter after every step, the last `max_len`
+    /// entries only if given, or stop keeping it with `enabled=False`
+    #[pyo3(signature = (enabled = true, max_len = None))]
+    fn with_order_history(&self, enabled: bool, max_len: Option<usize>) -> PyResult<Self> {
+        let length = enabled.then_some(match max_len {
+            Some(max_len) => OrderHistoryLength::Last(max_len),
+            None => OrderHistoryLength::Full,
+        });
+
+        Ok(Self(self.0.clone().with_order_history(length)?))
+    }
+
+    /// The order parameter history kept so far as `(times, orders)`, if keeping one
+    fn order_history(&self) -> Option<(Vec<Float>, Vec<Float>)> {
+        self.0
+            .order_history()
+            .map(|history| (history.times(), history.orders()))
+    }
+
+    /// Replay recorded noise draws (as returned by `noise_recording`) instead of drawing fresh
+    /// noise
+    fn with_noise_replay(&self, draws: Vec<Vec<Float>>) -> PyResult<Self> {
+        let stream = NoiseStream::new(draws)?;
+
+        Ok(Self(self.0.clone().with_noise_replay(stream)?))
+    }
+
+    /// Overwrite every particle's position and heading from NumPy arrays, in particle order, e.g.
+    /// to impose a custom initial condition or restore a saved state. Each array needs one entry
+    /// per particle.
+    fn set_state(
+        &mut self,
+        x: PyReadonlyArray1<'_, Float>,
+        y: PyReadonlyArray1<'_, Float>,
+        theta: PyReadonlyArray1<'_, Float>,
+    ) -> PyResult<()> {
+        let [x, y, theta] = [x, y, theta].map(|values| values.as_array().to_vec());
+
+        Ok(self.0.set_state(&x, &y, &theta)?)
+    }
+
+    /// Snapshot the full simulation for pickling, e.g. to ship it to a multiprocessing worker.
+    /// Observers and the random state aren't carried over, and closure-based settings (function
+    /// fields, noise schedules, and scalar rules) raise `TypeError` rather than being dropped.
+    #[cfg(feature = "checkpoint")]
+    fn __getstate__<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyBytes>> {
+        let state = self.0.to_snapshot_bytes().map_err(|error| {
+            pyo3::exceptions::PyTypeError::new_err(format!("cannot pickle simulation: {error}"))
+        })?;
+
+        Ok(PyBytes::new(py, &state))
+    }
+
+    /// Restore a snapshot taken by `__getstate__`
+    #[cfg(feature = "checkpoint")]
+    fn __setstate__(&mut self, state: &[u8]) -> PyResult<()> {
+        self.0 = Simulation::from_snapshot_bytes(state)?;
+
+        Ok(())
+    }
+
+    /// Get a human-readable JSON snapshot of the core parameters, time, and every particle's
+    /// state, e.g. for a bug report or a test fixture
+    fn to_json(&self) -> String {
+        self.0.to_json()
+    }
+
+    /// Restore a snapshot from `to_json`, or start from any JSON state file's contents
+    #[staticmethod]
+    fn from_json(json: &str) -> PyResult<Self> {
+        Ok(Self(Simulation::from_json(json)?))
+    }
+
+    /// Instantiate a simulator from a CSV or JSON file of per-particle `x`, `y`, and `theta` plus
+    /// the simulation parameters (see the Rust `Simulation::from_file` for the formats)
+    #[staticmethod]
+    fn from_file(path: PathBuf) -> PyResult<Self> {
+        Ok(Self(Simulation::from_file(path)?))
+    }
+
+    /// Replay a session saved from the control panel step for step, from the state it started
+    /// from, and return the simulation at its end
+    #[staticmethod]
+    fn replay_session(path: PathBuf) -> PyResult<Self> {
+        let session = Session::load(path)?;
+        let mut sim = session.initial_simulation()?;
+        sim.replay_session(&session, |_| Ok(()))?;
+
+        Ok(Self(sim))
+    }
+
+    /// Instantiate a simulator with two aligned bands of `num_particles_per_band` heading at each
+    /// other, tagged 0 (heading right) and 1 (heading left)
+    #[staticmethod]
+    #[allow(clippy::too_many_arguments)]
+    fn from_counter_propagating_bands(
+        num_particles_per_band: usize,
+        band_width: Float,
+        boundary_side_length: Float,
+        noise: Float,
+        speed: Float,
+        timestep: Float,
+        particle_distance_threshold: Float,
+    ) -> PyResult<Self> {
+        Ok(Self(Simulation::from_counter_propagating_bands(
+            num_particles_per_band,
+            band_width,
+            DomainBoundaryLength(boundary_side_length),
+            Noise(noise),
+            Speed(speed),
+            RelativeTime(timestep),
+            ParticleDistanceThreshold(particle_distance_threshold),
+        )?))
+    }
+
+    /// Classify how two counter-propagating bands ended up: `"merged"`, `"passed_through"`,
+    /// `"reflected"`, or `"dispersed"` (a band's polarization fell below `polarized_threshold`)
+    #[pyo3(signature = (polarized_threshold = 0.5))]
+    fn band_collision_outcome(&self, polarized_threshold: Float) -> &'static str {
+        band_collision_outcome_name(self.0.band_collision_outcome(polarized_threshold))
+    }
+
+    /// Instantiate a simulator from one frame of a tracking CSV (columns `frame`, `id`, `x`, `y`,
+    /// and optionally `heading`), optionally estimating headings from each particle's displacement
+    #[staticmethod]
+    #[pyo3(signature = (
+        path,
+        frame,
+        boundary_side_length,
+        noise,
+        speed,
+        timestep,
+        particle_distance_threshold,
+        estimate_heading = false,
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    fn from_tracking_csv(
+        path: PathBuf,
+        frame: u64,
+        boundary_side_length: Float,
+        noise: Float,
+        speed: Float,
+        timestep: Float,
+        particle_distance_threshold: Float,
+        estimate_heading: bool,
+    ) -> PyResult<Self> {
+        let tracking = TrackingData::from_csv_file(path)?;
+        let points = tracking.to_headed_frame(frame, estimate_heading)?;
+
+        Ok(Self(Simulation::from_tracking(
+            &points,
+            DomainBoundaryLength(boundary_side_length),
+            Noise(noise),
+            Speed(speed),
+            RelativeTime(timestep),
+            ParticleDistanceThreshold(particle_distance_threshold),
+        )?))
+    }
+
+    /// Label every particle with the same tag
+    fn to_tagged(&self, tag: usize) -> Self {
+        Self(self.0.to_tagged(tag))
+    }
+
+    /// Merge another simulation's particles into this simulation's domain, re-assigning IDs
+    fn to_merged(&self, other: &Self) -> Self {
+        Self(self.0.to_merged(&other.0))
+    }
+
+    /// Split into the simulations inside and outside of a rectangular region
+    fn split_by_region(
+        &self,
+        x_min: Float,
+        x_max: Float,
+        y_min: Float,
+        y_max: Float,
+    ) -> PyResult<(Self, Self)> {
+        let region = Region {
+            x_min,
+            x_max,
+            y_min,
+            y_max,
+        };
+        let (inside, outside) = self.0.split_by_region(&region)?;
+
+        Ok((Self(inside), Self(outside)))
+    }
+
+    /// Split into one simulation per particle tag
+    fn split_by_tag(&self) -> BTreeMap<usize, Self> {
+        self.0
+            .split_by_tag()
+            .into_iter()
+            .map(|(tag, sim)| (tag, Self(sim)))
+            .collect()
+    }
+
+    /// Make the given particles leaders holding a fixed heading
+    fn set_leaders(&mut self, ids: Vec<usize>, heading: Float) -> PyResult<()> {
+        Ok(self
+            .0
+            .set_leaders(&ids, LeaderHeading::Fixed(Angle(heading)))?)
+    }
+
+    /// Make the given particles leaders following a heading trajectory, linearly interpolated
+    /// between `(time, heading)` keyframes
+    fn set_leader_trajectory(
+        &mut self,
+        ids: Vec<usize>,
+        times: Vec<Float>,
+        headings: Vec<Float>,
+    ) -> PyResult<()> {
+        if times.len() != headings.len() {
+            return Err(anyhow::anyhow!(
+                "got `{}` times but `{}` headings",
+                times.len(),
+                headings.len()
+            )
+            .into());
+        }
+
+        let keyframes = times
+            .into_iter()
+            .zip(headings)
+            .map(|(time, heading)| (AbsoluteTime(time), Angle(heading)))
+            .collect();
+        let leader_heading = LeaderHeading::Prescribed(Arc::new(Schedule::new(keyframes)?));
+
+        Ok(self.0.set_leaders(&ids, leader_heading)?)
+    }
+
+    /// Return the given particles to normal alignment dynamics
+    fn clear_leaders(&mut self, ids: Vec<usize>) -> PyResult<()> {
+        Ok(self.0.clear_leaders(&ids)?)
+    }
+
+    /// Give each particle its own speed drawn from a distribution: `"uniform"` (between `a` and
+    /// `b`), `"normal"` (mean `a`, standard deviation `b`), or `"lognormal"` (log-mean `a`,
+    /// log-standard deviation `b`)
+    fn with_speed_distribution(&self, kind: &str, a: Float, b: Float) -> PyResult<Self> {
+        let speed_distribution = match kind {
+            "uniform" => SpeedDistribution::Uniform { min: a, max: b },
+            "normal" => SpeedDistribution::Normal {
+                mean: a,
+                std_dev: b,
+            },
+            "lognormal" => SpeedDistribution::LogNormal { mu: a, sigma: b },
+            kind => return Err(anyhow::anyhow!("unknown speed distribution `{}`", kind).into()),
+        };
+
+        Ok(Self(
+            self.0.clone().with_speed_distribution(speed_distribution)?,
+        ))
+    }
+
+    /// Let the noise amplitude vary over the domain as a square grid of cells, given as rows
+    /// starting at `y = 0`. Go back to the uniform noise with `rows=None`.
+    #[pyo3(signature = (rows = None))]
+    fn with_noise_grid(&self, rows: Option<Vec<Vec<Float>>>) -> PyResult<Self> {
+        let noise_field = rows.map(ScalarField::grid).transpose()?;
+
+        Ok(Self(self.0.clone().with_noise_field(noise_field)?))
+    }
+
+    /// Let the speed vary over the domain as a square grid of cells, given as rows starting at
+    /// `y = 0`. Go back to the uniform speed with `rows=None`.
+    #[pyo3(signature = (rows = None))]
+    fn with_speed_grid(&self, rows: Option<Vec<Vec<Float>>>) -> PyResult<Self> {
+        let speed_field = rows.map(ScalarField::grid).transpose()?;
+
+        Ok(Self(self.0.clone().with_speed_field(speed_field)?))
+    }
+
+    /// Carry particles along with a background flow: `"uniform"` (velocity `(a, b)`), `"shear"`
+    /// (rate `a`), or `"taylor_green"` (vortices of amplitude `a`). Remove it with `kind=None`.
+    #[pyo3(signature = (kind = None, a = 0.0, b = 0.0))]
+    fn with_flow(&self, kind: Option<&str>, a: Float, b: Float) -> PyResult<Self> {
+        let flow_field = match kind {
+            None => None,
+            Some("uniform") => Some(FlowField::Uniform { u: a, v: b }),
+            Some("shear") => Some(FlowField::Shear { rate: a }),
+            Some("taylor_green") => Some(FlowField::TaylorGreen { amplitude: a }),
+            Some(kind) => return Err(anyhow::anyhow!("unknown flow field `{}`", kind).into()),
+        };
+
+        Ok(Self(self.0.clone().with_flow_field(flow_field)))
+    }
+
+    /// Run the built-in checks against this simulation's configuration, returning a dict per
+    /// check with its `name`, whether it `passed`, and a `detail` message
+    #[pyo3(signature = (num_steps = 500))]
+    fn verify<'py>(&self, py: Python<'py>, num_steps: usize) -> PyResult<Vec<Bound<'py, PyDict>>> {
+        let options = VerificationOptions {
+            num_steps,
+            ..Default::default()
+        };
+
+        self.0
+            .verify(&options)?
+            .checks
+            .into_iter()
+            .map(|check| {
+                let dict = PyDict::new(py);
+                dict.set_item("name", check.name)?;
+                dict.set_item("passed", check.passed)?;
+                dict.set_item("detail", check.detail)?;
+
+                Ok(dict)
+            })
+            .collect()
+    }
+
+    /// Let particles randomly give birth (offspring appear within `spawn_radius`) and die, at
+    /// the given rates per particle per unit time. Go back to a fixed population with
+    /// `birth_rate=None`.
+    #[pyo3(signature = (birth_rate = None, death_rate = 0.0, spawn_radius = 0.0))]
+    fn with_birth_death(
+        &self,
+        birth_rate: Option<Float>,
+        death_rate: Float,
+        spawn_radius: Float,
+    ) -> PyResult<Self> {
+        let birth_death = birth_rate.map(|birth_rate| BirthDeath {
+            birth_rate,
+            death_rate,
+            spawn_radius,
+        });
+
+        Ok(Self(self.0.clone().with_birth_death(birth_death)?))
+    }
+
+    /// Give every particle an energy budget of `capacity` that depletes by `depletion_rate` per
+    /// unit distance moved and recovers by `recovery_rate` per unit time at rest (plus
+    /// `crowd_recovery_rate` per neighbor), slowing tired particles down. Remove it with
+    /// `capacity=None`.
+    #[pyo3(signature = (
+        capacity = None,
+        depletion_rate = 0.0,
+        recovery_rate = 0.0,
+        crowd_recovery_rate = 0.0,
+    ))]
+    fn with_energy_budget(
+        &self,
+        capacity: Option<Float>,
+        depletion_rate: Float,
+        recovery_rate: Float,
+        crowd_recovery_rate: Float,
+    ) -> PyResult<Self> {
+        let energy_budget = capacity.map(|capacity| EnergyBudget {
+            capacity,
+            depletion_rate,
+            recovery_rate,
+            crowd_recovery_rate,
+        });
+
+        Ok(Self(self.0.clone().with_energy_budget(energy_budget)?))
+    }
+
+    /// Add a particle at a position and heading, returning its ID
+    fn add_particle(&mut self, x: Float, y: Float, theta: Float) -> PyResult<usize> {
+        Ok(self.0.add_particle(x, y, theta)?)
+    }
+
+    /// Remove the particle with the given ID. Every other particle keeps its ID.
+    fn remove_particle(&mut self, id: usize) -> PyResult<()> {
+        Ok(self.0.remove_particle(id)?)
+    }
+
+    /// Make a random fraction of the particles turn away from their neighbors instead of aligning
+    fn set_dissenter_fraction(&mut self, fraction: Float) -> PyResult<()> {
+        Ok(self.0.set_dissenter_fraction(fraction)?)
+    }
+
+    /// Give each particle its own noise amplitude, in particle order
+    fn set_particle_noises(&mut self, noises: Vec<Float>) -> PyResult<()> {
+        let noises: Vec<_> = noises.into_iter().map(Noise).collect();
+
+        Ok(self.0.set_particle_noises(&noises)?)
+    }
+
+    /// Revert every particle to the simulation's noise amplitude
+    fn clear_particle_noises(&mut self) {
+        self.0.clear_particle_noises();
+    }
+
+    /// Give each particle its own reaction delay in steps, in particle order
+    fn set_reaction_delays(&mut self, delays: Vec<usize>) -> PyResult<()> {
+        Ok(self.0.set_reaction_delays(&delays)?)
+    }
+
+    /// Make every particle react to its neighbors' current headings again
+    fn clear_reaction_delays(&mut self) {
+        self.0.clear_reaction_delays();
+    }
+
+    /// Give each particle its own interaction radius, in particle order, so the Vicsek rules align
+    /// it with the neighbors within its own radius
+    fn set_interaction_radii(&mut self, radii: Vec<Float>) -> PyResult<()> {
+        let radii: Vec<_> = radii.into_iter().map(ParticleDistanceThreshold).collect();
+
+        Ok(self.0.set_interaction_radii(&radii)?)
+    }
+
+    /// Revert every particle to the simulation's distance threshold
+    fn clear_interaction_radii(&mut self) {
+        self.0.clear_interaction_radii();
+    }
+
+    /// Snapshot who aligns with whom as a directed graph, returning a dict with the particle
+    /// `ids`, the (particle, neighbor) `edges`, each particle's `in_degree` and `out_degree`, the
+    /// edges' `reciprocity`, and the graph in Graphviz `dot` format
+    fn interaction_graph<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let graph = self.0.interaction_graph();
+
+        let dict = PyDict::new(py);
+        dict.set_item("in_degree", graph.in_degrees())?;
+        dict.set_item("out_degree", graph.out_degrees())?;
+        dict.set_item("reciprocity", graph.reciprocity())?;
+        dict.set_item("dot", graph.to_dot())?;
+        dict.set_item("ids", graph.ids)?;
+        dict.set_item("edges", graph.edges)?;
+
+        Ok(dict)
+    }
+
+    /// Step up to `max_steps` times, firing triggers as observables cross thresholds. Each trigger
+    /// is a dict with an `observable` (`"order"`, `"num_particles"`, `"time"`, or the mean heading
+    /// components `"polarization_x"` and `"polarization_y"`), a threshold as either `above` or
+    /// `below`, how many consecutive steps to `sustain` it for (default 1), and an `action`:
+    /// `"mark"` (only note the event), `"stop"`, `"snapshot"`, `"record"` (every `stride` steps from then on), or
+    /// `"set_noise"`/`"set_speed"`/`"set_distance_threshold"` to a `value`. Triggers fire once
+    /// unless `rearm` is set.
+    ///
+    /// Returns a dict with the final `simulation`, the `num_steps` taken, the `events` (dicts of
+    /// `trigger` index, `step`, and `value`), the `snapshots`, the recorded `(step, simulation)`
+    /// `frames`, and the index of the trigger the run was `stopped_by`, if any.
+    fn run_with_triggers<'py>(
+        &self,
+        py: Python<'py>,
+        max_steps: usize,
+        triggers: Vec<Bound<'py, PyDict>>,
+    ) -> PyResult<Bound<'py, PyDict>> {
+        let triggers = triggers
+            .iter()
+            .map(trigger_from_dict)
+            .collect::<PyResult<Vec<_>>>()?;
+
+        let report = py.allow_threads(|| {
+            self.0
+                .clone()
+                .run_with_triggers(max_steps, &triggers, Some(&interrupt_token()))
+        })?;
+
+        trigger_report_to_dict(py, report)
+    }
+
+    /// Like `run_with_triggers`, but also stream a quantized trajectory to `path`: a frame every
+    /// `sparse_stride` steps, and every `dense_stride` steps from `steps_before` steps before to
+    /// `steps_after` steps after each step at which a trigger fires. Use the `"mark"` action for
+    /// triggers that should only mark events.
+    #[pyo3(signature = (
+        path,
+        max_steps,
+        triggers,
+        sparse_stride = 100,
+        dense_stride = 1,
+        steps_before = 50,
+        steps_after = 200,
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    fn run_with_adaptive_recording<'py>(
+        &self,
+        py: Python<'py>,
+        path: PathBuf,
+        max_steps: usize,
+        triggers: Vec<Bound<'py, PyDict>>,
+        sparse_stride: usize,
+        dense_stride: usize,
+        steps_before: usize,
+        steps_after: usize,
+    ) -> PyResult<Bound<'py, PyDict>> {
+        let triggers = triggers
+            .iter()
+            .map(trigger_from_dict)
+            .collect::<PyResult<Vec<_>>>()?;
+        let recording = AdaptiveRecording {
+            sparse_stride,
+            dense_stride,
+            steps_before,
+            steps_after,
+        };
+
+        let file = File::create(&path)
+            .with_context(|| format!("could not create trajectory `{}`", path.display()))?;
+        let mut writer = QuantizedTrajectoryWriter::new(BufWriter::new(file))?;
+
+        let report = py.allow_threads(|| {
+            self.0.clone().run_with_adaptive_recording(
+                max_steps,
+                &triggers,
+                &recording,
+                &mut writer,
+                Some(&interrupt_token()),
+            )
+        });
+
+        // Keep whatever was recorded, even if the run failed part way
+        writer.into_inner()?;
+
+        trigger_report_to_dict(py, report?)
+    }
+
     /// Compute the stationary order parameter
-    fn compute_stationary_order_parameter(&self) -> PyResult<Float> {
-        // We have to Ok(fn()?) to coerce anyhow error into PyResult
-        Ok(self.0.compute_stationary_order_parameter()?)
+    fn compute_stationary_order_parameter(&self, py: Python<'_>) -> PyResult<Float> {
+        let options = StationaryOrderOptions {
+            cancellation: Some(interrupt_token()),
+            ..Default::default()
+        };
+
+        let estimate = py.allow_threads(|| self.0.compute_stationary_order_estimate(&options))?;
+
+        // A bare float can't say it's partial, so surface the interrupt as usual
+        match estimate.stop_reason {
+            StopReason::Converged => Ok(estimate.value),
+            StopReason::Cancelled => Err(PyKeyboardInterrupt::new_err(
+                "stationary order parameter computation interrupted",
+            )),
+            StopReason::StepBudgetExhausted | StopReason::TimeBudgetExhausted => {
+                Err(anyhow::anyhow!(
+                    "max iterations (`{}`) reached for stationary order parameter (last estimate \
+                     `{}`, residual `{}`)",
+                    estimate.iterations,
+                    estimate.value,
+                    estimate.residual
+                )
+                .into())
+            }
+        }
     }
 
-    #[pyo3(name = "__repr__")]
-    fn repr(&self) -> String {
-        self.0.to_string()
+    /// Compute the stationary order parameter within an optional step and/or wall-clock budget
+    /// (in seconds), returning a best-effort estimate with diagnostics instead of raising when
+    /// the budget runs out or on KeyboardInterrupt
+    #[pyo3(signature = (max_steps = None, time_budget = None))]
+    fn compute_stationary_order_estimate<'py>(
+        &self,
+        py: Python<'py>,
+        max_steps: Option<usize>,
+        time_budget: Option<f64>,
+    ) -> PyResult<Bound<'py, PyDict>> {
+        let options = StationaryOrderOptions {
+            cancellation: Some(interrupt_token()),
+            max_steps,
+            time_budget: time_budget.map(seconds_to_duration).transpose()?,
+        };
+
+        let estimate = py.allow_threads(|| self.0.compute_stationary_order_estimate(&options))?;
+
+        let dict = PyDict::new(py);
+        dict.set_item("value", estimate.value)?;
+        dict.set_item("iterations", estimate.iterations)?;
+        dict.set_item("converged", estimate.is_converged())?;
+        dict.set_item("stop_reason", stop_reason_name(estimate.stop_reason))?;
+        dict.set_item("residual", estimate.residual)?;
+        dict.set_item("window_len", estimate.window_len)?;
+        dict.set_item("elapsed", estimate.elapsed.as_secs_f64())?;
+
+        Ok(dict)
     }
 
-    fn get_data(&self) -> PySimulationData {
-        PySimulationData((&self.0).into())
+    /// Step a copy of this simulation, collecting summary statistics (order curve, velocity
+    /// correlation C(r), and cluster-size distribution) for comparison against experiments
+    /// Restrict them to one `tag` or a list of particle `ids` to study a subgroup.
+    #[pyo3(signature = (num_steps, correlation_max_distance, cluster_distance, tag = None, ids = None))]
+    fn summary_statistics<'py>(
+        &self,
+        py: Python<'py>,
+        num_steps: usize,
+        correlation_max_distance: Float,
+        cluster_distance: Float,
+        tag: Option<usize>,
+        ids: Option<Vec<usize>>,
+    ) -> PyResult<Bound<'py, PyDict>> {
+        let options = ComparisonOptions::new(correlation_max_distance, cluster_distance);
+        let statistics = SummaryStatistics::from_simulation_selection(
+            &self.0,
+            num_steps,
+            &options,
+            &to_selection(tag, ids)?,
+        )?;
+
+        let dict = PyDict::new(py);
+        dict.set_item("order_curve", statistics.order_curve)?;
+        dict.set_item("velocity_correlation", statistics.velocity_correlation)?;
+        dict.set_item(
+            "cluster_size_distribution",
+            statistics.cluster_size_distribution,
+        )?;
+
+        Ok(dict)
     }
 
-    #[getter]
-    fn boundary_side_length(&self) -> Float {
-        self.0.params.boundary_side_length.0
+    /// Label each particle with its cluster, where particles closer than `cluster_distance`
+    /// (defaulting to the particle distance threshold) are connected. Restricted to one `tag` or
+    /// a list of particle `ids`, only those particles are clustered and the rest get `None`.
+    #[pyo3(signature = (cluster_distance = None, tag = None, ids = None))]
+    fn cluster_labels(
+        &self,
+        cluster_distance: Option<Float>,
+        tag: Option<usize>,
+        ids: Option<Vec<usize>>,
+    ) -> PyResult<Vec<Option<usize>>> {
+        Ok(self.0.selected_cluster_labels(
+            cluster_distance.unwrap_or(self.0.params.particle_distance_threshold.0),
+            &to_selection(tag, ids)?,
+        ))
     }
 
-    #[getter]
-    fn current_time(&self) -> Float {
-        self.0.current_time.0
+    /// The instantaneous order parameter, optionally over just one `tag` or a list of particle
+    /// `ids`
+    #[pyo3(signature = (tag = None, ids = None))]
+    fn order_parameter(&self, tag: Option<usize>, ids: Option<Vec<usize>>) -> PyResult<Float> {
+        Ok(self.0.order_parameter(&to_selection(tag, ids)?))
     }
-}
 
-#[pyclass(name = "SimulationData")]
-struct PySimulationData(SimulationData);
+    /// Report the memory used by this simulation, in bytes
+    fn memory_footprint<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        memory_footprint_to_dict(py, &self.0.memory_footprint())
+    }
 
-#[pymethods]
-impl PySimulationData {
-    #[getter]
-    fn x(&self) -> Vec<Float> {
-        // Must clone because Python has no concept of ownership lol
-        self.0.x.clone()
+    /// Get the wall-clock time (in seconds) spent in each phase of stepping so far
+    fn performance_counters<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let counters = self.0.performance_counters();
+
+        let dict = PyDict::new(py);
+        dict.set_item("steps", counters.steps)?;
+        dict.set_item("neighbors", counters.neighbors)?;
+        dict.set_item("neighbor_searches", counters.neighbor_searches)?;
+        dict.set_item("neighbor_search", counters.neighbor_search.as_secs_f64())?;
+        dict.set_item("alignment", counters.alignment.as_secs_f64())?;
+        dict.set_item("integration", counters.integration.as_secs_f64())?;
+        dict.set_item("observables", counters.observables.as_secs_f64())?;
+        dict.set_item("total", counters.total.as_secs_f64())?;
+        dict.set_item("steps_per_second", counters.steps_per_second())?;
+
+        Ok(dict)
     }
 
-    #[getter]
-    fn y(&self) -> Vec<Float> {
-        // Must clone because Python has no concept of ownership lol
-        self.0.y.clone()
+    /// Summarize stepping performance since the counters were last reset: `steps`,
+    /// `steps_per_second`, `mean_neighbors_per_particle`, and the average time per step (in
+    /// seconds) spent in each phase
+    fn stats<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let stats = self.0.stats();
+
+        let dict = PyDict::new(py);
+        dict.set_item("steps", stats.steps)?;
+        dict.set_item("steps_per_second", stats.steps_per_second)?;
+        dict.set_item(
+            "mean_neighbors_per_particle",
+            stats.mean_neighbors_per_particle,
+        )?;
+        dict.set_item(
+            "neighbor_search_per_step",
+            stats.neighbor_search_per_step.as_secs_f64(),
+        )?;
+        dict.set_item("alignment_per_step", stats.alignment_per_step.as_secs_f64())?;
+        dict.set_item(
+            "integration_per_step",
+            stats.integration_per_step.as_secs_f64(),
+        )?;
+        dict.set_item(
+            "observables_per_step",
+            stats.observables_per_step.as_secs_f64(),
+        )?;
+        dict.set_item("total_per_step", stats.total_per_step.as_secs_f64())?;
+
+        Ok(dict)
     }
 
-    #[getter]
-    fn u(&self) -> Vec<Float> {
-        // Must clone because Python has no concept of ownership lol
-        self.0.u.clone()
+    /// Zero the performance counters
+    fn reset_performance_counters(&mut self) {
+        self.0.reset_performance_counters();
     }
 
-    #[getter]
-    fn v(&self) -> Vec<Float> {
-        // Must clone because Python has no concept of ownership lol
-        self.0.v.clone()
+    #[pyo3(name = "__repr__")]
+    fn repr(&self) -> String {
+        self.0.to_string()
     }
-}
 
-#[pyfunction(name = "optimize_for_critical_noise")]
-fn py_optimize_for_critical_noise(
-    num_particles: usize,
-    boundary_side_length: Float,
-    timestep: Float,
-    noise_critical_target: Float,
-) -> PyResult<(Float, Float)> {
-    let (particle_distance_threshold, speed) = optimize_for_critical_noise(
-        num_particles,
-        DomainBoundaryLength(boundary_side_length),
-        RelativeTime(timestep),
-        Noise(noise_critical_target),
-    )?;
+    /// Notebook display: a parameter summary table beside a rendered snapshot
+    fn _repr_html_(&self) -> PyResult<String> {
+        let params = &self.0.params;
+        let rows = [
+            ("Current time", self.0.current_time.0),
+            ("Particles", self.0.particles.len() as Float),
+            ("Domain size", params.boundary_side_length.0),
+            ("Timestep", params.timestep.0),
+            ("Noise", params.noise.0),
+            ("Particle speed", params.speed.0),
+            ("Distance threshold", params.particle_distance_threshold.0),
+        ];
+
+        let table: String = rows
+            .iter()
+            .map(|(name, value)| format!("<tr><th>{name}</th><td>{value}</td></tr>"))
+            .collect();
 
-    Ok((particle_distance_threshold.0, speed.0))
+        Ok(format!(
+            "<div style=\"display: flex; gap: 1em; align-items: flex-start\">\
+             {}<table><caption>Simulation</caption>{table}</table></div>",
+            self.0
+                .to_svg(NOTEBOOK_RENDER_SIZE, &RenderOptions::default())?
+        ))
+    }
+
+    /// Notebook display as a static image
+    fn _repr_png_<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyBytes>> {
+        let png = self
+            .0
+            .to_png(NOTEBOOK_RENDER_SIZE, &RenderOptions::default())?;
+
+        Ok(PyBytes::new(py, &png))
+    }
+
+    /// Render the current state as an SVG image `size` pixels across, styled by a
+    /// `RenderOptions`
+    #[pyo3(signature = (size = NOTEBOOK_RENDER_SIZE, options = None))]
+    fn to_svg(&self, size: u32, options: Option<PyRef<PyRenderOptions>>) -> PyResult<String> {
+        Ok(self.0.to_svg(size, &render_options(options))?)
+    }
+
+    /// Render the current state as PNG bytes, `size` pixels across, styled by a `RenderOptions`
+    #[pyo3(signature = (size = NOTEBOOK_RENDER_SIZE, options = None))]
+    fn to_png<'py>(
+        &self,
+        py: Python<'py>,
+        size: u32,
+        options: Option<PyRef<PyRenderOptions>>,
+    ) -> PyResult<Bound<'py, PyBytes>> {
+        let png = self.0.to_png(size, &render_options(options))?;
+
+        Ok(PyBytes::new(py, &png))
+    }
+
+    /// Open a window showing the particles, styled by a `RenderOptions`, stepping in place until
+    /// it's closed. Space pauses and resumes, the right arrow key (or `S`) steps once while
+    /// paused, and Escape closes the window.
+    #[cfg(feature = "viz")]
+    #[pyo3(signature = (size = 600, steps_per_frame = 1, max_fps = 60, options = None))]
+    fn watch(
+        &mut self,
+        size: usize,
+        steps_per_frame: usize,
+        max_fps: usize,
+        options: Option<PyRef<PyRenderOptions>>,
+    ) -> PyResult<()> {
+        Ok(self.0.watch(&WatchOptions {
+            size,
+            steps_per_frame,
+            max_fps,
+            render: render_options(options),
+        })?)
+    }
+
+    /// Step in place, drawing `num_frames` frames `stride` steps apart, styled by a
+    /// `RenderOptions`, into a GIF, or an MP4 (via `ffmpeg`) if `path` ends in `.mp4`
+    #[cfg(feature = "animation")]
+    #[pyo3(signature = (path, num_frames, stride = 1, options = None))]
+    fn render_animation(
+        &mut self,
+        py: Python<'_>,
+        path: PathBuf,
+        num_frames: usize,
+        stride: usize,
+        options: Option<PyRef<PyRenderOptions>>,
+    ) -> PyResult<()> {
+        let options = render_options(options);
+        py.allow_threads(|| self.0.render_animation(path, num_frames, stride, &options))?;
+
+        Ok(())
+    }
+
+    /// Open an interactive window that steps the simulation in place, with sliders for the noise,
+    /// speed, and interaction radius and a live plot of the instantaneous order. With
+    /// `record_session`, every change made from the panel is saved there once it's closed, along
+    /// with the steps taken, the random state, and `seed` for reference, for
+    /// `Simulation.replay_session`.
+    #[cfg(feature = "gui")]
+    #[pyo3(signature = (record_session = None, seed = None))]
+    fn control_panel(
+        &mut self,
+        record_session: Option<PathBuf>,
+        seed: Option<u64>,
+    ) -> PyResult<()> {
+        match record_session {
+            Some(path) => Ok(self.0.record_control_panel(seed)?.save(path)?),
+            None => Ok(self.0.control_panel()?),
+        }
+    }
+
+    /// Step in place, broadcasting frames over WebSocket to every client connected to `addr`
+    /// after every `steps_per_frame` steps, until `num_frames` were sent (or forever, until
+    /// interrupted). Frames are `"json"` text or `"binary"` messages. Clients can send JSON
+    /// commands to change parameters, or to write snapshots and recordings into `output_dir`;
+    /// every change is appended to `event_log` if given. Returns the number of frames sent.
+    #[cfg(feature = "serve")]
+    #[pyo3(signature = (
+        addr,
+        format = "json",
+        steps_per_frame = 1,
+        max_fps = Some(30.0),
+        num_frames = None,
+        event_log = None,
+        output_dir = None,
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    fn serve(
+        &mut self,
+        py: Python<'_>,
+        addr: &str,
+        format: &str,
+        steps_per_frame: usize,
+        max_fps: Option<f64>,
+        num_frames: Option<usize>,
+        event_log: Option<PathBuf>,
+        output_dir: Option<PathBuf>,
+    ) -> PyResult<usize> {
+        let format = match format {
+            "json" => FrameFormat::Json,
+            "binary" => FrameFormat::Binary,
+            _ => {
+                return Err(PyValueError::new_err(format!(
+                    "frame format must be `json` or `binary`, got `{format}`"
+                )));
+            }
+        };
+        let options = ServeOptions {
+            format,
+            steps_per_frame,
+            max_fps,
+            num_frames,
+            cancellation: Some(interrupt_token()),
+            event_log,
+            output_dir,
+        };
+
+        Ok(py.allow_threads(|| self.0.serve(addr, &options))?)
+    }
+
+    fn get_data(&self) -> PySimulationData {
+        PySimulationData((&self.0).into())
+    }
+
+    /// Iterate over the following steps without end, yielding a `SimulationData` per step and
+    /// leaving this simulation untouched, e.g. `for frame in sim: ...`
+    fn __iter__(&self) -> PySteps {
+        PySteps {
+            steps: self.0.iter_steps(),
+            remaining: None,
+        }
+    }
+
+    /// Like iterating over the simulation, but stopping after `max_steps` steps if given
+    #[pyo3(signature = (max_steps = None))]
+    fn iter_steps(&self, max_steps: Option<usize>) -> PySteps {
+        PySteps {
+            steps: self.0.iter_steps(),
+            remaining: max_steps,
+        }
+    }
+
+    #[getter]
+    fn boundary_side_length(&self) -> Float {
+        self.0.params.boundary_side_length.0
+    }
+
+    #[getter]
+    fn noise(&self) -> Float {
+        self.0.params.noise.0
+    }
+
+    /// Change the noise amplitude in place, keeping the current particle state
+    #[setter]
+    fn set_noise(&mut self, noise: Float) -> PyResult<()> {
+        self.0 = self.0.clone().with_noise(Noise(noise))?;
+
+        Ok(())
+    }
+
+    #[getter]
+    fn speed(&self) -> Float {
+        self.0.params.speed.0
+    }
+
+    /// Change the particle speed in place, keeping the current particle state
+    #[setter]
+    fn set_speed(&mut self, speed: Float) -> PyResult<()> {
+        self.0 = self.0.clone().with_speed(Speed(speed))?;
+
+        Ok(())
+    }
+
+    #[getter]
+    fn particle_distance_threshold(&self) -> Float {
+        self.0.params.particle_distance_threshold.0
+    }
+
+    /// Change the interaction radius in place, keeping the current particle state
+    #[setter]
+    fn set_particle_distance_threshold(
+        &mut self,
+        particle_distance_threshold: Float,
+    ) -> PyResult<()> {
+        self.0 = self
+            .0
+            .clone()
+            .with_distance_threshold(ParticleDistanceThreshold(particle_distance_threshold))?;
+
+        Ok(())
+    }
+
+    #[getter]
+    fn timestep(&self) -> Float {
+        self.0.params.timestep.0
+    }
+
+    /// Change the timestep in place, keeping the current particle state
+    #[setter]
+    fn set_timestep(&mut self, timestep: Float) -> PyResult<()> {
+        self.0 = self.0.clone().with_timestep(RelativeTime(timestep))?;
+
+        Ok(())
+    }
+
+    #[getter]
+    fn current_time(&self) -> Float {
+        self.0.current_time.0
+    }
+
+    /// The polarization: the magnitude of the mean heading, from 0 (disordered) to 1 (aligned)
+    #[getter]
+    fn instantaneous_order(&self) -> Float {
+        self.0.instantaneous_order.0
+    }
+
+    #[getter]
+    fn num_particles(&self) -> usize {
+        self.0.num_particles()
+    }
+
+    /// Give every particle a value for a named scalar, e.g. "infection", in particle order
+    fn set_scalar(&mut self, name: &str, values: Vec<Float>) -> PyResult<()> {
+        Ok(self.0.set_scalar(name, &values)?)
+    }
+
+    /// Every particle's value for a named scalar, or `None` if no particle has it
+    fn scalar(&self, name: &str) -> Option<Vec<Float>> {
+        self.0.scalar(name)
+    }
+
+    fn remove_scalar(&mut self, name: &str) {
+        self.0.remove_scalar(name)
+    }
+
+    #[getter]
+    fn scalar_names(&self) -> Vec<String> {
+        self.0.scalar_names()
+    }
+
+    /// Rerun the current state at `num_levels` successively halved timesteps, reseeding with
+    /// `seed` each time, and compare the stationary order parameter and the order (plus any
+    /// named `observables`, as for triggers) averaged over the second half of a run of
+    /// `duration` simulated time
+    ///
+    /// Returns a list of dicts, coarsest timestep first, with the `timestep`,
+    /// `stationary_order_parameter`, `converged`, `mean_order`, and one entry per observable.
+    #[pyo3(signature = (num_levels = 4, duration = 50.0, seed = 0, observables = Vec::new()))]
+    fn timestep_convergence<'py>(
+        &self,
+        py: Python<'py>,
+        num_levels: usize,
+        duration: Float,
+        seed: u64,
+        observables: Vec<String>,
+    ) -> PyResult<Vec<Bound<'py, PyDict>>> {
+        let options = TimestepConvergenceOptions {
+            num_levels,
+            duration,
+            seed,
+            observables: observables
+                .into_iter()
+                .map(|name| Ok((name.clone(), observable_from_name(&name)?)))
+                .collect::<PyResult<_>>()?,
+            stationary_order: StationaryOrderOptions {
+                cancellation: Some(interrupt_token()),
+                ..Default::default()
+            },
+        };
+
+        let report = py.allow_threads(|| self.0.timestep_convergence(&options))?;
+
+        report
+            .levels
+            .iter()
+            .zip(&report.observables)
+            .map(|(level, values)| {
+                let dict = PyDict::new(py);
+                dict.set_item("timestep", level.timestep.0)?;
+                dict.set_item("stationary_order_parameter", level.stationary_order.value)?;
+                dict.set_item("converged", level.stationary_order.is_converged())?;
+                dict.set_item("mean_order", level.mean_order)?;
+                for (name, value) in report.observable_names.iter().zip(values) {
+                    dict.set_item(name, value)?;
+                }
+
+                Ok(dict)
+            })
+            .collect()
+    }
+
+    /// Run this configuration at each particle count in `sizes` (`num_runs` times each) with the
+    /// density held fixed, fit the stationary order parameter against 1/N, and extrapolate it to
+    /// an infinite system
+    ///
+    /// Returns a dict with the `extrapolated` value, its `extrapolated_std_error`, the fitted
+    /// `slope` against 1/N, and per size the `sizes`, `boundary_side_lengths`, and run `values`.
+    #[pyo3(signature = (sizes, num_runs = 3))]
+    fn extrapolate_system_size<'py>(
+        &self,
+        py: Python<'py>,
+        sizes: Vec<usize>,
+        num_runs: usize,
+    ) -> PyResult<Bound<'py, PyDict>> {
+        let options = FiniteSizeOptions {
+            num_runs,
+            stationary_order: StationaryOrderOptions {
+                cancellation: Some(interrupt_token()),
+                ..Default::default()
+            },
+            ..FiniteSizeOptions::new(sizes)
+        };
+
+        let report = py.allow_threads(|| self.0.extrapolate_system_size(&options))?;
+
+        let result = PyDict::new(py);
+        result.set_item("extrapolated", report.extrapolated)?;
+        result.set_item("extrapolated_std_error", report.extrapolated_std_error)?;
+        result.set_item("slope", report.slope)?;
+        result.set_item(
+            "sizes",
+            report
+                .points
+                .iter()
+                .map(|point| point.num_particles)
+                .collect::<Vec<_>>(),
+        )?;
+        result.set_item(
+            "boundary_side_lengths",
+            report
+                .points
+                .iter()
+                .map(|point| point.boundary_side_length.0)
+                .collect::<Vec<_>>(),
+        )?;
+        result.set_item(
+            "values",
+            report
+                .points
+                .into_iter()
+                .map(|point| point.values)
+                .collect::<Vec<_>>(),
+        )?;
+
+        Ok(result)
+    }
+
+    /// The number of particles per unit area
+    #[getter]
+    fn number_density(&self) -> Float {
+        self.0.number_density()
+    }
+
+    /// The mean number of neighbors each particle aligns with, averaged over a short run of
+    /// `num_steps` steps from the current state (which is left untouched), or right now with the
+    /// default of 0
+    #[pyo3(signature = (num_steps = 0))]
+    fn mean_neighbor_count(&self, num_steps: usize) -> Float {
+        self.0.measure_mean_neighbors(num_steps)
+    }
+
+    /// The number of particles per unit area in each cell of a `bins` by `bins` grid, as a list
+    /// of rows from y = 0, each from x = 0
+    #[pyo3(signature = (bins = 20))]
+    fn density_field(&self, bins: usize) -> PyResult<Vec<Vec<Float>>> {
+        Ok(self
+            .0
+            .density_field(bins)?
+            .chunks(bins)
+            .map(<[Float]>::to_vec)
+            .collect())
+    }
+
+    /// Spread an SIR contact process between neighbors at `transmission_rate` per infected
+    /// neighbor, recovering at `recovery_rate`, or stop it with `None`. The state is kept in the
+    /// `"sir_state"` scalar: 0 susceptible, 1 infected, 2 recovered.
+    #[pyo3(signature = (transmission_rate = None, recovery_rate = 0.0))]
+    fn with_contact_process(
+        &self,
+        transmission_rate: Option<Float>,
+        recovery_rate: Float,
+    ) -> PyResult<Self> {
+        let contact_process = transmission_rate.map(|transmission_rate| ContactProcess {
+            transmission_rate,
+            recovery_rate,
+        });
+
+        Ok(Self(self.0.clone().with_contact_process(contact_process)?))
+    }
+
+    /// Infect one `tag` or a list of particle `ids`, or everyone when neither is given
+    #[pyo3(signature = (tag = None, ids = None))]
+    fn infect(&mut self, tag: Option<usize>, ids: Option<Vec<usize>>) -> PyResult<()> {
+        self.0.infect(&to_selection(tag, ids)?);
+        Ok(())
+    }
+
+    /// How many particles are susceptible, infected, and recovered
+    #[getter]
+    fn sir_counts<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        sir_counts_to_dict(py, self.0.sir_counts())
+    }
+
+    /// Step until nobody is infected or `max_steps` have been taken
+    ///
+    /// Returns a dict with the final `simulation`, the epidemic curve as `time`, `susceptible`,
+    /// `infected`, and `recovered` lists, the `peak_time` and `peak_infected`, and the
+    /// `final_size` (the fraction ever infected).
+    fn run_epidemic<'py>(&self, py: Python<'py>, max_steps: usize) -> PyResult<Bound<'py, PyDict>> {
+        let (sim, curve) = self.0.clone().run_epidemic(max_steps);
+
+        let result = PyDict::new(py);
+        result.set_item("simulation", Self(sim))?;
+        result.set_item("time", &curve.times)?;
+        let counts = |count: fn(&SirCounts) -> usize| -> Vec<usize> {
+            curve.counts.iter().map(count).collect()
+        };
+        result.set_item("susceptible", counts(|counts| counts.susceptible))?;
+        result.set_item("infected", counts(|counts| counts.infected))?;
+        result.set_item("recovered", counts(|counts| counts.recovered))?;
+        if let Some((peak_time, peak_infected)) = curve.peak() {
+            result.set_item("peak_time", peak_time)?;
+            result.set_item("peak_infected", peak_infected)?;
+        }
+        result.set_item("final_size", curve.final_size())?;
+
+        Ok(result)
+    }
+
+    /// Each particle's remaining energy, or `None` without an energy budget
+    #[getter]
+    fn energies(&self) -> Option<Vec<Float>> {
+        self.0.energies()
+    }
+}
+
+/// Styling for rendered frames, shared by `Simulation.to_svg`, `to_png`, `watch`,
+/// `render_animation`, and the plotting helpers
+///
+/// color_by: "none", "heading" (color wheel), "cluster", "species" (the particle tags), or
+///     "scalar:<name>" for a user-defined per-particle scalar, e.g. "scalar:infection"
+/// glyph: "arrow" or "dot"
+/// color: "#rrggbb", or one of black, white, gray, red, green, and blue
+/// cluster_distance: connection distance for "cluster" coloring, defaulting to the particle
+///     distance threshold
+/// density_overlay: shade the particle density field underneath the particles
+/// scalar_cmap: "viridis" or "gray"
+/// scalar_range: (min, max) of the colormap for "scalar:<name>" coloring, defaulting to the
+///     range of the values in each frame
+#[pyclass(
+    name = "RenderOptions",
+    module = "particle_interactions_puzzle",
+    frozen
+)]
+struct PyRenderOptions(RenderOptions);
+
+#[pymethods]
+impl PyRenderOptions {
+    #[new]
+    #[pyo3(signature = (
+        color_by = "none",
+        glyph = "arrow",
+        color = "black",
+        cluster_distance = None,
+        density_overlay = false,
+        density_bins = 20,
+        scalar_cmap = "viridis",
+        scalar_range = None,
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        color_by: &str,
+        glyph: &str,
+        color: &str,
+        cluster_distance: Option<Float>,
+        density_overlay: bool,
+        density_bins: usize,
+        scalar_cmap: &str,
+        scalar_range: Option<(Float, Float)>,
+    ) -> PyResult<Self> {
+        let options = RenderOptions {
+            coloring: color_by.parse()?,
+            glyph: glyph.parse()?,
+            color: color.parse()?,
+            cluster_distance,
+            density_overlay,
+            density_bins,
+            scalar_colormap: scalar_cmap.parse()?,
+            scalar_range,
+        };
+        options.validate()?;
+
+        Ok(Self(options))
+    }
+
+    #[getter]
+    fn color_by(&self) -> String {
+        self.0.coloring.to_string()
+    }
+
+    #[getter]
+    fn glyph(&self) -> String {
+        self.0.glyph.to_string()
+    }
+
+    /// The uniform particle color, as `#rrggbb`
+    #[getter]
+    fn color(&self) -> String {
+        self.0.color.to_string()
+    }
+
+    #[getter]
+    fn cluster_distance(&self) -> Option<Float> {
+        self.0.cluster_distance
+    }
+
+    #[getter]
+    fn density_overlay(&self) -> bool {
+        self.0.density_overlay
+    }
+
+    #[getter]
+    fn density_bins(&self) -> usize {
+        self.0.density_bins
+    }
+
+    #[getter]
+    fn scalar_cmap(&self) -> String {
+        self.0.scalar_colormap.to_string()
+    }
+
+    #[getter]
+    fn scalar_range(&self) -> Option<(Float, Float)> {
+        self.0.scalar_range
+    }
+
+    #[pyo3(name = "__repr__")]
+    fn repr(&self) -> String {
+        format!(
+            "RenderOptions(color_by={:?}, glyph={:?}, color={:?}, density_overlay={})",
+            self.0.coloring.to_string(),
+            self.0.glyph.to_string(),
+            self.0.color.to_string(),
+            if self.0.density_overlay {
+                "True"
+            } else {
+                "False"
+            },
+        )
+    }
+}
+
+/// Use the given render options, or the defaults
+fn render_options(options: Option<PyRef<PyRenderOptions>>) -> RenderOptions {
+    options.map(|options| options.0.clone()).unwrap_or_default()
+}
+
+/// A snapshot of every particle, with each per-particle getter returning a NumPy array
+///
+/// Python can't borrow from Rust-owned data, so each array is a single copy of the Rust buffer,
+/// which is far cheaper than building a list of Python floats.
+#[pyclass(name = "SimulationData")]
+struct PySimulationData(SimulationData);
+
+#[pymethods]
+impl PySimulationData {
+    #[getter]
+    fn x<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<Float>> {
+        PyArray1::from_slice(py, &self.0.x)
+    }
+
+    #[getter]
+    fn y<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<Float>> {
+        PyArray1::from_slice(py, &self.0.y)
+    }
+
+    #[getter]
+    fn u<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<Float>> {
+        PyArray1::from_slice(py, &self.0.u)
+    }
+
+    #[getter]
+    fn v<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<Float>> {
+        PyArray1::from_slice(py, &self.0.v)
+    }
+
+    #[getter]
+    fn theta<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<Float>> {
+        PyArray1::from_slice(py, &self.0.theta)
+    }
+
+    /// Noise phase each particle drew on its last step
+    #[getter]
+    fn phase<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<Float>> {
+        PyArray1::from_slice(py, &self.0.phase)
+    }
+
+    #[getter]
+    fn id<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<usize>> {
+        PyArray1::from_slice(py, &self.0.id)
+    }
+
+    #[getter]
+    fn tag<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<usize>> {
+        PyArray1::from_slice(py, &self.0.tag)
+    }
+
+    #[getter]
+    fn leader<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<bool>> {
+        PyArray1::from_slice(py, &self.0.leader)
+    }
+
+    #[getter]
+    fn speed<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<Float>> {
+        PyArray1::from_slice(py, &self.0.speed)
+    }
+
+    /// Each user-defined scalar by name, with a value for every particle
+    #[getter]
+    fn scalars<'py>(&self, py: Python<'py>) -> BTreeMap<String, Bound<'py, PyArray1<Float>>> {
+        self.0
+            .scalars
+            .iter()
+            .map(|(name, values)| (name.clone(), PyArray1::from_slice(py, values)))
+            .collect()
+    }
+
+    /// Every per-particle column by name, as NumPy arrays, e.g. for `pandas.DataFrame(...)`.
+    /// Scalars are columns under their own names, or `"scalar:<name>"` for one named like a
+    /// built-in column.
+    fn to_dict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let data = &self.0;
+        let dict = PyDict::new(py);
+        dict.set_item("id", PyArray1::from_slice(py, &data.id))?;
+        dict.set_item("tag", PyArray1::from_slice(py, &data.tag))?;
+        dict.set_item("x", PyArray1::from_slice(py, &data.x))?;
+        dict.set_item("y", PyArray1::from_slice(py, &data.y))?;
+        dict.set_item("u", PyArray1::from_slice(py, &data.u))?;
+        dict.set_item("v", PyArray1::from_slice(py, &data.v))?;
+        dict.set_item("theta", PyArray1::from_slice(py, &data.theta))?;
+        dict.set_item("phase", PyArray1::from_slice(py, &data.phase))?;
+        dict.set_item("speed", PyArray1::from_slice(py, &data.speed))?;
+        dict.set_item("leader", PyArray1::from_slice(py, &data.leader))?;
+
+        for (name, values) in &data.scalars {
+            dict.set_item(scalar_column_name(name), PyArray1::from_slice(py, values))?;
+        }
+
+        Ok(dict)
+    }
+
+    /// One dict per particle with the same columns as `to_dict`, e.g. for
+    /// `pandas.DataFrame.from_records(...)`
+    fn to_records<'py>(&self, py: Python<'py>) -> PyResult<Vec<Bound<'py, PyDict>>> {
+        let data = &self.0;
+
+        (0..data.id.len())
+            .map(|idx| {
+                let record = PyDict::new(py);
+                record.set_item("id", data.id[idx])?;
+                record.set_item("tag", data.tag[idx])?;
+                record.set_item("x", data.x[idx])?;
+                record.set_item("y", data.y[idx])?;
+                record.set_item("u", data.u[idx])?;
+                record.set_item("v", data.v[idx])?;
+                record.set_item("theta", data.theta[idx])?;
+                record.set_item("phase", data.phase[idx])?;
+                record.set_item("speed", data.speed[idx])?;
+                record.set_item("leader", data.leader[idx])?;
+
+                for (name, values) in &data.scalars {
+                    record.set_item(scalar_column_name(name), values[idx])?;
+                }
+
+                Ok(record)
+            })
+            .collect()
+    }
+
+    /// Write the snapshot as a VTK PolyData (`.vtp`) file for ParaView
+    fn write_vtk(&self, path: PathBuf) -> PyResult<()> {
+        Ok(self.0.write_vtk(path)?)
+    }
+
+    /// Simulated time of the snapshot
+    #[getter]
+    fn time(&self) -> Float {
+        self.0.time
+    }
+
+    /// The polarization at the time of the snapshot
+    #[getter]
+    fn instantaneous_order(&self) -> Float {
+        self.0.instantaneous_order
+    }
+}
+
+/// Successive steps of a simulation, from iterating over it or `Simulation.iter_steps`
+#[pyclass(name = "Steps")]
+struct PySteps {
+    steps: Steps,
+
+    /// Steps left before stopping, or `None` to go on forever
+    remaining: Option<usize>,
+}
+
+#[pymethods]
+impl PySteps {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&mut self) -> Option<PySimulationData> {
+        if let Some(remaining) = &mut self.remaining {
+            *remaining = remaining.checked_sub(1)?;
+        }
+
+        self.steps.next().map(|sim| PySimulationData((&sim).into()))
+    }
+}
+
+/// A recorded history of positions and headings, from `Simulation.record_trajectory`
+#[pyclass(name = "Trajectory")]
+struct PyTrajectory(Trajectory);
+
+#[pymethods]
+impl PyTrajectory {
+    fn __len__(&self) -> usize {
+        self.0.len()
+    }
+
+    /// The simulated time of each frame
+    #[getter]
+    fn times(&self) -> Vec<Float> {
+        self.0.times()
+    }
+
+    /// A frame as a dict of `step`, `time`, `id`, `x`, `y`, and `theta`, by index (negative
+    /// indices count from the end) or, with `time`, the frame recorded closest to it
+    #[pyo3(signature = (idx = None, time = None))]
+    fn frame<'py>(
+        &self,
+        py: Python<'py>,
+        idx: Option<isize>,
+        time: Option<Float>,
+    ) -> PyResult<Bound<'py, PyDict>> {
+        let frame = match (idx, time) {
+            (Some(idx), None) => {
+                let resolved = match idx < 0 {
+                    true => self.0.len().checked_sub(idx.unsigned_abs()),
+                    false => Some(idx as usize),
+                };
+                resolved.and_then(|idx| self.0.frame(idx))
+            }
+            (None, Some(time)) => self.0.frame_at(AbsoluteTime(time)),
+            _ => return Err(anyhow::anyhow!("select a frame by idx or time, not both").into()),
+        }
+        .ok_or_else(|| PyIndexError::new_err("no such frame in the trajectory"))?;
+
+        let dict = PyDict::new(py);
+        dict.set_item("step", frame.step)?;
+        dict.set_item("time", frame.time.0)?;
+        dict.set_item("id", frame.id.clone())?;
+        dict.set_item("x", frame.x.clone())?;
+        dict.set_item("y", frame.y.clone())?;
+        dict.set_item("theta", frame.theta.clone())?;
+
+        Ok(dict)
+    }
+
+    /// The frames from index `start` up to (not including) `stop`, keeping every `stride`-th
+    #[pyo3(signature = (start = 0, stop = None, stride = 1))]
+    fn slice(&self, start: usize, stop: Option<usize>, stride: usize) -> PyResult<Self> {
+        let stop = stop.unwrap_or(self.0.len()).max(start);
+        Ok(Self(self.0.to_sliced(start..stop).to_thinned(stride)?))
+    }
+
+    /// The frames recorded between times `start` and `end`, inclusive
+    fn slice_time(&self, start: Float, end: Float) -> Self {
+        Self(
+            self.0
+                .to_time_sliced(AbsoluteTime(start), AbsoluteTime(end)),
+        )
+    }
+
+    /// One particle's `(time, x, y, theta)` in each frame it appears in
+    fn particle_track(&self, id: usize) -> Vec<(Float, Float, Float, Float)> {
+        self.0.particle_track(id)
+    }
+
+    /// Every frame's x-positions, one list per frame
+    #[getter]
+    fn x(&self) -> Vec<Vec<Float>> {
+        self.0
+            .frames()
+            .iter()
+            .map(|frame| frame.x.clone())
+            .collect()
+    }
+
+    /// Every frame's y-positions, one list per frame
+    #[getter]
+    fn y(&self) -> Vec<Vec<Float>> {
+        self.0
+            .frames()
+            .iter()
+            .map(|frame| frame.y.clone())
+            .collect()
+    }
+
+    /// Every frame's headings, one list per frame
+    #[getter]
+    fn theta(&self) -> Vec<Vec<Float>> {
+        self.0
+            .frames()
+            .iter()
+            .map(|frame| frame.theta.clone())
+            .collect()
+    }
+}
+
+/// Optimize speed and the radius threshold to find a target noise, returning
+/// `(distance_threshold, speed)`
+///
+/// An iteration and/or wall-clock budget (in seconds) can be given. When the budget runs out, or
+/// on KeyboardInterrupt, the best parameters found so far are returned. With `full_output` a dict
+/// is returned instead, which also says whether the optimizer converged.
+#[pyfunction(name = "optimize_for_critical_noise")]
+#[pyo3(signature = (
+    num_particles,
+    boundary_side_length,
+    timestep,
+    noise_critical_target,
+    max_iterations = None,
+    time_budget = None,
+    full_output = false,
+))]
+#[allow(clippy::too_many_arguments)]
+fn py_optimize_for_critical_noise(
+    py: Python<'_>,
+    num_particles: usize,
+    boundary_side_length: Float,
+    timestep: Float,
+    noise_critical_target: Float,
+    max_iterations: Option<u64>,
+    time_budget: Option<f64>,
+    full_output: bool,
+) -> PyResult<PyObject> {
+    let options = OptimizerOptions {
+        cancellation: Some(interrupt_token()),
+        max_iterations,
+        time_budget: time_budget.map(seconds_to_duration).transpose()?,
+    };
+
+    let optimum = py.allow_threads(|| {
+        optimize_for_critical_noise_with(
+            num_particles,
+            DomainBoundaryLength(boundary_side_length),
+            RelativeTime(timestep),
+            Noise(noise_critical_target),
+            &options,
+        )
+    })?;
+
+    if !full_output {
+        let result = (optimum.particle_distance_threshold.0, optimum.speed.0);
+        return Ok(result.into_pyobject(py)?.into_any().unbind());
+    }
+
+    let dict = PyDict::new(py);
+    dict.set_item(
+        "particle_distance_threshold",
+        optimum.particle_distance_threshold.0,
+    )?;
+    dict.set_item("speed", optimum.speed.0)?;
+    dict.set_item("residual", optimum.residual)?;
+    dict.set_item("iterations", optimum.iterations)?;
+    dict.set_item("converged", optimum.is_converged())?;
+    dict.set_item("stop_reason", stop_reason_name(optimum.stop_reason))?;
+    dict.set_item("elapsed", optimum.elapsed.as_secs_f64())?;
+
+    Ok(dict.into_any().unbind())
+}
+
+/// A cancellation token that fires when Python has a pending KeyboardInterrupt (or other signal)
+fn interrupt_token() -> CancellationToken {
+    CancellationToken::with_poll(|| Python::with_gil(|py| py.check_signals().is_err()))
+}
+
+/// Select particles by tag or ID, or all of them when neither is given
+fn to_selection(tag: Option<usize>, ids: Option<Vec<usize>>) -> PyResult<ParticleSelection> {
+    match (tag, ids) {
+        (None, None) => Ok(ParticleSelection::All),
+        (Some(tag), None) => Ok(ParticleSelection::Tag(tag)),
+        (None, Some(ids)) => Ok(ParticleSelection::Ids(ids.into_iter().collect())),
+        (Some(_), Some(_)) => {
+            Err(anyhow::anyhow!("select particles by tag or ids, not both").into())
+        }
+    }
+}
+
+/// Name a scalar's column in a `SimulationData` export, keeping clear of the built-in columns
+fn scalar_column_name(name: &str) -> String {
+    const BUILT_IN_COLUMNS: [&str; 10] = [
+        "id", "tag", "x", "y", "u", "v", "theta", "phase", "speed", "leader",
+    ];
+
+    match BUILT_IN_COLUMNS.contains(&name) {
+        true => format!("scalar:{name}"),
+        false => name.to_string(),
+    }
+}
+
+fn sir_counts_to_dict(py: Python<'_>, counts: SirCounts) -> PyResult<Bound<'_, PyDict>> {
+    let dict = PyDict::new(py);
+    dict.set_item("susceptible", counts.susceptible)?;
+    dict.set_item("infected", counts.infected)?;
+    dict.set_item("recovered", counts.recovered)?;
+
+    Ok(dict)
+}
+
+fn band_collision_outcome_name(outcome: BandCollisionOutcome) -> &'static str {
+    match outcome {
+        BandCollisionOutcome::Merged => "merged",
+        BandCollisionOutcome::PassedThrough => "passed_through",
+        BandCollisionOutcome::Reflected => "reflected",
+        BandCollisionOutcome::Dispersed => "dispersed",
+    }
+}
+
+fn stop_reason_name(stop_reason: StopReason) -> &'static str {
+    match stop_reason {
+        StopReason::Converged => "converged",
+        StopReason::Cancelled => "cancelled",
+        StopReason::StepBudgetExhausted => "step_budget_exhausted",
+        StopReason::TimeBudgetExhausted => "time_budget_exhausted",
+    }
+}
+
+/// Bad parameters surface as `ValueError` and everything else as `RuntimeError`, like other
+/// failures
+impl From<SimulationError> for PyErr {
+    fn from(error: SimulationError) -> Self {
+        match error {
+            SimulationError::InvalidParameter(_) => PyValueError::new_err(error.to_string()),
+            SimulationError::ConvergenceFailure { .. }
+            | SimulationError::OptimizerFailure(_)
+            | SimulationError::NumericalAnomaly { .. } => {
+                PyRuntimeError::new_err(error.to_string())
+            }
+        }
+    }
+}
+
+fn seconds_to_duration(seconds: f64) -> anyhow::Result<Duration> {
+    Duration::try_from_secs_f64(seconds)
+        .map_err(|_| anyhow::anyhow!("`{}` is not a valid number of seconds", seconds))
+}
+
+/// Writes a compact trajectory with 16-bit quantized positions and headings
+#[pyclass(name = "QuantizedTrajectoryWriter")]
+struct PyQuantizedTrajectoryWriter(Option<QuantizedTrajectoryWriter<BufWriter<File>>>);
+
+#[pymethods]
+impl PyQuantizedTrajectoryWriter {
+    /// Create (or truncate) a trajectory file
+    #[new]
+    fn new(path: PathBuf) -> PyResult<Self> {
+        let file = File::create(&path)
+            .with_context(|| format!("could not create `{}`", path.display()))?;
+
+        Ok(Self(Some(QuantizedTrajectoryWriter::new(BufWriter::new(
+            file,
+        ))?)))
+    }
+
+    /// Append the current state of a simulation
+    fn write_frame(&mut self, sim: &PySimulation) -> PyResult<()> {
+        let writer = self
+            .0
+            .as_mut()
+            .ok_or_else(|| anyhow::anyhow!("writer is already closed"))?;
+
+        Ok(writer.write_frame(&sim.0)?)
+    }
+
+    /// Flush and close the file
+    fn close(&mut self) -> PyResult<()> {
+        if let Some(writer) = self.0.take() {
+            writer.into_inner()?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Writes the long-format `t,id,x,y,theta` trajectory table as CSV
+#[pyclass(name = "CsvTrajectoryWriter")]
+struct PyCsvTrajectoryWriter(Option<CsvTrajectoryWriter<BufWriter<File>>>);
+
+impl PyCsvTrajectoryWriter {
+    fn writer(&mut self) -> anyhow::Result<&mut CsvTrajectoryWriter<BufWriter<File>>> {
+        self.0
+            .as_mut()
+            .ok_or_else(|| anyhow::anyhow!("writer is already closed"))
+    }
+}
+
+#[pymethods]
+impl PyCsvTrajectoryWriter {
+    /// Create (or truncate) a CSV file, recording every `stride`-th step passed to `write_step`
+    #[new]
+    #[pyo3(signature = (path, stride=1))]
+    fn new(path: PathBuf, stride: usize) -> PyResult<Self> {
+        let file = File::create(&path)
+            .with_context(|| format!("could not create `{}`", path.display()))?;
+
+        Ok(Self(Some(CsvTrajectoryWriter::new(
+            BufWriter::new(file),
+            stride,
+        )?)))
+    }
+
+    /// Append the current state of a simulation, regardless of the stride
+    fn write_frame(&mut self, sim: &PySimulation) -> PyResult<()> {
+        Ok(self.writer()?.write_frame(&sim.0)?)
+    }
+
+    /// Count a step, appending the state if it's a `stride`-th one. Returns whether it was
+    /// written.
+    fn write_step(&mut self, sim: &PySimulation) -> PyResult<bool> {
+        Ok(self.writer()?.write_step(&sim.0)?)
+    }
+
+    /// Flush and close the file
+    fn close(&mut self) -> PyResult<()> {
+        if let Some(writer) = self.0.take() {
+            writer.into_inner()?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Writes frames as VTK `.vtp` files plus a ParaView `.pvd` collection indexing them
+#[pyclass(name = "VtkSeriesWriter")]
+struct PyVtkSeriesWriter(Option<VtkSeriesWriter>);
+
+#[pymethods]
+impl PyVtkSeriesWriter {
+    /// Start a series indexed by the `.pvd` file at `path`, with frames written next to it
+    #[new]
+    fn new(path: PathBuf) -> PyResult<Self> {
+        Ok(Self(Some(VtkSeriesWriter::new(path)?)))
+    }
+
+    /// Write the current state of a simulation as the next frame
+    fn write_frame(&mut self, sim: &PySimulation) -> PyResult<()> {
+        let writer = self
+            .0
+            .as_mut()
+            .ok_or_else(|| anyhow::anyhow!("writer is already closed"))?;
+
+        Ok(writer.write_frame(&(&sim.0).into())?)
+    }
+
+    /// Write the `.pvd` collection and close the series
+    fn close(&mut self) -> PyResult<()> {
+        if let Some(writer) = self.0.take() {
+            writer.finish()?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Writes frames in the extended XYZ format for OVITO and VMD
+#[pyclass(name = "XyzTrajectoryWriter")]
+struct PyXyzTrajectoryWriter(Option<XyzTrajectoryWriter<BufWriter<File>>>);
+
+#[pymethods]
+impl PyXyzTrajectoryWriter {
+    /// Create (or truncate) a trajectory file
+    #[new]
+    fn new(path: PathBuf) -> PyResult<Self> {
+        let file = File::create(&path)
+            .with_context(|| format!("could not create `{}`", path.display()))?;
+
+        Ok(Self(Some(XyzTrajectoryWriter::new(BufWriter::new(file)))))
+    }
+
+    /// Append the current state of a simulation
+    fn write_frame(&mut self, sim: &PySimulation) -> PyResult<()> {
+        let writer = self
+            .0
+            .as_mut()
+            .ok_or_else(|| anyhow::anyhow!("writer is already closed"))?;
+
+        Ok(writer.write_frame(&sim.0)?)
+    }
+
+    /// Flush and close the file
+    fn close(&mut self) -> PyResult<()> {
+        if let Some(writer) = self.0.take() {
+            writer.into_inner()?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Writes the long-format `t, id, x, y, theta` trajectory table as Parquet, one row group per
+/// frame
+// Note: the Parquet writer isn't `Sync`, which Python classes must be, hence the lock
+#[cfg(feature = "parquet")]
+#[pyclass(name = "ParquetTrajectoryWriter")]
+struct PyParquetTrajectoryWriter(
+    std::sync::Mutex<Option<ParquetTrajectoryWriter<BufWriter<File>>>>,
+);
+
+#[cfg(feature = "parquet")]
+#[pymethods]
+impl PyParquetTrajectoryWriter {
+    /// Create (or truncate) a Parquet file
+    #[new]
+    fn new(path: PathBuf) -> PyResult<Self> {
+        let file = File::create(&path)
+            .with_context(|| format!("could not create `{}`", path.display()))?;
+
+        let writer = ParquetTrajectoryWriter::new(BufWriter::new(file))?;
+
+        Ok(Self(std::sync::Mutex::new(Some(writer))))
+    }
+
+    /// Append the current state of a simulation
+    fn write_frame(&mut self, sim: &PySimulation) -> PyResult<()> {
+        let mut writer = self
+            .0
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let writer = writer
+            .as_mut()
+            .ok_or_else(|| anyhow::anyhow!("writer is already closed"))?;
+
+        Ok(writer.write_frame(&sim.0)?)
+    }
+
+    /// Write the footer and close the file, which is unreadable until then
+    fn close(&mut self) -> PyResult<()> {
+        let writer = self
+            .0
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .take();
+
+        if let Some(writer) = writer {
+            writer.finish()?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A frame stream's destination, a file or a socket
+#[cfg(feature = "arrow")]
+type ArrowFrameSink = Box<dyn std::io::Write + Send>;
+
+/// Streams frames as Arrow IPC record batches to a file or a TCP socket, for another process to
+/// read live, e.g. with `pyarrow.ipc.open_stream`
+#[cfg(feature = "arrow")]
+#[pyclass(name = "ArrowFrameWriter")]
+struct PyArrowFrameWriter(std::sync::Mutex<Option<ArrowFrameWriter<ArrowFrameSink>>>);
+
+#[cfg(feature = "arrow")]
+impl PyArrowFrameWriter {
+    fn from_sink(sink: ArrowFrameSink) -> anyhow::Result<Self> {
+        Ok(Self(std::sync::Mutex::new(Some(ArrowFrameWriter::new(
+            sink,
+        )?))))
+    }
+}
+
+#[cfg(feature = "arrow")]
+#[pymethods]
+impl PyArrowFrameWriter {
+    /// Create (or truncate) a stream file
+    #[new]
+    fn new(path: PathBuf) -> PyResult<Self> {
+        let file = File::create(&path)
+            .with_context(|| format!("could not create `{}`", path.display()))?;
+
+        Ok(Self::from_sink(Box::new(BufWriter::new(file)))?)
+    }
+
+    /// Stream to a reader listening on a TCP `address`, e.g. `"127.0.0.1:9000"`
+    #[staticmethod]
+    fn connect(address: &str) -> PyResult<Self> {
+        let stream = std::net::TcpStream::connect(address)
+            .with_context(|| format!("could not connect to `{address}`"))?;
+
+        Ok(Self::from_sink(Box::new(BufWriter::new(stream)))?)
+    }
+
+    /// Append the current state of a simulation
+    fn write_frame(&mut self, sim: &PySimulation) -> PyResult<()> {
+        let mut writer = self
+            .0
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let writer = writer
+            .as_mut()
+            .ok_or_else(|| anyhow::anyhow!("writer is already closed"))?;
+
+        Ok(writer.write_frame(&sim.0)?)
+    }
+
+    /// End the stream and close the file or socket
+    fn close(&mut self) -> PyResult<()> {
+        let writer = self
+            .0
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .take();
+
+        if let Some(writer) = writer {
+            writer.finish()?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Writes full-precision, zstd-compressed frames that `TrajectoryReader` can seek between
+#[cfg(feature = "zstd")]
+#[pyclass(name = "CompressedTrajectoryWriter")]
+struct PyCompressedTrajectoryWriter(Option<CompressedTrajectoryWriter<BufWriter<File>>>);
+
+#[cfg(feature = "zstd")]
+#[pymethods]
+impl PyCompressedTrajectoryWriter {
+    /// Create (or truncate) a trajectory file, storing `sim`'s parameters in its header
+    #[new]
+    #[pyo3(signature = (path, sim, level=3))]
+    fn new(path: PathBuf, sim: &PySimulation, level: i32) -> PyResult<Self> {
+        let file = File::create(&path)
+            .with_context(|| format!("could not create `{}`", path.display()))?;
+
+        Ok(Self(Some(CompressedTrajectoryWriter::new(
+            BufWriter::new(file),
+            &sim.0,
+            level,
+        )?)))
+    }
+
+    /// Append the current state of a simulation
+    fn write_frame(&mut self, sim: &PySimulation) -> PyResult<()> {
+        let writer = self
+            .0
+            .as_mut()
+            .ok_or_else(|| anyhow::anyhow!("writer is already closed"))?;
+
+        Ok(writer.write_frame(&sim.0)?)
+    }
+
+    /// Flush and close the file
+    fn close(&mut self) -> PyResult<()> {
+        if let Some(writer) = self.0.take() {
+            writer.into_inner()?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Reads a compressed trajectory back, frame by frame in any order
+#[cfg(feature = "zstd")]
+#[pyclass(name = "TrajectoryReader")]
+struct PyTrajectoryReader(TrajectoryReader<BufReader<File>>);
+
+#[cfg(feature = "zstd")]
+#[pymethods]
+impl PyTrajectoryReader {
+    #[new]
+    fn new(path: PathBuf) -> PyResult<Self> {
+        let file =
+            File::open(&path).with_context(|| format!("could not open `{}`", path.display()))?;
+
+        Ok(Self(TrajectoryReader::new(BufReader::new(file))?))
+    }
+
+    fn __len__(&self) -> usize {
+        self.0.len()
+    }
+
+    /// The simulation parameters the trajectory was written with
+    fn parameters<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let header = self.0.header();
+        let dict = PyDict::new(py);
+        dict.set_item("boundary_side_length", header.boundary_side_length.0)?;
+        dict.set_item("noise", header.noise.0)?;
+        dict.set_item("speed", header.speed.0)?;
+        dict.set_item("timestep", header.timestep.0)?;
+        dict.set_item(
+            "particle_distance_threshold",
+            header.particle_distance_threshold.0,
+        )?;
+
+        Ok(dict)
+    }
+
+    /// Decompress frame `idx` as a dict of `time`, `id`, `x`, `y`, and `theta`
+    fn frame<'py>(&mut self, py: Python<'py>, idx: usize) -> PyResult<Bound<'py, PyDict>> {
+        let frame = self.0.frame(idx)?;
+        let dict = PyDict::new(py);
+        dict.set_item("time", frame.time.0)?;
+        dict.set_item("id", frame.id)?;
+        dict.set_item("x", frame.x)?;
+        dict.set_item("y", frame.y)?;
+        dict.set_item("theta", frame.theta)?;
+
+        Ok(dict)
+    }
+}
+
+/// Read a quantized trajectory back as a list of frame dicts
+#[pyfunction(name = "read_quantized_trajectory")]
+fn py_read_quantized_trajectory(py: Python<'_>, path: PathBuf) -> PyResult<Vec<Bound<'_, PyDict>>> {
+    let file = File::open(&path).with_context(|| format!("could not open `{}`", path.display()))?;
+    let frames = read_quantized_trajectory(BufReader::new(file))?;
+
+    frames
+        .iter()
+        .map(|frame| {
+            let dict = PyDict::new(py);
+            dict.set_item("time", frame.time.0)?;
+            dict.set_item("boundary_side_length", frame.boundary_side_length.0)?;
+            dict.set_item("x", frame.x())?;
+            dict.set_item("y", frame.y())?;
+            dict.set_item("theta", frame.theta())?;
+
+            Ok(dict)
+        })
+        .collect()
+}
+
+/// Keys accepted in a `run_worker` config
+const WORKER_CONFIG_KEYS: [&str; 12] = [
+    "num_particles",
+    "boundary_side_length",
+    "noise",
+    "speed",
+    "timestep",
+    "particle_distance_threshold",
+    "seed",
+    "num_steps",
+    "stationary",
+    "max_steps",
+    "time_budget",
+    "include_state",
+];
+
+/// Run one simulation described entirely by a config dict and return a result dict, for use with
+/// process pools. Config keys: `num_particles`, `boundary_side_length`, `noise`, `speed`,
+/// `timestep`, `particle_distance_threshold`, and optionally `seed` (drawn at random and reported
+/// back if missing), `num_steps` to step first (default 0), `stationary` to then compute the
+/// stationary order parameter (default true) within `max_steps`/`time_budget`, and
+/// `include_state` to return the final positions and headings (default false).
+#[pyfunction(name = "run_worker")]
+fn py_run_worker<'py>(
+    py: Python<'py>,
+    config: &Bound<'py, PyDict>,
+) -> PyResult<Bound<'py, PyDict>> {
+    for key in config.keys() {
+        let key: String = key.extract()?;
+        if !WORKER_CONFIG_KEYS.contains(&key.as_str()) {
+            return Err(anyhow::anyhow!("unknown worker config key `{}`", key).into());
+        }
+    }
+
+    let seed = config_value(config, "seed")?.unwrap_or_else(rand::random::<u64>);
+    seed_rng(seed);
+
+    let mut sim = Simulation::new(
+        required_config_value(config, "num_particles")?,
+        DomainBoundaryLength(required_config_value(config, "boundary_side_length")?),
+        Noise(required_config_value(config, "noise")?),
+        Speed(required_config_value(config, "speed")?),
+        RelativeTime(required_config_value(config, "timestep")?),
+        ParticleDistanceThreshold(required_config_value(
+            config,
+            "particle_distance_threshold",
+        )?),
+    )?;
+
+    let num_steps = config_value(config, "num_steps")?.unwrap_or(0usize);
+    let sim = py.allow_threads(|| {
+        for _ in 0..num_steps {
+            sim = sim.to_timestepped();
+        }
+        sim
+    });
+
+    let result = PyDict::new(py);
+    result.set_item("config", config.copy()?)?;
+    result.set_item("seed", seed)?;
+    result.set_item("current_time", sim.current_time.0)?;
+    result.set_item("instantaneous_order", sim.instantaneous_order.0)?;
+
+    if config_value(config, "stationary")?.unwrap_or(true) {
+        let options = StationaryOrderOptions {
+            cancellation: Some(interrupt_token()),
+            max_steps: config_value(config, "max_steps")?,
+            time_budget: config_value::<f64>(config, "time_budget")?
+                .map(seconds_to_duration)
+                .transpose()?,
+        };
+
+        let estimate = py.allow_threads(|| sim.compute_stationary_order_estimate(&options))?;
+        if estimate.stop_reason == StopReason::Cancelled {
+            return Err(PyKeyboardInterrupt::new_err(
+                "stationary order parameter computation was interrupted",
+            ));
+        }
+
+        result.set_item("stationary_order_parameter", estimate.value)?;
+        result.set_item("iterations", estimate.iterations)?;
+        result.set_item("converged", estimate.is_converged())?;
+        result.set_item("stop_reason", stop_reason_name(estimate.stop_reason))?;
+    }
+
+    if config_value(config, "include_state")?.unwrap_or(false) {
+        let data = SimulationData::from(&sim);
+        result.set_item("x", data.x)?;
+        result.set_item("y", data.y)?;
+        result.set_item("u", data.u)?;
+        result.set_item("v", data.v)?;
+    }
+
+    Ok(result)
+}
+
+/// Compute the stationary order parameter at each `(noise, speed, threshold)` point, returning a
+/// dict per point. With `results_path`, points are recorded to that CSV file as they finish and a
+/// rerun skips the ones already there, so an interrupted sweep picks up where it stopped. With a
+/// `seed`, point `i` is seeded with `seed + i`, and `num_threads` points are computed at once.
+#[pyfunction(name = "run_sweep")]
+#[pyo3(signature = (
+    noises,
+    speeds,
+    particle_distance_thresholds,
+    num_particles,
+    boundary_side_length,
+    timestep,
+    results_path = None,
+    max_steps = None,
+    time_budget = None,
+    seed = None,
+    num_threads = 1,
+))]
+#[allow(clippy::too_many_arguments)]
+fn py_run_sweep<'py>(
+    py: Python<'py>,
+    noises: Vec<Float>,
+    speeds: Vec<Float>,
+    particle_distance_thresholds: Vec<Float>,
+    num_particles: usize,
+    boundary_side_length: Float,
+    timestep: Float,
+    results_path: Option<PathBuf>,
+    max_steps: Option<usize>,
+    time_budget: Option<f64>,
+    seed: Option<u64>,
+    num_threads: usize,
+) -> PyResult<Vec<Bound<'py, PyDict>>> {
+    if noises.len() != speeds.len() || noises.len() != particle_distance_thresholds.len() {
+        return Err(anyhow::anyhow!(
+            "got `{}` noises, `{}` speeds, and `{}` thresholds",
+            noises.len(),
+            speeds.len(),
+            particle_distance_thresholds.len()
+        )
+        .into());
+    }
+
+    let points: Vec<_> = noises
+        .into_iter()
+        .zip(speeds)
+        .zip(particle_distance_thresholds)
+        .map(|((noise, speed), threshold)| SweepPoint {
+            num_particles,
+            noise: Noise(noise),
+            speed: Speed(speed),
+            particle_distance_threshold: ParticleDistanceThreshold(threshold),
+        })
+        .collect();
+
+    let options = SweepOptions {
+        stationary_order: StationaryOrderOptions {
+            cancellation: Some(interrupt_token()),
+            max_steps,
+            time_budget: time_budget.map(seconds_to_duration).transpose()?,
+        },
+        seed,
+        num_threads,
+    };
+
+    let report = py.allow_threads(|| {
+        run_sweep(
+            &points,
+            DomainBoundaryLength(boundary_side_length),
+            RelativeTime(timestep),
+            &options,
+            results_path.as_deref(),
+        )
+    })?;
+
+    if report.stop_reason == StopReason::Cancelled {
+        return Err(PyKeyboardInterrupt::new_err(format!(
+            "sweep was interrupted after `{}` of `{}` points",
+            report.results.len(),
+            points.len()
+        )));
+    }
+
+    report
+        .results
+        .iter()
+        .map(|result| {
+            let dict = PyDict::new(py);
+            dict.set_item("index", result.index)?;
+            dict.set_item("num_particles", result.point.num_particles)?;
+            dict.set_item("noise", result.point.noise.0)?;
+            dict.set_item("speed", result.point.speed.0)?;
+            dict.set_item(
+                "particle_distance_threshold",
+                result.point.particle_distance_threshold.0,
+            )?;
+            dict.set_item("stationary_order_parameter", result.value)?;
+            dict.set_item("converged", result.converged)?;
+            dict.set_item("iterations", result.iterations)?;
+
+            Ok(dict)
+        })
+        .collect()
+}
+
+/// Collide two counter-propagating bands at every combination of noise and density (particles per
+/// unit area within a band), returning a dict per combination with its `outcome` (`"merged"`,
+/// `"passed_through"`, `"reflected"`, or `"dispersed"`), each band's final mean heading vector,
+/// and the overall `order`
+#[pyfunction(name = "run_band_collisions")]
+#[pyo3(signature = (
+    noises,
+    densities,
+    boundary_side_length,
+    band_width,
+    speed,
+    timestep,
+    particle_distance_threshold,
+    num_steps = None,
+    polarized_threshold = 0.5,
+))]
+#[allow(clippy::too_many_arguments)]
+fn py_run_band_collisions<'py>(
+    py: Python<'py>,
+    noises: Vec<Float>,
+    densities: Vec<Float>,
+    boundary_side_length: Float,
+    band_width: Float,
+    speed: Float,
+    timestep: Float,
+    particle_distance_threshold: Float,
+    num_steps: Option<usize>,
+    polarized_threshold: Float,
+) -> PyResult<Vec<Bound<'py, PyDict>>> {
+    let options = BandCollisionOptions {
+        num_steps,
+        polarized_threshold,
+        cancellation: Some(interrupt_token()),
+        ..BandCollisionOptions::new(
+            DomainBoundaryLength(boundary_side_length),
+            band_width,
+            Speed(speed),
+            RelativeTime(timestep),
+            ParticleDistanceThreshold(particle_distance_threshold),
+        )
+    };
+    let noises: Vec<_> = noises.into_iter().map(Noise).collect();
+
+    let results = py.allow_threads(|| run_band_collisions(&noises, &densities, &options))?;
+
+    // Cancelling is the only way to come back short
+    let num_combinations = noises.len() * densities.len();
+    if results.len() < num_combinations {
+        return Err(PyKeyboardInterrupt::new_err(format!(
+            "band collisions were interrupted after `{}` of `{}` combinations",
+            results.len(),
+            num_combinations
+        )));
+    }
+
+    results
+        .iter()
+        .map(|result| {
+            let dict = PyDict::new(py);
+            dict.set_item("noise", result.noise.0)?;
+            dict.set_item("density", result.density)?;
+            dict.set_item("outcome", band_collision_outcome_name(result.outcome))?;
+            dict.set_item("rightward_heading", result.band_headings[0])?;
+            dict.set_item("leftward_heading", result.band_headings[1])?;
+            dict.set_item("order", result.order)?;
+
+            Ok(dict)
+        })
+        .collect()
+}
+
+/// Build a trigger from a dict, as described on `Simulation.run_with_triggers`
+fn observable_from_name(kind: &str) -> PyResult<Observable> {
+    match kind {
+        "order" => Ok(Observable::Order),
+        "num_particles" => Ok(Observable::NumParticles),
+        "time" => Ok(Observable::Time),
+        "polarization_x" => Ok(Observable::PolarizationX),
+        "polarization_y" => Ok(Observable::PolarizationY),
+        kind => Err(anyhow::anyhow!("unknown observable `{}`", kind).into()),
+    }
+}
+
+fn trigger_from_dict(config: &Bound<'_, PyDict>) -> PyResult<Trigger> {
+    let observable = match config_value::<String>(config, "observable")?.as_deref() {
+        Some(kind) => observable_from_name(kind)?,
+        None => return Err(anyhow::anyhow!("trigger is missing `observable`").into()),
+    };
+
+    let crossing = match (
+        config_value(config, "above")?,
+        config_value(config, "below")?,
+    ) {
+        (Some(threshold), None) => Crossing::Above(threshold),
+        (None, Some(threshold)) => Crossing::Below(threshold),
+        _ => {
+            return Err(anyhow::anyhow!("trigger needs exactly one of `above` and `below`").into());
+        }
+    };
+
+    let value = || -> PyResult<Float> {
+        config_value(config, "value")?
+            .ok_or_else(|| anyhow::anyhow!("trigger action is missing `value`").into())
+    };
+    let action = match config_value::<String>(config, "action")?.as_deref() {
+        Some("mark") => TriggerAction::Mark,
+        Some("stop") => TriggerAction::Stop,
+        Some("snapshot") => TriggerAction::Snapshot,
+        Some("record") => TriggerAction::StartRecording {
+            stride: config_value(config, "stride")?.unwrap_or(1),
+        },
+        Some("set_noise") => TriggerAction::SetNoise(Noise(value()?)),
+        Some("set_speed") => TriggerAction::SetSpeed(Speed(value()?)),
+        Some("set_distance_threshold") => {
+            TriggerAction::SetDistanceThreshold(ParticleDistanceThreshold(value()?))
+        }
+        Some(kind) => return Err(anyhow::anyhow!("unknown trigger action `{}`", kind).into()),
+        None => return Err(anyhow::anyhow!("trigger is missing `action`").into()),
+    };
+
+    Ok(Trigger {
+        rearm: config_value(config, "rearm")?.unwrap_or(false),
+        ..Trigger::new(
+            observable,
+            crossing,
+            config_value(config, "sustain")?.unwrap_or(1),
+            action,
+        )
+    })
+}
+
+/// Convert a triggered run's report to a dict, as described on `Simulation.run_with_triggers`
+fn trigger_report_to_dict(py: Python<'_>, report: TriggerRunReport) -> PyResult<Bound<'_, PyDict>> {
+    if report.cancelled {
+        return Err(PyKeyboardInterrupt::new_err(format!(
+            "triggered run was interrupted after `{}` steps",
+            report.num_steps
+        )));
+    }
+
+    let events = report
+        .events
+        .iter()
+        .map(|event| {
+            let dict = PyDict::new(py);
+            dict.set_item("trigger", event.trigger)?;
+            dict.set_item("step", event.step)?;
+            dict.set_item("value", event.value)?;
+
+            Ok(dict)
+        })
+        .collect::<PyResult<Vec<_>>>()?;
+    let snapshots: Vec<_> = report.snapshots.into_iter().map(PySimulation).collect();
+    let frames = report
+        .frames
+        .into_iter()
+        .map(|(step, sim)| Ok((step, Bound::new(py, PySimulation(sim))?)))
+        .collect::<PyResult<Vec<_>>>()?;
+
+    let dict = PyDict::new(py);
+    dict.set_item("simulation", PySimulation(report.simulation))?;
+    dict.set_item("num_steps", report.num_steps)?;
+    dict.set_item("events", events)?;
+    dict.set_item("snapshots", snapshots)?;
+    dict.set_item("frames", frames)?;
+    dict.set_item("stopped_by", report.stopped_by)?;
+
+    Ok(dict)
+}
+
+fn config_value<'py, T: FromPyObject<'py>>(
+    config: &Bound<'py, PyDict>,
+    key: &str,
+) -> PyResult<Option<T>> {
+    config
+        .get_item(key)?
+        .filter(|value| !value.is_none())
+        .map(|value| value.extract())
+        .transpose()
+}
+
+fn required_config_value<'py, T: FromPyObject<'py>>(
+    config: &Bound<'py, PyDict>,
+    key: &str,
+) -> PyResult<T> {
+    config_value(config, key)?
+        .ok_or_else(|| anyhow::anyhow!("worker config is missing `{}`", key).into())
+}
+
+/// Compare two sets of measurements (e.g. stationary order parameters, or order time series)
+/// for a difference in means, correcting for autocorrelation
+///
+/// Returns a dict with the `mean_difference` (second minus first), Cohen's `effect_size`, the
+/// Welch `t_statistic`, the block-permutation `p_value`, the `verdict` (`"consistent"` or
+/// `"different"` at significance level `alpha`), and a summary of each set as `first` and
+/// `second` (`mean`, `std_dev`, `autocorrelation_time`, `effective_size`, `std_error`).
+#[pyfunction(name = "compare_measurements")]
+#[pyo3(signature = (first, second, alpha = 0.05, num_permutations = 10_000))]
+fn py_compare_measurements<'py>(
+    py: Python<'py>,
+    first: Vec<Float>,
+    second: Vec<Float>,
+    alpha: Float,
+    num_permutations: usize,
+) -> PyResult<Bound<'py, PyDict>> {
+    let options = SignificanceOptions {
+        alpha,
+        num_permutations,
+    };
+    let test = compare_measurements(&first, &second, &options)?;
+
+    let summary_to_dict = |summary: MeasurementSummary| -> PyResult<Bound<'py, PyDict>> {
+        let dict = PyDict::new(py);
+        dict.set_item("mean", summary.mean)?;
+        dict.set_item("std_dev", summary.std_dev)?;
+        dict.set_item("autocorrelation_time", summary.autocorrelation_time)?;
+        dict.set_item("effective_size", summary.effective_size)?;
+        dict.set_item("std_error", summary.std_error)?;
+        Ok(dict)
+    };
+
+    let result = PyDict::new(py);
+    result.set_item("first", summary_to_dict(test.first)?)?;
+    result.set_item("second", summary_to_dict(test.second)?)?;
+    result.set_item("mean_difference", test.mean_difference)?;
+    result.set_item("effect_size", test.effect_size)?;
+    result.set_item("t_statistic", test.t_statistic)?;
+    result.set_item("p_value", test.p_value)?;
+    result.set_item(
+        "verdict",
+        match test.verdict {
+            Verdict::Consistent => "consistent",
+            Verdict::Different => "different",
+        },
+    )?;
+
+    Ok(result)
+}
+
+/// The distance threshold giving `mean_neighbors` neighbors on average at a number `density`
+/// (particles per unit area), assuming particles are spread uniformly
+#[pyfunction(name = "distance_threshold_for_neighbors")]
+fn py_distance_threshold_for_neighbors(density: Float, mean_neighbors: Float) -> PyResult<Float> {
+    Ok(distance_threshold_for_neighbors(density, mean_neighbors)?.0)
+}
+
+/// The mean neighbor count a distance threshold gives at a number `density`, assuming particles
+/// are spread uniformly
+#[pyfunction(name = "mean_neighbors_for_distance_threshold")]
+fn py_mean_neighbors_for_distance_threshold(
+    density: Float,
+    particle_distance_threshold: Float,
+) -> PyResult<Float> {
+    Ok(mean_neighbors_for_distance_threshold(
+        density,
+        ParticleDistanceThreshold(particle_distance_threshold),
+    )?)
+}
+
+/// Estimate whether a simulation of `num_particles` is feasible within a memory budget
+#[pyfunction(name = "plan_capacity")]
+fn py_plan_capacity(
+    py: Python<'_>,
+    num_particles: usize,
+    memory_budget_bytes: usize,
+) -> PyResult<Bound<'_, PyDict>> {
+    let plan = plan_capacity(num_particles, memory_budget_bytes);
+
+    let dict = PyDict::new(py);
+    dict.set_item("num_particles", plan.num_particles)?;
+    dict.set_item("footprint", memory_footprint_to_dict(py, &plan.footprint)?)?;
+    dict.set_item(
+        "distance_evaluations_per_step",
+        plan.distance_evaluations_per_step,
+    )?;
+    dict.set_item("fits_in_memory", plan.fits_in_memory)?;
+
+    Ok(dict)
+}
+
+fn memory_footprint_to_dict<'py>(
+    py: Python<'py>,
+    footprint: &MemoryFootprint,
+) -> PyResult<Bound<'py, PyDict>> {
+    let dict = PyDict::new(py);
+    dict.set_item("particle_storage", footprint.particle_storage)?;
+    dict.set_item("neighbor_structures", footprint.neighbor_structures)?;
+    dict.set_item("timestep_scratch", footprint.timestep_scratch)?;
+    dict.set_item("total", footprint.total())?;
+
+    Ok(dict)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scalar_columns_keep_clear_of_built_in_columns() {
+        assert_eq!(scalar_column_name("infected"), "infected");
+        assert_eq!(scalar_column_name("speed"), "scalar:speed");
+        assert_eq!(scalar_column_name("theta"), "scalar:theta");
+    }
 }
@@ -1,4 +1,7 @@
-use crate::types::Float;
+use crate::{
+    random,
+    types::{Float, PI},
+};
 
 pub(crate) trait Math {
     fn square(self) -> Float;
@@ -11,3 +14,12 @@ impl Math for Float {
         self * self
     }
 }
+
+/// Draw from a standard normal using the Box-Muller transform
+pub(crate) fn sample_standard_normal() -> Float {
+    // Shift away from 0 so the log stays finite
+    let u1 = 1.0 - random::random::<Float>();
+    let u2 = random::random::<Float>();
+
+    (-2.0 * u1.ln()).sqrt() * (2.0 * PI * u2).cos()
+}
@@ -0,0 +1,93 @@
+use std::mem::size_of;
+
+use crate::{particle::Particle, simulation::Simulation};
+
+/// A breakdown of the memory used by a simulation, in bytes
+#[derive(Copy, Clone, Debug)]
+pub struct MemoryFootprint {
+    /// The particle collection itself
+    pub particle_storage: usize,
+
+    /// Worst-case transient neighbor index lists built while stepping. Only one particle's list is
+    /// alive at a time, and at worst it holds every other particle.
+    pub neighbor_structures: usize,
+
+    /// Worst-case transient copy of the particles, since the next state is built alongside the
+    /// current one
+    pub timestep_scratch: usize,
+}
+
+impl MemoryFootprint {
+    /// Estimate the footprint of a simulation with `num_particles` particles
+    pub fn estimate(num_particles: usize) -> Self {
+        let particle_storage = num_particles * size_of::<Particle>();
+
+        Self {
+            particle_storage,
+            neighbor_structures: num_particles * size_of::<usize>(),
+            timestep_scratch: particle_storage,
+        }
+    }
+
+    /// Total peak bytes
+    pub fn total(&self) -> usize {
+        self.particle_storage + self.neighbor_structures + self.timestep_scratch
+    }
+}
+
+impl Simulation {
+    /// Report the memory used by this simulation
+    pub fn memory_footprint(&self) -> MemoryFootprint {
+        let particle_storage = self.particles.heap_bytes() + size_of::<Self>();
+
+        MemoryFootprint {
+            particle_storage,
+            ..MemoryFootprint::estimate(self.particles.len())
+        }
+    }
+}
+
+/// An up-front feasibility estimate for running a simulation of a given size
+#[derive(Copy, Clone, Debug)]
+pub struct CapacityPlan {
+    pub num_particles: usize,
+    pub footprint: MemoryFootprint,
+
+    /// Pairwise distance evaluations per timestep, since the neighbor search is O(n^2). This is
+    /// what dominates runtime for large runs.
+    pub distance_evaluations_per_step: u64,
+
+    /// Whether the estimated peak footprint fits in the given memory budget
+    pub fits_in_memory: bool,
+}
+
+/// Estimate whether a simulation of `num_particles` is feasible within a memory budget
+pub fn plan_capacity(num_particles: usize, memory_budget_bytes: usize) -> CapacityPlan {
+    let footprint = MemoryFootprint::estimate(num_particles);
+
+    let num_particles_wide = num_particles as u64;
+    let distance_evaluations_per_step = num_particles_wide * num_particles_wide.saturating_sub(1);
+
+    CapacityPlan {
+        num_particles,
+        footprint,
+        distance_evaluations_per_step,
+        fits_in_memory: footprint.total() <= memory_budget_bytes,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn capacity_plan_scales_with_particle_count() {
+        let small = plan_capacity(10, usize::MAX);
+        let large = plan_capacity(1000, usize::MAX);
+
+        assert_eq!(small.distance_evaluations_per_step, 90);
+        assert!(large.footprint.total() > small.footprint.total());
+        assert!(small.fits_in_memory);
+        assert!(!plan_capacity(1000, large.footprint.total() - 1).fits_in_memory);
+    }
+}
@@ -0,0 +1,145 @@
+use crate::{
+    Simulation,
+    error::{SimulationError, invalid_parameter},
+    types::{Float, PI, ParticleDistanceThreshold},
+};
+
+/// Find the distance threshold that gives each particle `mean_neighbors` neighbors on average at
+/// a number `density` (particles per unit area), assuming particles are spread uniformly
+///
+/// # Notes
+/// A disk of radius r holds ρπr² particles on average, so r = √(k / ρπ). Once the flock orders
+/// it clumps, so the real count ends up higher; see [`Simulation::measure_mean_neighbors`].
+pub fn distance_threshold_for_neighbors(
+    density: Float,
+    mean_neighbors: Float,
+) -> Result<ParticleDistanceThreshold, SimulationError> {
+    validate_density(density)?;
+
+    if !mean_neighbors.is_finite() || mean_neighbors <= 0.0 {
+        invalid_parameter!(
+            "mean neighbor count must be positive and finite, got `{}`",
+            mean_neighbors
+        );
+    }
+
+    Ok(ParticleDistanceThreshold(
+        (mean_neighbors / (density * PI)).sqrt(),
+    ))
+}
+
+/// Find the mean neighbor count a distance threshold gives at a number `density`, assuming
+/// particles are spread uniformly, i.e. the inverse of [`distance_threshold_for_neighbors`]
+pub fn mean_neighbors_for_distance_threshold(
+    density: Float,
+    particle_distance_threshold: ParticleDistanceThreshold,
+) -> Result<Float, SimulationError> {
+    validate_density(density)?;
+
+    if !particle_distance_threshold.0.is_finite() || particle_distance_threshold.0 <= 0.0 {
+        invalid_parameter!(
+            "particle distance threshold must be positive and finite, got `{}`",
+            particle_distance_threshold.0
+        );
+    }
+
+    Ok(density * PI * particle_distance_threshold.0.powi(2))
+}
+
+fn validate_density(density: Float) -> Result<(), SimulationError> {
+    if !density.is_finite() || density <= 0.0 {
+        invalid_parameter!("density must be positive and finite, got `{}`", density);
+    }
+
+    Ok(())
+}
+
+impl Simulation {
+    /// Get the number of particles per unit area
+    pub fn number_density(&self) -> Float {
+        self.particles.len() as Float / self.params.boundary_side_length.0.powi(2)
+    }
+
+    /// Get the number of particles per unit area in each cell of a `bins` by `bins` grid over the
+    /// domain, row by row from y = 0 and each row from x = 0
+    pub fn density_field(&self, bins: usize) -> Result<Vec<Float>, SimulationError> {
+        if bins == 0 {
+            invalid_parameter!("a density field needs at least one bin");
+        }
+
+        let length = self.params.boundary_side_length.0;
+        let cell = |position: Float| ((position / length * bins as Float) as usize).min(bins - 1);
+
+        let mut counts = vec![0usize; bins * bins];
+        for particle in self.particles.iter() {
+            counts[cell(particle.pos_y) * bins + cell(particle.pos_x)] += 1;
+        }
+
+        let cell_area = (length / bins as Float).powi(2);
+        Ok(counts
+            .into_iter()
+            .map(|count| count as Float / cell_area)
+            .collect())
+    }
+
+    /// Count how many neighbors each particle aligns with, on average, right now
+    pub fn mean_neighbor_count(&self) -> Float {
+        self.particles.compute_interaction_edges(&self.params).len() as Float
+            / self.particles.len() as Float
+    }
+
+    /// Check a threshold on a short run: the mean neighbor count averaged over `num_steps` steps
+    /// from the current state, leaving the simulation itself untouched
+    ///
+    /// # Notes
+    /// Compare with [`mean_neighbors_for_distance_threshold`]: a count well below a few
+    /// neighbors leaves most particles isolated, and the order curves then mostly reflect that.
+    pub fn measure_mean_neighbors(&self, num_steps: usize) -> Float {
+        let mut sim = self.clone();
+        let mut total = sim.mean_neighbor_count();
+
+        for _ in 0..num_steps {
+            sim = sim.to_timestepped();
+            total += sim.mean_neighbor_count();
+        }
+
+        total / (num_steps + 1) as Float
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{DomainBoundaryLength, Noise, RelativeTime, Speed};
+
+    #[test]
+    fn threshold_and_neighbor_count_are_inverses() {
+        let threshold = distance_threshold_for_neighbors(2.0, 6.0).unwrap();
+        assert!((threshold.0 - (3.0 / PI).sqrt()).abs() < 1e-9);
+
+        let mean_neighbors = mean_neighbors_for_distance_threshold(2.0, threshold).unwrap();
+        assert!((mean_neighbors - 6.0).abs() < 1e-9);
+
+        assert!(distance_threshold_for_neighbors(0.0, 6.0).is_err());
+        assert!(distance_threshold_for_neighbors(2.0, -1.0).is_err());
+    }
+
+    #[test]
+    fn neighbors_and_density_are_counted_from_the_particles() {
+        let sim = Simulation::with_particles(
+            &[(1.0, 1.0), (1.5, 1.0), (3.0, 3.0)],
+            &[0.0, 0.0, 0.0],
+            DomainBoundaryLength(4.0),
+            Noise(0.0),
+            Speed(0.0),
+            RelativeTime(1.0),
+            ParticleDistanceThreshold(1.0),
+        )
+        .unwrap();
+
+        assert!((sim.mean_neighbor_count() - 2.0 / 3.0).abs() < 1e-9);
+        assert!((sim.measure_mean_neighbors(3) - 2.0 / 3.0).abs() < 1e-9);
+        assert_eq!(sim.number_density(), 3.0 / 16.0);
+        assert_eq!(sim.density_field(2).unwrap(), [0.5, 0.0, 0.0, 0.25]);
+    }
+}
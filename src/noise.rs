@@ -0,0 +1,170 @@
+use std::sync::{Arc, Mutex};
+
+use crate::{
+    error::{SimulationError, invalid_parameter},
+    types::Float,
+};
+
+/// A recording of every particle's noise draws (the phase ξ) over a run
+///
+/// # Notes
+/// The first entry holds the phases the particles started with, and each later entry holds the
+/// phases drawn in one timestep. Replaying a stream against modified parameters gives a
+/// controlled comparison (common random numbers), since both runs see identical noise.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NoiseStream {
+    draws: Vec<Box<[Float]>>,
+}
+
+impl NoiseStream {
+    /// Build a stream from per-step phase draws, e.g. loaded back from disk
+    pub fn new(draws: Vec<Vec<Float>>) -> Result<Self, SimulationError> {
+        let Some(num_particles) = draws.first().map(Vec::len) else {
+            invalid_parameter!("a noise stream needs at least the starting phases");
+        };
+
+        if let Some(step) = draws
+            .iter()
+            .position(|phases| phases.len() != num_particles)
+        {
+            invalid_parameter!(
+                "noise stream step `{}` has `{}` draws but expected `{}`",
+                step,
+                draws[step].len(),
+                num_particles
+            );
+        }
+
+        Ok(Self {
+            draws: draws.into_iter().map(Vec::into_boxed_slice).collect(),
+        })
+    }
+
+    /// Number of timesteps recorded (excluding the starting phases)
+    pub fn num_steps(&self) -> usize {
+        self.draws.len().saturating_sub(1)
+    }
+
+    /// Number of particles the stream was recorded for
+    pub fn num_particles(&self) -> usize {
+        self.draws.first().map_or(0, |phases| phases.len())
+    }
+
+    /// Get the phase draws for a step, where step 0 is the starting phases
+    pub fn draws(&self, step: usize) -> Option<&[Float]> {
+        self.draws.get(step).map(AsRef::as_ref)
+    }
+
+    /// Iterate over the phase draws, starting with the starting phases
+    pub fn iter(&self) -> impl Iterator<Item = &[Float]> {
+        self.draws.iter().map(AsRef::as_ref)
+    }
+
+    pub(crate) fn push(&mut self, phases: Box<[Float]>) {
+        self.draws.push(phases);
+    }
+}
+
+/// Where each timestep's noise draws come from
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) enum NoiseSource {
+    /// Fresh random draws
+    #[default]
+    Random,
+
+    /// Fresh random draws, also appended to a recording
+    // Note: the recording is shared by every state stepped from the simulation it was started
+    // on, which keeps stepping cheap. Branching a run and stepping both branches will interleave
+    // their draws.
+    Recording(Arc<Mutex<NoiseStream>>),
+
+    /// Draws replayed from a recording. Once the recording runs out, draws are fresh and random.
+    Replay {
+        stream: Arc<NoiseStream>,
+        step: usize,
+    },
+}
+
+impl NoiseSource {
+    /// Get the replayed draws for the next timestep, if any
+    pub(crate) fn next_draws(&self) -> Option<&[Float]> {
+        match self {
+            Self::Replay { stream, step } => stream.draws(step + 1),
+            _ => None,
+        }
+    }
+
+    /// Move on to the next timestep, recording its draws if needed
+    pub(crate) fn to_advanced(&self, phases: impl FnOnce() -> Box<[Float]>) -> Self {
+        match self {
+            Self::Random => Self::Random,
+            Self::Recording(recording) => {
+                // A poisoned lock only means another thread panicked mid-push, and the draws
+                // themselves are still fine to keep appending to
+                recording
+                    .lock()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner())
+                    .push(phases());
+
+                Self::Recording(recording.clone())
+            }
+            Self::Replay { stream, step } => Self::Replay {
+                stream: stream.clone(),
+                step: step + 1,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        simulation::Simulation,
+        types::{DomainBoundaryLength, Noise, ParticleDistanceThreshold, RelativeTime, Speed},
+    };
+
+    #[test]
+    fn invalid_streams_are_invalid_parameters() {
+        assert!(matches!(
+            NoiseStream::new(Vec::new()),
+            Err(SimulationError::InvalidParameter(_))
+        ));
+        assert!(matches!(
+            NoiseStream::new(vec![vec![0.0, 1.0], vec![0.5]]),
+            Err(SimulationError::InvalidParameter(_))
+        ));
+
+        let stream = NoiseStream::new(vec![vec![0.0, 1.0], vec![0.5, 0.25]]).unwrap();
+        assert_eq!(stream.num_steps(), 1);
+        assert_eq!(stream.num_particles(), 2);
+    }
+
+    #[test]
+    fn replaying_a_recording_repeats_the_run() {
+        let start = Simulation::new(
+            20,
+            DomainBoundaryLength(5.0),
+            Noise(0.5),
+            Speed(0.1),
+            RelativeTime(1.0),
+            ParticleDistanceThreshold(1.0),
+        )
+        .unwrap();
+
+        let mut recorded = start.clone().with_noise_recording();
+        recorded.run_for(10).unwrap();
+        let stream = recorded.noise_recording().unwrap();
+        assert_eq!(stream.num_steps(), 10);
+
+        let mut replayed = start.clone().with_noise_replay(stream.clone()).unwrap();
+        replayed.run_for(10).unwrap();
+        assert_eq!(replayed.to_json(), recorded.to_json());
+
+        let mut fewer = start;
+        fewer.remove_particle(0).unwrap();
+        assert!(fewer.with_noise_replay(stream).is_err());
+    }
+}
@@ -0,0 +1,95 @@
+use std::sync::{Arc, Mutex};
+
+use crate::Simulation;
+
+/// Collects custom statistics as a simulation runs, without a hand-written stepping loop
+///
+/// Any `FnMut(&Simulation)` closure is an observer too.
+pub trait StepObserver: Send {
+    /// Called with the simulation after each step
+    fn on_step(&mut self, sim: &Simulation);
+}
+
+impl<F: FnMut(&Simulation) + Send> StepObserver for F {
+    fn on_step(&mut self, sim: &Simulation) {
+        self(sim)
+    }
+}
+
+impl Simulation {
+    /// Register an observer, called after every step taken by [`Simulation::run_for`],
+    /// [`Simulation::run_until`], and the stationary order parameter computations
+    ///
+    /// # Notes
+    /// Keep a clone of the `Arc` to read the observer's statistics back. Copies of the simulation,
+    /// including the ones stepped internally, share its observers, but simulations assembled from
+    /// others (e.g. by merging or splitting) start without any.
+    pub fn add_observer(&mut self, observer: Arc<Mutex<dyn StepObserver>>) {
+        self.observers.push(observer);
+    }
+
+    /// Unregister every observer
+    pub fn clear_observers(&mut self) {
+        self.observers.clear();
+    }
+
+    /// Hand the current state to every observer
+    pub(crate) fn notify_observers(&self) {
+        for observer in &self.observers {
+            // A poisoned observer panicked mid-update, but its statistics are still its own
+            observer
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .on_step(self);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{
+        AbsoluteTime, DomainBoundaryLength, Float, Noise, ParticleDistanceThreshold, RelativeTime,
+        Speed,
+    };
+
+    /// Keeps the time of every step it sees
+    #[derive(Default)]
+    struct TimeLog(Vec<Float>);
+
+    impl StepObserver for TimeLog {
+        fn on_step(&mut self, sim: &Simulation) {
+            self.0.push(sim.current_time.0);
+        }
+    }
+
+    #[test]
+    fn observers_see_every_step() {
+        let mut sim = Simulation::new(
+            5,
+            DomainBoundaryLength(5.0),
+            Noise(0.1),
+            Speed(0.1),
+            RelativeTime(1.0),
+            ParticleDistanceThreshold(1.0),
+        )
+        .unwrap();
+
+        let log = Arc::new(Mutex::new(TimeLog::default()));
+        let num_steps = Arc::new(Mutex::new(0));
+        sim.add_observer(log.clone());
+        sim.add_observer(Arc::new(Mutex::new({
+            let num_steps = num_steps.clone();
+            move |_: &Simulation| *num_steps.lock().unwrap() += 1
+        })));
+
+        sim.run_for(2).unwrap();
+        sim.run_until(AbsoluteTime(3.0)).unwrap();
+        assert_eq!(log.lock().unwrap().0, [1.0, 2.0, 3.0]);
+        assert_eq!(*num_steps.lock().unwrap(), 3);
+
+        sim.clear_observers();
+        sim.run_for(1).unwrap();
+        assert_eq!(log.lock().unwrap().0.len(), 3);
+    }
+}
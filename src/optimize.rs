@@ -1,5 +1,10 @@
+use std::time::{Duration, Instant};
+
 use argmin::{
-    core::{CostFunction, Executor},
+    core::{
+        CostFunction, Error, Executor, KV, Problem, Solver, State, TerminationReason,
+        TerminationStatus,
+    },
     solver::neldermead::NelderMead,
 };
 
@@ -7,6 +12,10 @@ use anyhow::{Context, anyhow};
 
 use crate::{
     DomainBoundaryLength, Float, Noise, ParticleDistanceThreshold, RelativeTime, Simulation, Speed,
+    compare::{ComparisonOptions, DiscrepancyWeights, SummaryStatistics},
+    control::{CancellationToken, StopReason},
+    error::SimulationError,
+    simulation::StationaryOrderOptions,
 };
 
 /// Defines how the left and right points are selected for the critical noise optimizer
@@ -23,13 +32,82 @@ pub fn optimize_for_critical_noise(
     boundary_side_length: DomainBoundaryLength,
     timestep: RelativeTime,
     noise_critical_target: Noise,
-) -> anyhow::Result<(ParticleDistanceThreshold, Speed)> {
+) -> Result<(ParticleDistanceThreshold, Speed), SimulationError> {
+    let optimum = optimize_for_critical_noise_with(
+        num_particles,
+        boundary_side_length,
+        timestep,
+        noise_critical_target,
+        &OptimizerOptions::default(),
+    )?;
+
+    Ok((optimum.particle_distance_threshold, optimum.speed))
+}
+
+/// Controls for the critical noise optimizer
+#[derive(Clone, Debug, Default)]
+pub struct OptimizerOptions {
+    /// Checked between (and within) cost evaluations; when cancelled the best parameters found so
+    /// far are returned
+    pub cancellation: Option<CancellationToken>,
+
+    /// Maximum number of Nelder-Mead iterations. Defaults to a fixed cap.
+    pub max_iterations: Option<u64>,
+
+    /// Maximum wall-clock time to spend, after which the best parameters so far are returned
+    pub time_budget: Option<Duration>,
+}
+
+/// The result of the critical noise optimizer
+#[derive(Copy, Clone, Debug)]
+pub struct CriticalNoiseOptimum {
+    pub particle_distance_threshold: ParticleDistanceThreshold,
+    pub speed: Speed,
+
+    /// The cost function value at the best parameters
+    pub residual: Float,
+
+    /// Number of Nelder-Mead iterations taken
+    pub iterations: u64,
+
+    pub stop_reason: StopReason,
+
+    /// Wall-clock time spent
+    pub elapsed: Duration,
+}
+
+impl CriticalNoiseOptimum {
+    /// Whether the optimizer met its own convergence criteria
+    pub fn is_converged(&self) -> bool {
+        self.stop_reason == StopReason::Converged
+    }
+}
+
+/// Optimize speed and the radius threshold to find a target noise, stopping early with the best
+/// parameters so far if cancelled or out of budget
+pub fn optimize_for_critical_noise_with(
+    num_particles: usize,
+    boundary_side_length: DomainBoundaryLength,
+    timestep: RelativeTime,
+    noise_critical_target: Noise,
+    options: &OptimizerOptions,
+) -> Result<CriticalNoiseOptimum, SimulationError> {
+    let _span = tracing::info_span!(
+        "optimize_for_critical_noise",
+        noise_critical_target = noise_critical_target.0
+    )
+    .entered();
+    let start = Instant::now();
+    let deadline = options.time_budget.map(|time_budget| start + time_budget);
+
     // This will be our residual function
     let cost = SimOptimizerCost::new(
         num_particles,
         boundary_side_length,
         timestep,
         noise_critical_target,
+        options.cancellation.clone(),
+        deadline,
     );
 
     // Initial conditions for the Nelder-Mead simplex to meander about. These are rough
@@ -40,21 +118,87 @@ pub fn optimize_for_critical_noise(
 
     let solver = NelderMead::new(initial_simplex)
         .with_sd_tolerance(0.0001)
-        .context("could not initialize NelderMead with tolerance")?;
+        .map_err(|error| {
+            SimulationError::OptimizerFailure(format!(
+                "could not initialize NelderMead with tolerance: {}",
+                error
+            ))
+        })?;
+
+    let solver = BudgetedSolver {
+        solver,
+        cancellation: options.cancellation.clone(),
+        deadline,
+    };
+
+    let max_iterations = options.max_iterations.unwrap_or(MAX_NELDER_MEAD_ITERATIONS);
 
     let result = Executor::new(cost, solver)
-        .configure(|state| state.max_iters(MAX_NELDER_MEAD_ITERATIONS))
+        .configure(|state| state.max_iters(max_iterations))
         .run()
-        .context("run failed")?;
+        .map_err(|error| SimulationError::OptimizerFailure(format!("run failed: {}", error)))?;
 
-    let best_param = result
-        .state
-        .best_param
-        .ok_or_else(|| anyhow!("optimizer found no best parameters"))?;
-    let best_particle_distance_threshold = ParticleDistanceThreshold(best_param[0]);
-    let best_speed = Speed(best_param[1]);
+    let stop_reason = match result.state.termination_status {
+        TerminationStatus::Terminated(TerminationReason::Interrupt) => StopReason::Cancelled,
+        TerminationStatus::Terminated(TerminationReason::Timeout) => {
+            StopReason::TimeBudgetExhausted
+        }
+        TerminationStatus::Terminated(TerminationReason::MaxItersReached) => {
+            StopReason::StepBudgetExhausted
+        }
+        _ => StopReason::Converged,
+    };
+
+    let best_param = result.state.best_param.ok_or_else(|| {
+        SimulationError::OptimizerFailure("optimizer found no best parameters".to_string())
+    })?;
+
+    Ok(CriticalNoiseOptimum {
+        particle_distance_threshold: ParticleDistanceThreshold(best_param[0]),
+        speed: Speed(best_param[1]),
+        residual: result.state.best_cost,
+        iterations: result.state.iter,
+        stop_reason,
+        elapsed: start.elapsed(),
+    })
+}
+
+/// Wraps a solver so it stops (keeping its best parameters) once cancelled or past a deadline
+struct BudgetedSolver<S> {
+    solver: S,
+    cancellation: Option<CancellationToken>,
+    deadline: Option<Instant>,
+}
+
+impl<O, I: State, S: Solver<O, I>> Solver<O, I> for BudgetedSolver<S> {
+    const NAME: &'static str = S::NAME;
 
-    Ok((best_particle_distance_threshold, best_speed))
+    fn init(&mut self, problem: &mut Problem<O>, state: I) -> Result<(I, Option<KV>), Error> {
+        self.solver.init(problem, state)
+    }
+
+    fn next_iter(&mut self, problem: &mut Problem<O>, state: I) -> Result<(I, Option<KV>), Error> {
+        self.solver.next_iter(problem, state)
+    }
+
+    fn terminate(&mut self, state: &I) -> TerminationStatus {
+        if self
+            .cancellation
+            .as_ref()
+            .is_some_and(CancellationToken::is_cancelled)
+        {
+            return TerminationStatus::Terminated(TerminationReason::Interrupt);
+        }
+
+        if self
+            .deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+        {
+            return TerminationStatus::Terminated(TerminationReason::Timeout);
+        }
+
+        self.solver.terminate(state)
+    }
 }
 
 /// This defines the cost function for Nelder-Mead to optimize against
@@ -64,6 +208,8 @@ struct SimOptimizerCost {
     timestep: RelativeTime,
     noise_critical_left: Noise,
     noise_critical_right: Noise,
+    cancellation: Option<CancellationToken>,
+    deadline: Option<Instant>,
 }
 
 impl SimOptimizerCost {
@@ -72,6 +218,8 @@ impl SimOptimizerCost {
         boundary_side_length: DomainBoundaryLength,
         timestep: RelativeTime,
         noise_critical_target: Noise,
+        cancellation: Option<CancellationToken>,
+        deadline: Option<Instant>,
     ) -> Self {
         // We set up the optimizer by considering target noise on either side of the target,
         // through an offset. This is roughly approximate, and can be made better through actually
@@ -85,6 +233,8 @@ impl SimOptimizerCost {
             timestep,
             noise_critical_left,
             noise_critical_right,
+            cancellation,
+            deadline,
         }
     }
 }
@@ -97,6 +247,12 @@ impl CostFunction for SimOptimizerCost {
     fn cost(&self, param: &Self::Param) -> Result<Self::Output, argmin::core::Error> {
         let particle_distance_threshold = ParticleDistanceThreshold(param[0]);
         let speed = Speed(param[1]);
+        let _span = tracing::debug_span!(
+            "cost",
+            particle_distance_threshold = particle_distance_threshold.0,
+            speed = speed.0
+        )
+        .entered();
 
         let sim_left = Simulation::new(
             self.num_particles,
@@ -118,21 +274,180 @@ impl CostFunction for SimOptimizerCost {
         )
         .context("could not instantiate noise-critical-right simulation in optimizer")?;
 
+        // Each evaluation may only use whatever is left of the overall time budget
+        let stationary_order_options = StationaryOrderOptions {
+            cancellation: self.cancellation.clone(),
+            time_budget: self
+                .deadline
+                .map(|deadline| deadline.saturating_duration_since(Instant::now())),
+            ..Default::default()
+        };
+
         let stationary_order_param_left = sim_left
-            .compute_stationary_order_parameter()
+            .compute_stationary_order_estimate(&stationary_order_options)
             .context("cound not compute stationary order param for noise-critical-left simulation in optimizer")?;
 
         let stationary_order_param_right = sim_right
-            .compute_stationary_order_parameter()
+            .compute_stationary_order_estimate(&stationary_order_options)
             .context("cound not compute stationary order param for noise-critical-right simulation in optimizer")?;
 
+        // A partial estimate would make for a bogus residual, so make sure it can never become
+        // the best point. When cancelled or out of time, the solver will stop before its next
+        // iteration anyway.
+        if !stationary_order_param_left.is_converged()
+            || !stationary_order_param_right.is_converged()
+        {
+            return Ok(Float::INFINITY);
+        }
+
+        let stationary_order_param_left = stationary_order_param_left.value;
+        let stationary_order_param_right = stationary_order_param_right.value;
+
         let delta_stationary_order_param =
             stationary_order_param_left - stationary_order_param_right;
 
         // If the left and right points for the stationary order param show significant change,
         // this likely means we've hit our target point
         let residual = (delta_stationary_order_param - CRITICAL_STATIONARY_ORDER_PARAM_DELTA).abs();
+        tracing::debug!(residual, "critical noise cost evaluated");
 
         Ok(residual)
     }
 }
+
+/// Parameters calibrated against reference summary statistics
+#[derive(Copy, Clone, Debug)]
+pub struct Calibration {
+    pub noise: Noise,
+    pub speed: Speed,
+    pub particle_distance_threshold: ParticleDistanceThreshold,
+
+    /// The discrepancy at the best parameters
+    pub residual: Float,
+
+    /// Number of Nelder-Mead iterations taken
+    pub iterations: u64,
+
+    pub stop_reason: StopReason,
+
+    /// Wall-clock time spent
+    pub elapsed: Duration,
+}
+
+/// Fit noise, speed, and the radius threshold so that simulated runs of `num_steps` steps match
+/// reference statistics, e.g. from experimental tracking data
+///
+/// # Notes
+/// The statistics are weighted equally in the discrepancy. Each cost evaluation is a fresh, randomly initialized run, so the discrepancy is noisy. Longer
+/// runs smooth it out at the cost of time.
+pub fn calibrate_to_statistics(
+    reference: &SummaryStatistics,
+    num_particles: usize,
+    boundary_side_length: DomainBoundaryLength,
+    timestep: RelativeTime,
+    num_steps: usize,
+    comparison: &ComparisonOptions,
+    options: &OptimizerOptions,
+) -> anyhow::Result<Calibration> {
+    let _span = tracing::info_span!("calibrate_to_statistics").entered();
+    let start = Instant::now();
+    let deadline = options.time_budget.map(|time_budget| start + time_budget);
+
+    let cost = CalibrationCost {
+        reference: reference.clone(),
+        num_particles,
+        boundary_side_length,
+        timestep,
+        num_steps,
+        comparison: *comparison,
+    };
+
+    // Simplex over (noise, speed, threshold), starting from the same rough region as the critical
+    // noise optimizer
+    let initial_simplex = vec![
+        vec![0.5, 1.0, 1.0],
+        vec![1.0, 1.0, 1.0],
+        vec![0.5, 1.5, 1.0],
+        vec![0.5, 1.0, 1.5],
+    ];
+
+    let solver = NelderMead::new(initial_simplex)
+        .with_sd_tolerance(0.0001)
+        .context("could not initialize NelderMead with tolerance")?;
+
+    let solver = BudgetedSolver {
+        solver,
+        cancellation: options.cancellation.clone(),
+        deadline,
+    };
+
+    let max_iterations = options.max_iterations.unwrap_or(MAX_NELDER_MEAD_ITERATIONS);
+
+    let result = Executor::new(cost, solver)
+        .configure(|state| state.max_iters(max_iterations))
+        .run()
+        .context("run failed")?;
+
+    let stop_reason = match result.state.termination_status {
+        TerminationStatus::Terminated(TerminationReason::Interrupt) => StopReason::Cancelled,
+        TerminationStatus::Terminated(TerminationReason::Timeout) => {
+            StopReason::TimeBudgetExhausted
+        }
+        TerminationStatus::Terminated(TerminationReason::MaxItersReached) => {
+            StopReason::StepBudgetExhausted
+        }
+        _ => StopReason::Converged,
+    };
+
+    let best_param = result
+        .state
+        .best_param
+        .ok_or_else(|| anyhow!("optimizer found no best parameters"))?;
+
+    Ok(Calibration {
+        noise: Noise(best_param[0]),
+        speed: Speed(best_param[1]),
+        particle_distance_threshold: ParticleDistanceThreshold(best_param[2]),
+        residual: result.state.best_cost,
+        iterations: result.state.iter,
+        stop_reason,
+        elapsed: start.elapsed(),
+    })
+}
+
+/// Discrepancy between a simulated run and the reference statistics
+struct CalibrationCost {
+    reference: SummaryStatistics,
+    num_particles: usize,
+    boundary_side_length: DomainBoundaryLength,
+    timestep: RelativeTime,
+    num_steps: usize,
+    comparison: ComparisonOptions,
+}
+
+impl CostFunction for CalibrationCost {
+    type Param = Vec<Float>;
+    type Output = Float;
+
+    fn cost(&self, param: &Self::Param) -> Result<Self::Output, argmin::core::Error> {
+        // The simplex is free to wander into unphysical parameters, so fence them off
+        if param.iter().any(|&value| value.is_nan() || value < 0.0) || param[2] == 0.0 {
+            return Ok(Float::INFINITY);
+        }
+
+        let sim = Simulation::new(
+            self.num_particles,
+            self.boundary_side_length,
+            Noise(param[0]),
+            Speed(param[1]),
+            self.timestep,
+            ParticleDistanceThreshold(param[2]),
+        )
+        .context("could not instantiate simulation in calibration")?;
+
+        let statistics = SummaryStatistics::from_simulation(&sim, self.num_steps, &self.comparison)
+            .context("could not compute summary statistics in calibration")?;
+
+        Ok(statistics.discrepancy(&self.reference, &DiscrepancyWeights::default()))
+    }
+}
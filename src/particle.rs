@@ -1,39 +1,427 @@
+use std::{
+    collections::{HashSet, VecDeque},
+    sync::Arc,
+};
+
 use num::Complex;
 
 use crate::{
-    math::Math,
+    error::{SimulationError, invalid_parameter},
+    math::{Math, sample_standard_normal},
+    perf::{PerformanceCounters, timed},
+    random,
+    scalars::{ParticleScalars, ParticleView, ScalarRule},
+    schedule::Schedule,
+    simulation::SimulationParameters,
     types::{
-        DomainBoundaryLength, Float, InstantaneosOrder, Noise, PI, ParticleDistanceThreshold,
-        RelativeTime, Speed,
+        AbsoluteTime, Angle, DomainBoundaryLength, Float, InstantaneosOrder, Noise, PI,
+        ParticleDistanceThreshold, RelativeTime, Speed,
     },
 };
 
 /// Represents 360 degrees of spatial rotation available
 const MAX_PARTICLE_ANGLE: Float = 2.0 * PI;
 
+/// How a leader particle steers. Leaders ignore their neighbors (and the noise), but still
+/// influence them.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LeaderHeading {
+    /// Hold a constant heading
+    Fixed(Angle),
+
+    /// Follow a heading that changes over time. Headings are interpolated as given, so a full
+    /// turn should be written as e.g. 0 -> 2π rather than wrapping back to 0.
+    Prescribed(Arc<Schedule<Angle>>),
+}
+
+impl LeaderHeading {
+    /// Get the heading to hold at a point in time
+    fn evaluate(&self, time: AbsoluteTime) -> Float {
+        match self {
+            Self::Fixed(angle) => angle.0,
+            Self::Prescribed(schedule) => schedule.evaluate(time).0,
+        }
+    }
+}
+
+/// How neighbors are weighted by distance when averaging their headings
+#[derive(Copy, Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum NeighborWeighting {
+    /// Every neighbor within the threshold counts equally (the standard Vicsek rule)
+    #[default]
+    Uniform,
+
+    /// Weight falls off linearly from 1 at zero distance to 0 at the threshold
+    LinearDecay,
+
+    /// Weight falls off as a Gaussian of the given width, cut off at the threshold
+    Gaussian { width: Float },
+}
+
+impl NeighborWeighting {
+    /// Compute a neighbor's weight given its distance
+    #[inline]
+    fn weight(self, distance: Float, distance_threshold: ParticleDistanceThreshold) -> Float {
+        match self {
+            Self::Uniform => 1.0,
+            Self::LinearDecay => 1.0 - distance / distance_threshold.0,
+            Self::Gaussian { width } => (-distance.square() / (2.0 * width.square())).exp(),
+        }
+    }
+}
+
+/// How a particle picks its new heading from its neighbors
+#[derive(Copy, Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum UpdateRule {
+    /// Align with the (optionally weighted) average heading of neighbors within the threshold
+    #[default]
+    Vicsek,
+
+    /// The "backward update" Vicsek variant: align as in `Vicsek`, but move along the newly
+    /// computed heading instead of the one held at the start of the step. Combined with the
+    /// random-sequential update order, neighbors' already-updated headings are used too.
+    BackwardVicsek,
+
+    /// Couzin's three-zone model: move away from neighbors in the repulsion zone, or otherwise
+    /// align with those in the orientation zone and move towards those in the attraction zone
+    Couzin(CouzinZones),
+
+    /// Active Brownian particles: no alignment at all, just rotational diffusion with coefficient
+    /// `rotational_diffusion` (D_r), so dθ = sqrt(2 D_r) dW. A non-interacting baseline.
+    ///
+    /// # Notes
+    /// The diffusion draws are separate from the recorded noise phases, so these runs can't be
+    /// replayed from a noise recording.
+    ActiveBrownian { rotational_diffusion: Float },
+}
+
+/// A distribution to draw per-particle speeds from
+#[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SpeedDistribution {
+    Uniform {
+        min: Float,
+        max: Float,
+    },
+    Normal {
+        mean: Float,
+        std_dev: Float,
+    },
+
+    /// The speed's logarithm is normal with mean `mu` and standard deviation `sigma`
+    LogNormal {
+        mu: Float,
+        sigma: Float,
+    },
+}
+
+impl SpeedDistribution {
+    /// Draw a speed. Negative normal draws are clamped to zero.
+    pub fn sample(self) -> Speed {
+        let speed = match self {
+            Self::Uniform { min, max } => min + (max - min) * random::random::<Float>(),
+            Self::Normal { mean, std_dev } => mean + std_dev * sample_standard_normal(),
+            Self::LogNormal { mu, sigma } => (mu + sigma * sample_standard_normal()).exp(),
+        };
+
+        Speed(speed.max(0.0))
+    }
+
+    pub(crate) fn validate(self) -> Result<(), SimulationError> {
+        let (a, b) = match self {
+            Self::Uniform { min, max } => (min, max),
+            Self::Normal { mean, std_dev } => (mean, std_dev),
+            Self::LogNormal { mu, sigma } => (mu, sigma),
+        };
+
+        if !a.is_finite() || !b.is_finite() {
+            invalid_parameter!(
+                "speed distribution parameters must be finite, got {:?}",
+                self
+            );
+        }
+
+        match self {
+            Self::Uniform { min, max } if min < 0.0 || max < min => {
+                invalid_parameter!(
+                    "uniform speeds need 0 <= min <= max, got `{}`, `{}`",
+                    min,
+                    max
+                )
+            }
+            Self::Normal { std_dev, .. } if std_dev < 0.0 => {
+                invalid_parameter!(
+                    "speed standard deviation must be non-negative, got `{}`",
+                    std_dev
+                )
+            }
+            Self::LogNormal { sigma, .. } if sigma < 0.0 => {
+                invalid_parameter!("log-normal sigma must be non-negative, got `{}`", sigma)
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// How noise perturbs the averaged heading
+#[derive(Copy, Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum NoiseModel {
+    /// Add a noise vector of length η to the averaged heading vector before taking its angle
+    /// (extrinsic noise, as in equation 1)
+    #[default]
+    Vectorial,
+
+    /// Add a uniform angle in [-η/2, η/2] to the averaged heading angle (intrinsic noise, as in
+    /// the original Vicsek model)
+    Scalar,
+}
+
+/// The order particles are updated in within a step
+#[derive(Copy, Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum UpdateOrder {
+    /// Every particle updates from the previous step's state at once
+    #[default]
+    Synchronous,
+
+    /// Particles update one at a time in a fresh random order each step, each seeing the
+    /// already-updated state of those before it
+    RandomSequential,
+}
+
+/// How particles are laid out when a simulation starts
+#[derive(Copy, Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum InitialCondition {
+    /// Positions and headings uniformly random over the domain
+    #[default]
+    UniformRandom,
+
+    /// Positions on the smallest square lattice that fits every particle, filled row by row, with
+    /// random headings
+    SquareLattice,
+
+    /// Positions normally distributed around the domain's center, with random headings
+    GaussianBlob { std_dev: Float },
+
+    /// Positions evenly spaced around a circle at the domain's center, with random headings
+    Ring { radius: Float },
+
+    /// Positions uniformly random, with every particle sharing the same heading
+    Aligned { heading: Float },
+}
+
+impl InitialCondition {
+    /// Pick the position of the `idx`th of `num_particles` particles, wrapped into the domain
+    fn sample_position(
+        self,
+        idx: usize,
+        num_particles: usize,
+        boundary_side_length: DomainBoundaryLength,
+    ) -> (Float, Float) {
+        let center = 0.5 * boundary_side_length.0;
+
+        let (pos_x, pos_y) = match self {
+            Self::UniformRandom | Self::Aligned { .. } => (
+                Particle::sample_random_linear_position(boundary_side_length),
+                Particle::sample_random_linear_position(boundary_side_length),
+            ),
+            Self::SquareLattice => {
+                // Particles sit in the middle of their lattice cells
+                let side = (num_particles as Float).sqrt().ceil() as usize;
+                let spacing = boundary_side_length.0 / side as Float;
+
+                (
+                    ((idx % side) as Float + 0.5) * spacing,
+                    ((idx / side) as Float + 0.5) * spacing,
+                )
+            }
+            Self::GaussianBlob { std_dev } => (
+                center + std_dev * sample_standard_normal(),
+                center + std_dev * sample_standard_normal(),
+            ),
+            Self::Ring { radius } => {
+                let angle = MAX_PARTICLE_ANGLE * idx as Float / num_particles as Float;
+
+                (center + radius * angle.cos(), center + radius * angle.sin())
+            }
+        };
+
+        (
+            pos_x.rem_euclid(boundary_side_length.0),
+            pos_y.rem_euclid(boundary_side_length.0),
+        )
+    }
+
+    /// Pick a particle's starting heading
+    fn sample_heading(self) -> Float {
+        match self {
+            Self::Aligned { heading } => heading.rem_euclid(MAX_PARTICLE_ANGLE),
+            _ => Particle::sample_random_angular_position(),
+        }
+    }
+
+    /// Check the layout's own parameters
+    pub(crate) fn validate(self) -> Result<(), SimulationError> {
+        match self {
+            Self::UniformRandom | Self::SquareLattice => {}
+            Self::GaussianBlob { std_dev } => {
+                if std_dev.is_nan() || std_dev < 0.0 {
+                    invalid_parameter!(
+                        "blob standard deviation must be non-negative, got `{}`",
+                        std_dev
+                    );
+                }
+            }
+            Self::Ring { radius } => {
+                if radius.is_nan() || radius < 0.0 {
+                    invalid_parameter!("ring radius must be non-negative, got `{}`", radius);
+                }
+            }
+            Self::Aligned { heading } => {
+                if !heading.is_finite() {
+                    invalid_parameter!("aligned heading must be finite, got `{}`", heading);
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The radii bounding each zone of the Couzin model, measured from the particle
+///
+/// # Notes
+/// The zones are nested, so `repulsion_radius <= orientation_radius <= attraction_radius`. The
+/// attraction radius takes the place of the distance threshold in the neighbor search.
+#[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CouzinZones {
+    pub repulsion_radius: Float,
+    pub orientation_radius: Float,
+    pub attraction_radius: Float,
+}
+
+/// A soft short-range repulsion that keeps particles from stacking on top of each other
+#[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Repulsion {
+    /// Particles closer than this push each other apart
+    pub radius: Float,
+
+    /// Repulsion speed between coincident particles, decaying linearly to zero at the radius
+    pub strength: Float,
+}
+
+/// Random births and deaths, which make the population fluctuate
+///
+/// # Notes
+/// Each step, every particle independently dies with probability 1 - exp(-death_rate * dt) and
+/// gives birth with probability 1 - exp(-birth_rate * dt). Offspring copy their parent's heading
+/// and per-particle settings (but not leadership), and appear uniformly within the spawn radius
+/// of it.
+#[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BirthDeath {
+    /// Births per particle per unit time
+    pub birth_rate: Float,
+
+    /// Deaths per particle per unit time
+    pub death_rate: Float,
+
+    /// Offspring appear within this distance of their parent
+    pub spawn_radius: Float,
+}
+
+/// A per-particle energy budget that fatigues moving particles
+///
+/// # Notes
+/// A particle moves at its usual speed scaled by the fraction of its capacity left. Each step it
+/// spends `depletion_rate` per unit distance moved, and regains `recovery_rate` per unit time
+/// scaled by how far below its usual speed it moves (so fully at rest, fully spent particles
+/// recover fastest), plus `crowd_recovery_rate` per neighbor per unit time. Energy stays within
+/// [0, capacity], and new particles start with a full budget.
+#[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EnergyBudget {
+    /// Energy of a fully rested particle
+    pub capacity: Float,
+
+    /// Energy spent per unit distance moved
+    pub depletion_rate: Float,
+
+    /// Energy regained per unit time while at rest
+    pub recovery_rate: Float,
+
+    /// Extra energy regained per neighbor per unit time, e.g. from feeding in a group
+    pub crowd_recovery_rate: Float,
+}
+
 /// An individual particle with spatial and rotational state
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub(crate) struct Particle {
+    /// Always the particle's index in its collection, for fast lookups
     pub(crate) id: usize,
+
+    /// The particle's identity as seen by users, which unlike `id` stays the same as other
+    /// particles are added and removed
+    pub(crate) stable_id: usize,
+
     pub(crate) pos_x: Float,
     pub(crate) pos_y: Float,
     pub(crate) theta: Float,
     pub(crate) phase: Float,
+
+    /// A user label, e.g. which flock the particle started in. Carried along but otherwise unused
+    /// by the dynamics.
+    pub(crate) tag: usize,
+
+    /// Set when this particle is a leader with an imposed heading
+    pub(crate) leader: Option<LeaderHeading>,
+
+    /// This particle's own noise amplitude, overriding the simulation's
+    pub(crate) noise: Option<Noise>,
+
+    /// This particle's own speed, overriding the simulation's
+    pub(crate) speed: Option<Speed>,
+
+    /// Set when this particle turns away from its neighbors' average heading instead of aligning
+    pub(crate) dissenter: bool,
+
+    /// How many steps old the neighbor headings this particle reacts to are
+    pub(crate) reaction_delay: usize,
+
+    /// This particle's own interaction radius, overriding the simulation's threshold
+    pub(crate) interaction_radius: Option<ParticleDistanceThreshold>,
+
+    /// How much of the energy budget this particle has spent, so new particles start rested
+    pub(crate) energy_spent: Float,
+
+    /// User-defined named values, e.g. an infection level, updated by the simulation's scalar
+    /// rule
+    pub(crate) scalars: ParticleScalars,
+
+    /// This particle's previous headings, most recent first, kept for as far back as any
+    /// particle's reaction delay reaches
+    pub(crate) heading_history: VecDeque<Float>,
 }
 
-// TODO: the random number generators here are instantiating each invocation. Instead, we should
-// instantiate it once and pass it through. Benchmarking to determine if this is actually expensive
-// will determine how urgent this is: use criterion.rs + cargo flamegraph.
 impl Particle {
     /// Generate a random linear spatial position
     #[inline]
     fn sample_random_linear_position(boundary_side_length: DomainBoundaryLength) -> Float {
-        boundary_side_length.0 * rand::random::<Float>()
+        boundary_side_length.0 * random::random::<Float>()
     }
 
     /// Generate a random angular position
     #[inline]
     fn sample_random_angular_position() -> Float {
-        MAX_PARTICLE_ANGLE * rand::random::<Float>()
+        MAX_PARTICLE_ANGLE * random::random::<Float>()
     }
 
     /// Generate a random phase
@@ -54,6 +442,54 @@ impl Particle {
         dx.min(boundary_side_length.0 - dx)
     }
 
+    /// Compute the signed displacement x2 - x1 along the shortest path for a periodic BC
+    #[inline]
+    fn compute_signed_coord_delta_w_periodic(
+        x1: Float,
+        x2: Float,
+        boundary_side_length: DomainBoundaryLength,
+    ) -> Float {
+        let dx = (x2 - x1).rem_euclid(boundary_side_length.0);
+        match dx > 0.5 * boundary_side_length.0 {
+            true => dx - boundary_side_length.0,
+            false => dx,
+        }
+    }
+
+    /// Check whether another particle lies within `half_angle` either side of this particle's
+    /// heading
+    #[inline]
+    fn is_within_vision_cone(
+        &self,
+        other: &Self,
+        half_angle: Angle,
+        boundary_side_length: DomainBoundaryLength,
+    ) -> bool {
+        let dx = Self::compute_signed_coord_delta_w_periodic(
+            self.pos_x,
+            other.pos_x,
+            boundary_side_length,
+        );
+        let dy = Self::compute_signed_coord_delta_w_periodic(
+            self.pos_y,
+            other.pos_y,
+            boundary_side_length,
+        );
+
+        let distance = (dx.square() + dy.square()).sqrt();
+
+        // A coincident particle has no direction, so treat it as seen
+        if distance == 0.0 {
+            return true;
+        }
+
+        // Compare cosines instead of angles to avoid any wrap-around headaches: the angle between
+        // our heading and the direction to the other particle comes from the dot product
+        let cos_angle = (dx * self.theta.cos() + dy * self.theta.sin()) / distance;
+
+        cos_angle >= half_angle.0.cos()
+    }
+
     /// Compute the shortest distance between this particle and another particle
     ///
     /// sqrt((x2-x1)^2 - (y2-y1)^2)
@@ -77,19 +513,76 @@ impl Particle {
         (dx.square() + dy.square()).sqrt()
     }
 
-    /// Create a new particle with random initialization
-    fn new(id: usize, boundary_side_length: DomainBoundaryLength) -> Self {
-        let pos_x = Self::sample_random_linear_position(boundary_side_length);
-        let pos_y = Self::sample_random_linear_position(boundary_side_length);
-        let theta = Self::sample_random_angular_position();
+    /// Create the `id`th of `num_particles` new particles, laid out per the initial condition
+    fn new(
+        id: usize,
+        num_particles: usize,
+        boundary_side_length: DomainBoundaryLength,
+        initial_condition: InitialCondition,
+    ) -> Self {
+        let (pos_x, pos_y) =
+            initial_condition.sample_position(id, num_particles, boundary_side_length);
+        let theta = initial_condition.sample_heading();
         let phase = Self::sample_random_phase();
 
         Self {
             id,
+            stable_id: id,
             pos_x,
             pos_y,
             theta,
             phase,
+            tag: 0,
+            leader: None,
+            noise: None,
+            speed: None,
+            dissenter: false,
+            reaction_delay: 0,
+            interaction_radius: None,
+            energy_spent: 0.0,
+            scalars: ParticleScalars::default(),
+            heading_history: VecDeque::new(),
+        }
+    }
+
+    /// Create a new particle at a known position and heading, with a random phase
+    ///
+    /// # Notes
+    /// The IDs are placeholders, so this must go through [`Particles::from_reindexed`] or have its
+    /// stable ID set by the caller.
+    pub(crate) fn from_state(pos_x: Float, pos_y: Float, theta: Float) -> Self {
+        Self {
+            id: 0,
+            stable_id: 0,
+            pos_x,
+            pos_y,
+            theta,
+            phase: Self::sample_random_phase(),
+            tag: 0,
+            leader: None,
+            noise: None,
+            speed: None,
+            dissenter: false,
+            reaction_delay: 0,
+            interaction_radius: None,
+            energy_spent: 0.0,
+            scalars: ParticleScalars::default(),
+            heading_history: VecDeque::new(),
+        }
+    }
+
+    /// This particle's heading `delay` steps ago, or the oldest one remembered if the run hasn't
+    /// been going that long
+    #[inline]
+    fn delayed_theta(&self, delay: usize) -> Float {
+        match delay.checked_sub(1) {
+            Some(idx) => self
+                .heading_history
+                .get(idx)
+                .or(self.heading_history.back())
+                .copied()
+                .unwrap_or(self.theta),
+            None => self.theta,
         }
     }
 
@@ -100,14 +593,9 @@ impl Particle {
     fn compute_new_theta(
         &self,
         particles: &Particles,
-        distance_threshold: ParticleDistanceThreshold,
-        speed: Speed,
-        noise: Noise,
-        boundary_side_length: DomainBoundaryLength,
+        idxs_closest: IdxsNeighborParticles,
+        params: &SimulationParameters,
     ) -> Float {
-        let idxs_closest =
-            self.compute_idxs_closest(particles, distance_threshold, boundary_side_length);
-
         // This is "|s_i(t)|"
         let num_closest = idxs_closest.0.len();
 
@@ -116,157 +604,802 @@ impl Particle {
             return self.theta;
         }
 
-        // This is \sum_{j in s_i(t)}(...) in equation 1
+        // This is \sum_{j in s_i(t)}(...) in equation 1, where each term is optionally weighted by
+        // distance. We also sum the weights, since with uniform weighting that's just |s_i(t)|.
         // Note: calling map 2x for readability instead of chonky inline block
-        let summed_terms: Complex<_> = idxs_closest
+        let (summed_terms, summed_weights) = idxs_closest
             .0
             // Iterate over all the closest particles "j"...
             .into_iter()
             // ...then grab their actual particle struct references for use...
             .map(|idx| &particles.0[idx])
-            // ...then compute each sum term...
+            // ...then compute each sum term and its weight...
             .map(|particle| {
-                // Decompose these with Euler's formula
+                let weight = match params.neighbor_weighting {
+                    NeighborWeighting::Uniform => 1.0,
+                    neighbor_weighting => neighbor_weighting.weight(
+                        self.compute_euclidean_distance(particle, params.boundary_side_length),
+                        self.interaction_radius(params),
+                    ),
+                };
+
+                // Decompose these with Euler's formula, reacting to the neighbor's heading as it
+                // was `reaction_delay` steps ago
                 // v * e^{i \theta_j(t)} = v * (\cos(\theta_j) + i*\sin(\theta_j))
-                speed.0 * Complex::new(particle.theta.cos(), particle.theta.sin())
+                let theta = particle.delayed_theta(self.reaction_delay);
+                let term = weight * params.speed.0 * Complex::new(theta.cos(), theta.sin());
+
+                (term, weight)
             })
-            // ...then compute the sum.
-            .sum();
+            // ...then compute the sums.
+            .fold(
+                (Complex::new(0.0, 0.0), 0.0),
+                |(summed_terms, summed_weights), (term, weight)| {
+                    (summed_terms + term, summed_weights + weight)
+                },
+            );
 
-        // \eta * e^{i \xi_n(t)} = \eta * (\cos(\xi_n) + i*\sin(\xi_n))
-        let noise_term = noise.0 * Complex::new(self.phase.cos(), self.phase.sin());
+        // Every neighbor can weigh nothing, e.g. a narrow Gaussian underflowing, so there's no
+        // average to align with either
+        if summed_weights.is_nan() || summed_weights <= 0.0 {
+            return self.theta;
+        }
 
-        let arg_argument = 1.0 / num_closest as Float * summed_terms + noise_term;
+        // Dissenters head the opposite way to their neighbors
+        let sign = match self.dissenter {
+            true => -1.0,
+            false => 1.0,
+        };
 
         // Lastly, we compute the angle per equation 1
-        arg_argument.arg()
+        self.apply_noise(sign / summed_weights * summed_terms, params)
     }
 
-    /// Compute the new spatial coordinates
-    fn compute_new_coords(&self, speed: Speed, delta_time: RelativeTime) -> (Float, Float) {
-        let new_pos_x = self.pos_x + speed.0 * delta_time.0 * self.theta.cos();
-        let new_pos_y = self.pos_y + speed.0 * delta_time.0 * self.theta.sin();
+    /// Turn an averaged heading vector into a noisy heading angle
+    ///
+    /// # Notes
+    /// Both models reuse the particle's phase ξ, uniform in [-π, π), so noise recordings replay
+    /// under either.
+    #[inline]
+    fn apply_noise(&self, averaged: Complex<Float>, params: &SimulationParameters) -> Float {
+        // The particle's own amplitude wins, then the field at its position, then the global one
+        let noise = self.noise.unwrap_or_else(|| match &params.noise_field {
+            Some(noise_field) => {
+                Noise(noise_field.evaluate(self.pos_x, self.pos_y, params.boundary_side_length))
+            }
+            None => params.noise,
+        });
+
+        match params.noise_model {
+            NoiseModel::Vectorial => {
+                // \eta * e^{i \xi_n(t)} = \eta * (\cos(\xi_n) + i*\sin(\xi_n))
+                let noise_term = noise.0 * Complex::new(self.phase.cos(), self.phase.sin());
 
-        (new_pos_x, new_pos_y)
+                (averaged + noise_term).arg()
+            }
+            // \eta \xi_n(t) / 2\pi is uniform in [-\eta/2, \eta/2)
+            NoiseModel::Scalar => averaged.arg() + noise.0 * self.phase / MAX_PARTICLE_ANGLE,
+        }
     }
 
-    /// Temporally update the particle to a new angle and position
-    fn to_timestepped(
+    /// Compute a new theta with the Couzin three-zone model
+    ///
+    /// # Notes
+    /// Any neighbor in the repulsion zone takes priority, and the particle heads directly away
+    /// from them. Otherwise it heads along the sum of the unit vectors aligning with the
+    /// orientation zone and pointing towards the attraction zone. Noise enters as in equation 1,
+    /// and neighbor weighting is ignored.
+    fn compute_new_theta_couzin(
         &self,
         particles: &Particles,
-        distance_threshold: ParticleDistanceThreshold,
-        speed: Speed,
-        noise: Noise,
-        delta_time: RelativeTime,
-        boundary_side_length: DomainBoundaryLength,
-    ) -> Self {
-        let theta = self.compute_new_theta(
-            particles,
-            distance_threshold,
-            speed,
-            noise,
-            boundary_side_length,
-        );
-        let (pos_x, pos_y) = self.compute_new_coords(speed, delta_time);
-        let phase = Self::sample_random_phase();
+        idxs_closest: IdxsNeighborParticles,
+        zones: CouzinZones,
+        params: &SimulationParameters,
+    ) -> Float {
+        let mut repulsion = Complex::new(0.0, 0.0);
+        let mut orientation = Complex::new(0.0, 0.0);
+        let mut attraction = Complex::new(0.0, 0.0);
 
-        // Enforce periodic boundary condition using modulus. Would normally use `%` operator but
-        // for floats we need to use something a bit more special.
-        let pos_x = pos_x.rem_euclid(boundary_side_length.0);
-        let pos_y = pos_y.rem_euclid(boundary_side_length.0);
+        for particle in idxs_closest.0.into_iter().map(|idx| &particles.0[idx]) {
+            // Displacement from us to the neighbor, along the shortest periodic path
+            let offset = Complex::new(
+                Self::compute_signed_coord_delta_w_periodic(
+                    self.pos_x,
+                    particle.pos_x,
+                    params.boundary_side_length,
+                ),
+                Self::compute_signed_coord_delta_w_periodic(
+                    self.pos_y,
+                    particle.pos_y,
+                    params.boundary_side_length,
+                ),
+            );
+            let distance = offset.norm();
 
-        Self {
-            pos_x,
-            pos_y,
-            theta,
-            phase,
-            id: self.id,
+            // A coincident neighbor has no direction to move away from or towards
+            if distance == 0.0 {
+                continue;
+            }
+
+            if distance < zones.repulsion_radius {
+                repulsion -= offset / distance;
+            } else if distance < zones.orientation_radius {
+                let theta = particle.delayed_theta(self.reaction_delay);
+                orientation += Complex::new(theta.cos(), theta.sin());
+            } else {
+                attraction += offset / distance;
+            }
+        }
+
+        let desired = match repulsion.norm() > 0.0 {
+            true => repulsion,
+            false => orientation + attraction,
+        };
+
+        // With nothing to react to (or perfectly balanced influences), stay on current heading
+        if desired.norm() == 0.0 {
+            return self.theta;
         }
+
+        self.apply_noise(params.speed.0 * desired / desired.norm(), params)
+    }
+
+    /// Compute a new theta for an active Brownian particle, which diffuses freely
+    fn compute_new_theta_active_brownian(
+        &self,
+        rotational_diffusion: Float,
+        delta_time: RelativeTime,
+    ) -> Float {
+        let delta_theta =
+            (2.0 * rotational_diffusion * delta_time.0).sqrt() * sample_standard_normal();
+
+        (self.theta + delta_theta).rem_euclid(MAX_PARTICLE_ANGLE)
     }
 
-    /// Get the indices of the closest particles in the swarm given a `distance`.
+    /// Turn part of the way towards a target heading, as in dθ/dt = (θ_target - θ) / τ, if the
+    /// heading relaxes at all
     ///
     /// # Notes
-    /// This function is O(n^2) when called externally on a collection, because it iterates over
-    /// each particle checking the distance. If improved performance is required, we can look into
-    /// something like a k-d tree alg that will partition the particles into spatial groups and
-    /// allow for O(log(n)) lookup. See Rust crate `kiddo`.
-    fn compute_idxs_closest(
+    /// The turn is taken along the shorter way around, and a timestep of at least τ snaps straight
+    /// to the target as the instantaneous rule would.
+    fn relax_theta(&self, target_theta: Float, params: &SimulationParameters) -> Float {
+        let Some(relaxation_time) = params.heading_relaxation_time else {
+            return target_theta;
+        };
+
+        let fraction = (params.timestep.0 / relaxation_time.0).min(1.0);
+        let delta_theta = (target_theta - self.theta + PI).rem_euclid(MAX_PARTICLE_ANGLE) - PI;
+
+        self.theta + fraction * delta_theta
+    }
+
+    /// This particle's speed: its own if set, then the speed field at its position, then the
+    /// simulation's, slowed by fatigue under an energy budget
+    #[inline]
+    pub(crate) fn speed(&self, params: &SimulationParameters) -> Speed {
+        let Speed(speed) = self.rested_speed(params);
+
+        match params.energy_budget {
+            Some(energy_budget) => Speed(speed * self.energy_fraction(energy_budget)),
+            None => Speed(speed),
+        }
+    }
+
+    /// This particle's speed with a full energy budget
+    #[inline]
+    fn rested_speed(&self, params: &SimulationParameters) -> Speed {
+        self.speed.unwrap_or_else(|| match &params.speed_field {
+            Some(speed_field) => {
+                Speed(speed_field.evaluate(self.pos_x, self.pos_y, params.boundary_side_length))
+            }
+            None => params.speed,
+        })
+    }
+
+    /// The fraction of the energy budget this particle has left
+    #[inline]
+    fn energy_fraction(&self, energy_budget: EnergyBudget) -> Float {
+        1.0 - self.energy_spent / energy_budget.capacity
+    }
+
+    /// Spend and regain energy for one step, per [`EnergyBudget`]
+    fn compute_new_energy_spent(
         &self,
         particles: &Particles,
-        distance_threshold: ParticleDistanceThreshold,
-        boundary_side_length: DomainBoundaryLength,
-    ) -> IdxsNeighborParticles {
-        IdxsNeighborParticles(
-            particles
+        energy_budget: EnergyBudget,
+        params: &SimulationParameters,
+    ) -> Float {
+        let delta_time = params.timestep.0;
+        let distance = self.speed(params).0 * delta_time;
+
+        // Only count neighbors when crowds actually help
+        let num_neighbors = match energy_budget.crowd_recovery_rate > 0.0 {
+            true => self
+                .compute_idxs_closest(
+                    particles,
+                    self.interaction_radius(params),
+                    params.boundary_side_length,
+                    None,
+                )
                 .0
-                // We iterate over each particle...
-                .iter()
-                // ...and ensure we aren't including the target particle itself...
-                .filter(|particle| self.id != particle.id)
-                // ...and then for each particle we compute the euclidean distance between them,
-                // filtering out any particles that are further away than our threshold...
-                .filter(|particle| {
-                    self.compute_euclidean_distance(particle, boundary_side_length)
-                        < distance_threshold.0
-                })
-                // ...and then we snag the remaining, filtered indices for particles we know are
-                // within the threshold distance...
-                .map(|particle| particle.id)
-                // ...and place them all together into a collection.
-                .collect(),
-        )
-    }
-}
+                .len(),
+            false => 0,
+        };
 
-/// Contains all the particles.
-pub(crate) struct Particles(Vec<Particle>);
+        let spent = energy_budget.depletion_rate * distance;
+        let regained = (energy_budget.recovery_rate * (1.0 - self.energy_fraction(energy_budget))
+            + energy_budget.crowd_recovery_rate * num_neighbors as Float)
+            * delta_time;
 
-impl Particles {
-    /// Create a new collection of particles with random initialization
-    // Note: we are technically duplicating the particle IDs because we store them on both the
-    // particle struct as well as implicitly in the array itself. However, it's very readable
-    // and nice to be able to grab an arbitrary particle's ID when iterating over an arbitrary
-    // collection. Plus, it allows for refactoring into different kinds of collections and passing
-    // around particles without also passing their indices separately.
-    pub(crate) fn new(num_particles: usize, boundary_side_length: DomainBoundaryLength) -> Self {
-        Self(
-            // For each particle...
-            (0..num_particles)
-                // ...instantiate a random new one...
-                .map(|id| Particle::new(id, boundary_side_length))
-                // ...then collect all the particles together into this data structure.
-                .collect(),
-        )
+        (self.energy_spent + spent - regained).clamp(0.0, energy_budget.capacity)
     }
 
-    /// Temporally update the particles to new angles and positions
-    pub(crate) fn to_timestepped(
+    /// How far this particle sees its Vicsek neighbors: its own radius if set, then the
+    /// simulation's threshold
+    #[inline]
+    pub(crate) fn interaction_radius(
         &self,
-        distance_threshold: ParticleDistanceThreshold,
-        speed: Speed,
-        noise: Noise,
-        delta_time: RelativeTime,
-        boundary_side_length: DomainBoundaryLength,
-    ) -> Self {
-        Self(
-            self.0
-                .iter()
-                .map(|particle| {
-                    particle.to_timestepped(
-                        self,
-                        distance_threshold,
-                        speed,
-                        noise,
-                        delta_time,
-                        boundary_side_length,
-                    )
-                })
-                .collect(),
-        )
+        params: &SimulationParameters,
+    ) -> ParticleDistanceThreshold {
+        self.interaction_radius
+            .unwrap_or(params.particle_distance_threshold)
     }
 
-    /// Compute the polarization / instantaneous order parameter of the system
+    /// Compute the new spatial coordinates
+    fn compute_new_coords(
+        &self,
+        particles: &Particles,
+        params: &SimulationParameters,
+        heading: Float,
+    ) -> (Float, Float) {
+        let speed = self.speed(params);
+        let delta_time = params.timestep;
+
+        let new_pos_x = self.pos_x + speed.0 * delta_time.0 * heading.cos();
+        let new_pos_y = self.pos_y + speed.0 * delta_time.0 * heading.sin();
+
+        let (repulsion_x, repulsion_y) = match params.repulsion {
+            Some(repulsion) => {
+                self.compute_repulsion_velocity(particles, repulsion, params.boundary_side_length)
+            }
+            None => (0.0, 0.0),
+        };
+
+        // The background flow carries particles along regardless of their heading
+        let (flow_x, flow_y) = match &params.flow_field {
+            Some(flow_field) => {
+                flow_field.evaluate(self.pos_x, self.pos_y, params.boundary_side_length)
+            }
+            None => (0.0, 0.0),
+        };
+
+        // Translational noise jitters the position independently of everything else
+        let (jitter_x, jitter_y) = match params.translational_diffusion {
+            Some(translational_diffusion) => {
+                let amplitude = (2.0 * translational_diffusion * delta_time.0).sqrt();
+                (
+                    amplitude * sample_standard_normal(),
+                    amplitude * sample_standard_normal(),
+                )
+            }
+            None => (0.0, 0.0),
+        };
+
+        (
+            new_pos_x + (repulsion_x + flow_x) * delta_time.0 + jitter_x,
+            new_pos_y + (repulsion_y + flow_y) * delta_time.0 + jitter_y,
+        )
+    }
+
+    /// Compute the velocity pushing this particle away from any particles inside the repulsion
+    /// radius
+    ///
+    /// # Notes
+    /// Each overlapping particle contributes `strength * (1 - d / r_rep)` along the direction
+    /// between them, so the push is soft: zero at the radius and strongest when coincident.
+    /// Exactly coincident particles have no direction between them and are skipped.
+    fn compute_repulsion_velocity(
+        &self,
+        particles: &Particles,
+        repulsion: Repulsion,
+        boundary_side_length: DomainBoundaryLength,
+    ) -> (Float, Float) {
+        particles
+            .0
+            .iter()
+            .filter(|particle| self.id != particle.id)
+            .filter_map(|particle| {
+                // Displacement from the other particle to us, along the shortest periodic path
+                let dx = Self::compute_signed_coord_delta_w_periodic(
+                    particle.pos_x,
+                    self.pos_x,
+                    boundary_side_length,
+                );
+                let dy = Self::compute_signed_coord_delta_w_periodic(
+                    particle.pos_y,
+                    self.pos_y,
+                    boundary_side_length,
+                );
+                let distance = (dx.square() + dy.square()).sqrt();
+
+                if distance == 0.0 || distance >= repulsion.radius {
+                    return None;
+                }
+
+                let magnitude = repulsion.strength * (1.0 - distance / repulsion.radius);
+
+                Some((magnitude * dx / distance, magnitude * dy / distance))
+            })
+            .fold((0.0, 0.0), |(sum_x, sum_y), (push_x, push_y)| {
+                (sum_x + push_x, sum_y + push_y)
+            })
+    }
+
+    /// Temporally update the particle to a new angle and position
+    fn to_timestepped(
+        &self,
+        particles: &Particles,
+        params: &SimulationParameters,
+        new_time: AbsoluteTime,
+        counters: &mut PerformanceCounters,
+        phase: Float,
+        history_len: usize,
+    ) -> Self {
+        // Leaders hold their imposed heading, everyone else reacts to their neighbors
+        let theta = match (&self.leader, params.update_rule) {
+            (Some(leader_heading), _) => leader_heading.evaluate(new_time),
+            (None, UpdateRule::Vicsek | UpdateRule::BackwardVicsek) => {
+                let idxs_closest = timed(&mut counters.neighbor_search, || {
+                    let _span = tracing::trace_span!("neighbor_search").entered();
+                    self.compute_idxs_vicsek_neighbors(particles, params)
+                });
+                counters.count_neighbor_search(idxs_closest.0.len());
+
+                timed(&mut counters.alignment, || {
+                    self.relax_theta(
+                        self.compute_new_theta(particles, idxs_closest, params),
+                        params,
+                    )
+                })
+            }
+            (None, UpdateRule::Couzin(zones)) => {
+                // The Couzin model sees out to its outermost zone instead of the threshold
+                let idxs_closest = timed(&mut counters.neighbor_search, || {
+                    let _span = tracing::trace_span!("neighbor_search").entered();
+                    let idxs_closest = self.compute_idxs_closest(
+                        particles,
+                        ParticleDistanceThreshold(zones.attraction_radius),
+                        params.boundary_side_length,
+                        params.vision_half_angle,
+                    );
+
+                    match params.occlusion_tolerance {
+                        Some(tolerance) => self.to_unoccluded(
+                            idxs_closest,
+                            particles,
+                            tolerance,
+                            params.boundary_side_length,
+                        ),
+                        None => idxs_closest,
+                    }
+                });
+                counters.count_neighbor_search(idxs_closest.0.len());
+
+                timed(&mut counters.alignment, || {
+                    self.relax_theta(
+                        self.compute_new_theta_couzin(particles, idxs_closest, zones, params),
+                        params,
+                    )
+                })
+            }
+            // Free diffusion needs no neighbors
+            (
+                None,
+                UpdateRule::ActiveBrownian {
+                    rotational_diffusion,
+                },
+            ) => timed(&mut counters.alignment, || {
+                self.compute_new_theta_active_brownian(rotational_diffusion, params.timestep)
+            }),
+        };
+
+        let (pos_x, pos_y) = timed(&mut counters.integration, || {
+            // The backward update moves along the heading just computed
+            let heading = match params.update_rule {
+                UpdateRule::BackwardVicsek => theta,
+                _ => self.theta,
+            };
+            let (pos_x, pos_y) = self.compute_new_coords(particles, params, heading);
+
+            // Enforce periodic boundary condition using modulus. Would normally use `%` operator
+            // but for floats we need to use something a bit more special.
+            (
+                pos_x.rem_euclid(params.boundary_side_length.0),
+                pos_y.rem_euclid(params.boundary_side_length.0),
+            )
+        });
+
+        // Remember the heading being left behind, if anyone reacts with a delay
+        let heading_history = match history_len {
+            0 => VecDeque::new(),
+            history_len => std::iter::once(self.theta)
+                .chain(self.heading_history.iter().copied())
+                .take(history_len)
+                .collect(),
+        };
+
+        let energy_spent = match params.energy_budget {
+            Some(energy_budget) => self.compute_new_energy_spent(particles, energy_budget, params),
+            None => self.energy_spent,
+        };
+
+        let scalars = match &params.scalar_rule {
+            Some(scalar_rule) => self.compute_new_scalars(particles, scalar_rule, params),
+            None => self.scalars.clone(),
+        };
+
+        Self {
+            pos_x,
+            pos_y,
+            theta,
+            phase,
+            energy_spent,
+            scalars,
+            heading_history,
+            ..self.clone()
+        }
+    }
+
+    /// Run the user's scalar rule with this particle's Vicsek neighbors
+    fn compute_new_scalars(
+        &self,
+        particles: &Particles,
+        scalar_rule: &ScalarRule,
+        params: &SimulationParameters,
+    ) -> ParticleScalars {
+        let neighbors: Vec<ParticleView> = self
+            .compute_idxs_vicsek_neighbors(particles, params)
+            .0
+            .into_iter()
+            .map(|idx| particles.0[idx].to_view())
+            .collect();
+
+        let mut scalars = self.scalars.clone();
+        scalar_rule.apply(&self.to_view(), &neighbors, params.timestep, &mut scalars);
+        scalars
+    }
+
+    /// Borrow a read-only look at the particle for user code
+    fn to_view(&self) -> ParticleView<'_> {
+        ParticleView {
+            id: self.stable_id,
+            x: self.pos_x,
+            y: self.pos_y,
+            theta: self.theta,
+            tag: self.tag,
+            scalars: &self.scalars,
+        }
+    }
+
+    /// Move the particle into a resized domain
+    fn to_resized(
+        &self,
+        old_boundary_side_length: DomainBoundaryLength,
+        new_boundary_side_length: DomainBoundaryLength,
+        rescale_positions: bool,
+    ) -> Self {
+        let (pos_x, pos_y) = if rescale_positions {
+            let scale = new_boundary_side_length.0 / old_boundary_side_length.0;
+            (self.pos_x * scale, self.pos_y * scale)
+        } else {
+            (self.pos_x, self.pos_y)
+        };
+
+        // Particles that end up outside a shrunk domain get wrapped back in by the periodic BC
+        let pos_x = pos_x.rem_euclid(new_boundary_side_length.0);
+        let pos_y = pos_y.rem_euclid(new_boundary_side_length.0);
+
+        Self {
+            pos_x,
+            pos_y,
+            ..self.clone()
+        }
+    }
+
+    /// Get the indices of the closest particles in the swarm given a `distance`, optionally only
+    /// counting those within a vision cone around the heading.
+    ///
+    /// # Notes
+    /// This function is O(n^2) when called externally on a collection, because it iterates over
+    /// each particle checking the distance. If improved performance is required, we can look into
+    /// something like a k-d tree alg that will partition the particles into spatial groups and
+    /// allow for O(log(n)) lookup. See Rust crate `kiddo`.
+    fn compute_idxs_closest(
+        &self,
+        particles: &Particles,
+        distance_threshold: ParticleDistanceThreshold,
+        boundary_side_length: DomainBoundaryLength,
+        vision_half_angle: Option<Angle>,
+    ) -> IdxsNeighborParticles {
+        IdxsNeighborParticles(
+            particles
+                .0
+                // We iterate over each particle...
+                .iter()
+                // ...and ensure we aren't including the target particle itself...
+                .filter(|particle| self.id != particle.id)
+                // ...and then for each particle we compute the euclidean distance between them,
+                // filtering out any particles that are further away than our threshold...
+                .filter(|particle| {
+                    self.compute_euclidean_distance(particle, boundary_side_length)
+                        < distance_threshold.0
+                })
+                // ...and drop any particles behind us, outside of our field of view...
+                .filter(|particle| {
+                    vision_half_angle.is_none_or(|half_angle| {
+                        self.is_within_vision_cone(particle, half_angle, boundary_side_length)
+                    })
+                })
+                // ...and then we snag the remaining, filtered indices for particles we know are
+                // within the threshold distance...
+                .map(|particle| particle.id)
+                // ...and place them all together into a collection.
+                .collect(),
+        )
+    }
+
+    /// Get the indices of the particles this one aligns with under the Vicsek rules: those within
+    /// its interaction radius and vision cone, and not occluded
+    ///
+    /// # Notes
+    /// With per-particle radii this relation is directed, since a particle may see a neighbor
+    /// that can't see it back.
+    fn compute_idxs_vicsek_neighbors(
+        &self,
+        particles: &Particles,
+        params: &SimulationParameters,
+    ) -> IdxsNeighborParticles {
+        let idxs_closest = self.compute_idxs_closest(
+            particles,
+            self.interaction_radius(params),
+            params.boundary_side_length,
+            params.vision_half_angle,
+        );
+
+        match params.occlusion_tolerance {
+            Some(tolerance) => self.to_unoccluded(
+                idxs_closest,
+                particles,
+                tolerance,
+                params.boundary_side_length,
+            ),
+            None => idxs_closest,
+        }
+    }
+
+    /// Drop the neighbors hidden behind a closer neighbor, i.e. those with a closer neighbor
+    /// within `tolerance` of the line of sight to them
+    ///
+    /// # Notes
+    /// Neighbors are sorted by bearing first, so each one only checks the neighbors within 90° of
+    /// it (the only ones that could be in front of it) rather than all of them.
+    fn to_unoccluded(
+        &self,
+        idxs_closest: IdxsNeighborParticles,
+        particles: &Particles,
+        tolerance: Float,
+        boundary_side_length: DomainBoundaryLength,
+    ) -> IdxsNeighborParticles {
+        // The bearing and distance to each neighbor...
+        let mut sightings: Vec<(Float, Float, usize)> = idxs_closest
+            .0
+            .iter()
+            .map(|&idx| {
+                let particle = &particles.0[idx];
+                let dx = Self::compute_signed_coord_delta_w_periodic(
+                    self.pos_x,
+                    particle.pos_x,
+                    boundary_side_length,
+                );
+                let dy = Self::compute_signed_coord_delta_w_periodic(
+                    self.pos_y,
+                    particle.pos_y,
+                    boundary_side_length,
+                );
+
+                (dy.atan2(dx), (dx * dx + dy * dy).sqrt(), idx)
+            })
+            .collect();
+
+        // ...sorted by bearing...
+        sightings.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        let num_sightings = sightings.len();
+        let is_blocked_by =
+            |&(bearing, distance, _): &(Float, Float, usize),
+             &(other_bearing, other_distance, _): &(Float, Float, usize)| {
+                let separation = other_bearing - bearing;
+
+                other_distance < distance
+                    && (other_distance * separation.sin()).abs() < tolerance
+                    && other_distance * separation.cos() > 0.0
+            };
+        let angular_gap = |a: Float, b: Float| {
+            let gap = (a - b).rem_euclid(2.0 * PI);
+            gap.min(2.0 * PI - gap)
+        };
+
+        // ...then each is kept unless a closer one in front of it blocks the view, scanning out
+        // both ways around the circle of bearings.
+        IdxsNeighborParticles(
+            (0..num_sightings)
+                .filter(|&pos| {
+                    let sighting = &sightings[pos];
+
+                    let is_occluded = |step: fn(usize, usize) -> usize| {
+                        let mut other_pos = pos;
+                        for _ in 1..num_sightings {
+                            other_pos = step(other_pos, num_sightings);
+                            let other = &sightings[other_pos];
+
+                            if angular_gap(sighting.0, other.0) > 0.5 * PI {
+                                return false;
+                            }
+
+                            if is_blocked_by(sighting, other) {
+                                return true;
+                            }
+                        }
+
+                        false
+                    };
+
+                    !is_occluded(|pos, len| (pos + 1) % len)
+                        && !is_occluded(|pos, len| (pos + len - 1) % len)
+                })
+                .map(|pos| sightings[pos].2)
+                .collect(),
+        )
+    }
+}
+
+/// Contains all the particles.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) struct Particles(Vec<Particle>);
+
+impl Particles {
+    /// Create a new collection of particles laid out per the initial condition
+    // Note: we are technically duplicating the particle IDs because we store them on both the
+    // particle struct as well as implicitly in the array itself. However, it's very readable
+    // and nice to be able to grab an arbitrary particle's ID when iterating over an arbitrary
+    // collection. Plus, it allows for refactoring into different kinds of collections and passing
+    // around particles without also passing their indices separately.
+    pub(crate) fn new(
+        num_particles: usize,
+        boundary_side_length: DomainBoundaryLength,
+        initial_condition: InitialCondition,
+    ) -> Self {
+        Self(
+            // For each particle...
+            (0..num_particles)
+                // ...instantiate a new one...
+                .map(|id| Particle::new(id, num_particles, boundary_side_length, initial_condition))
+                // ...then collect all the particles together into this data structure.
+                .collect(),
+        )
+    }
+
+    /// Collect arbitrary particles together, re-assigning IDs (stable ones included) so they
+    /// match their position in the collection
+    // Note: the neighbor search relies on a particle's ID being its index, so anything that
+    // shuffles particles between collections must come through here, or keep IDs in step itself
+    // like adding and removing do.
+    pub(crate) fn from_reindexed(particles: impl IntoIterator<Item = Particle>) -> Self {
+        Self(
+            particles
+                .into_iter()
+                .enumerate()
+                .map(|(id, particle)| Particle {
+                    id,
+                    stable_id: id,
+                    ..particle
+                })
+                .collect(),
+        )
+    }
+
+    /// Temporally update the particles to new angles and positions
+    pub(crate) fn to_timestepped(
+        &self,
+        params: &SimulationParameters,
+        new_time: AbsoluteTime,
+        counters: &mut PerformanceCounters,
+        replayed_phases: Option<&[Float]>,
+    ) -> Self {
+        // Next step's noise is either replayed or freshly drawn, including for any particles
+        // added since the recording
+        let phase = |particle: &Particle| match replayed_phases
+            .and_then(|phases| phases.get(particle.id))
+        {
+            Some(&phase) => phase,
+            None => Particle::sample_random_phase(),
+        };
+
+        let _span = tracing::debug_span!("update_particles", num_particles = self.len()).entered();
+
+        // Headings are only remembered as far back as someone reacts to
+        let history_len = self
+            .0
+            .iter()
+            .map(|particle| particle.reaction_delay)
+            .max()
+            .unwrap_or(0);
+
+        match params.update_order {
+            UpdateOrder::Synchronous => Self(
+                self.0
+                    .iter()
+                    .map(|particle| {
+                        particle.to_timestepped(
+                            self,
+                            params,
+                            new_time,
+                            counters,
+                            phase(particle),
+                            history_len,
+                        )
+                    })
+                    .collect(),
+            ),
+            UpdateOrder::RandomSequential => {
+                let mut order: Vec<usize> = (0..self.len()).collect();
+                for idx in (1..order.len()).rev() {
+                    order.swap(idx, random::random_range(0..=idx));
+                }
+
+                // Each update lands in place, so later particles see it straight away
+                let mut particles = self.clone();
+                for idx in order {
+                    let particle = &particles.0[idx];
+                    let phase = phase(particle);
+                    particles.0[idx] = particle.to_timestepped(
+                        &particles,
+                        params,
+                        new_time,
+                        counters,
+                        phase,
+                        history_len,
+                    );
+                }
+
+                particles
+            }
+        }
+    }
+
+    /// Move the particles into a resized domain
+    pub(crate) fn to_resized(
+        &self,
+        old_boundary_side_length: DomainBoundaryLength,
+        new_boundary_side_length: DomainBoundaryLength,
+        rescale_positions: bool,
+    ) -> Self {
+        Self(
+            self.0
+                .iter()
+                .map(|particle| {
+                    particle.to_resized(
+                        old_boundary_side_length,
+                        new_boundary_side_length,
+                        rescale_positions,
+                    )
+                })
+                .collect(),
+        )
+    }
+
+    /// Compute the polarization / instantaneous order parameter of the system
     pub(crate) fn compute_instantaneous_order(&self) -> InstantaneosOrder {
         let sum: Complex<_> = self
             .0
@@ -283,6 +1416,323 @@ impl Particles {
         InstantaneosOrder(sum.norm() / self.len() as Float)
     }
 
+    /// Label every particle with the same tag
+    pub(crate) fn to_tagged(&self, tag: usize) -> Self {
+        Self(
+            self.0
+                .iter()
+                .map(|particle| Particle {
+                    tag,
+                    ..particle.clone()
+                })
+                .collect(),
+        )
+    }
+
+    /// Make the given particles leaders, or clear their leadership with `None`
+    pub(crate) fn to_with_leaders(
+        &self,
+        ids: &[usize],
+        leader_heading: Option<LeaderHeading>,
+    ) -> Result<Self, SimulationError> {
+        let stable_ids: HashSet<_> = self.0.iter().map(|particle| particle.stable_id).collect();
+        if let Some(id) = ids.iter().find(|id| !stable_ids.contains(id)) {
+            invalid_parameter!("there is no particle with id `{}`", id);
+        }
+
+        let ids: HashSet<_> = ids.iter().collect();
+
+        Ok(Self(
+            self.0
+                .iter()
+                .map(|particle| match ids.contains(&particle.stable_id) {
+                    true => Particle {
+                        leader: leader_heading.clone(),
+                        ..particle.clone()
+                    },
+                    false => particle.clone(),
+                })
+                .collect(),
+        ))
+    }
+
+    /// Give each particle its own noise amplitude, indexed by ID, or revert them all to the
+    /// simulation's with `None`
+    pub(crate) fn to_with_noises(&self, noises: Option<&[Noise]>) -> Self {
+        Self(
+            self.0
+                .iter()
+                .map(|particle| Particle {
+                    noise: noises.map(|noises| noises[particle.id]),
+                    ..particle.clone()
+                })
+                .collect(),
+        )
+    }
+
+    /// Give each particle its own reaction delay in steps, indexed by ID, or remove them all with
+    /// `None`
+    pub(crate) fn to_with_reaction_delays(&self, delays: Option<&[usize]>) -> Self {
+        Self(
+            self.0
+                .iter()
+                .map(|particle| Particle {
+                    reaction_delay: delays.map_or(0, |delays| delays[particle.id]),
+                    ..particle.clone()
+                })
+                .collect(),
+        )
+    }
+
+    /// List who aligns with whom under the Vicsek rules, as (particle, neighbor) pairs of stable
+    /// IDs in particle order
+    pub(crate) fn compute_interaction_edges(
+        &self,
+        params: &SimulationParameters,
+    ) -> Vec<(usize, usize)> {
+        self.0
+            .iter()
+            .flat_map(|particle| {
+                particle
+                    .compute_idxs_vicsek_neighbors(self, params)
+                    .0
+                    .into_iter()
+                    .map(|idx| (particle.stable_id, self.0[idx].stable_id))
+            })
+            .collect()
+    }
+
+    /// Give every particle a full energy budget again
+    pub(crate) fn to_rested(&self) -> Self {
+        Self(
+            self.0
+                .iter()
+                .map(|particle| Particle {
+                    energy_spent: 0.0,
+                    ..particle.clone()
+                })
+                .collect(),
+        )
+    }
+
+    /// Set a named scalar on each particle, indexed by ID, or drop it from them all with `None`
+    pub(crate) fn to_with_scalar(&self, name: &str, values: Option<&[Float]>) -> Self {
+        Self(
+            self.0
+                .iter()
+                .map(|particle| {
+                    let mut scalars = particle.scalars.clone();
+                    match values {
+                        Some(values) => scalars.set(name, values[particle.id]),
+                        None => scalars.remove(name),
+                    }
+
+                    Particle {
+                        scalars,
+                        ..particle.clone()
+                    }
+                })
+                .collect(),
+        )
+    }
+
+    /// Give each particle its own interaction radius, indexed by ID, or revert them all to the
+    /// simulation's threshold with `None`
+    pub(crate) fn to_with_interaction_radii(
+        &self,
+        radii: Option<&[ParticleDistanceThreshold]>,
+    ) -> Self {
+        Self(
+            self.0
+                .iter()
+                .map(|particle| Particle {
+                    interaction_radius: radii.map(|radii| radii[particle.id]),
+                    ..particle.clone()
+                })
+                .collect(),
+        )
+    }
+
+    /// Give each particle its own speed drawn from a distribution
+    pub(crate) fn to_with_speeds(&self, speed_distribution: SpeedDistribution) -> Self {
+        Self(
+            self.0
+                .iter()
+                .map(|particle| Particle {
+                    speed: Some(speed_distribution.sample()),
+                    ..particle.clone()
+                })
+                .collect(),
+        )
+    }
+
+    /// Make exactly the given particles dissenters
+    pub(crate) fn to_with_dissenters(&self, ids: &HashSet<usize>) -> Self {
+        Self(
+            self.0
+                .iter()
+                .map(|particle| Particle {
+                    dissenter: ids.contains(&particle.id),
+                    ..particle.clone()
+                })
+                .collect(),
+        )
+    }
+
+    /// Add a particle at the end of the collection
+    pub(crate) fn to_with_added(&self, particle: Particle) -> Self {
+        let id = self.len();
+
+        Self(
+            self.0
+                .iter()
+                .cloned()
+                .chain(std::iter::once(Particle { id, ..particle }))
+                .collect(),
+        )
+    }
+
+    /// Remove the particle at an index, shifting the indices after it down while keeping every
+    /// particle's stable ID
+    pub(crate) fn to_removed(&self, idx: usize) -> Self {
+        Self(
+            self.0
+                .iter()
+                .filter(|particle| particle.id != idx)
+                .cloned()
+                .enumerate()
+                .map(|(id, particle)| Particle { id, ..particle })
+                .collect(),
+        )
+    }
+
+    /// Randomly kill particles and spawn offspring next to their parents, giving offspring stable
+    /// IDs counting up from `next_stable_id`
+    ///
+    /// # Notes
+    /// The population never dies out entirely: if every particle would die in the same step with
+    /// no births, the first one survives.
+    pub(crate) fn to_with_births_and_deaths(
+        &self,
+        birth_death: BirthDeath,
+        delta_time: RelativeTime,
+        boundary_side_length: DomainBoundaryLength,
+        next_stable_id: &mut usize,
+    ) -> Self {
+        let birth_probability = 1.0 - (-birth_death.birth_rate * delta_time.0).exp();
+        let death_probability = 1.0 - (-birth_death.death_rate * delta_time.0).exp();
+
+        let mut survivors = Vec::with_capacity(self.len());
+        let mut offspring = Vec::new();
+
+        for particle in self.0.iter() {
+            if random::random::<Float>() < birth_probability {
+                // Uniform over the disk around the parent
+                let distance = birth_death.spawn_radius * random::random::<Float>().sqrt();
+                let direction = Particle::sample_random_angular_position();
+
+                offspring.push(Particle {
+                    stable_id: *next_stable_id,
+                    pos_x: (particle.pos_x + distance * direction.cos())
+                        .rem_euclid(boundary_side_length.0),
+                    pos_y: (particle.pos_y + distance * direction.sin())
+                        .rem_euclid(boundary_side_length.0),
+                    phase: Particle::sample_random_phase(),
+                    leader: None,
+                    energy_spent: 0.0,
+                    ..particle.clone()
+                });
+                *next_stable_id += 1;
+            }
+
+            if random::random::<Float>() >= death_probability {
+                survivors.push(particle.clone());
+            }
+        }
+
+        if survivors.is_empty() && offspring.is_empty() {
+            survivors.extend(self.0.first().cloned());
+        }
+
+        Self(
+            survivors
+                .into_iter()
+                .chain(offspring)
+                .enumerate()
+                .map(|(id, particle)| Particle { id, ..particle })
+                .collect(),
+        )
+    }
+
+    /// Get every particle's current phase
+    pub(crate) fn phases(&self) -> Box<[Float]> {
+        self.0.iter().map(|particle| particle.phase).collect()
+    }
+
+    /// Overwrite every particle's phase, e.g. to replay recorded noise
+    pub(crate) fn to_with_phases(&self, phases: &[Float]) -> Self {
+        Self(
+            self.0
+                .iter()
+                .zip(phases)
+                .map(|(particle, &phase)| Particle {
+                    phase,
+                    ..particle.clone()
+                })
+                .collect(),
+        )
+    }
+
+    /// Overwrite every particle's position and heading, wrapping positions into the domain and
+    /// forgetting past headings
+    pub(crate) fn to_with_state(
+        &self,
+        x: &[Float],
+        y: &[Float],
+        theta: &[Float],
+        boundary_side_length: DomainBoundaryLength,
+    ) -> Self {
+        Self(
+            self.0
+                .iter()
+                .zip(x.iter().zip(y).zip(theta))
+                .map(|(particle, ((&x, &y), &theta))| Particle {
+                    pos_x: x.rem_euclid(boundary_side_length.0),
+                    pos_y: y.rem_euclid(boundary_side_length.0),
+                    theta,
+                    heading_history: VecDeque::new(),
+                    ..particle.clone()
+                })
+                .collect(),
+        )
+    }
+
+    /// Overwrite every particle's stable ID and tag, e.g. when restoring a snapshot
+    pub(crate) fn to_with_labels(&self, stable_ids: &[usize], tags: &[usize]) -> Self {
+        Self(
+            self.0
+                .iter()
+                .zip(stable_ids.iter().zip(tags))
+                .map(|(particle, (&stable_id, &tag))| Particle {
+                    stable_id,
+                    tag,
+                    ..particle.clone()
+                })
+                .collect(),
+        )
+    }
+
+    /// Get the number of bytes allocated for the particles
+    pub(crate) fn heap_bytes(&self) -> usize {
+        let history_bytes: usize = self
+            .0
+            .iter()
+            .map(|particle| particle.heading_history.capacity() * std::mem::size_of::<Float>())
+            .sum();
+
+        self.0.capacity() * std::mem::size_of::<Particle>() + history_bytes
+    }
+
     /// Get the number of particles
     pub(crate) fn len(&self) -> usize {
         self.0.len()
@@ -293,5 +1743,373 @@ impl Particles {
     }
 }
 
+impl IntoIterator for Particles {
+    type Item = Particle;
+    type IntoIter = std::vec::IntoIter<Particle>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
 /// Contains the indices for the nearest particles for a given particle
 struct IdxsNeighborParticles(Box<[usize]>);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::Simulation;
+
+    fn pair(separation: Float, neighbor_weighting: NeighborWeighting) -> Simulation {
+        Simulation::with_particles(
+            &[(1.0, 1.0), (1.0 + separation, 1.0)],
+            &[0.3, 1.1],
+            DomainBoundaryLength(5.0),
+            Noise(0.1),
+            Speed(0.1),
+            RelativeTime(1.0),
+            ParticleDistanceThreshold(1.0),
+        )
+        .unwrap()
+        .with_neighbor_weighting(neighbor_weighting)
+        .unwrap()
+    }
+
+    #[test]
+    fn underflowing_gaussian_weights_keep_heading() {
+        let mut sim = pair(0.5, NeighborWeighting::Gaussian { width: 1e-3 });
+        sim.run_for(1).unwrap();
+
+        let thetas: Vec<Float> = sim.particles.iter().map(|p| p.theta).collect();
+        assert_eq!(thetas, [0.3, 1.1]);
+        assert!(
+            sim.particles
+                .iter()
+                .all(|p| p.pos_x.is_finite() && p.pos_y.is_finite())
+        );
+    }
+
+    #[test]
+    fn linear_decay_neighbor_at_threshold_keeps_heading() {
+        let sim = pair(1.0, NeighborWeighting::LinearDecay);
+        let particle = &sim.particles.0[0];
+
+        let theta = particle.compute_new_theta(
+            &sim.particles,
+            IdxsNeighborParticles(Box::new([1])),
+            &sim.params,
+        );
+        assert_eq!(theta, particle.theta);
+    }
+
+    #[test]
+    fn gaussian_width_must_be_positive() {
+        for width in [0.0, -1.0, Float::NAN] {
+            let sim = pair(0.5, NeighborWeighting::Uniform);
+            assert!(matches!(
+                sim.with_neighbor_weighting(NeighborWeighting::Gaussian { width }),
+                Err(SimulationError::InvalidParameter(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn leaders_hold_their_heading_and_lead_followers() {
+        let mut sim = Simulation::with_particles(
+            &[(1.0, 1.0), (1.5, 1.0)],
+            &[1.0, 0.0],
+            DomainBoundaryLength(5.0),
+            Noise(0.0),
+            Speed(0.1),
+            RelativeTime(1.0),
+            ParticleDistanceThreshold(1.0),
+        )
+        .unwrap();
+        sim.set_leaders(&[0], LeaderHeading::Fixed(Angle(2.0)))
+            .unwrap();
+
+        sim.run_for(2).unwrap();
+
+        // The follower aligns with where the leader was heading the step before
+        let thetas: Vec<Float> = sim.particles.iter().map(|p| p.theta).collect();
+        assert_eq!(thetas[0], 2.0);
+        assert!((thetas[1] - 2.0).abs() < 1e-5);
+
+        sim.clear_leaders(&[0]).unwrap();
+        assert!(sim.particles.iter().all(|p| p.leader.is_none()));
+    }
+
+    #[test]
+    fn vision_cone_hides_neighbors_behind() {
+        let mut sim = Simulation::with_particles(
+            &[(2.0, 2.0), (1.5, 2.0)],
+            &[0.0, 1.0],
+            DomainBoundaryLength(5.0),
+            Noise(0.0),
+            Speed(0.01),
+            RelativeTime(1.0),
+            ParticleDistanceThreshold(1.0),
+        )
+        .unwrap()
+        .with_vision_cone(Some(Angle(0.5 * PI)))
+        .unwrap();
+
+        sim.run_for(1).unwrap();
+
+        // The front particle can't see the one behind it, which can see it and turns to follow
+        let thetas: Vec<Float> = sim.particles.iter().map(|p| p.theta).collect();
+        assert_eq!(thetas[0], 0.0);
+        assert!(thetas[1].abs() < 1e-5);
+    }
+
+    #[test]
+    fn repulsion_pushes_close_particles_apart() {
+        let mut sim = Simulation::with_particles(
+            &[(2.0, 2.0), (2.2, 2.0)],
+            &[0.0, 0.0],
+            DomainBoundaryLength(5.0),
+            Noise(0.0),
+            Speed(0.0),
+            RelativeTime(1.0),
+            ParticleDistanceThreshold(1.0),
+        )
+        .unwrap()
+        .with_repulsion(Some(Repulsion {
+            radius: 0.5,
+            strength: 0.1,
+        }))
+        .unwrap();
+
+        sim.run_for(1).unwrap();
+
+        let xs: Vec<Float> = sim.particles.iter().map(|p| p.pos_x).collect();
+        assert!(xs[0] < 2.0 && xs[1] > 2.2);
+    }
+
+    #[test]
+    fn couzin_particles_turn_away_inside_the_repulsion_zone() {
+        let zones = CouzinZones {
+            repulsion_radius: 0.3,
+            orientation_radius: 0.6,
+            attraction_radius: 1.0,
+        };
+        let mut sim = Simulation::with_particles(
+            &[(2.0, 2.0), (2.1, 2.0)],
+            &[0.5, 0.5],
+            DomainBoundaryLength(5.0),
+            Noise(0.0),
+            Speed(0.01),
+            RelativeTime(1.0),
+            ParticleDistanceThreshold(1.0),
+        )
+        .unwrap()
+        .with_update_rule(UpdateRule::Couzin(zones))
+        .unwrap();
+
+        sim.run_for(1).unwrap();
+
+        let thetas: Vec<Float> = sim.particles.iter().map(|p| p.theta).collect();
+        assert!((thetas[0].abs() - PI).abs() < 1e-5);
+        assert!(thetas[1].abs() < 1e-5);
+    }
+
+    #[test]
+    fn relaxing_headings_turn_part_way_along_the_shorter_arc() {
+        let sim = pair(0.5, NeighborWeighting::Uniform)
+            .with_heading_relaxation(Some(RelativeTime(4.0)))
+            .unwrap();
+        let particle = &sim.particles.0[0];
+
+        let theta = particle.relax_theta(1.1, &sim.params);
+        assert!((theta - 0.5).abs() < 1e-9);
+
+        // From 0.3 the shorter way to 2π - 0.1 is backwards through zero
+        let theta = particle.relax_theta(MAX_PARTICLE_ANGLE - 0.1, &sim.params);
+        assert!((theta - 0.2).abs() < 1e-9);
+
+        let sim = sim.with_heading_relaxation(None).unwrap();
+        assert_eq!(sim.particles.0[0].relax_theta(1.1, &sim.params), 1.1);
+    }
+
+    #[test]
+    fn heading_relaxation_time_must_be_positive() {
+        for relaxation_time in [0.0, -1.0, Float::NAN] {
+            let sim = pair(0.5, NeighborWeighting::Uniform);
+            assert!(matches!(
+                sim.with_heading_relaxation(Some(RelativeTime(relaxation_time))),
+                Err(SimulationError::InvalidParameter(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn active_brownian_particles_ignore_their_neighbors() {
+        let mut sim = pair(0.5, NeighborWeighting::Uniform)
+            .with_update_rule(UpdateRule::ActiveBrownian {
+                rotational_diffusion: 0.0,
+            })
+            .unwrap();
+        sim.run_for(3).unwrap();
+
+        let thetas: Vec<Float> = sim.particles.iter().map(|p| p.theta).collect();
+        assert_eq!(thetas, [0.3, 1.1]);
+
+        for rotational_diffusion in [-1.0, Float::NAN] {
+            assert!(matches!(
+                sim.clone().with_update_rule(UpdateRule::ActiveBrownian {
+                    rotational_diffusion
+                }),
+                Err(SimulationError::InvalidParameter(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn noise_models_perturb_the_vector_or_the_angle() {
+        let mut sim = pair(0.5, NeighborWeighting::Uniform).with_noise_model(NoiseModel::Vectorial);
+        sim.params.noise = Noise(1.0);
+        let mut particle = sim.particles.0[0].clone();
+        particle.phase = 0.5 * PI;
+
+        // A unit noise vector at right angles to the average splits the difference
+        let averaged = Complex::new(1.0, 0.0);
+        let theta = particle.apply_noise(averaged, &sim.params);
+        assert!((theta - 0.25 * PI).abs() < 1e-9);
+
+        // ξ = π/2 is a quarter of the way to the top of [-η/2, η/2)
+        let sim = sim.with_noise_model(NoiseModel::Scalar);
+        let theta = particle.apply_noise(averaged, &sim.params);
+        assert!((theta - 0.25).abs() < 1e-9);
+    }
+
+    #[test]
+    fn speed_distributions_give_each_particle_its_own_speed() {
+        let sim = pair(0.5, NeighborWeighting::Uniform)
+            .with_speed_distribution(SpeedDistribution::Uniform { min: 0.2, max: 0.4 })
+            .unwrap();
+        assert!(sim.particles.iter().all(|p| {
+            let speed = p.speed.unwrap().0;
+            (0.2..=0.4).contains(&speed) && p.speed(&sim.params).0 == speed
+        }));
+
+        // Negative normal draws stop the particle rather than reversing it
+        let speed = SpeedDistribution::Normal {
+            mean: -10.0,
+            std_dev: 0.0,
+        }
+        .sample();
+        assert_eq!(speed.0, 0.0);
+
+        for speed_distribution in [
+            SpeedDistribution::Uniform { min: 0.4, max: 0.2 },
+            SpeedDistribution::Normal {
+                mean: 1.0,
+                std_dev: -1.0,
+            },
+            SpeedDistribution::LogNormal {
+                mu: Float::NAN,
+                sigma: 1.0,
+            },
+        ] {
+            assert!(matches!(
+                sim.clone().with_speed_distribution(speed_distribution),
+                Err(SimulationError::InvalidParameter(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn occluded_neighbors_are_ignored() {
+        let line = || {
+            Simulation::with_particles(
+                &[(1.0, 1.0), (1.3, 1.0), (1.6, 1.0)],
+                &[0.0, 0.5, 2.0],
+                DomainBoundaryLength(5.0),
+                Noise(0.0),
+                Speed(0.01),
+                RelativeTime(1.0),
+                ParticleDistanceThreshold(1.0),
+            )
+            .unwrap()
+        };
+
+        // The middle particle hides the far one from the first
+        let mut sim = line().with_occlusion(Some(0.05)).unwrap();
+        sim.run_for(1).unwrap();
+        assert!((sim.particles.0[0].theta - 0.5).abs() < 1e-5);
+
+        let mut sim = line();
+        sim.run_for(1).unwrap();
+        assert!((sim.particles.0[0].theta - 1.25).abs() < 1e-5);
+
+        assert!(matches!(
+            line().with_occlusion(Some(0.0)),
+            Err(SimulationError::InvalidParameter(_))
+        ));
+    }
+
+    #[test]
+    fn backward_vicsek_moves_along_the_new_heading() {
+        let step = |update_rule| {
+            let mut sim = Simulation::with_particles(
+                &[(1.0, 1.0), (1.2, 1.0)],
+                &[0.3, 1.1],
+                DomainBoundaryLength(5.0),
+                Noise(0.0),
+                Speed(0.1),
+                RelativeTime(1.0),
+                ParticleDistanceThreshold(1.0),
+            )
+            .unwrap()
+            .with_update_rule(update_rule)
+            .unwrap();
+            sim.run_for(1).unwrap();
+
+            let particle = &sim.particles.0[0];
+            (particle.pos_y - 1.0).atan2(particle.pos_x - 1.0)
+        };
+
+        // The first particle turns from 0.3 to its neighbor's 1.1
+        assert!((step(UpdateRule::Vicsek) - 0.3).abs() < 1e-5);
+        assert!((step(UpdateRule::BackwardVicsek) - 1.1).abs() < 1e-5);
+    }
+
+    #[test]
+    fn spent_energy_slows_particles_down() {
+        let mut sim = Simulation::with_particles(
+            &[(1.0, 1.0)],
+            &[0.0],
+            DomainBoundaryLength(5.0),
+            Noise(0.0),
+            Speed(0.1),
+            RelativeTime(1.0),
+            ParticleDistanceThreshold(1.0),
+        )
+        .unwrap()
+        .with_energy_budget(Some(EnergyBudget {
+            capacity: 1.0,
+            depletion_rate: 5.0,
+            recovery_rate: 0.0,
+            crowd_recovery_rate: 0.0,
+        }))
+        .unwrap();
+
+        // A rested particle moves at full speed, then at the half of its energy left
+        sim.run_for(1).unwrap();
+        assert!((sim.particles.0[0].pos_x - 1.1).abs() < 1e-5);
+        assert!((sim.particles.0[0].energy_spent - 0.5).abs() < 1e-5);
+
+        sim.run_for(1).unwrap();
+        assert!((sim.particles.0[0].pos_x - 1.15).abs() < 1e-5);
+
+        assert!(matches!(
+            sim.with_energy_budget(Some(EnergyBudget {
+                capacity: 0.0,
+                depletion_rate: 1.0,
+                recovery_rate: 1.0,
+                crowd_recovery_rate: 0.0,
+            })),
+            Err(SimulationError::InvalidParameter(_))
+        ));
+    }
+}
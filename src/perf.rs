@@ -0,0 +1,197 @@
+use std::{
+    ops::Add,
+    time::{Duration, Instant},
+};
+
+use crate::types::Float;
+
+/// Wall-clock time spent in each phase of stepping, accumulated over a run
+#[derive(Copy, Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PerformanceCounters {
+    /// Number of timesteps taken
+    pub steps: u64,
+
+    /// Number of neighbors found, summed over every neighbor search
+    pub neighbors: u64,
+
+    /// Number of neighbor searches, one per particle per step for the neighbor-based rules
+    pub neighbor_searches: u64,
+
+    /// Finding each particle's neighbors
+    pub neighbor_search: Duration,
+
+    /// Averaging neighbor headings into a new heading
+    pub alignment: Duration,
+
+    /// Moving particles and enforcing the boundary conditions
+    pub integration: Duration,
+
+    /// Computing observables such as the instantaneous order
+    pub observables: Duration,
+
+    /// Total time spent stepping, including anything not covered by the phases above
+    pub total: Duration,
+}
+
+impl PerformanceCounters {
+    /// Average throughput over the accumulated steps
+    pub fn steps_per_second(&self) -> Float {
+        let seconds = self.total.as_secs_f64();
+
+        match seconds > 0.0 {
+            true => (self.steps as f64 / seconds) as Float,
+            false => 0.0,
+        }
+    }
+
+    /// Average number of neighbors each particle found per search
+    pub fn mean_neighbors_per_particle(&self) -> Float {
+        match self.neighbor_searches {
+            0 => 0.0,
+            neighbor_searches => (self.neighbors as f64 / neighbor_searches as f64) as Float,
+        }
+    }
+
+    /// Count one neighbor search that found `num_neighbors`
+    #[inline]
+    pub(crate) fn count_neighbor_search(&mut self, num_neighbors: usize) {
+        self.neighbors += num_neighbors as u64;
+        self.neighbor_searches += 1;
+    }
+
+    /// Summarize the counters per step
+    pub fn stats(&self) -> SimulationStats {
+        let per_step = |duration: Duration| match self.steps {
+            0 => Duration::ZERO,
+            steps => duration.div_f64(steps as f64),
+        };
+
+        SimulationStats {
+            steps: self.steps,
+            steps_per_second: self.steps_per_second(),
+            mean_neighbors_per_particle: self.mean_neighbors_per_particle(),
+            neighbor_search_per_step: per_step(self.neighbor_search),
+            alignment_per_step: per_step(self.alignment),
+            integration_per_step: per_step(self.integration),
+            observables_per_step: per_step(self.observables),
+            total_per_step: per_step(self.total),
+        }
+    }
+}
+
+impl Add for PerformanceCounters {
+    type Output = PerformanceCounters;
+    fn add(self, rhs: Self) -> Self::Output {
+        Self {
+            steps: self.steps + rhs.steps,
+            neighbors: self.neighbors + rhs.neighbors,
+            neighbor_searches: self.neighbor_searches + rhs.neighbor_searches,
+            neighbor_search: self.neighbor_search + rhs.neighbor_search,
+            alignment: self.alignment + rhs.alignment,
+            integration: self.integration + rhs.integration,
+            observables: self.observables + rhs.observables,
+            total: self.total + rhs.total,
+        }
+    }
+}
+
+/// Stepping performance since the counters were last reset, from [`Simulation::stats`]
+///
+/// [`Simulation::stats`]: crate::Simulation::stats
+#[derive(Copy, Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SimulationStats {
+    /// Number of timesteps taken
+    pub steps: u64,
+
+    /// Average throughput
+    pub steps_per_second: Float,
+
+    /// Average number of neighbors each particle aligned with
+    pub mean_neighbors_per_particle: Float,
+
+    /// Average wall-clock time per step spent finding neighbors
+    pub neighbor_search_per_step: Duration,
+
+    /// Average wall-clock time per step spent averaging neighbor headings
+    pub alignment_per_step: Duration,
+
+    /// Average wall-clock time per step spent moving particles
+    pub integration_per_step: Duration,
+
+    /// Average wall-clock time per step spent computing observables
+    pub observables_per_step: Duration,
+
+    /// Average wall-clock time per step overall
+    pub total_per_step: Duration,
+}
+
+/// Run `f`, adding the time it took onto `duration`
+#[inline]
+pub(crate) fn timed<T>(duration: &mut Duration, f: impl FnOnce() -> T) -> T {
+    let start = Instant::now();
+    let result = f();
+    *duration += start.elapsed();
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        simulation::Simulation,
+        types::{DomainBoundaryLength, Noise, ParticleDistanceThreshold, RelativeTime, Speed},
+    };
+
+    #[test]
+    fn counters_track_steps_and_neighbors() {
+        let mut sim = Simulation::with_particles(
+            &[(1.0, 1.0), (1.5, 1.0)],
+            &[0.0, 0.0],
+            DomainBoundaryLength(5.0),
+            Noise(0.1),
+            Speed(0.01),
+            RelativeTime(1.0),
+            ParticleDistanceThreshold(1.0),
+        )
+        .unwrap();
+        sim.run_for(3).unwrap();
+
+        let stats = sim.stats();
+        assert_eq!(stats.steps, 3);
+        assert_eq!(stats.mean_neighbors_per_particle, 1.0);
+        assert!(stats.total_per_step >= stats.neighbor_search_per_step);
+
+        let counters = PerformanceCounters::default() + sim.performance_counters();
+        assert_eq!(counters.neighbors, 6);
+
+        sim.reset_performance_counters();
+        assert_eq!(sim.stats().steps, 0);
+        assert_eq!(sim.stats().steps_per_second, 0.0);
+    }
+
+    #[test]
+    fn stats_average_the_counters_per_step() {
+        let counters = PerformanceCounters {
+            steps: 4,
+            neighbors: 10,
+            neighbor_searches: 5,
+            neighbor_search: Duration::from_millis(8),
+            total: Duration::from_secs(2),
+            ..Default::default()
+        };
+
+        let stats = counters.stats();
+        assert_eq!(stats.steps_per_second, 2.0);
+        assert_eq!(stats.mean_neighbors_per_particle, 2.0);
+        assert_eq!(stats.neighbor_search_per_step, Duration::from_millis(2));
+        assert_eq!(stats.total_per_step, Duration::from_millis(500));
+        assert_eq!(stats.alignment_per_step, Duration::ZERO);
+        assert_eq!(
+            PerformanceCounters::default().stats().total_per_step,
+            Duration::ZERO
+        );
+    }
+}
@@ -0,0 +1,162 @@
+use std::cell::RefCell;
+
+use anyhow::{Context, anyhow, bail};
+use rand::{
+    Rng, SeedableRng,
+    distr::{Distribution, StandardUniform, uniform::SampleRange, uniform::SampleUniform},
+};
+use rand_chacha::ChaCha12Rng;
+use serde_json::{Value, json};
+
+thread_local! {
+    /// Every random draw in the crate comes from here, so a run can be made reproducible by
+    /// seeding it
+    // Note: this is the generator behind `StdRng`, used directly so its state can be saved
+    static RNG: RefCell<ChaCha12Rng> = RefCell::new(ChaCha12Rng::from_os_rng());
+}
+
+/// Seed the current thread's random number generator, making everything drawn on this thread
+/// from here on reproducible
+///
+/// # Notes
+/// Work spread over other threads (e.g. the sensitivity analysis ensemble) draws from their own,
+/// unseeded generators.
+pub fn seed_rng(seed: u64) {
+    RNG.with(|rng| *rng.borrow_mut() = ChaCha12Rng::seed_from_u64(seed));
+}
+
+/// A snapshot of a random number generator, enough to resume its draws exactly
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RngState {
+    pub seed: [u8; 32],
+    pub stream: u64,
+
+    /// Number of 32-bit words drawn from the stream so far
+    pub word_pos: u128,
+}
+
+impl RngState {
+    /// Write the state as a JSON object, with the seed as hex digits and the counters as strings,
+    /// since they can pass 2⁵³ and wouldn't survive JSON readers as numbers
+    pub(crate) fn to_json(self) -> Value {
+        let seed: String = self.seed.iter().map(|byte| format!("{byte:02x}")).collect();
+
+        json!({
+            "seed": seed,
+            "stream": self.stream.to_string(),
+            "word_pos": self.word_pos.to_string(),
+        })
+    }
+
+    /// Read a state written by [`RngState::to_json`]
+    pub(crate) fn from_json(value: &Value) -> anyhow::Result<Self> {
+        let field = |name: &str| {
+            value
+                .get(name)
+                .and_then(Value::as_str)
+                .ok_or_else(|| anyhow!("random state has no `{name}` string"))
+        };
+
+        let seed_hex = field("seed")?;
+        let mut seed = [0; 32];
+        if seed_hex.len() != 2 * seed.len() || !seed_hex.is_ascii() {
+            bail!("random seed must be 64 hex digits, got `{}`", seed_hex);
+        }
+        for (idx, byte) in seed.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&seed_hex[2 * idx..2 * idx + 2], 16)
+                .with_context(|| format!("random seed `{seed_hex}` is not hex"))?;
+        }
+
+        Ok(Self {
+            seed,
+            stream: field("stream")?
+                .parse()
+                .context("random stream is not a number")?,
+            word_pos: field("word_pos")?
+                .parse()
+                .context("random word position is not a number")?,
+        })
+    }
+}
+
+/// Snapshot the current thread's random number generator
+pub fn rng_state() -> RngState {
+    RNG.with(|rng| {
+        let rng = rng.borrow();
+        RngState {
+            seed: rng.get_seed(),
+            stream: rng.get_stream(),
+            word_pos: rng.get_word_pos(),
+        }
+    })
+}
+
+/// Put the current thread's random number generator back to a snapshot, so the draws after it
+/// repeat
+pub fn restore_rng_state(state: &RngState) {
+    let mut restored = ChaCha12Rng::from_seed(state.seed);
+    restored.set_stream(state.stream);
+    restored.set_word_pos(state.word_pos);
+
+    RNG.with(|rng| *rng.borrow_mut() = restored);
+}
+
+/// Draw a random value, e.g. uniform in [0, 1) for floats
+#[inline]
+pub(crate) fn random<T>() -> T
+where
+    StandardUniform: Distribution<T>,
+{
+    RNG.with(|rng| rng.borrow_mut().random())
+}
+
+/// Draw a random value uniformly from a range
+#[inline]
+pub(crate) fn random_range<T: SampleUniform>(range: impl SampleRange<T>) -> T {
+    RNG.with(|rng| rng.borrow_mut().random_range(range))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        Simulation,
+        types::{DomainBoundaryLength, Noise, ParticleDistanceThreshold, RelativeTime, Speed},
+    };
+
+    #[test]
+    fn seeding_repeats_the_draws() {
+        let draws = |seed| {
+            seed_rng(seed);
+            (0..4).map(|_| random::<u64>()).collect::<Vec<_>>()
+        };
+
+        assert_eq!(draws(3), draws(3));
+        assert_ne!(draws(3), draws(4));
+    }
+
+    #[test]
+    fn seeded_simulations_repeat() {
+        let run = || {
+            seed_rng(11);
+            let mut sim = Simulation::new(
+                10,
+                DomainBoundaryLength(5.0),
+                Noise(0.5),
+                Speed(0.1),
+                RelativeTime(1.0),
+                ParticleDistanceThreshold(1.0),
+            )
+            .unwrap();
+            sim.run_for(5).unwrap();
+
+            sim.particles
+                .iter()
+                .map(|p| (p.pos_x, p.pos_y, p.theta))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(run(), run());
+    }
+}
@@ -0,0 +1,772 @@
+use std::{
+    fmt::{Display, Write},
+    str::FromStr,
+};
+
+use crate::{
+    error::{SimulationError, invalid_parameter},
+    simulation::Simulation,
+    types::{Float, PI},
+};
+
+/// Radius of each particle dot in pixels
+const DOT_RADIUS: Float = 2.0;
+
+/// Length of each arrow, as a fraction of the image size
+const ARROW_LENGTH: Float = 0.03;
+
+/// Length of each arrowhead barb, as a fraction of the arrow length
+const ARROWHEAD_LENGTH: Float = 0.4;
+
+/// Angle of each arrowhead barb off the shaft, in radians
+const ARROWHEAD_ANGLE: Float = 0.5;
+
+/// How dark the densest cell of the density overlay is, from 0 (invisible) to 1 (black)
+const DENSITY_OVERLAY_STRENGTH: Float = 0.4;
+
+/// Matplotlib's `tab10` palette, for species
+const TAB10: [Rgb; 10] = [
+    Rgb([0x1f, 0x77, 0xb4]),
+    Rgb([0xff, 0x7f, 0x0e]),
+    Rgb([0x2c, 0xa0, 0x2c]),
+    Rgb([0xd6, 0x27, 0x28]),
+    Rgb([0x94, 0x67, 0xbd]),
+    Rgb([0x8c, 0x56, 0x4b]),
+    Rgb([0xe3, 0x77, 0xc2]),
+    Rgb([0x7f, 0x7f, 0x7f]),
+    Rgb([0xbc, 0xbd, 0x22]),
+    Rgb([0x17, 0xbe, 0xcf]),
+];
+
+/// Matplotlib's `tab20` palette, for clusters
+const TAB20: [Rgb; 20] = [
+    Rgb([0x1f, 0x77, 0xb4]),
+    Rgb([0xae, 0xc7, 0xe8]),
+    Rgb([0xff, 0x7f, 0x0e]),
+    Rgb([0xff, 0xbb, 0x78]),
+    Rgb([0x2c, 0xa0, 0x2c]),
+    Rgb([0x98, 0xdf, 0x8a]),
+    Rgb([0xd6, 0x27, 0x28]),
+    Rgb([0xff, 0x98, 0x96]),
+    Rgb([0x94, 0x67, 0xbd]),
+    Rgb([0xc5, 0xb0, 0xd5]),
+    Rgb([0x8c, 0x56, 0x4b]),
+    Rgb([0xc4, 0x9c, 0x94]),
+    Rgb([0xe3, 0x77, 0xc2]),
+    Rgb([0xf7, 0xb6, 0xd2]),
+    Rgb([0x7f, 0x7f, 0x7f]),
+    Rgb([0xc7, 0xc7, 0xc7]),
+    Rgb([0xbc, 0xbd, 0x22]),
+    Rgb([0xdb, 0xdb, 0x8d]),
+    Rgb([0x17, 0xbe, 0xcf]),
+    Rgb([0x9e, 0xda, 0xe5]),
+];
+
+/// Evenly spaced stops along matplotlib's `viridis` colormap
+const VIRIDIS: [Rgb; 5] = [
+    Rgb([0x44, 0x01, 0x54]),
+    Rgb([0x3b, 0x52, 0x8b]),
+    Rgb([0x21, 0x91, 0x8c]),
+    Rgb([0x5e, 0xc9, 0x62]),
+    Rgb([0xfd, 0xe7, 0x25]),
+];
+
+/// An 8-bit RGB color, written as `#rrggbb`
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Rgb(pub [u8; 3]);
+
+impl Rgb {
+    pub const BLACK: Self = Self([0, 0, 0]);
+    pub const WHITE: Self = Self([255, 255, 255]);
+
+    /// Blend towards another color, from 0 (this color) to 1 (the other)
+    fn mixed(self, other: Self, amount: Float) -> Self {
+        let amount = amount.clamp(0.0, 1.0);
+        Self(std::array::from_fn(|idx| {
+            let (from, to) = (self.0[idx] as Float, other.0[idx] as Float);
+            (from + amount * (to - from)).round() as u8
+        }))
+    }
+
+    /// A fully saturated, fully bright color with hue `hue` in [0, 1)
+    fn from_hue(hue: Float) -> Self {
+        let sector = hue.rem_euclid(1.0) * 6.0;
+        let rising = (255.0 * sector.fract()).round() as u8;
+        let falling = 255 - rising;
+
+        match sector as usize {
+            0 => Self([255, rising, 0]),
+            1 => Self([falling, 255, 0]),
+            2 => Self([0, 255, rising]),
+            3 => Self([0, falling, 255]),
+            4 => Self([rising, 0, 255]),
+            _ => Self([255, 0, falling]),
+        }
+    }
+}
+
+impl Display for Rgb {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let [r, g, b] = self.0;
+        write!(f, "#{r:02x}{g:02x}{b:02x}")
+    }
+}
+
+impl FromStr for Rgb {
+    type Err = SimulationError;
+
+    /// Parse `#rrggbb`, or one of a few names that mean the same to matplotlib
+    fn from_str(color: &str) -> Result<Self, Self::Err> {
+        let named = match color {
+            "black" => Some(Self::BLACK),
+            "white" => Some(Self::WHITE),
+            "gray" | "grey" => Some(Self([0x80, 0x80, 0x80])),
+            "red" => Some(Self([0xff, 0x00, 0x00])),
+            "green" => Some(Self([0x00, 0x80, 0x00])),
+            "blue" => Some(Self([0x00, 0x00, 0xff])),
+            _ => None,
+        };
+        if let Some(named) = named {
+            return Ok(named);
+        }
+
+        let channel = |idx: usize| {
+            color
+                .strip_prefix('#')
+                .filter(|hex| hex.len() == 6 && hex.is_ascii())
+                .and_then(|hex| u8::from_str_radix(&hex[2 * idx..2 * idx + 2], 16).ok())
+        };
+        match (channel(0), channel(1), channel(2)) {
+            (Some(r), Some(g), Some(b)) => Ok(Self([r, g, b])),
+            _ => invalid_parameter!(
+                "colors are `#rrggbb` or one of black, white, gray, red, green, and blue, got `{}`",
+                color
+            ),
+        }
+    }
+}
+
+/// What each particle's color shows
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum ParticleColoring {
+    /// Every particle in [`RenderOptions::color`]
+    #[default]
+    Uniform,
+
+    /// The heading, around a color wheel
+    Heading,
+
+    /// The cluster, connecting particles within [`RenderOptions::cluster_distance`]
+    Cluster,
+
+    /// The particle tag
+    Species,
+
+    /// A named per-particle scalar, through [`RenderOptions::scalar_colormap`]
+    Scalar(String),
+}
+
+impl Display for ParticleColoring {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Uniform => write!(f, "none"),
+            Self::Heading => write!(f, "heading"),
+            Self::Cluster => write!(f, "cluster"),
+            Self::Species => write!(f, "species"),
+            Self::Scalar(name) => write!(f, "scalar:{name}"),
+        }
+    }
+}
+
+impl FromStr for ParticleColoring {
+    type Err = SimulationError;
+
+    /// Parse `none`, `heading`, `cluster`, `species`, or `scalar:<name>`
+    fn from_str(coloring: &str) -> Result<Self, Self::Err> {
+        match coloring {
+            "none" => Ok(Self::Uniform),
+            "heading" => Ok(Self::Heading),
+            "cluster" => Ok(Self::Cluster),
+            "species" => Ok(Self::Species),
+            _ => match coloring.strip_prefix("scalar:") {
+                Some(name) if !name.is_empty() => Ok(Self::Scalar(name.to_string())),
+                _ => invalid_parameter!(
+                    "particle colorings are none, heading, cluster, species, or scalar:<name>, \
+                     got `{}`",
+                    coloring
+                ),
+            },
+        }
+    }
+}
+
+/// How each particle is drawn
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Glyph {
+    /// An arrow along the heading, centered on the position
+    #[default]
+    Arrow,
+
+    /// A dot at the position
+    Dot,
+}
+
+impl Display for Glyph {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Arrow => write!(f, "arrow"),
+            Self::Dot => write!(f, "dot"),
+        }
+    }
+}
+
+impl FromStr for Glyph {
+    type Err = SimulationError;
+
+    fn from_str(glyph: &str) -> Result<Self, Self::Err> {
+        match glyph {
+            "arrow" => Ok(Self::Arrow),
+            "dot" => Ok(Self::Dot),
+            _ => invalid_parameter!("particle glyphs are arrow or dot, got `{}`", glyph),
+        }
+    }
+}
+
+/// Maps scalar values onto colors, named as in matplotlib
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Colormap {
+    #[default]
+    Viridis,
+
+    /// Black to white
+    Gray,
+}
+
+impl Colormap {
+    /// Look up the color for a value in [0, 1]
+    fn sample(self, value: Float) -> Rgb {
+        let value = value.clamp(0.0, 1.0);
+
+        match self {
+            Self::Viridis => {
+                let position = value * (VIRIDIS.len() - 1) as Float;
+                let idx = (position as usize).min(VIRIDIS.len() - 2);
+                VIRIDIS[idx].mixed(VIRIDIS[idx + 1], position - idx as Float)
+            }
+            Self::Gray => Rgb::BLACK.mixed(Rgb::WHITE, value),
+        }
+    }
+}
+
+impl Display for Colormap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Viridis => write!(f, "viridis"),
+            Self::Gray => write!(f, "gray"),
+        }
+    }
+}
+
+impl FromStr for Colormap {
+    type Err = SimulationError;
+
+    fn from_str(colormap: &str) -> Result<Self, Self::Err> {
+        match colormap {
+            "viridis" => Ok(Self::Viridis),
+            "gray" => Ok(Self::Gray),
+            _ => invalid_parameter!("colormaps are viridis or gray, got `{}`", colormap),
+        }
+    }
+}
+
+/// Styling for rendered frames, shared by the images, animations, live window, and command line
+#[derive(Clone, Debug)]
+pub struct RenderOptions {
+    pub coloring: ParticleColoring,
+    pub glyph: Glyph,
+
+    /// Color of every particle under [`ParticleColoring::Uniform`]
+    pub color: Rgb,
+
+    /// Connection distance for [`ParticleColoring::Cluster`], or the particle distance threshold
+    /// when unset
+    pub cluster_distance: Option<Float>,
+
+    /// Shade the particle density underneath the particles
+    pub density_overlay: bool,
+
+    /// Number of cells across the density overlay's grid
+    pub density_bins: usize,
+
+    pub scalar_colormap: Colormap,
+
+    /// Values mapped onto either end of the colormap under [`ParticleColoring::Scalar`], or the
+    /// range of each frame's values when unset
+    pub scalar_range: Option<(Float, Float)>,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            coloring: ParticleColoring::default(),
+            glyph: Glyph::default(),
+            color: Rgb::BLACK,
+            cluster_distance: None,
+            density_overlay: false,
+            density_bins: 20,
+            scalar_colormap: Colormap::default(),
+            scalar_range: None,
+        }
+    }
+}
+
+impl RenderOptions {
+    pub(crate) fn validate(&self) -> Result<(), SimulationError> {
+        if self.density_bins == 0 {
+            invalid_parameter!("the density overlay needs at least one bin");
+        }
+        if let Some(distance) = self.cluster_distance
+            && !(distance.is_finite() && distance > 0.0)
+        {
+            invalid_parameter!("cluster distance must be positive, got `{}`", distance);
+        }
+        if let Some((min, max)) = self.scalar_range
+            && !(min.is_finite() && max.is_finite() && min < max)
+        {
+            invalid_parameter!(
+                "scalar range must be finite and increasing, got `({}, {})`",
+                min,
+                max
+            );
+        }
+
+        Ok(())
+    }
+}
+
+impl Simulation {
+    /// Render the current state as an SVG image `size` pixels across, styled by `options`
+    pub fn to_svg(&self, size: u32, options: &RenderOptions) -> Result<String, SimulationError> {
+        let colors = self.particle_colors(options)?;
+        let scale = size as Float / self.params.boundary_side_length.0;
+        let arrow_length = ARROW_LENGTH * size as Float;
+
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{size}\" height=\"{size}\" \
+             viewBox=\"0 0 {size} {size}\">\
+             <rect width=\"{size}\" height=\"{size}\" fill=\"white\" stroke=\"black\"/>"
+        );
+
+        // Writing to a String can't fail
+        if options.density_overlay {
+            let bins = options.density_bins;
+            let cell_size = size as Float / bins as Float;
+
+            for (idx, density) in self.density_shading(bins)?.into_iter().enumerate() {
+                if density > 0.0 {
+                    let _ = write!(
+                        svg,
+                        "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"{cell_size:.1}\" \
+                         height=\"{cell_size:.1}\" fill-opacity=\"{:.3}\"/>",
+                        (idx % bins) as Float * cell_size,
+                        (idx / bins) as Float * cell_size,
+                        DENSITY_OVERLAY_STRENGTH * density,
+                    );
+                }
+            }
+        }
+
+        // y points up in the simulation but down in the image
+        for (particle, color) in self.particles.iter().zip(colors) {
+            let x = particle.pos_x * scale;
+            let y = size as Float - particle.pos_y * scale;
+
+            let _ = match options.glyph {
+                Glyph::Arrow => {
+                    let [(tail, tip), (_, barb_a), (_, barb_b)] =
+                        arrow_segments(x, y, particle.theta, arrow_length);
+                    write!(
+                        svg,
+                        "<path d=\"M{:.1} {:.1}L{:.1} {:.1}M{:.1} {:.1}L{:.1} {:.1}L{:.1} {:.1}\" \
+                         fill=\"none\" stroke=\"{color}\"/>",
+                        tail.0,
+                        tail.1,
+                        tip.0,
+                        tip.1,
+                        barb_a.0,
+                        barb_a.1,
+                        tip.0,
+                        tip.1,
+                        barb_b.0,
+                        barb_b.1,
+                    )
+                }
+                Glyph::Dot => write!(
+                    svg,
+                    "<circle cx=\"{x:.1}\" cy=\"{y:.1}\" r=\"{DOT_RADIUS}\" fill=\"{color}\"/>"
+                ),
+            };
+        }
+
+        svg.push_str("</svg>");
+        Ok(svg)
+    }
+
+    /// Render the current state as a PNG image `size` pixels across, drawn like
+    /// [`Simulation::to_rgb_pixels`]
+    ///
+    /// # Notes
+    /// The image data is stored uncompressed, which keeps the encoder dependency-free at the cost
+    /// of ~`3 * size²` bytes.
+    pub fn to_png(&self, size: u32, options: &RenderOptions) -> Result<Vec<u8>, SimulationError> {
+        let size = size.max(1);
+        let pixels = self.to_rgb_pixels(size as usize, options)?;
+
+        Ok(encode_rgb_png(&pixels, size))
+    }
+
+    /// Rasterize the current state as `size` by `size` 8-bit RGB pixels, top row first, styled
+    /// by `options`
+    pub fn to_rgb_pixels(
+        &self,
+        size: usize,
+        options: &RenderOptions,
+    ) -> Result<Vec<u8>, SimulationError> {
+        let colors = self.particle_colors(options)?;
+        let size = size.max(1);
+        let scale = size as Float / self.params.boundary_side_length.0;
+        let arrow_length = ARROW_LENGTH * size as Float;
+
+        let mut canvas = Canvas::new(size);
+
+        if options.density_overlay {
+            let bins = options.density_bins;
+            let densities = self.density_shading(bins)?;
+
+            for (idx, pixel) in canvas.pixels.iter_mut().enumerate() {
+                let (row, column) = (idx / size * bins / size, idx % size * bins / size);
+                let shade = DENSITY_OVERLAY_STRENGTH * densities[row * bins + column];
+                *pixel = pixel.mixed(Rgb::BLACK, shade);
+            }
+        }
+
+        for (particle, color) in self.particles.iter().zip(colors) {
+            // y points up in the simulation but down in the image
+            let x = particle.pos_x * scale;
+            let y = size as Float - particle.pos_y * scale;
+
+            match options.glyph {
+                Glyph::Arrow => {
+                    for (start, end) in arrow_segments(x, y, particle.theta, arrow_length) {
+                        canvas.draw_line(start, end, color);
+                    }
+                }
+                Glyph::Dot => canvas.fill_dot((x, y), color),
+            }
+        }
+
+        Ok(canvas
+            .pixels
+            .into_iter()
+            .flat_map(|pixel| pixel.0)
+            .collect())
+    }
+
+    /// Get each particle's color, in particle order
+    fn particle_colors(&self, options: &RenderOptions) -> Result<Vec<Rgb>, SimulationError> {
+        options.validate()?;
+
+        let colors = match &options.coloring {
+            ParticleColoring::Uniform => vec![options.color; self.particles.len()],
+            ParticleColoring::Heading => self
+                .particles
+                .iter()
+                .map(|particle| Rgb::from_hue(particle.theta / (2.0 * PI)))
+                .collect(),
+            ParticleColoring::Cluster => self
+                .cluster_labels(
+                    options
+                        .cluster_distance
+                        .unwrap_or(self.params.particle_distance_threshold.0),
+                )
+                .into_iter()
+                .map(|label| TAB20[label % TAB20.len()])
+                .collect(),
+            ParticleColoring::Species => self
+                .particles
+                .iter()
+                .map(|particle| TAB10[particle.tag % TAB10.len()])
+                .collect(),
+            ParticleColoring::Scalar(name) => {
+                let Some(values) = self.scalar(name) else {
+                    invalid_parameter!("particles have no scalar `{}`", name);
+                };
+
+                let (min, max) = options.scalar_range.unwrap_or_else(|| {
+                    values.iter().fold(
+                        (Float::INFINITY, Float::NEG_INFINITY),
+                        |(min, max), &value| (min.min(value), max.max(value)),
+                    )
+                });
+                // All equal values sit in the middle of the colormap
+                let span = max - min;
+
+                values
+                    .iter()
+                    .map(|value| match span > 0.0 {
+                        true => options.scalar_colormap.sample((value - min) / span),
+                        false => options.scalar_colormap.sample(0.5),
+                    })
+                    .collect()
+            }
+        };
+
+        Ok(colors)
+    }
+
+    /// The density field on a `bins` by `bins` grid, as a fraction of the most crowded cell, row
+    /// by row from the top of the image
+    fn density_shading(&self, bins: usize) -> Result<Vec<Float>, SimulationError> {
+        let densities = self.density_field(bins)?;
+        let max_density = densities.iter().copied().fold(0.0, Float::max);
+
+        Ok(densities
+            .chunks(bins)
+            .rev()
+            .flatten()
+            .map(|density| match max_density > 0.0 {
+                true => density / max_density,
+                false => 0.0,
+            })
+            .collect())
+    }
+}
+
+/// The shaft and both barbs of an arrow of length `length` centered on `(x, y)` in image
+/// coordinates, each as a `(start, end)` pair, with the barbs starting from the tip
+fn arrow_segments(
+    x: Float,
+    y: Float,
+    theta: Float,
+    length: Float,
+) -> [((Float, Float), (Float, Float)); 3] {
+    let (sin, cos) = theta.sin_cos();
+    let barb_length = ARROWHEAD_LENGTH * length;
+
+    let tail = (x - 0.5 * length * cos, y + 0.5 * length * sin);
+    let tip = (x + 0.5 * length * cos, y - 0.5 * length * sin);
+
+    // Barbs point back from the tip, either side of the shaft
+    let barb = |side: Float| {
+        let angle = theta + PI - side * ARROWHEAD_ANGLE;
+        (
+            tip.0 + barb_length * angle.cos(),
+            tip.1 - barb_length * angle.sin(),
+        )
+    };
+
+    [(tail, tip), (tip, barb(-1.0)), (tip, barb(1.0))]
+}
+
+/// A square RGB image being drawn on, top row first
+struct Canvas {
+    size: usize,
+    pixels: Vec<Rgb>,
+}
+
+impl Canvas {
+    fn new(size: usize) -> Self {
+        Self {
+            size,
+            pixels: vec![Rgb::WHITE; size * size],
+        }
+    }
+
+    /// Color one pixel, ignoring anything outside the image
+    fn plot(&mut self, x: Float, y: Float, color: Rgb) {
+        if x >= 0.0 && y >= 0.0 && (x as usize) < self.size && (y as usize) < self.size {
+            self.pixels[y as usize * self.size + x as usize] = color;
+        }
+    }
+
+    /// Draw a line between two points, one pixel at a time
+    fn draw_line(&mut self, start: (Float, Float), end: (Float, Float), color: Rgb) {
+        let (dx, dy) = (end.0 - start.0, end.1 - start.1);
+        let num_steps = dx.abs().max(dy.abs()).ceil().max(1.0) as usize;
+
+        for step in 0..=num_steps {
+            let along = step as Float / num_steps as Float;
+            self.plot(start.0 + along * dx, start.1 + along * dy, color);
+        }
+    }
+
+    /// Fill a dot of [`DOT_RADIUS`] around a point
+    fn fill_dot(&mut self, center: (Float, Float), color: Rgb) {
+        let radius = DOT_RADIUS.ceil() as i64;
+
+        for dy in -radius..=radius {
+            for dx in -radius..=radius {
+                if ((dx * dx + dy * dy) as Float).sqrt() <= DOT_RADIUS {
+                    self.plot(center.0 + dx as Float, center.1 + dy as Float, color);
+                }
+            }
+        }
+    }
+}
+
+/// Encode a square 8-bit RGB image as PNG, with the pixel data stored uncompressed
+fn encode_rgb_png(pixels: &[u8], size: u32) -> Vec<u8> {
+    let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+
+    // Width, height, bit depth 8, truecolor, then default compression, filter, and no interlace
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&size.to_be_bytes());
+    header.extend_from_slice(&size.to_be_bytes());
+    header.extend_from_slice(&[8, 2, 0, 0, 0]);
+    write_chunk(&mut png, b"IHDR", &header);
+
+    // Each scanline starts with filter type 0 (none)
+    let scanlines: Vec<u8> = pixels
+        .chunks(3 * size as usize)
+        .flat_map(|row| std::iter::once(0).chain(row.iter().copied()))
+        .collect();
+    write_chunk(&mut png, b"IDAT", &zlib_stored(&scanlines));
+
+    write_chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+/// Append a PNG chunk: length, type, data, then the CRC of type and data
+fn write_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+
+    let start = png.len();
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+
+    let crc = crc32(&png[start..]);
+    png.extend_from_slice(&crc.to_be_bytes());
+}
+
+/// Wrap data in a zlib stream of uncompressed deflate blocks
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    // Deflate with a 32K window, no preset dictionary, and the fastest compression level
+    let mut stream = vec![0x78, 0x01];
+
+    let mut blocks = data.chunks(u16::MAX as usize).peekable();
+    if blocks.peek().is_none() {
+        // An empty stream still needs one final block
+        stream.extend_from_slice(&[1, 0, 0, 0xff, 0xff]);
+    }
+
+    while let Some(block) = blocks.next() {
+        let is_final = blocks.peek().is_none();
+        let len = block.len() as u16;
+
+        stream.push(u8::from(is_final));
+        stream.extend_from_slice(&len.to_le_bytes());
+        stream.extend_from_slice(&(!len).to_le_bytes());
+        stream.extend_from_slice(block);
+    }
+
+    stream.extend_from_slice(&adler32(data).to_be_bytes());
+    stream
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    !data.iter().fold(!0u32, |crc, &byte| {
+        (0..8).fold(crc ^ byte as u32, |crc, _| match crc & 1 {
+            1 => (crc >> 1) ^ 0xedb8_8320,
+            _ => crc >> 1,
+        })
+    })
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (a, b) = data.iter().fold((1u32, 0u32), |(a, b), &byte| {
+        let a = (a + byte as u32) % 65521;
+        (a, (b + a) % 65521)
+    });
+
+    (b << 16) | a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{
+        DomainBoundaryLength, Noise, ParticleDistanceThreshold, RelativeTime, Speed,
+    };
+
+    fn single_particle() -> Simulation {
+        Simulation::with_particles(
+            &[(2.5, 2.5)],
+            &[0.0],
+            DomainBoundaryLength(5.0),
+            Noise(0.0),
+            Speed(0.1),
+            RelativeTime(1.0),
+            ParticleDistanceThreshold(1.0),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn colors_parse_and_print_as_hex() {
+        let color: Rgb = "#1a2b3c".parse().unwrap();
+        assert_eq!(color, Rgb([0x1a, 0x2b, 0x3c]));
+        assert_eq!(color.to_string(), "#1a2b3c");
+        assert_eq!("grey".parse::<Rgb>().unwrap(), Rgb([0x80, 0x80, 0x80]));
+
+        for color in ["#12345", "1a2b3c", "#zzzzzz", "purple"] {
+            assert!(matches!(
+                color.parse::<Rgb>(),
+                Err(SimulationError::InvalidParameter(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn dots_are_drawn_in_the_chosen_color() {
+        let options = RenderOptions {
+            glyph: Glyph::Dot,
+            color: Rgb([0xff, 0x00, 0x00]),
+            ..Default::default()
+        };
+        let pixels = single_particle().to_rgb_pixels(10, &options).unwrap();
+        assert_eq!(pixels.len(), 3 * 10 * 10);
+
+        // The particle sits in the middle of the image, the corners stay white
+        let pixel = |row: usize, column: usize| &pixels[3 * (row * 10 + column)..][..3];
+        assert_eq!(pixel(5, 5), [0xff, 0x00, 0x00]);
+        assert_eq!(pixel(0, 0), [0xff, 0xff, 0xff]);
+
+        let svg = single_particle().to_svg(10, &options).unwrap();
+        assert!(svg.contains("fill=\"#ff0000\""));
+    }
+
+    #[test]
+    fn invalid_render_options_are_rejected() {
+        let invalid = [
+            RenderOptions {
+                density_bins: 0,
+                ..Default::default()
+            },
+            RenderOptions {
+                cluster_distance: Some(0.0),
+                ..Default::default()
+            },
+            RenderOptions {
+                scalar_range: Some((1.0, 1.0)),
+                ..Default::default()
+            },
+        ];
+
+        for options in invalid {
+            assert!(matches!(
+                single_particle().to_svg(10, &options),
+                Err(SimulationError::InvalidParameter(_))
+            ));
+        }
+    }
+}
@@ -0,0 +1,226 @@
+use std::{collections::BTreeMap, fmt::Debug, sync::Arc};
+
+use crate::{
+    Simulation,
+    error::{SimulationError, invalid_parameter},
+    simulation::SimulationParameters,
+    types::{Float, RelativeTime},
+};
+
+/// Named values carried by a particle, e.g. an infection level, an opinion, or fuel
+///
+/// # Notes
+/// A name that was never set reads as zero, so particles added part-way through a run start
+/// from zero too.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ParticleScalars(BTreeMap<Arc<str>, Float>);
+
+impl ParticleScalars {
+    /// Get a value, or zero if it was never set
+    pub fn get(&self, name: &str) -> Float {
+        self.0.get(name).copied().unwrap_or(0.0)
+    }
+
+    /// Set a value, adding it if it's new
+    pub fn set(&mut self, name: &str, value: Float) {
+        match self.0.get_mut(name) {
+            Some(existing) => *existing = value,
+            None => {
+                self.0.insert(name.into(), value);
+            }
+        }
+    }
+
+    /// Check whether a value was ever set
+    pub fn contains(&self, name: &str) -> bool {
+        self.0.contains_key(name)
+    }
+
+    /// Iterate over the names and values, in name order
+    pub fn iter(&self) -> impl Iterator<Item = (&str, Float)> {
+        self.0.iter().map(|(name, &value)| (&**name, value))
+    }
+
+    pub(crate) fn remove(&mut self, name: &str) {
+        self.0.remove(name);
+    }
+}
+
+/// A read-only look at a particle, as handed to a [`ScalarRule`]
+#[derive(Copy, Clone, Debug)]
+pub struct ParticleView<'a> {
+    /// The particle's stable ID
+    pub id: usize,
+
+    pub x: Float,
+    pub y: Float,
+    pub theta: Float,
+    pub tag: usize,
+    pub scalars: &'a ParticleScalars,
+}
+
+/// A user update rule for the per-particle scalars, run for every particle once per step
+///
+/// The rule is given the particle, the neighbors it aligns with under the Vicsek rules, and the
+/// timestep, and writes the particle's new values into the scalars it's handed (which start as
+/// the particle's current ones).
+///
+/// # Notes
+/// The rule runs alongside the heading update, so with synchronous updates every particle sees
+/// its neighbors as they were at the start of the step, and e.g. an infection spreads by at most
+/// one neighbor per step.
+#[derive(Clone)]
+pub struct ScalarRule(Arc<ScalarRuleFn>);
+
+type ScalarRuleFn =
+    dyn Fn(&ParticleView, &[ParticleView], RelativeTime, &mut ParticleScalars) + Send + Sync;
+
+impl ScalarRule {
+    pub fn new(
+        rule: impl Fn(&ParticleView, &[ParticleView], RelativeTime, &mut ParticleScalars)
+        + Send
+        + Sync
+        + 'static,
+    ) -> Self {
+        Self(Arc::new(rule))
+    }
+
+    #[inline]
+    pub(crate) fn apply(
+        &self,
+        particle: &ParticleView,
+        neighbors: &[ParticleView],
+        timestep: RelativeTime,
+        scalars: &mut ParticleScalars,
+    ) {
+        (self.0)(particle, neighbors, timestep, scalars)
+    }
+}
+
+impl Debug for ScalarRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "ScalarRule(..)")
+    }
+}
+
+/// Rules are closures, so a simulation with one fails to serialize rather than silently losing it
+#[cfg(feature = "serde")]
+impl serde::Serialize for ScalarRule {
+    fn serialize<S: serde::Serializer>(&self, _serializer: S) -> Result<S::Ok, S::Error> {
+        Err(serde::ser::Error::custom(
+            "a scalar rule is a closure and can't be serialized",
+        ))
+    }
+}
+
+/// Never written by [`Serialize`](serde::Serialize), but needed so a simulation without a rule
+/// reads back in step with how it was written
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for ScalarRule {
+    fn deserialize<D: serde::Deserializer<'de>>(_deserializer: D) -> Result<Self, D::Error> {
+        Err(serde::de::Error::custom(
+            "a scalar rule is a closure and can't be deserialized",
+        ))
+    }
+}
+
+impl Simulation {
+    /// Give every particle a value for a named scalar, in particle order
+    pub fn set_scalar(&mut self, name: &str, values: &[Float]) -> Result<(), SimulationError> {
+        if values.len() != self.particles.len() {
+            invalid_parameter!(
+                "got `{}` values of `{}` for `{}` particles",
+                values.len(),
+                name,
+                self.particles.len()
+            );
+        }
+
+        self.particles = self.particles.to_with_scalar(name, Some(values));
+
+        Ok(())
+    }
+
+    /// Get every particle's value for a named scalar, in particle order, or `None` if no particle
+    /// has it
+    pub fn scalar(&self, name: &str) -> Option<Vec<Float>> {
+        self.particles
+            .iter()
+            .any(|particle| particle.scalars.contains(name))
+            .then(|| {
+                self.particles
+                    .iter()
+                    .map(|particle| particle.scalars.get(name))
+                    .collect()
+            })
+    }
+
+    /// Drop a named scalar from every particle
+    pub fn remove_scalar(&mut self, name: &str) {
+        self.particles = self.particles.to_with_scalar(name, None);
+    }
+
+    /// List the names of the scalars any particle has, in name order
+    pub fn scalar_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .particles
+            .iter()
+            .flat_map(|particle| particle.scalars.iter().map(|(name, _)| name.to_string()))
+            .collect();
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    /// Update the per-particle scalars with a rule every step, or leave them as they are with
+    /// `None`
+    pub fn with_scalar_rule(self, scalar_rule: Option<ScalarRule>) -> Self {
+        let params = SimulationParameters {
+            scalar_rule,
+            ..self.params
+        };
+
+        Self { params, ..self }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{DomainBoundaryLength, Noise, ParticleDistanceThreshold, Speed};
+
+    #[test]
+    fn scalar_rules_spread_values_between_neighbors() {
+        let mut sim = Simulation::with_particles(
+            &[(1.0, 1.0), (1.5, 1.0), (4.0, 4.0)],
+            &[0.0, 0.0, 0.0],
+            DomainBoundaryLength(5.0),
+            Noise(0.0),
+            Speed(0.0),
+            RelativeTime(1.0),
+            ParticleDistanceThreshold(1.0),
+        )
+        .unwrap()
+        .with_scalar_rule(Some(ScalarRule::new(|_, neighbors, _, scalars| {
+            let infected = neighbors
+                .iter()
+                .map(|neighbor| neighbor.scalars.get("infected"))
+                .fold(scalars.get("infected"), Float::max);
+            scalars.set("infected", infected);
+        })));
+        assert_eq!(sim.scalar("infected"), None);
+
+        sim.set_scalar("infected", &[1.0, 0.0, 0.0]).unwrap();
+        sim.run_for(2).unwrap();
+        assert_eq!(sim.scalar("infected"), Some(vec![1.0, 1.0, 0.0]));
+        assert_eq!(sim.scalar_names(), ["infected"]);
+
+        sim.remove_scalar("infected");
+        assert!(sim.scalar_names().is_empty());
+        assert!(matches!(
+            sim.set_scalar("infected", &[1.0]),
+            Err(SimulationError::InvalidParameter(_))
+        ));
+    }
+}
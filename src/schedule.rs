@@ -0,0 +1,186 @@
+use std::{fmt::Debug, sync::Arc};
+
+use crate::{
+    error::{SimulationError, invalid_parameter},
+    types::{AbsoluteTime, DomainBoundaryLength, Float, Noise, Quantity},
+};
+
+/// A piecewise-linear schedule of a quantity over absolute simulation time
+///
+/// # Notes
+/// Before the first keyframe the first value is held, and after the last keyframe the last value
+/// is held, so a single keyframe is simply a constant.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Schedule<Q: Quantity> {
+    keyframes: Vec<(AbsoluteTime, Q)>,
+}
+
+impl<Q: Quantity> Schedule<Q> {
+    /// Create a schedule from `(time, value)` keyframes, which must be sorted by time
+    pub fn new(keyframes: Vec<(AbsoluteTime, Q)>) -> Result<Self, SimulationError> {
+        if keyframes.is_empty() {
+            invalid_parameter!("a schedule needs at least one keyframe");
+        }
+
+        if keyframes.windows(2).any(|pair| pair[1].0.0 <= pair[0].0.0) {
+            invalid_parameter!("schedule keyframe times must be strictly increasing");
+        }
+
+        Ok(Self { keyframes })
+    }
+
+    /// Evaluate the schedule at a point in time, linearly interpolating between keyframes
+    pub fn evaluate(&self, time: AbsoluteTime) -> Q {
+        // This is the index of the first keyframe strictly after `time`
+        let idx_next = self
+            .keyframes
+            .partition_point(|(keyframe_time, _)| keyframe_time.0 <= time.0);
+
+        if idx_next == 0 {
+            return self.keyframes[0].1;
+        }
+
+        if idx_next == self.keyframes.len() {
+            return self.keyframes[idx_next - 1].1;
+        }
+
+        let (time_left, value_left) = self.keyframes[idx_next - 1];
+        let (time_right, value_right) = self.keyframes[idx_next];
+
+        let fraction: Float = (time.0 - time_left.0) / (time_right.0 - time_left.0);
+
+        Q::from_value(value_left.value() + fraction * (value_right.value() - value_left.value()))
+    }
+}
+
+/// Rescales the simulation domain over time, e.g. for compression/expansion experiments
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DomainResizeSchedule {
+    pub(crate) lengths: Schedule<DomainBoundaryLength>,
+
+    /// When set, particle positions are stretched with the domain (an affine rescale). Otherwise
+    /// positions are kept and simply re-wrapped into the new domain.
+    pub(crate) rescale_positions: bool,
+}
+
+impl DomainResizeSchedule {
+    /// Create a new domain resizing schedule
+    pub fn new(
+        lengths: Schedule<DomainBoundaryLength>,
+        rescale_positions: bool,
+    ) -> Result<Self, SimulationError> {
+        if lengths
+            .keyframes
+            .iter()
+            .any(|(_, length)| length.0.is_nan() || length.0 <= 0.0)
+        {
+            invalid_parameter!("scheduled domain lengths must be positive");
+        }
+
+        Ok(Self {
+            lengths,
+            rescale_positions,
+        })
+    }
+}
+
+/// Varies the noise over time, e.g. to anneal a flock from disorder into order
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum NoiseSchedule {
+    /// Piecewise-linear between keyframes
+    Piecewise(Schedule<Noise>),
+
+    /// Noise as a function of absolute time, which should never return a negative amplitude.
+    /// Fails to serialize.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    Function(Arc<dyn Fn(AbsoluteTime) -> Noise + Send + Sync>),
+}
+
+impl NoiseSchedule {
+    /// Create a piecewise-linear noise schedule
+    pub fn piecewise(noises: Schedule<Noise>) -> Result<Self, SimulationError> {
+        if noises
+            .keyframes
+            .iter()
+            .any(|(_, noise)| noise.0.is_nan() || noise.0 < 0.0)
+        {
+            invalid_parameter!("scheduled noise amplitudes must be non-negative");
+        }
+
+        Ok(Self::Piecewise(noises))
+    }
+
+    /// Create a noise schedule from a function of absolute time
+    pub fn function(noise: impl Fn(AbsoluteTime) -> Noise + Send + Sync + 'static) -> Self {
+        Self::Function(Arc::new(noise))
+    }
+
+    /// Get the noise amplitude at a point in time
+    pub fn evaluate(&self, time: AbsoluteTime) -> Noise {
+        match self {
+            Self::Piecewise(noises) => noises.evaluate(time),
+            Self::Function(noise) => noise(time),
+        }
+    }
+}
+
+impl Debug for NoiseSchedule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Piecewise(noises) => f
+                .debug_tuple("NoiseSchedule::Piecewise")
+                .field(noises)
+                .finish(),
+            Self::Function(_) => f.write_str("NoiseSchedule::Function"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn schedule_interpolates_and_holds_its_ends() {
+        let schedule = Schedule::new(vec![
+            (AbsoluteTime(1.0), Noise(0.0)),
+            (AbsoluteTime(3.0), Noise(2.0)),
+        ])
+        .unwrap();
+
+        assert_eq!(schedule.evaluate(AbsoluteTime(0.0)).0, 0.0);
+        assert_eq!(schedule.evaluate(AbsoluteTime(2.0)).0, 1.0);
+        assert_eq!(schedule.evaluate(AbsoluteTime(5.0)).0, 2.0);
+    }
+
+    #[test]
+    fn invalid_schedules_are_invalid_parameters() {
+        assert!(matches!(
+            Schedule::<Noise>::new(Vec::new()),
+            Err(SimulationError::InvalidParameter(_))
+        ));
+        assert!(matches!(
+            Schedule::new(vec![
+                (AbsoluteTime(1.0), Noise(0.0)),
+                (AbsoluteTime(1.0), Noise(1.0)),
+            ]),
+            Err(SimulationError::InvalidParameter(_))
+        ));
+
+        let negative_noise = Schedule::new(vec![(AbsoluteTime(0.0), Noise(-1.0))]).unwrap();
+        assert!(matches!(
+            NoiseSchedule::piecewise(negative_noise),
+            Err(SimulationError::InvalidParameter(_))
+        ));
+
+        let zero_length =
+            Schedule::new(vec![(AbsoluteTime(0.0), DomainBoundaryLength(0.0))]).unwrap();
+        assert!(matches!(
+            DomainResizeSchedule::new(zero_length, true),
+            Err(SimulationError::InvalidParameter(_))
+        ));
+    }
+}
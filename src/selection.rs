@@ -0,0 +1,29 @@
+use std::collections::BTreeSet;
+
+use crate::particle::Particle;
+
+/// Which particles an observable is computed over, e.g. one species in a mixed population
+#[derive(Clone, Debug, Default)]
+pub enum ParticleSelection {
+    /// Every particle
+    #[default]
+    All,
+
+    /// Particles carrying a tag
+    Tag(usize),
+
+    /// Particles with the given IDs, as reported in `SimulationData`
+    Ids(BTreeSet<usize>),
+}
+
+impl ParticleSelection {
+    /// Check whether a particle is selected
+    #[inline]
+    pub(crate) fn contains(&self, particle: &Particle) -> bool {
+        match self {
+            Self::All => true,
+            Self::Tag(tag) => particle.tag == *tag,
+            Self::Ids(ids) => ids.contains(&particle.stable_id),
+        }
+    }
+}
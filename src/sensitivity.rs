@@ -0,0 +1,330 @@
+use std::thread;
+
+use anyhow::{Context, anyhow, bail};
+
+use crate::{
+    Simulation, random,
+    simulation::StationaryOrderOptions,
+    types::{DomainBoundaryLength, Float, Noise, ParticleDistanceThreshold, RelativeTime, Speed},
+};
+
+/// Inclusive `(min, max)` ranges to perturb each parameter within
+#[derive(Copy, Clone, Debug)]
+pub struct ParameterRanges {
+    pub noise: (Float, Float),
+    pub speed: (Float, Float),
+    pub particle_distance_threshold: (Float, Float),
+}
+
+impl ParameterRanges {
+    fn bounds(&self) -> [(Float, Float); 3] {
+        [self.noise, self.speed, self.particle_distance_threshold]
+    }
+}
+
+/// How the base parameter samples are spread over the ranges
+#[derive(Copy, Clone, Debug, Default)]
+pub enum SamplingScheme {
+    /// Independent uniform draws
+    Random,
+
+    /// Latin hypercube: each parameter's range is split into one stratum per sample, and each
+    /// stratum is drawn from exactly once
+    #[default]
+    LatinHypercube,
+}
+
+/// Controls for the sensitivity analysis
+#[derive(Clone, Debug)]
+pub struct SensitivityOptions {
+    /// Number of base samples. The ensemble runs 5x this many simulations.
+    pub num_samples: usize,
+
+    pub sampling: SamplingScheme,
+
+    /// Number of worker threads, defaulting to the available parallelism
+    pub num_threads: Option<usize>,
+
+    /// Budget for each run's stationary order parameter. Runs that don't converge contribute
+    /// their partial estimate.
+    pub stationary_order: StationaryOrderOptions,
+}
+
+impl Default for SensitivityOptions {
+    fn default() -> Self {
+        Self {
+            num_samples: 64,
+            sampling: SamplingScheme::default(),
+            num_threads: None,
+            stationary_order: StationaryOrderOptions::default(),
+        }
+    }
+}
+
+/// One value per perturbed parameter
+#[derive(Copy, Clone, Debug)]
+pub struct ParameterIndices {
+    pub noise: Float,
+    pub speed: Float,
+    pub particle_distance_threshold: Float,
+}
+
+impl From<[Float; 3]> for ParameterIndices {
+    fn from([noise, speed, particle_distance_threshold]: [Float; 3]) -> Self {
+        Self {
+            noise,
+            speed,
+            particle_distance_threshold,
+        }
+    }
+}
+
+/// Variance-based (Sobol) sensitivity indices of the stationary order parameter
+#[derive(Copy, Clone, Debug)]
+pub struct SensitivityReport {
+    /// Share of the output variance explained by each parameter alone
+    pub first_order: ParameterIndices,
+
+    /// Share of the output variance involving each parameter, including interactions
+    pub total_order: ParameterIndices,
+
+    /// Mean of the stationary order parameter over the ensemble
+    pub mean: Float,
+
+    /// Variance of the stationary order parameter over the ensemble
+    pub variance: Float,
+
+    /// Number of simulations run
+    pub num_runs: usize,
+}
+
+/// Estimate how sensitive the stationary order parameter is to noise, speed, and the distance
+/// threshold within the given ranges
+///
+/// # Notes
+/// This uses Saltelli's scheme: two independent sample matrices A and B, plus one matrix per
+/// parameter taking that column from B and the rest from A. First-order indices use Saltelli's
+/// 2010 estimator and total-order indices use Jansen's. The indices are noisy estimates, so small
+/// negative values just mean "about zero".
+pub fn analyze_sensitivity(
+    ranges: &ParameterRanges,
+    num_particles: usize,
+    boundary_side_length: DomainBoundaryLength,
+    timestep: RelativeTime,
+    options: &SensitivityOptions,
+) -> anyhow::Result<SensitivityReport> {
+    for (min, max) in ranges.bounds() {
+        if !min.is_finite() || !max.is_finite() || max < min {
+            bail!("parameter ranges must be finite with min <= max, got `{min}`, `{max}`");
+        }
+    }
+
+    if options.num_samples < 2 {
+        bail!("sensitivity analysis needs at least two base samples");
+    }
+
+    let samples_a = sample_parameters(ranges, options.num_samples, options.sampling);
+    let samples_b = sample_parameters(ranges, options.num_samples, options.sampling);
+
+    // Lay out every run as A, B, then AB_i for each parameter i
+    let mut runs: Vec<[Float; 3]> = samples_a.iter().chain(&samples_b).copied().collect();
+    for dim in 0..3 {
+        runs.extend(samples_a.iter().zip(&samples_b).map(|(a, b)| {
+            let mut ab = *a;
+            ab[dim] = b[dim];
+            ab
+        }));
+    }
+
+    let outputs = run_ensemble(
+        &runs,
+        num_particles,
+        boundary_side_length,
+        timestep,
+        options,
+    )?;
+
+    let num_samples = options.num_samples;
+    let f_a = &outputs[..num_samples];
+    let f_b = &outputs[num_samples..2 * num_samples];
+
+    let all = &outputs[..2 * num_samples];
+    let mean = all.iter().sum::<Float>() / all.len() as Float;
+    let variance = all.iter().map(|f| (f - mean).powi(2)).sum::<Float>() / all.len() as Float;
+
+    let mut first_order = [0.0; 3];
+    let mut total_order = [0.0; 3];
+    for dim in 0..3 {
+        let f_ab = &outputs[(2 + dim) * num_samples..(3 + dim) * num_samples];
+
+        // With no variance at all, nothing matters
+        if variance > 0.0 {
+            first_order[dim] = f_a
+                .iter()
+                .zip(f_b)
+                .zip(f_ab)
+                .map(|((f_a, f_b), f_ab)| f_b * (f_ab - f_a))
+                .sum::<Float>()
+                / num_samples as Float
+                / variance;
+
+            total_order[dim] = f_a
+                .iter()
+                .zip(f_ab)
+                .map(|(f_a, f_ab)| (f_a - f_ab).powi(2))
+                .sum::<Float>()
+                / (2.0 * num_samples as Float)
+                / variance;
+        }
+    }
+
+    Ok(SensitivityReport {
+        first_order: first_order.into(),
+        total_order: total_order.into(),
+        mean,
+        variance,
+        num_runs: runs.len(),
+    })
+}
+
+/// Draw parameter samples spread over the ranges
+fn sample_parameters(
+    ranges: &ParameterRanges,
+    num_samples: usize,
+    sampling: SamplingScheme,
+) -> Vec<[Float; 3]> {
+    let bounds = ranges.bounds();
+
+    // Unit-interval draws for each parameter...
+    let unit_columns: [Vec<Float>; 3] = std::array::from_fn(|_| match sampling {
+        SamplingScheme::Random => (0..num_samples).map(|_| random::random()).collect(),
+        SamplingScheme::LatinHypercube => {
+            // ...one per stratum, shuffled so strata pair up randomly across parameters
+            let mut column: Vec<Float> = (0..num_samples)
+                .map(|stratum| {
+                    (stratum as Float + random::random::<Float>()) / num_samples as Float
+                })
+                .collect();
+
+            for idx in (1..column.len()).rev() {
+                column.swap(idx, random::random_range(0..=idx));
+            }
+
+            column
+        }
+    });
+
+    // ...then scaled onto the ranges.
+    (0..num_samples)
+        .map(|sample| {
+            std::array::from_fn(|dim| {
+                let (min, max) = bounds[dim];
+                min + (max - min) * unit_columns[dim][sample]
+            })
+        })
+        .collect()
+}
+
+/// Compute the stationary order parameter for every run, spread over worker threads
+fn run_ensemble(
+    runs: &[[Float; 3]],
+    num_particles: usize,
+    boundary_side_length: DomainBoundaryLength,
+    timestep: RelativeTime,
+    options: &SensitivityOptions,
+) -> anyhow::Result<Vec<Float>> {
+    let num_threads = options
+        .num_threads
+        .or_else(|| thread::available_parallelism().ok().map(Into::into))
+        .unwrap_or(1)
+        .max(1);
+    let chunk_size = runs.len().div_ceil(num_threads);
+
+    let run = |params: &[Float; 3]| -> anyhow::Result<Float> {
+        let sim = Simulation::new(
+            num_particles,
+            boundary_side_length,
+            Noise(params[0]),
+            Speed(params[1]),
+            timestep,
+            ParticleDistanceThreshold(params[2]),
+        )
+        .context("could not instantiate simulation for sensitivity analysis")?;
+
+        Ok(sim
+            .compute_stationary_order_estimate(&options.stationary_order)?
+            .value)
+    };
+
+    thread::scope(|scope| {
+        let workers: Vec<_> = runs
+            .chunks(chunk_size)
+            .map(|chunk| scope.spawn(move || chunk.iter().map(run).collect::<Vec<_>>()))
+            .collect();
+
+        workers
+            .into_iter()
+            .map(|worker| {
+                worker
+                    .join()
+                    .map_err(|_| anyhow!("a sensitivity analysis worker panicked"))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()
+    })?
+    .into_iter()
+    .flatten()
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ranges() -> ParameterRanges {
+        ParameterRanges {
+            noise: (0.0, 1.0),
+            speed: (0.1, 0.5),
+            particle_distance_threshold: (1.0, 2.0),
+        }
+    }
+
+    #[test]
+    fn latin_hypercube_draws_once_from_every_stratum() {
+        let ranges = ranges();
+        let samples = sample_parameters(&ranges, 10, SamplingScheme::LatinHypercube);
+        assert_eq!(samples.len(), 10);
+
+        for (dim, (min, max)) in ranges.bounds().into_iter().enumerate() {
+            let mut strata: Vec<usize> = samples
+                .iter()
+                .map(|sample| ((sample[dim] - min) / (max - min) * 10.0) as usize)
+                .collect();
+            strata.sort();
+            assert_eq!(strata, (0..10).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn invalid_ranges_and_sample_counts_are_rejected() {
+        let run = |ranges: &ParameterRanges, num_samples| {
+            let options = SensitivityOptions {
+                num_samples,
+                ..Default::default()
+            };
+            analyze_sensitivity(
+                ranges,
+                10,
+                DomainBoundaryLength(5.0),
+                RelativeTime(1.0),
+                &options,
+            )
+        };
+
+        let inverted = ParameterRanges {
+            speed: (0.5, 0.1),
+            ..ranges()
+        };
+        assert!(run(&inverted, 4).is_err());
+        assert!(run(&ranges(), 1).is_err());
+    }
+}
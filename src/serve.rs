@@ -0,0 +1,628 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{BufWriter, ErrorKind, Write},
+    net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+    path::PathBuf,
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::{Context, anyhow, bail};
+use serde_json::{Value, json};
+use tungstenite::{Message, WebSocket};
+
+use crate::{
+    control::CancellationToken,
+    error::{SimulationError, invalid_parameter},
+    export::CsvTrajectoryWriter,
+    particle::Particle,
+    session::ParameterChange,
+    simulation::Simulation,
+    types::Float,
+};
+
+/// How long a client gets to finish its handshake or take a frame before it's dropped
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// How each frame is encoded by [`Simulation::serve`]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum FrameFormat {
+    /// A text message holding a JSON object with the simulated `time`, the instantaneous `order`,
+    /// and per-particle `id`, `x`, `y`, and `theta` arrays
+    #[default]
+    Json,
+
+    /// A binary message of little-endian 64-bit values: the time, the instantaneous order, and
+    /// the number of particles `n`, followed by `n` unsigned IDs, then `n` each of `x`, `y`, and
+    /// `theta`
+    Binary,
+}
+
+/// Controls for [`Simulation::serve`]
+#[derive(Clone, Debug)]
+pub struct ServeOptions {
+    pub format: FrameFormat,
+
+    /// Number of steps taken between frames
+    pub steps_per_frame: usize,
+
+    /// Upper limit on frames per second, or `None` to step as fast as possible
+    pub max_fps: Option<f64>,
+
+    /// Number of frames to broadcast before returning, or `None` to keep going until cancelled
+    pub num_frames: Option<usize>,
+
+    /// Checked every frame; when cancelled the server closes and returns
+    pub cancellation: Option<CancellationToken>,
+
+    /// Append every parameter change clients make, and every snapshot or recording they trigger,
+    /// to this file as one JSON object per line
+    pub event_log: Option<PathBuf>,
+
+    /// Directory that snapshots and recordings triggered by clients are written into, created if
+    /// missing; without one clients can only change parameters
+    pub output_dir: Option<PathBuf>,
+}
+
+impl Default for ServeOptions {
+    fn default() -> Self {
+        Self {
+            format: FrameFormat::default(),
+            steps_per_frame: 1,
+            max_fps: Some(30.0),
+            num_frames: None,
+            cancellation: None,
+            event_log: None,
+            output_dir: None,
+        }
+    }
+}
+
+impl ServeOptions {
+    fn validate(&self) -> Result<(), SimulationError> {
+        if self.steps_per_frame == 0 {
+            invalid_parameter!("steps per frame must be at least 1");
+        }
+        if let Some(max_fps) = self.max_fps
+            && !(max_fps > 0.0 && max_fps.is_finite())
+        {
+            invalid_parameter!("max frames per second must be positive, got `{}`", max_fps);
+        }
+
+        Ok(())
+    }
+}
+
+impl Simulation {
+    /// Step in place, broadcasting a frame of the current state over WebSocket to every client
+    /// connected to `addr` (e.g. `"0.0.0.0:9001"`) after every `steps_per_frame` steps, and return
+    /// the number of frames broadcast
+    ///
+    /// # Notes
+    /// The first frame is the state before stepping. Clients can connect and disconnect at any
+    /// point, and only see frames from when they joined; one that can't keep up with the frame
+    /// rate is dropped rather than holding the run back. Steps go through the same path as
+    /// [`Simulation::run_for`], so observers and any watchdog see them.
+    ///
+    /// Clients can also send text messages holding JSON commands, handled between frames:
+    /// - `{"command": "set", "parameters": {"noise": 0.5}}` changes any of the parameters
+    ///   [`ParameterChange::from_json`] reads
+    /// - `{"command": "snapshot"}` writes the full state to `snapshot_<frame>.json`
+    /// - `{"command": "start_recording"}` and `{"command": "stop_recording"}` write the frames in
+    ///   between to `recording_<frame>.csv`
+    ///
+    /// Snapshots and recordings go into `output_dir`. Whatever a command does is broadcast to
+    /// every client as an `{"event": ...}` text message and appended to the event log, while a
+    /// command that fails is answered with an `{"error": ...}` message to its sender only.
+    pub fn serve(
+        &mut self,
+        addr: impl ToSocketAddrs,
+        options: &ServeOptions,
+    ) -> anyhow::Result<usize> {
+        options.validate()?;
+        let frame_interval = options.max_fps.map_or(Duration::ZERO, |max_fps| {
+            Duration::from_secs_f64(1.0 / max_fps)
+        });
+
+        let listener = TcpListener::bind(addr).context("could not bind WebSocket server")?;
+        // Accept connections between frames instead of waiting on them
+        listener
+            .set_nonblocking(true)
+            .context("could not configure WebSocket server")?;
+        tracing::info!(addr = ?listener.local_addr().ok(), "serving frames over WebSocket");
+
+        let mut control = ClientControl::new(options)?;
+        let mut clients: Vec<Client> = Vec::new();
+        let mut num_frames = 0;
+
+        loop {
+            let frame_start = Instant::now();
+
+            let is_cancelled = options
+                .cancellation
+                .as_ref()
+                .is_some_and(CancellationToken::is_cancelled);
+            let is_done = options
+                .num_frames
+                .is_some_and(|max_frames| num_frames >= max_frames);
+            if is_cancelled || is_done {
+                break;
+            }
+
+            accept_clients(&listener, &mut clients)?;
+
+            for (peer, command) in read_commands(&mut clients) {
+                match control.handle(self, num_frames, &command) {
+                    Ok(events) => {
+                        for event in events {
+                            control.log(self, num_frames, peer, &event)?;
+                            let message = Message::text(json!({ "event": event }).to_string());
+                            broadcast(&mut clients, &message);
+                        }
+                    }
+                    Err(error) => {
+                        tracing::warn!(%peer, error = format!("{error:#}"), "rejected client command");
+                        let message =
+                            Message::text(json!({ "error": format!("{error:#}") }).to_string());
+                        if let Some(client) = clients.iter_mut().find(|client| client.peer == peer)
+                        {
+                            // A client that's gone is dropped with the next frame
+                            let _ = client.socket.send(message);
+                        }
+                    }
+                }
+            }
+
+            if num_frames > 0 {
+                self.run_for(options.steps_per_frame)?;
+            }
+
+            broadcast(&mut clients, &self.to_frame_message(options.format));
+            control.record(self)?;
+            num_frames += 1;
+
+            thread::sleep(frame_interval.saturating_sub(frame_start.elapsed()));
+        }
+
+        control.stop_recording()?;
+        for mut client in clients {
+            // Clients may already be gone, which is fine when closing anyway
+            let _ = client
+                .socket
+                .close(None)
+                .and_then(|_| client.socket.flush());
+        }
+
+        Ok(num_frames)
+    }
+
+    /// Encode the current state as a frame message
+    // Note: `Float` may be 32-bit, but binary frames always carry 64-bit floats
+    #[allow(clippy::unnecessary_cast)]
+    fn to_frame_message(&self, format: FrameFormat) -> Message {
+        let column = |value: fn(&Particle) -> Float| -> Vec<Float> {
+            self.particles.iter().map(value).collect()
+        };
+
+        match format {
+            FrameFormat::Json => Message::text(
+                json!({
+                    "time": self.current_time.0,
+                    "order": self.instantaneous_order.0,
+                    "id": self.particles.iter().map(|particle| particle.stable_id).collect::<Vec<_>>(),
+                    "x": column(|particle| particle.pos_x),
+                    "y": column(|particle| particle.pos_y),
+                    "theta": column(|particle| particle.theta),
+                })
+                .to_string(),
+            ),
+            FrameFormat::Binary => {
+                let num_particles = self.particles.len();
+                let mut bytes = Vec::with_capacity(24 + 32 * num_particles);
+                bytes.extend((self.current_time.0 as f64).to_le_bytes());
+                bytes.extend((self.instantaneous_order.0 as f64).to_le_bytes());
+                bytes.extend((num_particles as u64).to_le_bytes());
+                bytes.extend(
+                    self.particles
+                        .iter()
+                        .flat_map(|particle| (particle.stable_id as u64).to_le_bytes()),
+                );
+                let columns: [fn(&Particle) -> Float; 3] = [
+                    |particle| particle.pos_x,
+                    |particle| particle.pos_y,
+                    |particle| particle.theta,
+                ];
+                for value in columns {
+                    bytes.extend(
+                        self.particles
+                            .iter()
+                            .flat_map(|particle| (value(particle) as f64).to_le_bytes()),
+                    );
+                }
+
+                Message::binary(bytes)
+            }
+        }
+    }
+}
+
+/// A connected WebSocket client
+struct Client {
+    socket: WebSocket<TcpStream>,
+    peer: SocketAddr,
+}
+
+/// Send a message to every client, dropping the ones that can't take it
+fn broadcast(clients: &mut Vec<Client>, message: &Message) {
+    clients.retain_mut(|client| match client.socket.send(message.clone()) {
+        Ok(()) => true,
+        Err(error) => {
+            tracing::debug!(peer = %client.peer, %error, "dropping WebSocket client");
+            false
+        }
+    });
+}
+
+/// Take every text message clients have sent since the last frame, without waiting on them,
+/// dropping clients that have closed or failed
+fn read_commands(clients: &mut Vec<Client>) -> Vec<(SocketAddr, String)> {
+    let mut commands = Vec::new();
+
+    clients.retain_mut(|client| {
+        let stream = client.socket.get_ref();
+        if let Err(error) = stream.set_nonblocking(true) {
+            tracing::debug!(peer = %client.peer, %error, "dropping WebSocket client");
+            return false;
+        }
+
+        let outcome = loop {
+            match client.socket.read() {
+                Ok(Message::Text(text)) => commands.push((client.peer, text.to_string())),
+                Ok(_) => {}
+                Err(tungstenite::Error::Io(error)) if error.kind() == ErrorKind::WouldBlock => {
+                    break client
+                        .socket
+                        .get_ref()
+                        .set_nonblocking(false)
+                        .map_err(Into::into);
+                }
+                Err(error) => break Err(error),
+            }
+        };
+
+        match outcome {
+            Ok(()) => true,
+            Err(error) => {
+                tracing::debug!(peer = %client.peer, %error, "dropping WebSocket client");
+                false
+            }
+        }
+    });
+
+    commands
+}
+
+/// A trajectory being recorded on a client's request
+struct Recording {
+    path: PathBuf,
+    writer: CsvTrajectoryWriter<BufWriter<File>>,
+    num_frames: usize,
+}
+
+/// What clients' commands act on while serving
+struct ClientControl {
+    output_dir: Option<PathBuf>,
+    event_log: Option<(PathBuf, BufWriter<File>)>,
+    recording: Option<Recording>,
+}
+
+impl ClientControl {
+    fn new(options: &ServeOptions) -> anyhow::Result<Self> {
+        if let Some(dir) = &options.output_dir {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("could not create `{}`", dir.display()))?;
+        }
+
+        let event_log = match &options.event_log {
+            Some(path) => {
+                let file = OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path)
+                    .with_context(|| format!("could not open `{}`", path.display()))?;
+                Some((path.clone(), BufWriter::new(file)))
+            }
+            None => None,
+        };
+
+        Ok(Self {
+            output_dir: options.output_dir.clone(),
+            event_log,
+            recording: None,
+        })
+    }
+
+    /// Carry out a client's command, returning what it did as events
+    fn handle(
+        &mut self,
+        sim: &mut Simulation,
+        frame: usize,
+        command: &str,
+    ) -> anyhow::Result<Vec<Value>> {
+        let command: Value = serde_json::from_str(command).context("commands must be JSON")?;
+
+        match command["command"].as_str() {
+            Some("set") => {
+                let parameters = command["parameters"]
+                    .as_object()
+                    .ok_or_else(|| anyhow!("`set` needs a `parameters` object"))?;
+                let changes = parameters
+                    .iter()
+                    .map(|(name, value)| ParameterChange::from_json(name, value))
+                    .collect::<anyhow::Result<Vec<_>>>()?;
+
+                // Check every change before applying any, so a bad one leaves the rest unapplied
+                let mut changed = sim.clone();
+                for change in &changes {
+                    changed.apply_change(change)?;
+                }
+                *sim = changed;
+
+                changes
+                    .iter()
+                    .map(|change| Ok(json!({ "kind": "set", "parameter": change.to_json()? })))
+                    .collect()
+            }
+            Some("snapshot") => {
+                let path = self.output_path(&format!("snapshot_{frame:06}.json"))?;
+                fs::write(&path, sim.to_json())
+                    .with_context(|| format!("could not write `{}`", path.display()))?;
+
+                Ok(vec![json!({ "kind": "snapshot", "path": path })])
+            }
+            Some("start_recording") => {
+                if let Some(recording) = &self.recording {
+                    bail!("already recording to `{}`", recording.path.display());
+                }
+
+                let path = self.output_path(&format!("recording_{frame:06}.csv"))?;
+                let file = File::create(&path)
+                    .with_context(|| format!("could not create `{}`", path.display()))?;
+                self.recording = Some(Recording {
+                    writer: CsvTrajectoryWriter::new(BufWriter::new(file), 1)?,
+                    path: path.clone(),
+                    num_frames: 0,
+                });
+
+                Ok(vec![json!({ "kind": "start_recording", "path": path })])
+            }
+            Some("stop_recording") => match self.stop_recording()? {
+                Some(event) => Ok(vec![event]),
+                None => bail!("not recording"),
+            },
+            _ => bail!(
+                "commands are `{{\"command\": ...}}` with `set`, `snapshot`, `start_recording`, \
+                 or `stop_recording`"
+            ),
+        }
+    }
+
+    /// Write the current frame to the recording, if there is one
+    fn record(&mut self, sim: &Simulation) -> anyhow::Result<()> {
+        if let Some(recording) = &mut self.recording {
+            recording
+                .writer
+                .write_frame(sim)
+                .with_context(|| format!("could not write `{}`", recording.path.display()))?;
+            recording.num_frames += 1;
+        }
+
+        Ok(())
+    }
+
+    /// Finish the recording, if there is one, returning the event for stopping it
+    fn stop_recording(&mut self) -> anyhow::Result<Option<Value>> {
+        let Some(recording) = self.recording.take() else {
+            return Ok(None);
+        };
+
+        recording
+            .writer
+            .into_inner()
+            .and_then(|mut writer| Ok(writer.flush()?))
+            .with_context(|| format!("could not write `{}`", recording.path.display()))?;
+
+        Ok(Some(json!({
+            "kind": "stop_recording",
+            "path": recording.path,
+            "num_frames": recording.num_frames,
+        })))
+    }
+
+    /// Append an event a client caused to the log
+    fn log(
+        &mut self,
+        sim: &Simulation,
+        frame: usize,
+        peer: SocketAddr,
+        event: &Value,
+    ) -> anyhow::Result<()> {
+        tracing::info!(%peer, frame, %event, "client command");
+
+        let Some((path, writer)) = &mut self.event_log else {
+            return Ok(());
+        };
+        let entry = json!({
+            "frame": frame,
+            "time": sim.current_time.0,
+            "client": peer.to_string(),
+            "event": event,
+        });
+
+        writeln!(writer, "{entry}")
+            .and_then(|_| writer.flush())
+            .with_context(|| format!("could not write `{}`", path.display()))
+    }
+
+    fn output_path(&self, file_name: &str) -> anyhow::Result<PathBuf> {
+        let dir = self
+            .output_dir
+            .as_ref()
+            .ok_or_else(|| anyhow!("the server has no output directory to write into"))?;
+
+        Ok(dir.join(file_name))
+    }
+}
+
+/// Take every pending connection, keeping the ones that complete the WebSocket handshake
+fn accept_clients(listener: &TcpListener, clients: &mut Vec<Client>) -> anyhow::Result<()> {
+    loop {
+        let (stream, peer) = match listener.accept() {
+            Ok(connection) => connection,
+            Err(error) if error.kind() == ErrorKind::WouldBlock => return Ok(()),
+            Err(error) => return Err(error).context("could not accept WebSocket client"),
+        };
+
+        let handshake = stream
+            .set_nonblocking(false)
+            .and_then(|_| stream.set_read_timeout(Some(CLIENT_TIMEOUT)))
+            .and_then(|_| stream.set_write_timeout(Some(CLIENT_TIMEOUT)))
+            .map_err(anyhow::Error::from)
+            .and_then(|_| tungstenite::accept(stream).map_err(|error| anyhow::anyhow!("{error}")));
+
+        match handshake {
+            Ok(socket) => {
+                tracing::debug!(%peer, "WebSocket client connected");
+                clients.push(Client { socket, peer });
+            }
+            Err(error) => tracing::debug!(%peer, %error, "WebSocket handshake failed"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{
+        DomainBoundaryLength, Noise, ParticleDistanceThreshold, RelativeTime, Speed,
+    };
+
+    fn simulation() -> Simulation {
+        Simulation::with_particles(
+            &[(1.0, 1.5), (2.5, 3.0)],
+            &[0.0, 0.5],
+            DomainBoundaryLength(5.0),
+            Noise(0.0),
+            Speed(0.1),
+            RelativeTime(1.0),
+            ParticleDistanceThreshold(1.0),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn frames_encode_the_particles_as_json_or_binary() {
+        let sim = simulation();
+
+        let Message::Text(text) = sim.to_frame_message(FrameFormat::Json) else {
+            panic!("JSON frames are text messages");
+        };
+        let frame: Value = serde_json::from_str(&text).unwrap();
+        assert_eq!(frame["id"], json!([0, 1]));
+        assert_eq!(frame["x"], json!([1.0, 2.5]));
+        assert_eq!(frame["theta"], json!([0.0, 0.5]));
+
+        let Message::Binary(bytes) = sim.to_frame_message(FrameFormat::Binary) else {
+            panic!("binary frames are binary messages");
+        };
+        let words: Vec<[u8; 8]> = bytes
+            .chunks_exact(8)
+            .map(|word| word.try_into().unwrap())
+            .collect();
+        assert_eq!(words.len(), 3 + 4 * 2);
+        assert_eq!(u64::from_le_bytes(words[2]), 2);
+        assert_eq!(u64::from_le_bytes(words[4]), 1);
+        assert_eq!(f64::from_le_bytes(words[6]), 2.5);
+        assert_eq!(f64::from_le_bytes(words[10]), 0.5);
+    }
+
+    #[test]
+    fn serving_stops_after_the_frames_asked_for() {
+        let mut sim = simulation();
+        let options = ServeOptions {
+            steps_per_frame: 2,
+            max_fps: None,
+            num_frames: Some(3),
+            ..Default::default()
+        };
+
+        assert_eq!(sim.serve("127.0.0.1:0", &options).unwrap(), 3);
+        assert_eq!(sim.current_time.0, 4.0);
+
+        let options = ServeOptions {
+            max_fps: Some(0.0),
+            ..options
+        };
+        assert!(sim.serve("127.0.0.1:0", &options).is_err());
+    }
+
+    #[test]
+    fn client_commands_change_parameters_and_write_files() {
+        let dir = std::env::temp_dir().join(format!("serve-output-{}", std::process::id()));
+        let mut sim = simulation();
+        let mut control = ClientControl::new(&ServeOptions::default()).unwrap();
+
+        // Without an output directory clients can only change parameters
+        let events = control
+            .handle(
+                &mut sim,
+                0,
+                r#"{"command": "set", "parameters": {"noise": 0.4}}"#,
+            )
+            .unwrap();
+        assert_eq!(
+            events,
+            [json!({"kind": "set", "parameter": {"noise": 0.4}})]
+        );
+        assert_eq!(sim.params.noise.0, 0.4);
+        assert!(
+            control
+                .handle(&mut sim, 0, r#"{"command": "snapshot"}"#)
+                .is_err()
+        );
+
+        // A bad change leaves the others unapplied too
+        let bad_set = r#"{"command": "set", "parameters": {"noise": 0.1, "speed": -1}}"#;
+        assert!(control.handle(&mut sim, 0, bad_set).is_err());
+        assert_eq!(sim.params.noise.0, 0.4);
+
+        let options = ServeOptions {
+            output_dir: Some(dir.clone()),
+            ..Default::default()
+        };
+        let mut control = ClientControl::new(&options).unwrap();
+        control
+            .handle(&mut sim, 1, r#"{"command": "snapshot"}"#)
+            .unwrap();
+        assert!(dir.join("snapshot_000001.json").exists());
+
+        control
+            .handle(&mut sim, 2, r#"{"command": "start_recording"}"#)
+            .unwrap();
+        assert!(
+            control
+                .handle(&mut sim, 2, r#"{"command": "start_recording"}"#)
+                .is_err()
+        );
+        control.record(&sim).unwrap();
+        control.record(&sim).unwrap();
+        let events = control
+            .handle(&mut sim, 3, r#"{"command": "stop_recording"}"#)
+            .unwrap();
+        assert_eq!(events[0]["num_frames"], 2);
+        let csv = fs::read_to_string(dir.join("recording_000002.csv")).unwrap();
+        assert_eq!(csv.lines().count(), 1 + 2 * 2);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,539 @@
+use std::{
+    fs::{self, File},
+    io::{BufWriter, Write},
+    path::Path,
+    time::Instant,
+};
+
+use anyhow::{Context, anyhow, bail};
+use serde_json::{Map, Value, json};
+
+use crate::{
+    error::SimulationError,
+    field::{FlowField, ScalarField},
+    random::{self, RngState},
+    simulation::Simulation,
+    types::{Float, Noise, ParticleDistanceThreshold, Speed},
+};
+
+/// A change to a running simulation's parameters, as made live from a control panel or by a
+/// streaming client
+#[derive(Clone, Debug)]
+pub enum ParameterChange {
+    Noise(Noise),
+    Speed(Speed),
+    ParticleDistanceThreshold(ParticleDistanceThreshold),
+
+    /// A noise field over the domain, or `None` to go back to the uniform noise
+    NoiseField(Option<ScalarField>),
+
+    /// A speed field over the domain, or `None` to go back to the uniform speed
+    SpeedField(Option<ScalarField>),
+
+    /// A background flow, or `None` to remove it
+    FlowField(Option<FlowField>),
+}
+
+impl ParameterChange {
+    /// Read a change to the parameter `name` from its JSON value
+    ///
+    /// # Notes
+    /// `noise`, `speed`, and `particle_distance_threshold` take a number. `noise_field` and
+    /// `speed_field` take rows of grid cells from `y = 0` upwards, and `flow_field` takes one of
+    /// `{"uniform": [u, v]}`, `{"shear": rate}`, or `{"taylor_green": amplitude}`; any field can
+    /// be `null` to remove it.
+    pub fn from_json(name: &str, value: &Value) -> anyhow::Result<Self> {
+        let number = || {
+            value
+                .as_f64()
+                .map(|value| value as Float)
+                .ok_or_else(|| anyhow!("`{}` must be a number", name))
+        };
+
+        Ok(match name {
+            "noise" => Self::Noise(Noise(number()?)),
+            "speed" => Self::Speed(Speed(number()?)),
+            "particle_distance_threshold" => {
+                Self::ParticleDistanceThreshold(ParticleDistanceThreshold(number()?))
+            }
+            "noise_field" => Self::NoiseField(scalar_field_from_json(name, value)?),
+            "speed_field" => Self::SpeedField(scalar_field_from_json(name, value)?),
+            "flow_field" => Self::FlowField(flow_field_from_json(value)?),
+            _ => bail!(
+                "parameters that can change are noise, speed, particle_distance_threshold, \
+                 noise_field, speed_field, or flow_field, got `{name}`"
+            ),
+        })
+    }
+
+    /// Write the change as a one-entry JSON object, in the form [`ParameterChange::from_json`]
+    /// reads, which fails for fields given as functions
+    pub fn to_json(&self) -> anyhow::Result<Value> {
+        let (name, value) = match self {
+            Self::Noise(noise) => ("noise", json!(noise.0)),
+            Self::Speed(speed) => ("speed", json!(speed.0)),
+            Self::ParticleDistanceThreshold(threshold) => {
+                ("particle_distance_threshold", json!(threshold.0))
+            }
+            Self::NoiseField(field) => ("noise_field", scalar_field_to_json(field.as_ref())?),
+            Self::SpeedField(field) => ("speed_field", scalar_field_to_json(field.as_ref())?),
+            Self::FlowField(field) => ("flow_field", flow_field_to_json(field.as_ref())?),
+        };
+
+        Ok(Value::Object(Map::from_iter([(name.to_string(), value)])))
+    }
+}
+
+impl Simulation {
+    /// Apply a parameter change in place through its validating setter, leaving the simulation
+    /// untouched if it's rejected
+    pub fn apply_change(&mut self, change: &ParameterChange) -> Result<(), SimulationError> {
+        let sim = self.clone();
+
+        *self = match change {
+            ParameterChange::Noise(noise) => sim.with_noise(*noise)?,
+            ParameterChange::Speed(speed) => sim.with_speed(*speed)?,
+            ParameterChange::ParticleDistanceThreshold(threshold) => {
+                sim.with_distance_threshold(*threshold)?
+            }
+            ParameterChange::NoiseField(field) => sim.with_noise_field(field.clone())?,
+            ParameterChange::SpeedField(field) => sim.with_speed_field(field.clone())?,
+            ParameterChange::FlowField(field) => sim.with_flow_field(field.clone()),
+        };
+
+        Ok(())
+    }
+}
+
+/// A parameter change made during an interactive session, and when it was made
+#[derive(Clone, Debug)]
+pub struct SessionChange {
+    /// Number of steps taken since the session started, before the change
+    pub step: usize,
+
+    /// Simulated time the change was made at
+    pub time: Float,
+
+    /// Wall-clock seconds since the session started
+    pub elapsed: f64,
+
+    pub change: ParameterChange,
+}
+
+/// Everything needed to replay an interactive session exactly: the state and random generator it
+/// started from, and every parameter change made along the way
+///
+/// # Notes
+/// The starting state is what [`Simulation::to_json`] writes, so settings it leaves out (e.g. the
+/// update rule or fields set before the session) have to be set again on the simulation a replay
+/// starts from.
+#[derive(Clone, Debug)]
+pub struct Session {
+    /// The seed the run was started from, if known, kept for reference; the random state below
+    /// is what makes the replay exact
+    pub seed: Option<u64>,
+
+    /// Random state at the start of the session
+    pub rng: RngState,
+
+    /// State at the start of the session, as [`Simulation::to_json`] writes it
+    pub initial_state: String,
+
+    /// Number of steps taken over the whole session
+    pub num_steps: usize,
+
+    pub changes: Vec<SessionChange>,
+}
+
+impl Session {
+    /// Load a session saved with [`Session::save`]
+    pub fn load(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let read = || -> anyhow::Result<Self> {
+            Self::from_json(&serde_json::from_str(&fs::read_to_string(path)?)?)
+        };
+
+        read().with_context(|| format!("could not read session `{}`", path.display()))
+    }
+
+    /// Save the session as JSON
+    pub fn save(&self, path: impl AsRef<Path>) -> anyhow::Result<()> {
+        let path = path.as_ref();
+        let write = || -> anyhow::Result<()> {
+            let mut writer = BufWriter::new(File::create(path)?);
+            serde_json::to_writer_pretty(&mut writer, &self.to_json()?)?;
+            writeln!(writer)?;
+            writer.flush()?;
+
+            Ok(())
+        };
+
+        write().with_context(|| format!("could not write session `{}`", path.display()))
+    }
+
+    /// The simulation the session started from
+    pub fn initial_simulation(&self) -> anyhow::Result<Simulation> {
+        Simulation::from_json(&self.initial_state)
+    }
+
+    fn to_json(&self) -> anyhow::Result<Value> {
+        let changes = self
+            .changes
+            .iter()
+            .map(|change| {
+                Ok(json!({
+                    "step": change.step,
+                    "time": change.time,
+                    "elapsed": change.elapsed,
+                    "parameter": change.change.to_json()?,
+                }))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let initial_state: Value = serde_json::from_str(&self.initial_state)?;
+
+        Ok(json!({
+            "seed": self.seed,
+            "rng": self.rng.to_json(),
+            "initial_state": initial_state,
+            "num_steps": self.num_steps,
+            "changes": changes,
+        }))
+    }
+
+    fn from_json(root: &Value) -> anyhow::Result<Self> {
+        let count = |value: &Value, name: &str| {
+            value[name]
+                .as_u64()
+                .map(|count| count as usize)
+                .ok_or_else(|| anyhow!("`{}` must be a non-negative integer", name))
+        };
+        let number = |value: &Value, name: &str| {
+            value[name]
+                .as_f64()
+                .ok_or_else(|| anyhow!("`{}` must be a number", name))
+        };
+
+        let changes = root["changes"]
+            .as_array()
+            .ok_or_else(|| anyhow!("`changes` must be an array"))?
+            .iter()
+            .enumerate()
+            .map(|(idx, change)| {
+                let read = || -> anyhow::Result<SessionChange> {
+                    let (name, value) = change["parameter"]
+                        .as_object()
+                        .filter(|parameter| parameter.len() == 1)
+                        .and_then(|parameter| parameter.iter().next())
+                        .ok_or_else(|| anyhow!("`parameter` must be a one-entry object"))?;
+
+                    Ok(SessionChange {
+                        step: count(change, "step")?,
+                        time: number(change, "time")? as Float,
+                        elapsed: number(change, "elapsed")?,
+                        change: ParameterChange::from_json(name, value)?,
+                    })
+                };
+
+                read().with_context(|| format!("invalid change `{idx}`"))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        if changes.windows(2).any(|pair| pair[1].step < pair[0].step) {
+            bail!("changes must be in step order");
+        }
+
+        let seed = match &root["seed"] {
+            Value::Null => None,
+            seed => Some(
+                seed.as_u64()
+                    .ok_or_else(|| anyhow!("`seed` must be a non-negative integer, or null"))?,
+            ),
+        };
+        let initial_state = root
+            .get("initial_state")
+            .filter(|state| state.is_object())
+            .ok_or_else(|| anyhow!("`initial_state` must be an object"))?;
+
+        Ok(Self {
+            seed,
+            rng: RngState::from_json(&root["rng"])?,
+            initial_state: initial_state.to_string(),
+            num_steps: count(root, "num_steps")?,
+            changes,
+        })
+    }
+}
+
+/// Builds a [`Session`] as it's played, timing each change from when recording started
+#[derive(Debug)]
+pub struct SessionRecorder {
+    session: Session,
+    started: Instant,
+}
+
+impl SessionRecorder {
+    /// Start recording from the simulation's current state and this thread's random state, which
+    /// the session's steps have to draw from
+    pub fn start(sim: &Simulation, seed: Option<u64>) -> Self {
+        Self {
+            session: Session {
+                seed,
+                rng: random::rng_state(),
+                initial_state: sim.to_json(),
+                num_steps: 0,
+                changes: Vec::new(),
+            },
+            started: Instant::now(),
+        }
+    }
+
+    /// Note that the simulation took `num_steps` more steps
+    pub fn record_steps(&mut self, num_steps: usize) {
+        self.session.num_steps += num_steps;
+    }
+
+    /// Note a change just applied to the simulation
+    pub fn record_change(&mut self, sim: &Simulation, change: ParameterChange) {
+        self.session.changes.push(SessionChange {
+            step: self.session.num_steps,
+            time: sim.current_time.0,
+            elapsed: self.started.elapsed().as_secs_f64(),
+            change,
+        });
+    }
+
+    pub fn finish(self) -> Session {
+        self.session
+    }
+}
+
+impl Simulation {
+    /// Replay a session in place, from this simulation (normally
+    /// [`Session::initial_simulation`]): the random state is put back to where the session
+    /// started, and every change is applied at the step it was made, calling `on_step` with the
+    /// state before stepping and after every step
+    ///
+    /// # Notes
+    /// The replay repeats the session exactly as long as this simulation matches the one it
+    /// started from, and this thread's random state isn't drawn from in between.
+    pub fn replay_session(
+        &mut self,
+        session: &Session,
+        mut on_step: impl FnMut(&Simulation) -> anyhow::Result<()>,
+    ) -> anyhow::Result<()> {
+        random::restore_rng_state(&session.rng);
+        on_step(self)?;
+
+        let mut changes = session.changes.iter().peekable();
+        for step in 0..=session.num_steps {
+            while let Some(change) = changes.next_if(|change| change.step == step) {
+                self.apply_change(&change.change)?;
+            }
+            if step == session.num_steps {
+                break;
+            }
+
+            self.run_for(1)?;
+            on_step(self)?;
+        }
+
+        if let Some(change) = changes.next() {
+            bail!(
+                "session changes a parameter at step `{}`, after its last step `{}`",
+                change.step,
+                session.num_steps
+            );
+        }
+
+        Ok(())
+    }
+}
+
+fn scalar_field_from_json(name: &str, value: &Value) -> anyhow::Result<Option<ScalarField>> {
+    if value.is_null() {
+        return Ok(None);
+    }
+
+    let rows = value
+        .as_array()
+        .and_then(|rows| {
+            rows.iter()
+                .map(|row| {
+                    row.as_array()?
+                        .iter()
+                        .map(|cell| cell.as_f64().map(|cell| cell as Float))
+                        .collect::<Option<Vec<_>>>()
+                })
+                .collect::<Option<Vec<_>>>()
+        })
+        .ok_or_else(|| anyhow!("`{}` must be rows of numbers, or null", name))?;
+
+    Ok(Some(ScalarField::grid(rows)?))
+}
+
+fn scalar_field_to_json(field: Option<&ScalarField>) -> anyhow::Result<Value> {
+    match field {
+        None => Ok(Value::Null),
+        Some(ScalarField::Grid {
+            cells_per_side,
+            values,
+        }) => Ok(json!(
+            values
+                .chunks(*cells_per_side)
+                .map(<[Float]>::to_vec)
+                .collect::<Vec<_>>()
+        )),
+        Some(ScalarField::Function(_)) => bail!("fields given as functions can't be written"),
+    }
+}
+
+fn flow_field_from_json(value: &Value) -> anyhow::Result<Option<FlowField>> {
+    let number = |value: &Value| value.as_f64().map(|value| value as Float);
+    let invalid = || {
+        anyhow!(
+            "`flow_field` must be `{{\"uniform\": [u, v]}}`, `{{\"shear\": rate}}`, \
+             `{{\"taylor_green\": amplitude}}`, or null"
+        )
+    };
+
+    if value.is_null() {
+        return Ok(None);
+    }
+
+    let flow = match value
+        .as_object()
+        .map(|flow| flow.iter().collect::<Vec<_>>())
+    {
+        Some(flow) => match flow[..] {
+            [(kind, value)] if kind == "uniform" => match value.as_array().map(Vec::as_slice) {
+                Some([u, v]) => FlowField::Uniform {
+                    u: number(u).ok_or_else(invalid)?,
+                    v: number(v).ok_or_else(invalid)?,
+                },
+                _ => return Err(invalid()),
+            },
+            [(kind, value)] if kind == "shear" => FlowField::Shear {
+                rate: number(value).ok_or_else(invalid)?,
+            },
+            [(kind, value)] if kind == "taylor_green" => FlowField::TaylorGreen {
+                amplitude: number(value).ok_or_else(invalid)?,
+            },
+            _ => return Err(invalid()),
+        },
+        None => return Err(invalid()),
+    };
+
+    Ok(Some(flow))
+}
+
+fn flow_field_to_json(field: Option<&FlowField>) -> anyhow::Result<Value> {
+    match field {
+        None => Ok(Value::Null),
+        Some(FlowField::Uniform { u, v }) => Ok(json!({ "uniform": [u, v] })),
+        Some(FlowField::Shear { rate }) => Ok(json!({ "shear": rate })),
+        Some(FlowField::TaylorGreen { amplitude }) => Ok(json!({ "taylor_green": amplitude })),
+        Some(FlowField::Function(_)) => bail!("flows given as functions can't be written"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{DomainBoundaryLength, RelativeTime};
+
+    fn simulation() -> Simulation {
+        Simulation::new(
+            10,
+            DomainBoundaryLength(5.0),
+            Noise(0.5),
+            Speed(0.1),
+            RelativeTime(1.0),
+            ParticleDistanceThreshold(1.0),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn changes_round_trip_through_json_and_apply_through_the_setters() {
+        for change in [
+            json!({"noise": 0.25}),
+            json!({"particle_distance_threshold": 1.5}),
+            json!({"speed_field": [[1.0, 2.0], [3.0, 4.0]]}),
+            json!({"flow_field": {"uniform": [0.1, 0.0]}}),
+            json!({"flow_field": null}),
+        ] {
+            let (name, value) = change.as_object().unwrap().iter().next().unwrap();
+            let parsed = ParameterChange::from_json(name, value).unwrap();
+            assert_eq!(parsed.to_json().unwrap(), change);
+        }
+
+        let mut sim = simulation();
+        sim.apply_change(&ParameterChange::from_json("noise", &json!(0.25)).unwrap())
+            .unwrap();
+        assert_eq!(sim.params.noise.0, 0.25);
+
+        // A rejected change leaves the simulation as it was
+        assert!(
+            sim.apply_change(&ParameterChange::Speed(Speed(-1.0)))
+                .is_err()
+        );
+        assert_eq!(sim.params.speed.0, 0.1);
+
+        assert!(ParameterChange::from_json("timestep", &json!(0.5)).is_err());
+        assert!(ParameterChange::from_json("noise", &json!("loud")).is_err());
+        let function_field =
+            ParameterChange::FlowField(Some(FlowField::function(|_, _| (0.0, 0.0))));
+        assert!(function_field.to_json().is_err());
+    }
+
+    #[test]
+    fn saved_sessions_replay_exactly() {
+        let path = std::env::temp_dir().join(format!("session-{}.json", std::process::id()));
+        random::seed_rng(4);
+        let mut sim = simulation();
+        let mut recorder = SessionRecorder::start(&sim, Some(4));
+
+        sim.run_for(2).unwrap();
+        recorder.record_steps(2);
+        let change = ParameterChange::Noise(Noise(0.1));
+        sim.apply_change(&change).unwrap();
+        recorder.record_change(&sim, change);
+        sim.run_for(3).unwrap();
+        recorder.record_steps(3);
+
+        recorder.finish().save(&path).unwrap();
+        let session = Session::load(&path).unwrap();
+        assert_eq!((session.num_steps, session.changes[0].step), (5, 2));
+
+        let mut times = Vec::new();
+        let mut replayed = session.initial_simulation().unwrap();
+        replayed
+            .replay_session(&session, |sim| {
+                times.push(sim.current_time.0);
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(times, [0.0, 1.0, 2.0, 3.0, 4.0, 5.0]);
+        assert_eq!(replayed.params.noise.0, 0.1);
+
+        let positions = |sim: &Simulation| -> Vec<_> {
+            sim.particles
+                .iter()
+                .map(|p| (p.pos_x, p.pos_y, p.theta))
+                .collect()
+        };
+        assert_eq!(positions(&replayed), positions(&sim));
+
+        // Changes past the last step can't be replayed
+        let mut late = session.clone();
+        late.changes[0].step = 6;
+        assert!(
+            late.initial_simulation()
+                .unwrap()
+                .replay_session(&late, |_| Ok(()))
+                .is_err()
+        );
+
+        let _ = fs::remove_file(&path);
+    }
+}
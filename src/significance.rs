@@ -0,0 +1,284 @@
+use anyhow::bail;
+
+use crate::{random, types::Float};
+
+/// Controls for comparing two sets of measurements
+#[derive(Copy, Clone, Debug)]
+pub struct SignificanceOptions {
+    /// A p-value below this counts as a real difference
+    pub alpha: Float,
+
+    /// Number of random relabelings in the permutation test
+    pub num_permutations: usize,
+}
+
+impl Default for SignificanceOptions {
+    fn default() -> Self {
+        Self {
+            alpha: 0.05,
+            num_permutations: 10_000,
+        }
+    }
+}
+
+/// Whether two sets of measurements differ
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Verdict {
+    /// No difference detected at the chosen significance level
+    Consistent,
+
+    /// The means differ at the chosen significance level
+    Different,
+}
+
+/// Summary of one set of measurements, with its error corrected for autocorrelation
+#[derive(Copy, Clone, Debug)]
+pub struct MeasurementSummary {
+    pub mean: Float,
+    pub std_dev: Float,
+
+    /// Integrated autocorrelation time, in samples: ½ for independent measurements
+    pub autocorrelation_time: Float,
+
+    /// Number of effectively independent measurements
+    pub effective_size: Float,
+
+    /// Standard error of the mean from the effective size
+    pub std_error: Float,
+}
+
+/// The outcome of comparing two sets of measurements
+#[derive(Copy, Clone, Debug)]
+pub struct SignificanceTest {
+    pub first: MeasurementSummary,
+    pub second: MeasurementSummary,
+
+    /// The second mean minus the first
+    pub mean_difference: Float,
+
+    /// Cohen's d: the difference in means over the pooled standard deviation
+    pub effect_size: Float,
+
+    /// Welch's t statistic, using the autocorrelation-corrected standard errors
+    pub t_statistic: Float,
+
+    /// Two-sided p-value from a block permutation test
+    pub p_value: Float,
+
+    pub verdict: Verdict,
+}
+
+/// Compare two sets of measurements, e.g. stationary order parameters from two parameter sets
+/// or two backends, or the instantaneous order over two runs
+///
+/// # Notes
+/// Measurements are taken in order, so a time series can be passed as is: its integrated
+/// autocorrelation time shrinks the effective sample size, and the permutation test shuffles
+/// blocks of about two autocorrelation times rather than single samples, so correlated samples
+/// don't pass for independent evidence. Each set needs at least two such blocks.
+pub fn compare_measurements(
+    first: &[Float],
+    second: &[Float],
+    options: &SignificanceOptions,
+) -> anyhow::Result<SignificanceTest> {
+    if !(options.alpha > 0.0 && options.alpha < 1.0) {
+        bail!(
+            "significance level must be in (0, 1), got `{}`",
+            options.alpha
+        );
+    }
+
+    if options.num_permutations == 0 {
+        bail!("the permutation test needs at least one permutation");
+    }
+
+    if let Some(value) = first.iter().chain(second).find(|value| !value.is_finite()) {
+        bail!("measurements must be finite, got `{}`", value);
+    }
+
+    let first_summary = summarize(first)?;
+    let second_summary = summarize(second)?;
+
+    // Shuffle block means, so each unit of the test is roughly independent
+    let first_blocks = block_means(first, block_len(first_summary.autocorrelation_time));
+    let second_blocks = block_means(second, block_len(second_summary.autocorrelation_time));
+    if first_blocks.len() < 2 || second_blocks.len() < 2 {
+        bail!(
+            "each set needs at least two independent blocks of measurements, got `{}` and `{}`",
+            first_blocks.len(),
+            second_blocks.len()
+        );
+    }
+
+    let mean_difference = second_summary.mean - first_summary.mean;
+    let p_value = permutation_p_value(&first_blocks, &second_blocks, options.num_permutations);
+
+    let pooled_std_dev =
+        ((first_summary.std_dev.powi(2) + second_summary.std_dev.powi(2)) / 2.0).sqrt();
+    let effect_size = match pooled_std_dev > 0.0 {
+        true => mean_difference / pooled_std_dev,
+        false => 0.0,
+    };
+
+    let difference_std_error =
+        (first_summary.std_error.powi(2) + second_summary.std_error.powi(2)).sqrt();
+    let t_statistic = match difference_std_error > 0.0 {
+        true => mean_difference / difference_std_error,
+        false => 0.0,
+    };
+
+    Ok(SignificanceTest {
+        first: first_summary,
+        second: second_summary,
+        mean_difference,
+        effect_size,
+        t_statistic,
+        p_value,
+        verdict: match p_value < options.alpha {
+            true => Verdict::Different,
+            false => Verdict::Consistent,
+        },
+    })
+}
+
+fn summarize(values: &[Float]) -> anyhow::Result<MeasurementSummary> {
+    if values.len() < 2 {
+        bail!(
+            "each set needs at least two measurements, got `{}`",
+            values.len()
+        );
+    }
+
+    let len = values.len() as Float;
+    let mean = values.iter().sum::<Float>() / len;
+    let variance = values
+        .iter()
+        .map(|value| (value - mean).powi(2))
+        .sum::<Float>()
+        / (len - 1.0);
+
+    let autocorrelation_time = integrated_autocorrelation_time(values, mean, variance);
+    let effective_size = (len / (2.0 * autocorrelation_time)).clamp(1.0, len);
+
+    Ok(MeasurementSummary {
+        mean,
+        std_dev: variance.sqrt(),
+        autocorrelation_time,
+        effective_size,
+        std_error: (variance / effective_size).sqrt(),
+    })
+}
+
+/// Sum the autocorrelation up to the first lag where it stops being positive, a simple and
+/// robust cutoff for noisy estimates
+fn integrated_autocorrelation_time(values: &[Float], mean: Float, variance: Float) -> Float {
+    if variance == 0.0 {
+        return 0.5;
+    }
+
+    let len = values.len();
+    let mut time = 0.5;
+
+    for lag in 1..len / 2 {
+        let covariance = values[..len - lag]
+            .iter()
+            .zip(&values[lag..])
+            .map(|(a, b)| (a - mean) * (b - mean))
+            .sum::<Float>()
+            / (len - lag) as Float;
+        let correlation = covariance / variance;
+
+        if correlation <= 0.0 {
+            break;
+        }
+        time += correlation;
+    }
+
+    time
+}
+
+fn block_len(autocorrelation_time: Float) -> usize {
+    (2.0 * autocorrelation_time).ceil().max(1.0) as usize
+}
+
+/// Average consecutive blocks of measurements, dropping any incomplete last block
+fn block_means(values: &[Float], block_len: usize) -> Vec<Float> {
+    values
+        .chunks_exact(block_len)
+        .map(|block| block.iter().sum::<Float>() / block_len as Float)
+        .collect()
+}
+
+/// The fraction of random relabelings of the blocks whose difference in means is at least as
+/// large as the observed one
+fn permutation_p_value(first: &[Float], second: &[Float], num_permutations: usize) -> Float {
+    let mean = |values: &[Float]| values.iter().sum::<Float>() / values.len() as Float;
+    let observed = (mean(second) - mean(first)).abs();
+
+    let mut pooled: Vec<Float> = first.iter().chain(second).copied().collect();
+    let mut num_extreme = 0;
+
+    for _ in 0..num_permutations {
+        for idx in (1..pooled.len()).rev() {
+            pooled.swap(idx, random::random_range(0..=idx));
+        }
+
+        let (relabeled_first, relabeled_second) = pooled.split_at(first.len());
+        if (mean(relabeled_second) - mean(relabeled_first)).abs() >= observed {
+            num_extreme += 1;
+        }
+    }
+
+    // Count the observed labeling too, so the p-value is never exactly zero
+    (num_extreme + 1) as Float / (num_permutations + 1) as Float
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn alternating(offset: Float) -> Vec<Float> {
+        (0..40).map(|idx| offset + (idx % 2) as Float).collect()
+    }
+
+    #[test]
+    fn shifted_measurements_differ_and_equal_ones_do_not() {
+        random::seed_rng(7);
+        let options = SignificanceOptions {
+            num_permutations: 500,
+            ..Default::default()
+        };
+
+        let test = compare_measurements(&alternating(0.0), &alternating(5.0), &options).unwrap();
+        assert_eq!(test.verdict, Verdict::Different);
+        assert!((test.mean_difference - 5.0).abs() < 1e-12);
+        assert!(test.p_value < options.alpha);
+        assert_eq!(test.first.autocorrelation_time, 0.5);
+
+        let test = compare_measurements(&alternating(0.0), &alternating(0.0), &options).unwrap();
+        assert_eq!(test.verdict, Verdict::Consistent);
+        assert_eq!(test.p_value, 1.0);
+        assert_eq!(test.effect_size, 0.0);
+    }
+
+    #[test]
+    fn invalid_measurements_and_options_are_rejected() {
+        let options = SignificanceOptions::default();
+        let values = alternating(0.0);
+
+        assert!(compare_measurements(&values, &[1.0], &options).is_err());
+        assert!(compare_measurements(&values, &[1.0, Float::NAN, 2.0], &options).is_err());
+
+        let options = SignificanceOptions {
+            alpha: 1.0,
+            ..Default::default()
+        };
+        assert!(compare_measurements(&values, &values, &options).is_err());
+
+        let options = SignificanceOptions {
+            num_permutations: 0,
+            ..Default::default()
+        };
+        assert!(compare_measurements(&values, &values, &options).is_err());
+    }
+}
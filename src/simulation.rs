@@ -1,16 +1,33 @@
-use std::{collections::VecDeque, fmt::Display};
-
-use anyhow::{anyhow, bail};
+use std::{
+    collections::{BTreeMap, VecDeque},
+    fmt::Display,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
 
 use crate::{
-    particle::Particles,
+    control::{CancellationToken, StopReason},
+    error::{SimulationError, invalid_parameter},
+    field::{FlowField, ScalarField},
+    history::OrderHistory,
+    noise::{NoiseSource, NoiseStream},
+    observer::StepObserver,
+    particle::{
+        BirthDeath, EnergyBudget, InitialCondition, LeaderHeading, NeighborWeighting, NoiseModel,
+        Particle, Particles, Repulsion, SpeedDistribution, UpdateOrder, UpdateRule,
+    },
+    perf::{PerformanceCounters, SimulationStats, timed},
+    random,
+    scalars::ScalarRule,
+    schedule::{DomainResizeSchedule, NoiseSchedule},
     types::{
-        AbsoluteTime, DomainBoundaryLength, Float, InstantaneosOrder, Noise,
+        AbsoluteTime, Angle, DomainBoundaryLength, Float, InstantaneosOrder, Noise, PI,
         ParticleDistanceThreshold, RelativeTime, Speed,
     },
+    watchdog::Watchdog,
 };
 
-/// Beyond this many iterations the computation will fail
+/// Beyond this many iterations the computation will give up, unless given a different step budget
 const MAX_STATIONARY_ORDER_PARAM_ITERATIONS: usize = 5000;
 
 /// Defines the sliding window size for stationary order parameter
@@ -20,25 +37,135 @@ const STATIONARY_ORDER_EPSILON: Float = 0.001;
 
 // By putting these parameters in their own struct it also makes the copy update more readable and
 // easier to maintain
-#[derive(Copy, Clone)]
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub(crate) struct SimulationParameters {
     pub(crate) boundary_side_length: DomainBoundaryLength,
     pub(crate) noise: Noise,
     pub(crate) speed: Speed,
     pub(crate) timestep: RelativeTime,
     pub(crate) particle_distance_threshold: ParticleDistanceThreshold,
+
+    /// Particles only align with neighbors within this angle either side of their heading
+    pub(crate) vision_half_angle: Option<Angle>,
+
+    pub(crate) neighbor_weighting: NeighborWeighting,
+
+    /// Soft short-range repulsion applied to the position update
+    pub(crate) repulsion: Option<Repulsion>,
+
+    /// How headings are updated from neighbors
+    pub(crate) update_rule: UpdateRule,
+
+    /// Time scale over which headings turn towards their target, or `None` to snap instantly
+    pub(crate) heading_relaxation_time: Option<RelativeTime>,
+
+    /// How noise perturbs the averaged heading
+    pub(crate) noise_model: NoiseModel,
+
+    /// Position-dependent noise amplitude, replacing `noise` where set
+    pub(crate) noise_field: Option<ScalarField>,
+
+    /// Position-dependent speed, replacing `speed` where set
+    pub(crate) speed_field: Option<ScalarField>,
+
+    /// Background flow advecting the particles
+    pub(crate) flow_field: Option<FlowField>,
+
+    /// Random births and deaths, or a fixed population when unset
+    pub(crate) birth_death: Option<BirthDeath>,
+
+    /// Whether particles update all at once or one at a time
+    pub(crate) update_order: UpdateOrder,
+
+    /// Neighbors within this distance of the line of sight to a further neighbor hide it, or
+    /// nothing is hidden when unset
+    pub(crate) occlusion_tolerance: Option<Float>,
+
+    /// Translational diffusion coefficient D_t jittering positions each step, or no jitter when
+    /// unset
+    pub(crate) translational_diffusion: Option<Float>,
+
+    /// Energy budget fatiguing moving particles, or none when unset
+    pub(crate) energy_budget: Option<EnergyBudget>,
+
+    /// User rule updating the per-particle scalars each step, or none when unset
+    pub(crate) scalar_rule: Option<ScalarRule>,
+}
+
+/// Check a parameter is finite and positive
+pub(crate) fn validate_positive(name: &str, value: Float) -> Result<(), SimulationError> {
+    if !value.is_finite() || value <= 0.0 {
+        invalid_parameter!("{} must be positive and finite, got `{}`", name, value);
+    }
+
+    Ok(())
+}
+
+/// Check a parameter is finite and non-negative
+fn validate_non_negative(name: &str, value: Float) -> Result<(), SimulationError> {
+    if !value.is_finite() || value < 0.0 {
+        invalid_parameter!("{} must be non-negative and finite, got `{}`", name, value);
+    }
+
+    Ok(())
+}
+
+/// An axis-aligned rectangular region of the domain, used e.g. to split a simulation
+#[derive(Copy, Clone, Debug)]
+pub struct Region {
+    pub x_min: Float,
+    pub x_max: Float,
+    pub y_min: Float,
+    pub y_max: Float,
+}
+
+impl Region {
+    /// Check whether a point lies in the region (inclusive lower bound, exclusive upper bound)
+    fn contains(&self, x: Float, y: Float) -> bool {
+        (self.x_min..self.x_max).contains(&x) && (self.y_min..self.y_max).contains(&y)
+    }
 }
 
 /// A particle interaction simulator
+///
+/// # Notes
+/// With the `serde` feature the whole state serializes, except the step observers, which are
+/// dropped. Closure-based settings (function fields, noise schedules, and scalar rules) fail to
+/// serialize. A shared noise recording or order history is deserialized as a fresh copy, no
+/// longer shared with the simulation it came from, and deserialized states aren't validated.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Simulation {
     pub(crate) particles: Particles,
     pub(crate) instantaneous_order: InstantaneosOrder,
     pub(crate) current_time: AbsoluteTime,
     pub(crate) params: SimulationParameters,
+    pub(crate) domain_schedule: Option<DomainResizeSchedule>,
+    pub(crate) noise_schedule: Option<NoiseSchedule>,
+    pub(crate) performance_counters: PerformanceCounters,
+    pub(crate) noise_source: NoiseSource,
+
+    /// The stable ID the next added particle gets, so IDs are never reused
+    pub(crate) next_stable_id: usize,
+
+    /// Called after each step of the running helpers
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) observers: Vec<Arc<Mutex<dyn StepObserver>>>,
+
+    /// The instantaneous order after every step, if kept
+    pub(crate) order_history: Option<Arc<Mutex<OrderHistory>>>,
+
+    /// Checks each step taken in place, if set
+    pub(crate) watchdog: Option<Arc<Watchdog>>,
 }
 
 impl Simulation {
     /// Instantiate a new particle simulator with randomized initial conditions
+    ///
+    /// # Notes
+    /// Every constructor rejects an empty simulation, a non-positive domain size, timestep, or
+    /// distance threshold, negative noise or speed, and any NaN or infinite parameter.
     pub fn new(
         num_particles: usize,
         boundary_side_length: DomainBoundaryLength,
@@ -46,67 +173,1213 @@ impl Simulation {
         speed: Speed,
         timestep: RelativeTime,
         particle_distance_threshold: ParticleDistanceThreshold,
-    ) -> anyhow::Result<Self> {
-        if num_particles == 0 {
-            bail!("at least one particle must be simulated");
+    ) -> Result<Self, SimulationError> {
+        Self::from_initial_condition(
+            num_particles,
+            InitialCondition::UniformRandom,
+            boundary_side_length,
+            noise,
+            speed,
+            timestep,
+            particle_distance_threshold,
+        )
+    }
+
+    /// Instantiate a new particle simulator with particles laid out per a preset, e.g. a lattice
+    /// or an aligned flock
+    pub fn from_initial_condition(
+        num_particles: usize,
+        initial_condition: InitialCondition,
+        boundary_side_length: DomainBoundaryLength,
+        noise: Noise,
+        speed: Speed,
+        timestep: RelativeTime,
+        particle_distance_threshold: ParticleDistanceThreshold,
+    ) -> Result<Self, SimulationError> {
+        initial_condition.validate()?;
+
+        Self::from_initial_particles(
+            Particles::new(num_particles, boundary_side_length, initial_condition),
+            boundary_side_length,
+            noise,
+            speed,
+            timestep,
+            particle_distance_threshold,
+        )
+    }
+
+    /// Instantiate a new particle simulator from prescribed positions and headings, in particle
+    /// order, e.g. to perturb a known configuration
+    ///
+    /// # Notes
+    /// Positions outside the domain are wrapped back into it. Phases are still random, so use
+    /// [`Simulation::with_noise_replay`] as well for a fully reproducible run.
+    pub fn with_particles(
+        positions: &[(Float, Float)],
+        thetas: &[Float],
+        boundary_side_length: DomainBoundaryLength,
+        noise: Noise,
+        speed: Speed,
+        timestep: RelativeTime,
+        particle_distance_threshold: ParticleDistanceThreshold,
+    ) -> Result<Self, SimulationError> {
+        if positions.len() != thetas.len() {
+            invalid_parameter!(
+                "got `{}` positions but `{}` headings",
+                positions.len(),
+                thetas.len()
+            );
+        }
+
+        if let Some(((x, y), theta)) = positions
+            .iter()
+            .zip(thetas)
+            .find(|((x, y), theta)| !(x.is_finite() && y.is_finite() && theta.is_finite()))
+        {
+            invalid_parameter!(
+                "particle state must be finite, got `({}, {}, {})`",
+                x,
+                y,
+                theta
+            );
+        }
+
+        let particles =
+            Particles::from_reindexed(positions.iter().zip(thetas).map(|(&(x, y), &theta)| {
+                Particle::from_state(
+                    x.rem_euclid(boundary_side_length.0),
+                    y.rem_euclid(boundary_side_length.0),
+                    theta,
+                )
+            }));
+
+        Self::from_initial_particles(
+            particles,
+            boundary_side_length,
+            noise,
+            speed,
+            timestep,
+            particle_distance_threshold,
+        )
+    }
+
+    /// Instantiate a new particle simulator starting from the given particles
+    pub(crate) fn from_initial_particles(
+        particles: Particles,
+        boundary_side_length: DomainBoundaryLength,
+        noise: Noise,
+        speed: Speed,
+        timestep: RelativeTime,
+        particle_distance_threshold: ParticleDistanceThreshold,
+    ) -> Result<Self, SimulationError> {
+        if particles.len() == 0 {
+            invalid_parameter!("at least one particle must be simulated");
+        }
+
+        // Anything that isn't a finite, positive length or duration would quietly produce garbage
+        for (name, value) in [
+            ("domain boundary side length", boundary_side_length.0),
+            ("timestep", timestep.0),
+            ("particle distance threshold", particle_distance_threshold.0),
+        ] {
+            validate_positive(name, value)?;
+        }
+
+        for (name, value) in [("noise", noise.0), ("speed", speed.0)] {
+            validate_non_negative(name, value)?;
+        }
+
+        let instantaneous_order = particles.compute_instantaneous_order();
+        let next_stable_id = particles.len();
+
+        let params = SimulationParameters {
+            boundary_side_length,
+            noise,
+            speed,
+            timestep,
+            particle_distance_threshold,
+            vision_half_angle: None,
+            neighbor_weighting: NeighborWeighting::Uniform,
+            repulsion: None,
+            update_rule: UpdateRule::Vicsek,
+            heading_relaxation_time: None,
+            noise_model: NoiseModel::Vectorial,
+            noise_field: None,
+            speed_field: None,
+            flow_field: None,
+            birth_death: None,
+            update_order: UpdateOrder::Synchronous,
+            occlusion_tolerance: None,
+            translational_diffusion: None,
+            energy_budget: None,
+            scalar_rule: None,
+        };
+
+        let current_time = AbsoluteTime(0.0);
+
+        Ok(Self {
+            particles,
+            instantaneous_order,
+            current_time,
+            params,
+            domain_schedule: None,
+            noise_schedule: None,
+            performance_counters: PerformanceCounters::default(),
+            noise_source: NoiseSource::Random,
+            next_stable_id,
+            observers: Vec::new(),
+            order_history: None,
+            watchdog: None,
+        })
+    }
+
+    /// Assemble a simulation from existing state, recomputing the derived quantities
+    fn from_parts(
+        particles: Particles,
+        current_time: AbsoluteTime,
+        params: SimulationParameters,
+        domain_schedule: Option<DomainResizeSchedule>,
+        noise_schedule: Option<NoiseSchedule>,
+    ) -> Self {
+        let instantaneous_order = particles.compute_instantaneous_order();
+        // Particles may keep the stable IDs they had, so count on from the largest rather than
+        // the number of particles
+        let next_stable_id = particles
+            .iter()
+            .map(|particle| particle.stable_id + 1)
+            .max()
+            .unwrap_or(0);
+
+        Self {
+            particles,
+            instantaneous_order,
+            current_time,
+            params,
+            domain_schedule,
+            noise_schedule,
+            performance_counters: PerformanceCounters::default(),
+            noise_source: NoiseSource::Random,
+            next_stable_id,
+            observers: Vec::new(),
+            order_history: None,
+            watchdog: None,
+        }
+    }
+
+    /// Label every particle with the same tag, e.g. before merging two flocks together
+    ///
+    /// # Notes
+    /// Everything else, including stable IDs, observers, and any watchdog, is kept.
+    pub fn to_tagged(&self, tag: usize) -> Self {
+        Self {
+            particles: self.particles.to_tagged(tag),
+            ..self.clone()
+        }
+    }
+
+    /// Merge another simulation's particles into this simulation's domain
+    ///
+    /// # Notes
+    /// The merged simulation keeps this simulation's parameters and time. The other particles are
+    /// wrapped into this domain if it is smaller, and all IDs are re-assigned with this
+    /// simulation's particles first.
+    pub fn to_merged(&self, other: &Simulation) -> Self {
+        let other_particles = other
+            .particles
+            .to_resized(
+                other.params.boundary_side_length,
+                self.params.boundary_side_length,
+                false,
+            )
+            .into_iter();
+
+        let particles =
+            Particles::from_reindexed(self.particles.iter().cloned().chain(other_particles));
+
+        Self::from_parts(
+            particles,
+            self.current_time,
+            self.params.clone(),
+            self.domain_schedule.clone(),
+            self.noise_schedule.clone(),
+        )
+    }
+
+    /// Split into the particles inside and outside of a region, as independent simulations
+    /// sharing this simulation's domain and parameters
+    pub fn split_by_region(&self, region: &Region) -> Result<(Self, Self), SimulationError> {
+        let (inside, outside): (Vec<_>, Vec<_>) = self
+            .particles
+            .iter()
+            .cloned()
+            .partition(|particle| region.contains(particle.pos_x, particle.pos_y));
+
+        if inside.is_empty() || outside.is_empty() {
+            invalid_parameter!(
+                "splitting by region would leave an empty simulation (`{}` inside, `{}` outside)",
+                inside.len(),
+                outside.len()
+            );
+        }
+
+        Ok((self.to_subset(inside), self.to_subset(outside)))
+    }
+
+    /// Split into one independent simulation per particle tag
+    pub fn split_by_tag(&self) -> BTreeMap<usize, Self> {
+        let mut particles_by_tag: BTreeMap<usize, Vec<_>> = BTreeMap::new();
+        for particle in self.particles.iter() {
+            particles_by_tag
+                .entry(particle.tag)
+                .or_default()
+                .push(particle.clone());
+        }
+
+        particles_by_tag
+            .into_iter()
+            .map(|(tag, particles)| (tag, self.to_subset(particles)))
+            .collect()
+    }
+
+    /// Build a simulation holding only some of this simulation's particles
+    fn to_subset(&self, particles: Vec<Particle>) -> Self {
+        Self::from_parts(
+            Particles::from_reindexed(particles),
+            self.current_time,
+            self.params.clone(),
+            self.domain_schedule.clone(),
+            self.noise_schedule.clone(),
+        )
+    }
+
+    /// Add a particle at a position and heading, returning its ID
+    ///
+    /// # Notes
+    /// The position is wrapped into the domain. The new particle starts with the simulation's
+    /// noise and speed, and no tag.
+    pub fn add_particle(
+        &mut self,
+        x: Float,
+        y: Float,
+        theta: Float,
+    ) -> Result<usize, SimulationError> {
+        if !(x.is_finite() && y.is_finite() && theta.is_finite()) {
+            invalid_parameter!(
+                "particle state must be finite, got `({}, {}, {})`",
+                x,
+                y,
+                theta
+            );
+        }
+
+        let boundary_side_length = self.params.boundary_side_length.0;
+        let stable_id = self.next_stable_id;
+
+        let particle = Particle {
+            stable_id,
+            ..Particle::from_state(
+                x.rem_euclid(boundary_side_length),
+                y.rem_euclid(boundary_side_length),
+                theta,
+            )
+        };
+
+        self.particles = self.particles.to_with_added(particle);
+        self.instantaneous_order = self.particles.compute_instantaneous_order();
+        self.next_stable_id += 1;
+
+        Ok(stable_id)
+    }
+
+    /// Overwrite every particle's position and heading, in particle order, e.g. to impose a custom
+    /// configuration or restore a saved one. Everything else about the particles is kept, apart
+    /// from the headings remembered for reaction delays.
+    ///
+    /// # Notes
+    /// Positions outside the domain are wrapped back into it.
+    pub fn set_state(
+        &mut self,
+        x: &[Float],
+        y: &[Float],
+        theta: &[Float],
+    ) -> Result<(), SimulationError> {
+        let num_particles = self.particles.len();
+        if x.len() != num_particles || y.len() != num_particles || theta.len() != num_particles {
+            invalid_parameter!(
+                "got `{}` x, `{}` y, and `{}` theta values for `{}` particles",
+                x.len(),
+                y.len(),
+                theta.len(),
+                num_particles
+            );
+        }
+
+        if let Some(((x, y), theta)) = x
+            .iter()
+            .zip(y)
+            .zip(theta)
+            .find(|((x, y), theta)| !(x.is_finite() && y.is_finite() && theta.is_finite()))
+        {
+            invalid_parameter!(
+                "particle state must be finite, got `({}, {}, {})`",
+                x,
+                y,
+                theta
+            );
+        }
+
+        self.particles =
+            self.particles
+                .to_with_state(x, y, theta, self.params.boundary_side_length);
+        self.instantaneous_order = self.particles.compute_instantaneous_order();
+
+        Ok(())
+    }
+
+    /// Remove the particle with the given ID. Every other particle keeps its ID.
+    pub fn remove_particle(&mut self, id: usize) -> Result<(), SimulationError> {
+        let Some(idx) = self
+            .particles
+            .iter()
+            .find(|particle| particle.stable_id == id)
+            .map(|particle| particle.id)
+        else {
+            invalid_parameter!("there is no particle with id `{}`", id);
+        };
+
+        if self.particles.len() == 1 {
+            invalid_parameter!("at least one particle must be simulated");
+        }
+
+        self.particles = self.particles.to_removed(idx);
+        self.instantaneous_order = self.particles.compute_instantaneous_order();
+
+        Ok(())
+    }
+
+    /// Make the given particles leaders, which hold an imposed heading while still influencing
+    /// their neighbors
+    pub fn set_leaders(
+        &mut self,
+        ids: &[usize],
+        leader_heading: LeaderHeading,
+    ) -> Result<(), SimulationError> {
+        self.particles = self.particles.to_with_leaders(ids, Some(leader_heading))?;
+
+        Ok(())
+    }
+
+    /// Return the given particles to normal alignment dynamics
+    pub fn clear_leaders(&mut self, ids: &[usize]) -> Result<(), SimulationError> {
+        self.particles = self.particles.to_with_leaders(ids, None)?;
+
+        Ok(())
+    }
+
+    /// Change the noise amplitude, keeping the particles where they are, e.g. to quench an
+    /// ordered flock into disorder
+    ///
+    /// # Notes
+    /// A noise schedule, noise field, or per-particle noise still takes precedence.
+    pub fn with_noise(self, noise: Noise) -> Result<Self, SimulationError> {
+        validate_non_negative("noise", noise.0)?;
+
+        let params = SimulationParameters {
+            noise,
+            ..self.params
+        };
+
+        Ok(Self { params, ..self })
+    }
+
+    /// Change the particle speed, keeping the particles where they are
+    ///
+    /// # Notes
+    /// A speed field or per-particle speed still takes precedence.
+    pub fn with_speed(self, speed: Speed) -> Result<Self, SimulationError> {
+        validate_non_negative("speed", speed.0)?;
+
+        let params = SimulationParameters {
+            speed,
+            ..self.params
+        };
+
+        Ok(Self { params, ..self })
+    }
+
+    /// Change the interaction radius, keeping the particles where they are
+    ///
+    /// # Notes
+    /// Per-particle interaction radii still take precedence.
+    pub fn with_distance_threshold(
+        self,
+        particle_distance_threshold: ParticleDistanceThreshold,
+    ) -> Result<Self, SimulationError> {
+        validate_positive("particle distance threshold", particle_distance_threshold.0)?;
+
+        let params = SimulationParameters {
+            particle_distance_threshold,
+            ..self.params
+        };
+
+        Ok(Self { params, ..self })
+    }
+
+    /// Change the timestep, keeping the particles where they are
+    pub fn with_timestep(self, timestep: RelativeTime) -> Result<Self, SimulationError> {
+        validate_positive("timestep", timestep.0)?;
+
+        let params = SimulationParameters {
+            timestep,
+            ..self.params
+        };
+
+        Ok(Self { params, ..self })
+    }
+
+    /// Limit perception to a vision cone of `half_angle` either side of each particle's heading,
+    /// or restore the full circle with `None`
+    pub fn with_vision_cone(
+        self,
+        vision_half_angle: Option<Angle>,
+    ) -> Result<Self, SimulationError> {
+        if let Some(half_angle) = vision_half_angle
+            && !(half_angle.0 > 0.0 && half_angle.0 <= PI)
+        {
+            invalid_parameter!(
+                "vision cone half angle must be in (0, π], got `{}`",
+                half_angle.0
+            );
+        }
+
+        let params = SimulationParameters {
+            vision_half_angle,
+            ..self.params
+        };
+
+        Ok(Self { params, ..self })
+    }
+
+    /// Weight neighbors by distance when averaging their headings
+    pub fn with_neighbor_weighting(
+        self,
+        neighbor_weighting: NeighborWeighting,
+    ) -> Result<Self, SimulationError> {
+        if let NeighborWeighting::Gaussian { width } = neighbor_weighting
+            && (width.is_nan() || width <= 0.0)
+        {
+            invalid_parameter!(
+                "gaussian neighbor weighting width must be positive, got `{}`",
+                width
+            );
+        }
+
+        let params = SimulationParameters {
+            neighbor_weighting,
+            ..self.params
+        };
+
+        Ok(Self { params, ..self })
+    }
+
+    /// Switch the heading update between the Vicsek rule and alternative models
+    pub fn with_update_rule(self, update_rule: UpdateRule) -> Result<Self, SimulationError> {
+        if let UpdateRule::ActiveBrownian {
+            rotational_diffusion,
+        } = update_rule
+            && (rotational_diffusion.is_nan() || rotational_diffusion < 0.0)
+        {
+            invalid_parameter!(
+                "rotational diffusion coefficient must be non-negative, got `{}`",
+                rotational_diffusion
+            );
+        }
+
+        if let UpdateRule::Couzin(zones) = update_rule {
+            if zones.repulsion_radius.is_nan() || zones.repulsion_radius <= 0.0 {
+                invalid_parameter!(
+                    "Couzin repulsion radius must be positive, got `{}`",
+                    zones.repulsion_radius
+                );
+            }
+
+            if zones.orientation_radius.is_nan()
+                || zones.orientation_radius < zones.repulsion_radius
+                || zones.attraction_radius.is_nan()
+                || zones.attraction_radius < zones.orientation_radius
+            {
+                invalid_parameter!(
+                    "Couzin zones must be nested, got radii `{}`, `{}`, `{}`",
+                    zones.repulsion_radius,
+                    zones.orientation_radius,
+                    zones.attraction_radius
+                );
+            }
+        }
+
+        let params = SimulationParameters {
+            update_rule,
+            ..self.params
+        };
+
+        Ok(Self { params, ..self })
+    }
+
+    /// Give each particle its own noise amplitude, e.g. to model heterogeneous agents
+    pub fn set_particle_noises(&mut self, noises: &[Noise]) -> Result<(), SimulationError> {
+        if noises.len() != self.particles.len() {
+            invalid_parameter!(
+                "got `{}` noise amplitudes for `{}` particles",
+                noises.len(),
+                self.particles.len()
+            );
+        }
+
+        if let Some(noise) = noises
+            .iter()
+            .find(|noise| noise.0.is_nan() || noise.0 < 0.0)
+        {
+            invalid_parameter!("noise amplitudes must be non-negative, got `{}`", noise.0);
+        }
+
+        self.particles = self.particles.to_with_noises(Some(noises));
+
+        Ok(())
+    }
+
+    /// Give each particle its own reaction delay, in steps, so it aligns with its neighbors'
+    /// headings from that many steps ago
+    ///
+    /// # Notes
+    /// Neighbors are still found by their current positions. Until a run has gone on for a
+    /// particle's delay, it reacts to the oldest headings remembered.
+    pub fn set_reaction_delays(&mut self, delays: &[usize]) -> Result<(), SimulationError> {
+        if delays.len() != self.particles.len() {
+            invalid_parameter!(
+                "got `{}` reaction delays for `{}` particles",
+                delays.len(),
+                self.particles.len()
+            );
+        }
+
+        self.particles = self.particles.to_with_reaction_delays(Some(delays));
+
+        Ok(())
+    }
+
+    /// Make every particle react to its neighbors' current headings again
+    pub fn clear_reaction_delays(&mut self) {
+        self.particles = self.particles.to_with_reaction_delays(None);
+    }
+
+    /// Give each particle its own noise amplitude drawn from a distribution, in particle order
+    pub fn set_particle_noise_distribution(
+        &mut self,
+        mut sample: impl FnMut() -> Noise,
+    ) -> Result<(), SimulationError> {
+        let noises: Vec<_> = (0..self.particles.len()).map(|_| sample()).collect();
+
+        self.set_particle_noises(&noises)
+    }
+
+    /// Revert every particle to the simulation's noise amplitude
+    pub fn clear_particle_noises(&mut self) {
+        self.particles = self.particles.to_with_noises(None);
+    }
+
+    /// Make a random `fraction` of the particles dissenters, which turn away from their
+    /// neighbors' average heading instead of aligning with it. Any previous dissenters are reset.
+    ///
+    /// # Notes
+    /// Exactly `round(fraction * N)` particles dissent. This only affects the Vicsek update rule.
+    pub fn set_dissenter_fraction(&mut self, fraction: Float) -> Result<(), SimulationError> {
+        if !(0.0..=1.0).contains(&fraction) {
+            invalid_parameter!(
+                "dissenter fraction must be between 0 and 1, got `{}`",
+                fraction
+            );
+        }
+
+        let num_particles = self.particles.len();
+        let num_dissenters = (fraction * num_particles as Float).round() as usize;
+
+        // Partially shuffle the IDs, so the first few are a uniform random pick
+        let mut ids: Vec<usize> = (0..num_particles).collect();
+        for idx in 0..num_dissenters.min(num_particles.saturating_sub(1)) {
+            ids.swap(idx, random::random_range(idx..num_particles));
+        }
+
+        let dissenters = ids.into_iter().take(num_dissenters).collect();
+        self.particles = self.particles.to_with_dissenters(&dissenters);
+
+        Ok(())
+    }
+
+    /// Give each particle its own speed, drawn once from a distribution
+    pub fn with_speed_distribution(
+        self,
+        speed_distribution: SpeedDistribution,
+    ) -> Result<Self, SimulationError> {
+        speed_distribution.validate()?;
+
+        let particles = self.particles.to_with_speeds(speed_distribution);
+
+        Ok(Self { particles, ..self })
+    }
+
+    /// Let the noise amplitude vary over the domain, or go back to the uniform noise with `None`
+    ///
+    /// # Notes
+    /// Particles given their own noise amplitude keep it regardless of the field. Fails if the
+    /// field holds negative or non-finite values (see [`ScalarField`] for how function fields are
+    /// checked).
+    pub fn with_noise_field(
+        self,
+        noise_field: Option<ScalarField>,
+    ) -> Result<Self, SimulationError> {
+        if let Some(noise_field) = &noise_field {
+            noise_field.validate(self.params.boundary_side_length)?;
+        }
+
+        let params = SimulationParameters {
+            noise_field,
+            ..self.params
+        };
+
+        Ok(Self { params, ..self })
+    }
+
+    /// Let the speed vary over the domain, e.g. slowing particles down in rough terrain, or go
+    /// back to the uniform speed with `None`
+    ///
+    /// # Notes
+    /// Particles given their own speed keep it regardless of the field. Fails if the field holds
+    /// negative or non-finite values (see [`ScalarField`] for how function fields are checked).
+    pub fn with_speed_field(
+        self,
+        speed_field: Option<ScalarField>,
+    ) -> Result<Self, SimulationError> {
+        if let Some(speed_field) = &speed_field {
+            speed_field.validate(self.params.boundary_side_length)?;
+        }
+
+        let params = SimulationParameters {
+            speed_field,
+            ..self.params
+        };
+
+        Ok(Self { params, ..self })
+    }
+
+    /// Carry particles along with a background flow, or remove it with `None`
+    pub fn with_flow_field(self, flow_field: Option<FlowField>) -> Self {
+        let params = SimulationParameters {
+            flow_field,
+            ..self.params
+        };
+
+        Self { params, ..self }
+    }
+
+    /// Switch between vectorial (the default) and scalar angular noise
+    pub fn with_noise_model(self, noise_model: NoiseModel) -> Self {
+        let params = SimulationParameters {
+            noise_model,
+            ..self.params
+        };
+
+        Self { params, ..self }
+    }
+
+    /// Hide neighbors behind closer ones: a neighbor is ignored if a closer neighbor lies within
+    /// `tolerance` of the line of sight to it. Disable with `None`.
+    pub fn with_occlusion(self, tolerance: Option<Float>) -> Result<Self, SimulationError> {
+        if let Some(tolerance) = tolerance
+            && (tolerance.is_nan() || tolerance <= 0.0)
+        {
+            invalid_parameter!("occlusion tolerance must be positive, got `{}`", tolerance);
+        }
+
+        let params = SimulationParameters {
+            occlusion_tolerance: tolerance,
+            ..self.params
+        };
+
+        Ok(Self { params, ..self })
+    }
+
+    /// Jitter positions each step with translational noise of diffusion coefficient D_t, on top of
+    /// the angular noise. Disable with `None`.
+    ///
+    /// # Notes
+    /// Each coordinate moves by an extra √(2 D_t Δt) ξ per step, with ξ standard normal. These
+    /// draws are fresh every step, so noise replay only reproduces runs without them.
+    pub fn with_translational_noise(
+        self,
+        translational_diffusion: Option<Float>,
+    ) -> Result<Self, SimulationError> {
+        if let Some(translational_diffusion) = translational_diffusion
+            && (translational_diffusion.is_nan() || translational_diffusion < 0.0)
+        {
+            invalid_parameter!(
+                "translational diffusion must be non-negative, got `{}`",
+                translational_diffusion
+            );
+        }
+
+        let params = SimulationParameters {
+            translational_diffusion,
+            ..self.params
+        };
+
+        Ok(Self { params, ..self })
+    }
+
+    /// Switch between synchronous updates (the default) and random-sequential ones
+    pub fn with_update_order(self, update_order: UpdateOrder) -> Self {
+        let params = SimulationParameters {
+            update_order,
+            ..self.params
+        };
+
+        Self { params, ..self }
+    }
+
+    /// Give headings inertia, so they turn towards their target over a relaxation time instead of
+    /// snapping to it each step. Disable with `None`.
+    pub fn with_heading_relaxation(
+        self,
+        heading_relaxation_time: Option<RelativeTime>,
+    ) -> Result<Self, SimulationError> {
+        if let Some(relaxation_time) = heading_relaxation_time
+            && (relaxation_time.0.is_nan() || relaxation_time.0 <= 0.0)
+        {
+            invalid_parameter!(
+                "heading relaxation time must be positive, got `{}`",
+                relaxation_time.0
+            );
+        }
+
+        let params = SimulationParameters {
+            heading_relaxation_time,
+            ..self.params
+        };
+
+        Ok(Self { params, ..self })
+    }
+
+    /// Push apart particles closer than a repulsion radius, or disable repulsion with `None`
+    pub fn with_repulsion(self, repulsion: Option<Repulsion>) -> Result<Self, SimulationError> {
+        if let Some(repulsion) = repulsion {
+            if repulsion.radius.is_nan() || repulsion.radius <= 0.0 {
+                invalid_parameter!(
+                    "repulsion radius must be positive, got `{}`",
+                    repulsion.radius
+                );
+            }
+
+            if repulsion.strength.is_nan() || repulsion.strength < 0.0 {
+                invalid_parameter!(
+                    "repulsion strength must be non-negative, got `{}`",
+                    repulsion.strength
+                );
+            }
+        }
+
+        let params = SimulationParameters {
+            repulsion,
+            ..self.params
+        };
+
+        Ok(Self { params, ..self })
+    }
+
+    /// Let particles randomly give birth and die, or go back to a fixed population with `None`
+    ///
+    /// # Notes
+    /// Noise recordings of a fluctuating population don't line up particle-for-particle, so they
+    /// can't be replayed exactly.
+    pub fn with_birth_death(
+        self,
+        birth_death: Option<BirthDeath>,
+    ) -> Result<Self, SimulationError> {
+        if let Some(birth_death) = birth_death {
+            for (name, value) in [
+                ("birth rate", birth_death.birth_rate),
+                ("death rate", birth_death.death_rate),
+                ("spawn radius", birth_death.spawn_radius),
+            ] {
+                if value.is_nan() || value < 0.0 {
+                    invalid_parameter!("{} must be non-negative, got `{}`", name, value);
+                }
+            }
+        }
+
+        let params = SimulationParameters {
+            birth_death,
+            ..self.params
+        };
+
+        Ok(Self { params, ..self })
+    }
+
+    /// Give every particle an energy budget that depletes as it moves and recovers as it rests,
+    /// slowing tired particles down, or remove it with `None`. Every particle starts rested.
+    pub fn with_energy_budget(
+        self,
+        energy_budget: Option<EnergyBudget>,
+    ) -> Result<Self, SimulationError> {
+        if let Some(energy_budget) = energy_budget {
+            if energy_budget.capacity.is_nan() || energy_budget.capacity <= 0.0 {
+                invalid_parameter!(
+                    "energy capacity must be positive, got `{}`",
+                    energy_budget.capacity
+                );
+            }
+
+            for (name, value) in [
+                ("depletion rate", energy_budget.depletion_rate),
+                ("recovery rate", energy_budget.recovery_rate),
+                ("crowd recovery rate", energy_budget.crowd_recovery_rate),
+            ] {
+                if value.is_nan() || value < 0.0 {
+                    invalid_parameter!("{} must be non-negative, got `{}`", name, value);
+                }
+            }
+        }
+
+        let particles = self.particles.to_rested();
+        let params = SimulationParameters {
+            energy_budget,
+            ..self.params
+        };
+
+        Ok(Self {
+            particles,
+            params,
+            ..self
+        })
+    }
+
+    /// Each particle's remaining energy, in particle order, if there's an energy budget
+    pub fn energies(&self) -> Option<Vec<Float>> {
+        let energy_budget = self.params.energy_budget?;
+
+        Some(
+            self.particles
+                .iter()
+                .map(|particle| energy_budget.capacity - particle.energy_spent)
+                .collect(),
+        )
+    }
+
+    /// Number of particles currently simulated
+    pub fn num_particles(&self) -> usize {
+        self.particles.len()
+    }
+
+    /// Simulated time elapsed so far
+    pub fn current_time(&self) -> AbsoluteTime {
+        self.current_time
+    }
+
+    /// Start recording every noise draw from here on, for later replay
+    pub fn with_noise_recording(self) -> Self {
+        let mut recording = NoiseStream::default();
+        recording.push(self.particles.phases());
+
+        Self {
+            noise_source: NoiseSource::Recording(Arc::new(Mutex::new(recording))),
+            ..self
+        }
+    }
+
+    /// Get the noise recorded so far, if recording
+    pub fn noise_recording(&self) -> Option<NoiseStream> {
+        match &self.noise_source {
+            NoiseSource::Recording(recording) => Some(
+                recording
+                    .lock()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner())
+                    .clone(),
+            ),
+            _ => None,
+        }
+    }
+
+    /// Replay recorded noise instead of drawing fresh noise. The particles take on the recorded
+    /// starting phases, and once the recording runs out fresh random draws resume.
+    pub fn with_noise_replay(self, stream: NoiseStream) -> Result<Self, SimulationError> {
+        if stream.num_particles() != self.particles.len() {
+            invalid_parameter!(
+                "noise stream was recorded for `{}` particles but the simulation has `{}`",
+                stream.num_particles(),
+                self.particles.len()
+            );
+        }
+
+        let particles = match stream.draws(0) {
+            Some(phases) => self.particles.to_with_phases(phases),
+            None => self.particles,
+        };
+
+        Ok(Self {
+            particles,
+            noise_source: NoiseSource::Replay {
+                stream: Arc::new(stream),
+                step: 0,
+            },
+            ..self
+        })
+    }
+
+    /// Attach a schedule that resizes the domain as the simulation advances
+    pub fn with_domain_schedule(self, domain_schedule: DomainResizeSchedule) -> Self {
+        Self {
+            domain_schedule: Some(domain_schedule),
+            ..self
         }
+    }
+
+    /// Vary the noise over time, or go back to the fixed noise parameter with `None`
+    ///
+    /// # Notes
+    /// Each step uses the noise scheduled at the time the step starts.
+    pub fn with_noise_schedule(self, noise_schedule: Option<NoiseSchedule>) -> Self {
+        Self {
+            noise_schedule,
+            ..self
+        }
+    }
 
-        let particles = Particles::new(num_particles, boundary_side_length);
+    /// Resize the domain immediately, optionally stretching particle positions along with it
+    pub fn to_resized(
+        &self,
+        boundary_side_length: DomainBoundaryLength,
+        rescale_positions: bool,
+    ) -> Result<Self, SimulationError> {
+        if boundary_side_length.0.is_nan() || boundary_side_length.0 <= 0.0 {
+            invalid_parameter!("domain boundary side length must be positive");
+        }
 
-        let instantaneous_order = particles.compute_instantaneous_order();
+        let particles = self.particles.to_resized(
+            self.params.boundary_side_length,
+            boundary_side_length,
+            rescale_positions,
+        );
 
         let params = SimulationParameters {
             boundary_side_length,
-            noise,
-            speed,
-            timestep,
-            particle_distance_threshold,
+            ..self.params.clone()
         };
 
-        let current_time = AbsoluteTime(0.0);
-
         Ok(Self {
             particles,
-            instantaneous_order,
-            current_time,
+            instantaneous_order: self.instantaneous_order,
+            current_time: self.current_time,
             params,
+            domain_schedule: self.domain_schedule.clone(),
+            noise_schedule: self.noise_schedule.clone(),
+            performance_counters: self.performance_counters,
+            noise_source: self.noise_source.clone(),
+            next_stable_id: self.next_stable_id,
+            observers: self.observers.clone(),
+            order_history: self.order_history.clone(),
+            watchdog: self.watchdog.clone(),
         })
     }
 
     /// Update the simulation to new timestep
     pub fn to_timestepped(&self) -> Self {
+        let _span = tracing::debug_span!("step", time = self.current_time.0).entered();
+        let step_start = Instant::now();
+        let mut step_counters = PerformanceCounters {
+            steps: 1,
+            ..Default::default()
+        };
+
+        // Take the noise from the schedule as of the start of the step
+        let step_params = match &self.noise_schedule {
+            Some(noise_schedule) => SimulationParameters {
+                noise: noise_schedule.evaluate(self.current_time),
+                ..self.params.clone()
+            },
+            None => self.params.clone(),
+        };
+
+        let current_time = self.current_time + self.params.timestep;
+
         let particles = self.particles.to_timestepped(
-            self.params.particle_distance_threshold,
-            self.params.speed,
-            self.params.noise,
-            self.params.timestep,
-            self.params.boundary_side_length,
+            &step_params,
+            current_time,
+            &mut step_counters,
+            self.noise_source.next_draws(),
         );
 
-        let instantaneous_order = particles.compute_instantaneous_order();
+        let noise_source = self.noise_source.to_advanced(|| particles.phases());
 
-        let current_time = self.current_time + self.params.timestep;
+        // Births and deaths happen once everyone has moved
+        let mut next_stable_id = self.next_stable_id;
+        let particles = match step_params.birth_death {
+            Some(birth_death) => particles.to_with_births_and_deaths(
+                birth_death,
+                step_params.timestep,
+                step_params.boundary_side_length,
+                &mut next_stable_id,
+            ),
+            None => particles,
+        };
 
-        Self {
+        let instantaneous_order = timed(&mut step_counters.observables, || {
+            particles.compute_instantaneous_order()
+        });
+
+        // Apply any scheduled domain resize for the new time. The scheduled noise only drove this
+        // step, so the stored parameters keep the configured one.
+        let (particles, params) = match &self.domain_schedule {
+            Some(domain_schedule) => {
+                let boundary_side_length = domain_schedule.lengths.evaluate(current_time);
+                let particles = particles.to_resized(
+                    self.params.boundary_side_length,
+                    boundary_side_length,
+                    domain_schedule.rescale_positions,
+                );
+                let params = SimulationParameters {
+                    boundary_side_length,
+                    ..self.params.clone()
+                };
+                (particles, params)
+            }
+            None => (particles, self.params.clone()),
+        };
+
+        step_counters.total = step_start.elapsed();
+
+        let sim = Self {
             particles,
             instantaneous_order,
             current_time,
-            params: self.params,
+            params,
+            domain_schedule: self.domain_schedule.clone(),
+            noise_schedule: self.noise_schedule.clone(),
+            performance_counters: self.performance_counters + step_counters,
+            noise_source,
+            next_stable_id,
+            observers: self.observers.clone(),
+            order_history: self.order_history.clone(),
+            watchdog: self.watchdog.clone(),
+        };
+        sim.record_order_history();
+
+        sim
+    }
+
+    /// Step `num_steps` times in place
+    ///
+    /// # Notes
+    /// Only fails if a watchdog is set and catches a bad step, leaving the simulation at the state
+    /// before it.
+    pub fn run_for(&mut self, num_steps: usize) -> Result<(), SimulationError> {
+        for _ in 0..num_steps {
+            self.step_in_place()?;
+        }
+
+        Ok(())
+    }
+
+    /// Lazily step from the current state, yielding the simulation after each step, which is left
+    /// untouched itself
+    ///
+    /// # Notes
+    /// The iterator never ends by itself, so bound it with e.g. `take`. Each item is a full
+    /// simulation; use [`SimulationData::from`] for the plain per-particle arrays.
+    pub fn iter_steps(&self) -> Steps {
+        Steps { sim: self.clone() }
+    }
+
+    /// Step in place until the simulated time reaches `time`, returning the number of steps taken
+    ///
+    /// # Notes
+    /// The run ends on the step closest to `time`, so summing up timesteps can't leave it one
+    /// step short or long. A time already passed takes no steps.
+    pub fn run_until(&mut self, time: AbsoluteTime) -> Result<usize, SimulationError> {
+        if !time.0.is_finite() {
+            invalid_parameter!("time to run until must be finite, got `{}`", time.0);
+        }
+
+        let mut num_steps = 0;
+
+        while self.current_time.0 + 0.5 * self.params.timestep.0 < time.0 {
+            self.step_in_place()?;
+            num_steps += 1;
         }
+
+        Ok(num_steps)
+    }
+
+    /// Get the wall-clock time spent in each phase of stepping so far
+    pub fn performance_counters(&self) -> PerformanceCounters {
+        self.performance_counters
+    }
+
+    /// Summarize stepping performance since the counters were last reset: throughput, the mean
+    /// number of neighbors per particle, and the average time per step in each phase
+    pub fn stats(&self) -> SimulationStats {
+        self.performance_counters.stats()
+    }
+
+    /// Zero the performance counters, e.g. to exclude a warm-up period
+    pub fn reset_performance_counters(&mut self) {
+        self.performance_counters = PerformanceCounters::default();
     }
 
     /// Compute the stationary order parameter, which is the temporal average of the particle
     /// system polarization
-    pub fn compute_stationary_order_parameter(&self) -> anyhow::Result<Float> {
+    pub fn compute_stationary_order_parameter(&self) -> Result<Float, SimulationError> {
+        let estimate = self.compute_stationary_order_estimate(&StationaryOrderOptions::default())?;
+
+        if !estimate.is_converged() {
+            return Err(SimulationError::ConvergenceFailure {
+                quantity: "stationary order parameter",
+                iterations: estimate.iterations,
+                value: estimate.value,
+                residual: estimate.residual,
+            });
+        }
+
+        Ok(estimate.value)
+    }
+
+    /// Compute the stationary order parameter, stopping early with a best-effort estimate if
+    /// cancelled or out of budget
+    pub fn compute_stationary_order_estimate(
+        &self,
+        options: &StationaryOrderOptions,
+    ) -> Result<StationaryOrderEstimate, SimulationError> {
+        let _span = tracing::debug_span!("stationary_order").entered();
+        let start = Instant::now();
+        let max_steps = options
+            .max_steps
+            .unwrap_or(MAX_STATIONARY_ORDER_PARAM_ITERATIONS);
+
+        if max_steps == 0 {
+            invalid_parameter!(
+                "the step budget for the stationary order parameter must be at least one"
+            );
+        }
+
         // Get an initial simulation
-        let mut sim = self.to_timestepped();
+        let mut sim = self.clone();
+        sim.step_in_place()?;
 
         // This will store a sliding window of our instantaneous orders
         let mut instantaneous_order_window =
             VecDeque::with_capacity(STATIONARY_ORDER_PARAM_AVG_WINDOWSIZE);
 
-        for _ in 0..MAX_STATIONARY_ORDER_PARAM_ITERATIONS {
-            sim = sim.to_timestepped();
+        let mut iteration = 0;
+        loop {
+            sim.step_in_place()?;
+            iteration += 1;
 
             // Keep track of the values over time for a sliding average
             if instantaneous_order_window.len() >= STATIONARY_ORDER_PARAM_AVG_WINDOWSIZE {
@@ -117,21 +1390,94 @@ impl Simulation {
             // Sliding average of the parameter over time
             // TODO: could consider a running sum to optimize (reduce evaluations)
             let stationary_order_parameter = instantaneous_order_window.iter().sum::<Float>()
-                / instantaneous_order_window.len() as f64;
+                / instantaneous_order_window.len() as Float;
+
+            let residual = (sim.instantaneous_order.0 - stationary_order_parameter).abs();
 
             // Convergence criteria- also ensure window is full
-            if instantaneous_order_window.len() == STATIONARY_ORDER_PARAM_AVG_WINDOWSIZE
-                && (sim.instantaneous_order.0 - stationary_order_parameter).abs()
-                    <= STATIONARY_ORDER_EPSILON
+            let stop_reason = if instantaneous_order_window.len()
+                == STATIONARY_ORDER_PARAM_AVG_WINDOWSIZE
+                && residual <= STATIONARY_ORDER_EPSILON
+            {
+                Some(StopReason::Converged)
+            } else if options
+                .cancellation
+                .as_ref()
+                .is_some_and(CancellationToken::is_cancelled)
+            {
+                Some(StopReason::Cancelled)
+            } else if iteration >= max_steps {
+                Some(StopReason::StepBudgetExhausted)
+            } else if options
+                .time_budget
+                .is_some_and(|time_budget| start.elapsed() >= time_budget)
             {
-                return Ok(stationary_order_parameter);
+                Some(StopReason::TimeBudgetExhausted)
+            } else {
+                None
+            };
+
+            // Hand back what we have so far, converged or not
+            if let Some(stop_reason) = stop_reason {
+                tracing::debug!(
+                    value = stationary_order_parameter,
+                    iterations = iteration,
+                    ?stop_reason,
+                    "stationary order parameter estimated"
+                );
+
+                return Ok(StationaryOrderEstimate {
+                    value: stationary_order_parameter,
+                    iterations: iteration,
+                    stop_reason,
+                    residual,
+                    window_len: instantaneous_order_window.len(),
+                    elapsed: start.elapsed(),
+                });
             }
         }
+    }
+}
+
+/// Controls for a stationary order parameter computation
+#[derive(Clone, Debug, Default)]
+pub struct StationaryOrderOptions {
+    /// Checked every step; when cancelled the current sliding average is returned
+    pub cancellation: Option<CancellationToken>,
+
+    /// Maximum number of steps to take before giving up on convergence. Defaults to a fixed cap.
+    pub max_steps: Option<usize>,
+
+    /// Maximum wall-clock time to spend before giving up on convergence
+    pub time_budget: Option<Duration>,
+}
+
+/// The result of a stationary order parameter computation
+#[derive(Copy, Clone, Debug)]
+pub struct StationaryOrderEstimate {
+    /// The sliding average of the instantaneous order when the computation stopped
+    pub value: Float,
+
+    /// Number of timesteps taken (after the initial one)
+    pub iterations: usize,
+
+    pub stop_reason: StopReason,
+
+    /// How far the last instantaneous order was from the sliding average. Convergence requires
+    /// this to be small with a full window.
+    pub residual: Float,
 
-        Err(anyhow!(
-            "max iterations (`{}`) reached for stationary order parameter",
-            MAX_STATIONARY_ORDER_PARAM_ITERATIONS
-        ))
+    /// How many samples the sliding average was taken over
+    pub window_len: usize,
+
+    /// Wall-clock time spent
+    pub elapsed: Duration,
+}
+
+impl StationaryOrderEstimate {
+    /// Whether the convergence criteria were actually met
+    pub fn is_converged(&self) -> bool {
+        self.stop_reason == StopReason::Converged
     }
 }
 
@@ -147,8 +1493,26 @@ impl Display for Simulation {
     }
 }
 
+/// An endless iterator over successive states of a simulation, from [`Simulation::iter_steps`]
+#[derive(Clone)]
+pub struct Steps {
+    sim: Simulation,
+}
+
+impl Iterator for Steps {
+    type Item = Simulation;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.sim = self.sim.to_timestepped();
+        Some(self.sim.clone())
+    }
+}
+
 /// Storage API for simulation data
 pub struct SimulationData {
+    /// ID of each particle, which stays the same as particles are added and removed
+    pub id: Vec<usize>,
+
     /// x-position for each particle
     pub x: Vec<Float>,
 
@@ -160,11 +1524,41 @@ pub struct SimulationData {
 
     /// Particle direction in y
     pub v: Vec<Float>,
+
+    /// Heading of each particle in radians
+    pub theta: Vec<Float>,
+
+    /// Noise phase ξ each particle drew on its last step, uniform in [-π, π)
+    pub phase: Vec<Float>,
+
+    /// User label for each particle
+    pub tag: Vec<usize>,
+
+    /// Whether each particle is a leader
+    pub leader: Vec<bool>,
+
+    /// Speed of each particle
+    pub speed: Vec<Float>,
+
+    /// Each user-defined scalar by name, with a value for every particle
+    pub scalars: BTreeMap<String, Vec<Float>>,
+
+    /// Simulated time of the snapshot
+    pub time: Float,
+
+    /// Polarization of the whole simulation, i.e. the magnitude of the mean heading
+    pub instantaneous_order: Float,
 }
 
 impl From<&Simulation> for SimulationData {
     /// Generate from a Simulation
     fn from(sim: &Simulation) -> Self {
+        let id = sim
+            .particles
+            .iter()
+            .map(|particle| particle.stable_id)
+            .collect();
+
         let x = sim
             .particles
             .iter()
@@ -189,6 +1583,642 @@ impl From<&Simulation> for SimulationData {
             .map(|particle| particle.theta.sin())
             .collect();
 
-        Self { x, y, u, v }
+        let theta = sim
+            .particles
+            .iter()
+            .map(|particle| particle.theta)
+            .collect();
+
+        let phase = sim
+            .particles
+            .iter()
+            .map(|particle| particle.phase)
+            .collect();
+
+        let tag = sim.particles.iter().map(|particle| particle.tag).collect();
+
+        let leader = sim
+            .particles
+            .iter()
+            .map(|particle| particle.leader.is_some())
+            .collect();
+
+        let speed = sim
+            .particles
+            .iter()
+            .map(|particle| particle.speed(&sim.params).0)
+            .collect();
+
+        let scalars = sim
+            .scalar_names()
+            .into_iter()
+            .map(|name| {
+                let values = sim
+                    .particles
+                    .iter()
+                    .map(|particle| particle.scalars.get(&name))
+                    .collect();
+                (name, values)
+            })
+            .collect();
+
+        Self {
+            id,
+            x,
+            y,
+            u,
+            v,
+            theta,
+            phase,
+            tag,
+            leader,
+            speed,
+            scalars,
+            time: sim.current_time.0,
+            instantaneous_order: sim.instantaneous_order.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::{
+        random::seed_rng,
+        schedule::{DomainResizeSchedule, Schedule},
+    };
+
+    #[test]
+    fn added_particles_get_unused_stable_ids_after_tagging() {
+        let mut sim = Simulation::new(
+            5,
+            DomainBoundaryLength(5.0),
+            Noise(0.1),
+            Speed(0.03),
+            RelativeTime(1.0),
+            ParticleDistanceThreshold(1.0),
+        )
+        .unwrap();
+
+        sim.remove_particle(0).unwrap();
+        let mut sim = sim.to_tagged(1);
+        let id = sim.add_particle(1.0, 1.0, 0.0).unwrap();
+
+        let stable_ids: HashSet<_> = sim.particles.iter().map(|p| p.stable_id).collect();
+        assert_eq!(id, 5);
+        assert_eq!(stable_ids.len(), sim.num_particles());
+    }
+
+    #[test]
+    fn clearing_noise_schedule_goes_back_to_fixed_noise() {
+        let mut sim = Simulation::with_particles(
+            &[(1.0, 1.0), (1.2, 1.0)],
+            &[0.3, 1.1],
+            DomainBoundaryLength(5.0),
+            Noise(0.0),
+            Speed(0.01),
+            RelativeTime(1.0),
+            ParticleDistanceThreshold(1.0),
+        )
+        .unwrap()
+        .with_noise_schedule(Some(
+            NoiseSchedule::piecewise(Schedule::new(vec![(AbsoluteTime(0.0), Noise(3.0))]).unwrap())
+                .unwrap(),
+        ));
+
+        sim.run_for(3).unwrap();
+        assert_eq!(sim.params.noise.0, 0.0);
+
+        let mut sim = sim.with_noise_schedule(None);
+        let thetas: Vec<Float> = sim.particles.iter().map(|p| p.theta).collect();
+        sim.run_for(1).unwrap();
+
+        // Without noise, each of the two particles takes the other's heading
+        let angle_between = |a: Float, b: Float| (a - b).sin().abs() + (1.0 - (a - b).cos());
+        let next_thetas: Vec<Float> = sim.particles.iter().map(|p| p.theta).collect();
+        assert!(angle_between(next_thetas[0], thetas[1]) < 1e-5);
+        assert!(angle_between(next_thetas[1], thetas[0]) < 1e-5);
+    }
+
+    /// Particles that stay put, to check bookkeeping without motion getting in the way
+    fn still_particles(positions: &[(Float, Float)]) -> Simulation {
+        Simulation::with_particles(
+            positions,
+            &vec![0.0; positions.len()],
+            DomainBoundaryLength(5.0),
+            Noise(0.0),
+            Speed(0.0),
+            RelativeTime(1.0),
+            ParticleDistanceThreshold(1.0),
+        )
+        .unwrap()
+    }
+
+    fn positions(sim: &Simulation) -> Vec<(Float, Float)> {
+        sim.particles.iter().map(|p| (p.pos_x, p.pos_y)).collect()
+    }
+
+    #[test]
+    fn domain_schedule_stretches_positions_with_the_domain() {
+        let lengths = Schedule::new(vec![
+            (AbsoluteTime(0.0), DomainBoundaryLength(5.0)),
+            (AbsoluteTime(1.0), DomainBoundaryLength(10.0)),
+        ])
+        .unwrap();
+        let mut sim = still_particles(&[(1.0, 1.0), (4.0, 2.0)])
+            .with_domain_schedule(DomainResizeSchedule::new(lengths, true).unwrap());
+
+        sim.run_for(1).unwrap();
+
+        assert_eq!(sim.params.boundary_side_length.0, 10.0);
+        assert_eq!(positions(&sim), [(2.0, 2.0), (8.0, 4.0)]);
+    }
+
+    #[test]
+    fn shrinking_without_rescaling_wraps_positions() {
+        let sim = still_particles(&[(1.0, 1.0), (4.0, 2.0)])
+            .to_resized(DomainBoundaryLength(3.0), false)
+            .unwrap();
+
+        assert_eq!(positions(&sim), [(1.0, 1.0), (1.0, 2.0)]);
+        assert!(
+            still_particles(&[(1.0, 1.0)])
+                .to_resized(DomainBoundaryLength(0.0), false)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn merging_and_splitting_keep_every_particle() {
+        let left = still_particles(&[(1.0, 1.0), (1.5, 4.0)]);
+        let right = still_particles(&[(3.5, 1.0)]).to_tagged(1);
+
+        let merged = left.to_merged(&right);
+        assert_eq!(merged.num_particles(), 3);
+
+        let region = Region {
+            x_min: 0.0,
+            x_max: 2.5,
+            y_min: 0.0,
+            y_max: 5.0,
+        };
+        let (inside, outside) = merged.split_by_region(&region).unwrap();
+        assert_eq!((inside.num_particles(), outside.num_particles()), (2, 1));
+
+        let by_tag = merged.split_by_tag();
+        assert_eq!(by_tag.keys().copied().collect::<Vec<_>>(), [0, 1]);
+        assert_eq!(by_tag[&1].num_particles(), 1);
+
+        let everything = Region {
+            x_min: 0.0,
+            x_max: 5.0,
+            y_min: 0.0,
+            y_max: 5.0,
+        };
+        assert!(merged.split_by_region(&everything).is_err());
+    }
+
+    #[test]
+    fn cancelled_stationary_order_returns_a_partial_estimate() {
+        let sim = still_particles(&[(1.0, 1.0), (2.0, 2.0)]);
+        let cancellation = CancellationToken::new();
+        cancellation.cancel();
+
+        let estimate = sim
+            .compute_stationary_order_estimate(&StationaryOrderOptions {
+                cancellation: Some(cancellation),
+                ..Default::default()
+            })
+            .unwrap();
+
+        assert_eq!(estimate.stop_reason, StopReason::Cancelled);
+        assert!(!estimate.is_converged());
+    }
+
+    #[test]
+    fn stationary_order_stops_at_its_budgets() {
+        let sim = Simulation::new(
+            20,
+            DomainBoundaryLength(5.0),
+            Noise(3.0),
+            Speed(0.1),
+            RelativeTime(1.0),
+            ParticleDistanceThreshold(1.0),
+        )
+        .unwrap();
+
+        let estimate = sim
+            .compute_stationary_order_estimate(&StationaryOrderOptions {
+                max_steps: Some(5),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(estimate.stop_reason, StopReason::StepBudgetExhausted);
+        assert_eq!(estimate.iterations, 5);
+
+        let estimate = sim
+            .compute_stationary_order_estimate(&StationaryOrderOptions {
+                time_budget: Some(Duration::ZERO),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(estimate.stop_reason, StopReason::TimeBudgetExhausted);
+    }
+
+    /// Two particles close enough to see each other, with different headings
+    fn facing_pair(noise: Noise) -> Simulation {
+        Simulation::with_particles(
+            &[(1.0, 1.0), (1.2, 1.0)],
+            &[0.3, 1.1],
+            DomainBoundaryLength(5.0),
+            noise,
+            Speed(0.01),
+            RelativeTime(1.0),
+            ParticleDistanceThreshold(1.0),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn particle_noise_overrides_the_simulation_noise() {
+        let mut sim = facing_pair(Noise(3.0));
+        sim.set_particle_noises(&[Noise(0.0), Noise(0.0)]).unwrap();
+        sim.run_for(1).unwrap();
+
+        // Each particle takes the other's heading exactly, despite the global noise
+        let thetas: Vec<Float> = sim.particles.iter().map(|p| p.theta).collect();
+        assert!((thetas[0] - 1.1).abs() < 1e-5 && (thetas[1] - 0.3).abs() < 1e-5);
+
+        sim.clear_particle_noises();
+        assert!(sim.particles.iter().all(|p| p.noise.is_none()));
+
+        for noises in [vec![Noise(0.0)], vec![Noise(0.1), Noise(-0.1)]] {
+            assert!(matches!(
+                sim.set_particle_noises(&noises),
+                Err(SimulationError::InvalidParameter(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn dissenters_turn_away_from_their_neighbors() {
+        let mut sim = facing_pair(Noise(0.0));
+        sim.set_dissenter_fraction(0.5).unwrap();
+        assert_eq!(sim.particles.iter().filter(|p| p.dissenter).count(), 1);
+
+        sim.set_dissenter_fraction(1.0).unwrap();
+        sim.run_for(1).unwrap();
+
+        // Each particle heads opposite to the other's heading
+        let angle_between = |a: Float, b: Float| (a - b).sin().abs() + (1.0 - (a - b).cos());
+        let thetas: Vec<Float> = sim.particles.iter().map(|p| p.theta).collect();
+        assert!(angle_between(thetas[0], 1.1 + PI) < 1e-5);
+        assert!(angle_between(thetas[1], 0.3 + PI) < 1e-5);
+
+        assert!(matches!(
+            sim.set_dissenter_fraction(1.5),
+            Err(SimulationError::InvalidParameter(_))
+        ));
+    }
+
+    #[test]
+    fn births_double_the_population_and_deaths_never_wipe_it_out() {
+        let mut sim = facing_pair(Noise(0.1))
+            .with_birth_death(Some(BirthDeath {
+                birth_rate: 1e3,
+                death_rate: 0.0,
+                spawn_radius: 0.1,
+            }))
+            .unwrap();
+        sim.run_for(2).unwrap();
+
+        let stable_ids: HashSet<_> = sim.particles.iter().map(|p| p.stable_id).collect();
+        assert_eq!(sim.num_particles(), 8);
+        assert_eq!(stable_ids.len(), 8);
+
+        let mut sim = sim
+            .with_birth_death(Some(BirthDeath {
+                birth_rate: 0.0,
+                death_rate: 1e3,
+                spawn_radius: 0.1,
+            }))
+            .unwrap();
+        sim.run_for(2).unwrap();
+        assert_eq!(sim.num_particles(), 1);
+
+        assert!(matches!(
+            sim.with_birth_death(Some(BirthDeath {
+                birth_rate: -1.0,
+                death_rate: 0.0,
+                spawn_radius: 0.1,
+            })),
+            Err(SimulationError::InvalidParameter(_))
+        ));
+    }
+
+    #[test]
+    fn random_sequential_updates_see_earlier_updates() {
+        let mut sim = facing_pair(Noise(0.0)).with_update_order(UpdateOrder::RandomSequential);
+        sim.run_for(1).unwrap();
+
+        // Whichever particle goes second copies the first's new heading, which was its own
+        let thetas: Vec<Float> = sim.particles.iter().map(|p| p.theta).collect();
+        assert!((thetas[0] - thetas[1]).abs() < 1e-5);
+        assert!(
+            [0.3, 1.1]
+                .iter()
+                .any(|theta| (thetas[0] - theta).abs() < 1e-5)
+        );
+    }
+
+    #[test]
+    fn delayed_particles_react_to_older_headings() {
+        let mut sim = facing_pair(Noise(0.0));
+        sim.set_reaction_delays(&[1, 0]).unwrap();
+        sim.run_for(2).unwrap();
+
+        // The headings swap each step, but the delayed particle still sees the one before the swap
+        let thetas: Vec<Float> = sim.particles.iter().map(|p| p.theta).collect();
+        assert!((thetas[0] - 1.1).abs() < 1e-5 && (thetas[1] - 1.1).abs() < 1e-5);
+
+        sim.clear_reaction_delays();
+        assert!(sim.particles.iter().all(|p| p.reaction_delay == 0));
+        assert!(matches!(
+            sim.set_reaction_delays(&[1]),
+            Err(SimulationError::InvalidParameter(_))
+        ));
+    }
+
+    #[test]
+    fn prescribed_particles_wrap_into_the_domain() {
+        let sim = still_particles(&[(6.0, -1.0), (2.0, 3.0)]);
+        assert_eq!(positions(&sim), [(1.0, 4.0), (2.0, 3.0)]);
+        assert_eq!(
+            sim.particles
+                .iter()
+                .map(|p| p.stable_id)
+                .collect::<Vec<_>>(),
+            [0, 1]
+        );
+
+        let with_particles = |positions: &[(Float, Float)], thetas: &[Float]| {
+            Simulation::with_particles(
+                positions,
+                thetas,
+                DomainBoundaryLength(5.0),
+                Noise(0.0),
+                Speed(0.0),
+                RelativeTime(1.0),
+                ParticleDistanceThreshold(1.0),
+            )
+        };
+        for (positions, thetas) in [
+            (&[(1.0, 1.0)][..], &[0.0, 1.0][..]),
+            (&[(Float::NAN, 1.0)][..], &[0.0][..]),
+            (&[(1.0, 1.0)][..], &[Float::INFINITY][..]),
+        ] {
+            assert!(matches!(
+                with_particles(positions, thetas),
+                Err(SimulationError::InvalidParameter(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn translational_noise_diffuses_still_particles() {
+        seed_rng(3);
+        let mut sim = still_particles(&vec![(2.5, 2.5); 400])
+            .with_translational_noise(Some(0.001))
+            .unwrap();
+        sim.run_for(10).unwrap();
+
+        // Each coordinate spreads by 2 D_t t
+        let msd = positions(&sim)
+            .iter()
+            .map(|(x, y)| (x - 2.5).powi(2) + (y - 2.5).powi(2))
+            .sum::<Float>()
+            / 400.0;
+        assert!((msd - 0.04).abs() < 0.01, "{msd}");
+
+        assert!(matches!(
+            sim.with_translational_noise(Some(-1.0)),
+            Err(SimulationError::InvalidParameter(_))
+        ));
+    }
+
+    #[test]
+    fn initial_conditions_lay_particles_out() {
+        let start = |num_particles, initial_condition| {
+            Simulation::from_initial_condition(
+                num_particles,
+                initial_condition,
+                DomainBoundaryLength(4.0),
+                Noise(0.1),
+                Speed(0.1),
+                RelativeTime(1.0),
+                ParticleDistanceThreshold(1.0),
+            )
+        };
+
+        // Three particles fill the first row of a 2 by 2 lattice and start the next
+        let sim = start(3, InitialCondition::SquareLattice).unwrap();
+        assert_eq!(positions(&sim), [(1.0, 1.0), (3.0, 1.0), (1.0, 3.0)]);
+
+        let sim = start(4, InitialCondition::Ring { radius: 1.0 }).unwrap();
+        for (x, y) in positions(&sim) {
+            assert!(((x - 2.0).hypot(y - 2.0) - 1.0).abs() < 1e-9);
+        }
+
+        let sim = start(5, InitialCondition::Aligned { heading: -0.5 * PI }).unwrap();
+        assert!(
+            sim.particles
+                .iter()
+                .all(|p| (p.theta - 1.5 * PI).abs() < 1e-9)
+        );
+
+        assert!(matches!(
+            start(5, InitialCondition::GaussianBlob { std_dev: -1.0 }),
+            Err(SimulationError::InvalidParameter(_))
+        ));
+    }
+
+    #[test]
+    fn constructor_names_the_bad_parameter() {
+        let new = |num_particles, boundary_side_length, noise, timestep| {
+            Simulation::new(
+                num_particles,
+                DomainBoundaryLength(boundary_side_length),
+                Noise(noise),
+                Speed(0.1),
+                RelativeTime(timestep),
+                ParticleDistanceThreshold(1.0),
+            )
+        };
+
+        for (sim, name) in [
+            (new(0, 5.0, 0.1, 1.0), "particle"),
+            (
+                new(5, Float::INFINITY, 0.1, 1.0),
+                "domain boundary side length",
+            ),
+            (new(5, 5.0, -0.1, 1.0), "noise"),
+            (new(5, 5.0, 0.1, 0.0), "timestep"),
+        ] {
+            match sim {
+                Err(SimulationError::InvalidParameter(message)) => {
+                    assert!(message.contains(name), "{message}")
+                }
+                _ => panic!("expected `{name}` to be rejected"),
+            }
+        }
+
+        assert!(new(5, 5.0, 0.0, 1.0).is_ok());
+    }
+
+    #[test]
+    fn parameter_setters_keep_the_particles() {
+        let sim = facing_pair(Noise(0.1));
+        let before = positions(&sim);
+
+        let sim = sim
+            .with_noise(Noise(0.4))
+            .unwrap()
+            .with_speed(Speed(0.2))
+            .unwrap()
+            .with_distance_threshold(ParticleDistanceThreshold(0.1))
+            .unwrap();
+        assert_eq!(positions(&sim), before);
+        assert_eq!(sim.params.noise.0, 0.4);
+        assert_eq!(sim.params.speed.0, 0.2);
+
+        // The particles are now too far apart to see each other
+        let mut sim = sim.with_noise(Noise(0.0)).unwrap();
+        sim.run_for(1).unwrap();
+        let thetas: Vec<Float> = sim.particles.iter().map(|p| p.theta).collect();
+        assert_eq!(thetas, [0.3, 1.1]);
+
+        assert!(sim.clone().with_noise(Noise(-1.0)).is_err());
+        assert!(sim.clone().with_speed(Speed(Float::NAN)).is_err());
+        assert!(
+            sim.with_distance_threshold(ParticleDistanceThreshold(0.0))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn run_until_lands_on_the_closest_step() {
+        let mut sim = facing_pair(Noise(0.1))
+            .with_timestep(RelativeTime(0.1))
+            .unwrap();
+
+        // Summing tenths falls just short of 1, which mustn't cost an extra step
+        assert_eq!(sim.run_until(AbsoluteTime(1.0)).unwrap(), 10);
+        assert!((sim.current_time.0 - 1.0).abs() < 1e-9);
+        assert_eq!(sim.run_until(AbsoluteTime(0.5)).unwrap(), 0);
+
+        sim.run_for(5).unwrap();
+        assert!((sim.current_time.0 - 1.5).abs() < 1e-9);
+
+        assert!(matches!(
+            sim.run_until(AbsoluteTime(Float::INFINITY)),
+            Err(SimulationError::InvalidParameter(_))
+        ));
+    }
+
+    #[test]
+    fn iterating_steps_leaves_the_simulation_alone() {
+        let sim = facing_pair(Noise(0.1));
+        let times: Vec<Float> = sim
+            .iter_steps()
+            .take(3)
+            .map(|step| step.current_time.0)
+            .collect();
+
+        assert_eq!(times, [1.0, 2.0, 3.0]);
+        assert_eq!(sim.current_time.0, 0.0);
+    }
+
+    #[test]
+    fn simulation_data_reports_headings_phases_and_time() {
+        let mut sim = facing_pair(Noise(0.0));
+        sim.run_for(2).unwrap();
+        let data = SimulationData::from(&sim);
+
+        assert_eq!(data.time, 2.0);
+        assert_eq!(data.id, [0, 1]);
+        assert_eq!(data.speed, [0.01, 0.01]);
+
+        // Two swaps bring each heading back to where it started
+        assert!((data.theta[0] - 0.3).abs() < 1e-12 && (data.theta[1] - 1.1).abs() < 1e-12);
+        for idx in 0..2 {
+            assert_eq!(data.u[idx], data.theta[idx].cos());
+            assert_eq!(data.v[idx], data.theta[idx].sin());
+            assert!((-PI..PI).contains(&data.phase[idx]));
+        }
+
+        let phases: Vec<Float> = sim.particles.iter().map(|p| p.phase).collect();
+        assert_eq!(data.phase, phases);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serialized_simulations_step_like_the_original() {
+        let sim = facing_pair(Noise(0.5))
+            .with_flow_field(Some(FlowField::Uniform { u: 0.1, v: 0.0 }))
+            .with_noise_model(NoiseModel::Scalar)
+            .with_update_order(UpdateOrder::RandomSequential);
+        let restored: Simulation =
+            serde_json::from_str(&serde_json::to_string(&sim).unwrap()).unwrap();
+
+        let seeded_run = |mut sim: Simulation| {
+            seed_rng(2);
+            sim.run_for(3).unwrap();
+            sim.particles
+                .iter()
+                .map(|p| (p.pos_x, p.pos_y, p.theta))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(seeded_run(restored), seeded_run(sim));
+
+        let sim =
+            facing_pair(Noise(0.5)).with_flow_field(Some(FlowField::function(|_, _| (0.1, 0.0))));
+        assert!(serde_json::to_string(&sim).is_err());
+    }
+
+    #[test]
+    fn set_state_wraps_positions_and_updates_the_order() {
+        let mut sim = facing_pair(Noise(0.0));
+        sim.set_state(&[6.0, -1.0], &[1.0, 2.0], &[0.5, 0.5])
+            .unwrap();
+
+        let state: Vec<_> = sim
+            .particles
+            .iter()
+            .map(|p| (p.pos_x, p.pos_y, p.theta))
+            .collect();
+        assert_eq!(state, [(1.0, 1.0, 0.5), (4.0, 2.0, 0.5)]);
+        assert!((sim.instantaneous_order.0 - 1.0).abs() < 1e-12);
+
+        assert!(sim.set_state(&[1.0], &[1.0], &[0.0]).is_err());
+        assert!(
+            sim.set_state(&[1.0, Float::NAN], &[1.0, 1.0], &[0.0, 0.0])
+                .is_err()
+        );
+        assert_eq!(sim.particles.iter().next().unwrap().pos_x, 1.0);
+    }
+
+    #[test]
+    fn simulation_data_carries_the_instantaneous_order() {
+        let mut sim = facing_pair(Noise(0.0));
+        sim.set_state(&[1.0, 3.0], &[1.0, 1.0], &[0.0, PI]).unwrap();
+        assert!(SimulationData::from(&sim).instantaneous_order.abs() < 1e-12);
+
+        sim.run_for(1).unwrap();
+        assert_eq!(
+            SimulationData::from(&sim).instantaneous_order,
+            sim.instantaneous_order.0
+        );
     }
 }
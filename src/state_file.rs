@@ -0,0 +1,450 @@
+use std::{
+    collections::HashSet,
+    fs::File,
+    io::{BufRead, BufReader, Read, Write},
+    path::Path,
+};
+
+use anyhow::{Context, anyhow, bail};
+use serde_json::{Value, json};
+
+use crate::{
+    Simulation,
+    particle::Particle,
+    types::{
+        AbsoluteTime, DomainBoundaryLength, Float, Noise, ParticleDistanceThreshold, RelativeTime,
+        Speed,
+    },
+};
+
+/// The parameters every state file must give, in the order they're passed to the constructor
+const PARAMETER_NAMES: [&str; 5] = [
+    "boundary_side_length",
+    "noise",
+    "speed",
+    "timestep",
+    "particle_distance_threshold",
+];
+
+/// A configuration read from a state file, before it's validated by the constructor
+struct State {
+    parameters: [Float; 5],
+    positions: Vec<(Float, Float)>,
+    thetas: Vec<Float>,
+}
+
+impl Simulation {
+    /// Instantiate a simulator from a file of per-particle `x`, `y`, and `theta` plus the
+    /// simulation parameters, read as JSON if the extension is `.json` and as CSV otherwise
+    ///
+    /// # Notes
+    /// The JSON format is an object with a `parameters` object and equal-length `x`, `y`, and
+    /// `theta` arrays. The CSV format gives each parameter on its own `# name = value` line before
+    /// a header row naming the `x`, `y`, and `theta` columns, in any order. Either way, the
+    /// parameters are `boundary_side_length`, `noise`, `speed`, `timestep`, and
+    /// `particle_distance_threshold`, and positions outside the domain are wrapped back into it.
+    pub fn from_file(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let file = File::open(path)
+            .with_context(|| format!("could not open state file `{}`", path.display()))?;
+
+        let is_json = path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("json"));
+        let state = match is_json {
+            true => read_json(BufReader::new(file)),
+            false => read_csv(BufReader::new(file)),
+        }
+        .with_context(|| format!("could not read state file `{}`", path.display()))?;
+
+        Self::from_state(state)
+    }
+}
+
+impl Simulation {
+    /// Validate a state through the constructor
+    fn from_state(state: State) -> anyhow::Result<Self> {
+        let [
+            boundary_side_length,
+            noise,
+            speed,
+            timestep,
+            particle_distance_threshold,
+        ] = state.parameters;
+
+        Ok(Self::with_particles(
+            &state.positions,
+            &state.thetas,
+            DomainBoundaryLength(boundary_side_length),
+            Noise(noise),
+            Speed(speed),
+            RelativeTime(timestep),
+            ParticleDistanceThreshold(particle_distance_threshold),
+        )?)
+    }
+
+    /// Get a human-readable JSON snapshot of the core parameters, the simulated time, and every
+    /// particle's ID, tag, position, heading, noise phase, and scalars, e.g. for a bug report or
+    /// a test fixture
+    ///
+    /// # Notes
+    /// This is the JSON state file format plus extra fields, so [`Simulation::from_file`] loads it
+    /// too, but only [`Simulation::from_json`] restores the extras. Other settings (e.g. the
+    /// update rule, fields, schedules, and leaders) aren't included.
+    pub fn to_json(&self) -> String {
+        format!("{:#}", self.json_state())
+    }
+
+    /// Restore a snapshot from [`Simulation::to_json`], or start from any JSON state file's
+    /// contents
+    pub fn from_json(json: &str) -> anyhow::Result<Self> {
+        Self::read_json_state(json.as_bytes())
+    }
+
+    /// Write the compact form of [`Simulation::to_json`]
+    pub(crate) fn write_json_state(&self, writer: impl Write) -> anyhow::Result<()> {
+        serde_json::to_writer(writer, &self.json_state()).context("could not write state")
+    }
+
+    /// Build the JSON state file format, plus the simulated `time` and each particle's `id`,
+    /// `tag`, noise `phase`, and `scalars`, which [`Simulation::from_file`] ignores
+    fn json_state(&self) -> Value {
+        let column = |value: fn(&Particle) -> Float| -> Vec<Float> {
+            self.particles.iter().map(value).collect()
+        };
+
+        let scalars: serde_json::Map<String, Value> = self
+            .scalar_names()
+            .into_iter()
+            .map(|name| {
+                let values: Vec<Float> = self
+                    .particles
+                    .iter()
+                    .map(|particle| particle.scalars.get(&name))
+                    .collect();
+                (name, json!(values))
+            })
+            .collect();
+
+        json!({
+            "parameters": {
+                "boundary_side_length": self.params.boundary_side_length.0,
+                "noise": self.params.noise.0,
+                "speed": self.params.speed.0,
+                "timestep": self.params.timestep.0,
+                "particle_distance_threshold": self.params.particle_distance_threshold.0,
+            },
+            "time": self.current_time.0,
+            "next_id": self.next_stable_id,
+            "id": self.particles.iter().map(|particle| particle.stable_id).collect::<Vec<_>>(),
+            "tag": self.particles.iter().map(|particle| particle.tag).collect::<Vec<_>>(),
+            "x": column(|particle| particle.pos_x),
+            "y": column(|particle| particle.pos_y),
+            "theta": column(|particle| particle.theta),
+            "phase": column(|particle| particle.phase),
+            "scalars": scalars,
+        })
+    }
+
+    /// Restore a state written by [`Simulation::write_json_state`], or start from any JSON state
+    /// file, taking whichever of the extra fields it has
+    pub(crate) fn read_json_state(reader: impl Read) -> anyhow::Result<Self> {
+        let root: Value = serde_json::from_reader(reader).context("invalid JSON")?;
+        let mut sim = Self::from_state(state_from_json(&root)?)?;
+        let num_particles = sim.particles.len();
+
+        if let Some(time) = root.get("time") {
+            let time = time
+                .as_f64()
+                .filter(|time| time.is_finite())
+                .ok_or_else(|| anyhow!("`time` is not a finite number"))?;
+            sim.current_time = AbsoluteTime(time as Float);
+        }
+
+        if let Some(phases) = optional_column(&root, "phase", num_particles, Value::as_f64)? {
+            let phases: Vec<Float> = phases.into_iter().map(|phase| phase as Float).collect();
+            sim.particles = sim.particles.to_with_phases(&phases);
+        }
+
+        let as_usize = |value: &Value| value.as_u64().map(|value| value as usize);
+        let ids = optional_column(&root, "id", num_particles, as_usize)?;
+        let tags = optional_column(&root, "tag", num_particles, as_usize)?;
+        if ids.is_some() || tags.is_some() {
+            let ids = ids.unwrap_or_else(|| (0..num_particles).collect());
+            if ids.iter().collect::<HashSet<_>>().len() != ids.len() {
+                bail!("particle `id`s must be unique");
+            }
+
+            let tags = tags.unwrap_or_else(|| vec![0; num_particles]);
+            sim.particles = sim.particles.to_with_labels(&ids, &tags);
+
+            // IDs are never reused, even those of particles since removed
+            let min_next_id = ids.iter().max().map_or(0, |id| id + 1);
+            sim.next_stable_id = match root.get("next_id") {
+                Some(next_id) => as_usize(next_id)
+                    .filter(|&next_id| next_id >= min_next_id)
+                    .ok_or_else(|| anyhow!("`next_id` must be above every particle `id`"))?,
+                None => min_next_id,
+            };
+        }
+
+        if let Some(scalars) = root.get("scalars") {
+            let names = scalars
+                .as_object()
+                .ok_or_else(|| anyhow!("`scalars` is not an object"))?
+                .keys();
+
+            for name in names {
+                let values = optional_column(scalars, name, num_particles, Value::as_f64)?
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|value| value as Float)
+                    .collect::<Vec<_>>();
+                sim.set_scalar(name, &values)?;
+            }
+        }
+
+        Ok(sim)
+    }
+}
+
+/// Read an optional per-particle array, which must have a valid entry for every particle
+fn optional_column<T>(
+    root: &Value,
+    name: &str,
+    num_particles: usize,
+    parse: impl Fn(&Value) -> Option<T>,
+) -> anyhow::Result<Option<Vec<T>>> {
+    let Some(column) = root.get(name) else {
+        return Ok(None);
+    };
+
+    let column = column
+        .as_array()
+        .ok_or_else(|| anyhow!("`{name}` is not an array"))?;
+    if column.len() != num_particles {
+        bail!(
+            "`{name}` has `{}` entries but there are `{}` particles",
+            column.len(),
+            num_particles
+        );
+    }
+
+    column
+        .iter()
+        .enumerate()
+        .map(|(idx, value)| {
+            parse(value).ok_or_else(|| anyhow!("`{name}` entry `{idx}` is invalid"))
+        })
+        .collect::<anyhow::Result<_>>()
+        .map(Some)
+}
+
+fn read_json(reader: impl Read) -> anyhow::Result<State> {
+    let root: Value = serde_json::from_reader(reader).context("invalid JSON")?;
+    state_from_json(&root)
+}
+
+fn state_from_json(root: &Value) -> anyhow::Result<State> {
+    let parameters = root
+        .get("parameters")
+        .ok_or_else(|| anyhow!("state has no `parameters` object"))?;
+
+    let mut values = [0.0; 5];
+    for (value, name) in values.iter_mut().zip(PARAMETER_NAMES) {
+        *value = parameters
+            .get(name)
+            .and_then(Value::as_f64)
+            .ok_or_else(|| anyhow!("parameter `{name}` is missing or not a number"))?
+            as Float;
+    }
+
+    let column = |name: &str| -> anyhow::Result<Vec<Float>> {
+        root.get(name)
+            .and_then(Value::as_array)
+            .ok_or_else(|| anyhow!("state has no `{name}` array"))?
+            .iter()
+            .enumerate()
+            .map(|(idx, value)| {
+                value
+                    .as_f64()
+                    .map(|value| value as Float)
+                    .ok_or_else(|| anyhow!("`{name}` entry `{idx}` is not a number"))
+            })
+            .collect()
+    };
+
+    let (x, y) = (column("x")?, column("y")?);
+    if x.len() != y.len() {
+        bail!(
+            "got `{}` x coordinates but `{}` y coordinates",
+            x.len(),
+            y.len()
+        );
+    }
+
+    Ok(State {
+        parameters: values,
+        positions: x.into_iter().zip(y).collect(),
+        thetas: column("theta")?,
+    })
+}
+
+fn read_csv(reader: impl BufRead) -> anyhow::Result<State> {
+    let mut parameters: [Option<Float>; 5] = [None; 5];
+    let mut columns: Option<[usize; 3]> = None;
+    let mut positions = Vec::new();
+    let mut thetas = Vec::new();
+
+    for (line_idx, line) in reader.lines().enumerate() {
+        // Report line numbers as a text editor would
+        let line_number = line_idx + 1;
+        let line = line.with_context(|| format!("could not read line `{line_number}`"))?;
+        let line = line.trim();
+
+        if line.is_empty() {
+            continue;
+        }
+
+        // Parameters come as comments, ahead of the header...
+        if let Some(comment) = line.strip_prefix('#') {
+            let Some((name, value)) = comment.split_once('=') else {
+                continue;
+            };
+
+            let name = name.trim();
+            let Some(position) = PARAMETER_NAMES.iter().position(|&known| known == name) else {
+                bail!("line `{line_number}` sets unknown parameter `{name}`");
+            };
+
+            let value = value.trim();
+            parameters[position] = Some(value.parse().with_context(|| {
+                format!("line `{line_number}` has invalid `{name}` value `{value}`")
+            })?);
+            continue;
+        }
+
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+
+        // ...then the header names the columns...
+        let Some(columns) = columns else {
+            let column = |name: &str| {
+                fields
+                    .iter()
+                    .position(|&field| field == name)
+                    .ok_or_else(|| anyhow!("state has no `{name}` column"))
+            };
+            columns = Some([column("x")?, column("y")?, column("theta")?]);
+            continue;
+        };
+
+        // ...and every other line is a particle.
+        let parse_float = |column: usize, name: &str| {
+            let field = fields.get(column).copied().unwrap_or_default();
+            field.parse::<Float>().with_context(|| {
+                format!("line `{line_number}` has invalid `{name}` value `{field}`")
+            })
+        };
+        let [x, y, theta] = [
+            parse_float(columns[0], "x"),
+            parse_float(columns[1], "y"),
+            parse_float(columns[2], "theta"),
+        ];
+        positions.push((x?, y?));
+        thetas.push(theta?);
+    }
+
+    if columns.is_none() {
+        bail!("state has no header row");
+    }
+
+    let mut values = [0.0; 5];
+    for ((value, parameter), name) in values.iter_mut().zip(parameters).zip(PARAMETER_NAMES) {
+        *value = parameter.ok_or_else(|| anyhow!("parameter `{name}` is missing"))?;
+    }
+
+    Ok(State {
+        parameters: values,
+        positions,
+        thetas,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CSV_STATE: &str = "\
+# boundary_side_length = 5
+# noise = 0.1
+# speed = 0.03
+# timestep = 1
+# particle_distance_threshold = 1
+
+theta, y, x
+0.5, 2.0, 1.0
+1.5, 3.0, 6.0
+";
+
+    #[test]
+    fn csv_and_json_states_load_the_same_simulation() {
+        let from_csv = Simulation::from_state(read_csv(CSV_STATE.as_bytes()).unwrap()).unwrap();
+
+        let json = r#"{
+            "parameters": {
+                "boundary_side_length": 5, "noise": 0.1, "speed": 0.03, "timestep": 1,
+                "particle_distance_threshold": 1
+            },
+            "x": [1.0, 6.0], "y": [2.0, 3.0], "theta": [0.5, 1.5]
+        }"#;
+        let from_json = Simulation::from_state(read_json(json.as_bytes()).unwrap()).unwrap();
+
+        for sim in [from_csv, from_json] {
+            let particles: Vec<_> = sim
+                .particles
+                .iter()
+                .map(|p| (p.pos_x, p.pos_y, p.theta))
+                .collect();
+            assert_eq!(particles, [(1.0, 2.0, 0.5), (1.0, 3.0, 1.5)]);
+            assert_eq!(sim.params.noise.0, 0.1);
+        }
+    }
+
+    #[test]
+    fn incomplete_csv_states_are_rejected() {
+        let missing_parameter = CSV_STATE.replace("# noise = 0.1\n", "");
+        let error = read_csv(missing_parameter.as_bytes()).err().unwrap();
+        assert!(error.to_string().contains("`noise`"));
+
+        let missing_column = CSV_STATE.replace("theta, ", "heading, ");
+        assert!(read_csv(missing_column.as_bytes()).is_err());
+
+        let bad_value = CSV_STATE.replace("6.0", "six");
+        assert!(read_csv(bad_value.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn json_snapshots_restore_the_extra_fields() {
+        let mut sim = Simulation::from_state(read_csv(CSV_STATE.as_bytes()).unwrap())
+            .unwrap()
+            .to_tagged(3);
+        sim.set_scalar("energy", &[0.25, 0.75]).unwrap();
+        sim.run_for(2).unwrap();
+
+        let restored = Simulation::from_json(&sim.to_json()).unwrap();
+        assert_eq!(restored.current_time.0, 2.0);
+        assert_eq!(restored.scalar("energy"), sim.scalar("energy"));
+
+        let labels = |sim: &Simulation| -> Vec<_> {
+            sim.particles
+                .iter()
+                .map(|p| (p.stable_id, p.tag, p.pos_x, p.pos_y, p.theta, p.phase))
+                .collect()
+        };
+        assert_eq!(labels(&restored), labels(&sim));
+
+        let mut duplicate_ids: Value = serde_json::from_str(&sim.to_json()).unwrap();
+        duplicate_ids["id"] = json!([0, 0]);
+        assert!(Simulation::from_json(&duplicate_ids.to_string()).is_err());
+    }
+}
@@ -0,0 +1,434 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::{Read, Write},
+    path::Path,
+    sync::{
+        Mutex,
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+    },
+    thread,
+};
+
+use anyhow::{Context, anyhow, bail};
+
+use crate::{
+    Simulation,
+    control::StopReason,
+    random::seed_rng,
+    simulation::StationaryOrderOptions,
+    types::{DomainBoundaryLength, Float, Noise, ParticleDistanceThreshold, RelativeTime, Speed},
+};
+
+const RESULTS_HEADER: &str =
+    "index,num_particles,noise,speed,particle_distance_threshold,value,converged,iterations";
+
+/// One parameter combination in a sweep
+#[derive(Copy, Clone, Debug)]
+pub struct SweepPoint {
+    pub num_particles: usize,
+    pub noise: Noise,
+    pub speed: Speed,
+    pub particle_distance_threshold: ParticleDistanceThreshold,
+}
+
+/// Controls for running a sweep
+#[derive(Clone, Debug)]
+pub struct SweepOptions {
+    /// Controls for each point's stationary order parameter computation, whose cancellation
+    /// also stops the sweep
+    pub stationary_order: StationaryOrderOptions,
+
+    /// Seed the random number generator with `seed + i` before point `i`, so results don't
+    /// depend on the number of threads or on which points a resumed sweep recomputes
+    pub seed: Option<u64>,
+
+    /// Number of points to compute at once, counting the calling thread
+    pub num_threads: usize,
+}
+
+impl Default for SweepOptions {
+    fn default() -> Self {
+        Self {
+            stationary_order: StationaryOrderOptions::default(),
+            seed: None,
+            num_threads: 1,
+        }
+    }
+}
+
+/// The stationary order parameter computed for one sweep point
+#[derive(Copy, Clone, Debug)]
+pub struct SweepResult {
+    /// Position of the point in the sweep
+    pub index: usize,
+
+    pub point: SweepPoint,
+
+    /// The stationary order parameter estimate, converged or not
+    pub value: Float,
+
+    pub converged: bool,
+
+    /// Number of timesteps the estimate took
+    pub iterations: usize,
+}
+
+/// The results of a (possibly partial) sweep
+#[derive(Clone, Debug)]
+pub struct SweepReport {
+    /// Every completed point, including ones recovered from a previous run, in sweep order
+    pub results: Vec<SweepResult>,
+
+    /// Number of points computed by this run, as opposed to recovered
+    pub num_computed: usize,
+
+    /// `Converged` once every point is done, or `Cancelled` if the sweep was interrupted
+    pub stop_reason: StopReason,
+}
+
+/// Everything the sweep's workers share, behind one lock
+struct SweepProgress {
+    results: Vec<Option<SweepResult>>,
+    results_file: Option<File>,
+    num_computed: usize,
+}
+
+/// Compute the stationary order parameter at every sweep point
+///
+/// # Notes
+/// With a `results_path`, each point is appended to that CSV file as soon as it finishes, and the
+/// file doubles as the completion index: rerunning the same sweep against it skips every point
+/// already recorded, so an interrupted sweep resumes where it stopped. A half-written last line
+/// (e.g. from a crash) is discarded, and recorded points must match the sweep's own. With more
+/// than one thread, points are taken in order as threads free up and recorded as they finish.
+pub fn run_sweep(
+    points: &[SweepPoint],
+    boundary_side_length: DomainBoundaryLength,
+    timestep: RelativeTime,
+    options: &SweepOptions,
+    results_path: Option<&Path>,
+) -> anyhow::Result<SweepReport> {
+    let mut results: Vec<Option<SweepResult>> = vec![None; points.len()];
+
+    let results_file = match results_path {
+        Some(path) => Some(
+            open_results(path, points, &mut results)
+                .with_context(|| format!("could not resume sweep from `{}`", path.display()))?,
+        ),
+        None => None,
+    };
+
+    let pending: Vec<usize> = (0..points.len())
+        .filter(|&index| results[index].is_none())
+        .collect();
+
+    let progress = Mutex::new(SweepProgress {
+        results,
+        results_file,
+        num_computed: 0,
+    });
+    let next_pending = AtomicUsize::new(0);
+    let is_cancelled = AtomicBool::new(false);
+    let is_stopped = AtomicBool::new(false);
+
+    let compute = |index: usize| -> anyhow::Result<Option<SweepResult>> {
+        if let Some(seed) = options.seed {
+            seed_rng(seed.wrapping_add(index as u64));
+        }
+
+        let point = points[index];
+        let estimate = Simulation::new(
+            point.num_particles,
+            boundary_side_length,
+            point.noise,
+            point.speed,
+            timestep,
+            point.particle_distance_threshold,
+        )
+        .context("could not instantiate simulation for sweep")?
+        .compute_stationary_order_estimate(&options.stationary_order)?;
+
+        // An interrupted estimate isn't a result, so it's recomputed on resume
+        if estimate.stop_reason == StopReason::Cancelled {
+            return Ok(None);
+        }
+
+        Ok(Some(SweepResult {
+            index,
+            point,
+            value: estimate.value,
+            converged: estimate.is_converged(),
+            iterations: estimate.iterations,
+        }))
+    };
+
+    let work = || -> anyhow::Result<()> {
+        while !is_stopped.load(Ordering::Relaxed) {
+            let Some(&index) = pending.get(next_pending.fetch_add(1, Ordering::Relaxed)) else {
+                break;
+            };
+
+            let cancelled_before = options
+                .stationary_order
+                .cancellation
+                .as_ref()
+                .is_some_and(|cancellation| cancellation.is_cancelled());
+            let result = match cancelled_before {
+                true => None,
+                false => compute(index)?,
+            };
+            let Some(result) = result else {
+                is_cancelled.store(true, Ordering::Relaxed);
+                break;
+            };
+
+            let mut progress = progress
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            if let Some(file) = &mut progress.results_file {
+                // Flush every line, so a crash loses at most the points in progress
+                writeln!(
+                    file,
+                    "{},{},{},{},{},{},{},{}",
+                    result.index,
+                    result.point.num_particles,
+                    result.point.noise.0,
+                    result.point.speed.0,
+                    result.point.particle_distance_threshold.0,
+                    result.value,
+                    result.converged,
+                    result.iterations,
+                )
+                .and_then(|_| file.flush())
+                .context("could not record sweep result")?;
+            }
+            progress.results[index] = Some(result);
+            progress.num_computed += 1;
+        }
+
+        Ok(())
+    };
+
+    // The calling thread works too, so a single-threaded sweep runs (and checks for
+    // cancellation) exactly where it was called
+    let work_until_stopped = || {
+        let outcome = work();
+        if outcome.is_err() || is_cancelled.load(Ordering::Relaxed) {
+            is_stopped.store(true, Ordering::Relaxed);
+        }
+        outcome
+    };
+    let num_threads = options.num_threads.clamp(1, pending.len().max(1));
+
+    thread::scope(|scope| {
+        let workers: Vec<_> = (1..num_threads)
+            .map(|_| scope.spawn(work_until_stopped))
+            .collect();
+
+        let outcome = work_until_stopped();
+
+        workers
+            .into_iter()
+            .map(|worker| {
+                worker
+                    .join()
+                    .map_err(|_| anyhow!("a sweep worker panicked"))?
+            })
+            .fold(outcome, Result::and)
+    })?;
+
+    let progress = progress
+        .into_inner()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+    Ok(SweepReport {
+        results: progress.results.into_iter().flatten().collect(),
+        num_computed: progress.num_computed,
+        stop_reason: match is_cancelled.load(Ordering::Relaxed) {
+            true => StopReason::Cancelled,
+            false => StopReason::Converged,
+        },
+    })
+}
+
+/// Open a results file for appending, first loading any points it already records
+fn open_results(
+    path: &Path,
+    points: &[SweepPoint],
+    results: &mut [Option<SweepResult>],
+) -> anyhow::Result<File> {
+    let mut file = OpenOptions::new()
+        .read(true)
+        .append(true)
+        .create(true)
+        .open(path)?;
+
+    let mut contents = String::new();
+    file.read_to_string(&mut contents)?;
+
+    // Only lines with their newline made it to disk in full
+    let complete_len = contents.rfind('\n').map_or(0, |idx| idx + 1);
+    if complete_len < contents.len() {
+        file.set_len(complete_len as u64)?;
+    }
+
+    let mut lines = contents[..complete_len].lines();
+
+    match lines.next() {
+        None => writeln!(file, "{RESULTS_HEADER}")?,
+        Some(header) if header.trim() == RESULTS_HEADER => {}
+        Some(header) => bail!("unexpected results header `{}`", header),
+    }
+
+    for (line_idx, line) in lines.enumerate() {
+        // Report line numbers as a text editor would, counting the header
+        let line_number = line_idx + 2;
+
+        let result =
+            parse_result(line).with_context(|| format!("could not parse line `{line_number}`"))?;
+
+        let Some(point) = points.get(result.index) else {
+            bail!(
+                "line `{line_number}` records point `{}`, but the sweep only has `{}`",
+                result.index,
+                points.len()
+            );
+        };
+
+        let recorded = result.point;
+        if recorded.num_particles != point.num_particles
+            || recorded.noise.0 != point.noise.0
+            || recorded.speed.0 != point.speed.0
+            || recorded.particle_distance_threshold.0 != point.particle_distance_threshold.0
+        {
+            bail!(
+                "line `{line_number}` records different parameters for point `{}`, so it belongs \
+                 to another sweep",
+                result.index
+            );
+        }
+
+        results[result.index] = Some(result);
+    }
+
+    Ok(file)
+}
+
+fn parse_result(line: &str) -> anyhow::Result<SweepResult> {
+    let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+
+    let [
+        index,
+        num_particles,
+        noise,
+        speed,
+        threshold,
+        value,
+        converged,
+        iterations,
+    ] = fields[..]
+    else {
+        bail!("expected 8 fields, got `{}`", fields.len());
+    };
+
+    let float = |field: &str| {
+        field
+            .parse::<Float>()
+            .map_err(|_| anyhow!("`{field}` is not a number"))
+    };
+
+    Ok(SweepResult {
+        index: index.parse()?,
+        point: SweepPoint {
+            num_particles: num_particles.parse()?,
+            noise: Noise(float(noise)?),
+            speed: Speed(float(speed)?),
+            particle_distance_threshold: ParticleDistanceThreshold(float(threshold)?),
+        },
+        value: float(value)?,
+        converged: converged.parse()?,
+        iterations: iterations.parse()?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn points() -> Vec<SweepPoint> {
+        [0.1, 0.5, 1.0]
+            .map(|noise| SweepPoint {
+                num_particles: 10,
+                noise: Noise(noise),
+                speed: Speed(0.1),
+                particle_distance_threshold: ParticleDistanceThreshold(1.0),
+            })
+            .to_vec()
+    }
+
+    fn options() -> SweepOptions {
+        SweepOptions {
+            stationary_order: StationaryOrderOptions {
+                max_steps: Some(5),
+                ..Default::default()
+            },
+            seed: Some(1),
+            num_threads: 2,
+        }
+    }
+
+    #[test]
+    fn sweeps_resume_from_their_results_file() {
+        let path = std::env::temp_dir().join(format!("sweep-resume-{}.csv", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let sweep = |points: &[SweepPoint]| {
+            run_sweep(
+                points,
+                DomainBoundaryLength(5.0),
+                RelativeTime(1.0),
+                &options(),
+                Some(&path),
+            )
+        };
+
+        // Run the first two points, then leave a half-written line behind as a crash would
+        let points = points();
+        let first = sweep(&points[..2]).unwrap();
+        assert_eq!(first.num_computed, 2);
+        OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap()
+            .write_all(b"2,10,1,0.")
+            .unwrap();
+
+        let resumed = sweep(&points).unwrap();
+        assert_eq!(resumed.num_computed, 1);
+        assert_eq!(resumed.stop_reason, StopReason::Converged);
+        assert_eq!(
+            resumed.results.iter().map(|r| r.index).collect::<Vec<_>>(),
+            [0, 1, 2]
+        );
+        for (first, resumed) in first.results.iter().zip(&resumed.results) {
+            assert_eq!(first.value, resumed.value);
+        }
+
+        // Seeding per point makes a fresh run agree with the resumed one
+        let fresh = run_sweep(
+            &points,
+            DomainBoundaryLength(5.0),
+            RelativeTime(1.0),
+            &options(),
+            None,
+        )
+        .unwrap();
+        assert_eq!(fresh.results[2].value, resumed.results[2].value);
+
+        // A file from a different sweep is refused
+        let mut other = points.clone();
+        other[0].noise = Noise(0.2);
+        assert!(sweep(&other).is_err());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,277 @@
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{BufRead, BufReader},
+    path::Path,
+};
+
+use anyhow::{Context, anyhow};
+
+use crate::{
+    Simulation,
+    error::{SimulationError, invalid_parameter},
+    particle::{Particle, Particles},
+    types::{
+        DomainBoundaryLength, Float, Noise, PI, ParticleDistanceThreshold, RelativeTime, Speed,
+    },
+};
+
+/// One tracked particle in one frame of an experimental recording
+#[derive(Copy, Clone, Debug)]
+pub struct TrackedPoint {
+    /// The tracker's ID for the particle, stable across frames
+    pub id: u64,
+    pub x: Float,
+    pub y: Float,
+
+    /// The recorded heading in radians, if the tracker provides one
+    pub heading: Option<Float>,
+}
+
+/// Particle tracks from an experiment, grouped by frame
+///
+/// # Notes
+/// The CSV format has a header row naming the `frame`, `id`, `x`, and `y` columns, plus an
+/// optional `heading` column, in any order. Other columns are ignored, and empty headings are
+/// treated as missing.
+#[derive(Clone, Debug, Default)]
+pub struct TrackingData {
+    frames: BTreeMap<u64, Vec<TrackedPoint>>,
+}
+
+impl TrackingData {
+    /// Load tracks from a CSV file
+    pub fn from_csv_file(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let file = File::open(path)
+            .with_context(|| format!("could not open tracking data `{}`", path.display()))?;
+
+        Self::from_csv(BufReader::new(file))
+            .with_context(|| format!("could not read tracking data `{}`", path.display()))
+    }
+
+    /// Load tracks from CSV text
+    pub fn from_csv(reader: impl BufRead) -> anyhow::Result<Self> {
+        let mut lines = reader.lines();
+
+        let header = lines
+            .next()
+            .ok_or_else(|| anyhow!("tracking data is empty"))?
+            .context("could not read tracking data header")?;
+        let columns: Vec<&str> = header.split(',').map(str::trim).collect();
+
+        let column = |name: &str| columns.iter().position(|&column| column == name);
+        let required_column = |name: &str| {
+            column(name).ok_or_else(|| anyhow!("tracking data has no `{name}` column"))
+        };
+
+        let frame_column = required_column("frame")?;
+        let id_column = required_column("id")?;
+        let x_column = required_column("x")?;
+        let y_column = required_column("y")?;
+        let heading_column = column("heading");
+
+        let mut frames: BTreeMap<u64, Vec<TrackedPoint>> = BTreeMap::new();
+
+        for (line_idx, line) in lines.enumerate() {
+            // Report line numbers as a text editor would, counting the header
+            let line_number = line_idx + 2;
+            let line = line.with_context(|| format!("could not read line `{line_number}`"))?;
+
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+            let field = |column: usize| {
+                fields.get(column).copied().ok_or_else(|| {
+                    anyhow!(
+                        "line `{line_number}` is missing column `{}`",
+                        columns[column]
+                    )
+                })
+            };
+            let parse_float = |column: usize| -> anyhow::Result<Float> {
+                let field = field(column)?;
+                field.parse().with_context(|| {
+                    format!(
+                        "line `{line_number}` has invalid `{}` value `{field}`",
+                        columns[column]
+                    )
+                })
+            };
+            let parse_integer = |column: usize| -> anyhow::Result<u64> {
+                let field = field(column)?;
+                field.parse().with_context(|| {
+                    format!(
+                        "line `{line_number}` has invalid `{}` value `{field}`",
+                        columns[column]
+                    )
+                })
+            };
+
+            let heading = match heading_column {
+                Some(column) if fields.get(column).is_some_and(|field| !field.is_empty()) => {
+                    Some(parse_float(column)?)
+                }
+                _ => None,
+            };
+
+            frames
+                .entry(parse_integer(frame_column)?)
+                .or_default()
+                .push(TrackedPoint {
+                    id: parse_integer(id_column)?,
+                    x: parse_float(x_column)?,
+                    y: parse_float(y_column)?,
+                    heading,
+                });
+        }
+
+        Ok(Self { frames })
+    }
+
+    /// The frame numbers present, in order
+    pub fn frames(&self) -> impl Iterator<Item = u64> + '_ {
+        self.frames.keys().copied()
+    }
+
+    /// The tracked points in a frame
+    pub fn frame(&self, frame: u64) -> Option<&[TrackedPoint]> {
+        self.frames.get(&frame).map(Vec::as_slice)
+    }
+
+    /// Get a frame with every particle's heading resolved
+    ///
+    /// # Notes
+    /// With `estimate_heading`, headings come from each particle's displacement to its position
+    /// in the next frame, or from the previous frame for the last one. Particles that don't move
+    /// or aren't tracked in a neighboring frame fall back to their recorded heading. Without it,
+    /// every particle needs a recorded heading.
+    pub fn to_headed_frame(
+        &self,
+        frame: u64,
+        estimate_heading: bool,
+    ) -> Result<Vec<TrackedPoint>, SimulationError> {
+        let points = self.frame(frame).ok_or_else(|| {
+            SimulationError::InvalidParameter(format!("tracking data has no frame `{frame}`"))
+        })?;
+
+        let next_frame = self.frames.range(frame + 1..).next();
+        let previous_frame = self.frames.range(..frame).next_back();
+
+        let find =
+            |points: &[TrackedPoint], id: u64| points.iter().find(|point| point.id == id).copied();
+
+        points
+            .iter()
+            .map(|&point| {
+                let estimated_heading = match estimate_heading {
+                    true => {
+                        let displacement = next_frame
+                            .and_then(|(_, next)| find(next, point.id))
+                            .map(|next| (next.x - point.x, next.y - point.y))
+                            .or_else(|| {
+                                previous_frame
+                                    .and_then(|(_, previous)| find(previous, point.id))
+                                    .map(|previous| (point.x - previous.x, point.y - previous.y))
+                            });
+
+                        displacement
+                            .filter(|&(dx, dy)| dx != 0.0 || dy != 0.0)
+                            .map(|(dx, dy)| dy.atan2(dx))
+                    }
+                    false => None,
+                };
+
+                let heading = estimated_heading.or(point.heading).ok_or_else(|| {
+                    SimulationError::InvalidParameter(format!(
+                        "particle `{}` in frame `{frame}` has no heading",
+                        point.id
+                    ))
+                })?;
+
+                Ok(TrackedPoint {
+                    heading: Some(heading),
+                    ..point
+                })
+            })
+            .collect()
+    }
+}
+
+impl Simulation {
+    /// Instantiate a simulator from one frame of experimental tracking data, e.g. as returned by
+    /// [`TrackingData::to_headed_frame`]
+    ///
+    /// # Notes
+    /// Particles are ordered as they appear in the frame, and positions outside the domain are
+    /// wrapped back into it.
+    pub fn from_tracking(
+        points: &[TrackedPoint],
+        boundary_side_length: DomainBoundaryLength,
+        noise: Noise,
+        speed: Speed,
+        timestep: RelativeTime,
+        particle_distance_threshold: ParticleDistanceThreshold,
+    ) -> Result<Self, SimulationError> {
+        if let Some(point) = points.iter().find(|point| point.heading.is_none()) {
+            invalid_parameter!("tracked particle `{}` has no heading", point.id);
+        }
+
+        let particles = Particles::from_reindexed(points.iter().map(|point| {
+            Particle::from_state(
+                point.x.rem_euclid(boundary_side_length.0),
+                point.y.rem_euclid(boundary_side_length.0),
+                point.heading.unwrap_or_default().rem_euclid(2.0 * PI),
+            )
+        }));
+
+        Self::from_initial_particles(
+            particles,
+            boundary_side_length,
+            noise,
+            speed,
+            timestep,
+            particle_distance_threshold,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TRACKS: &str = "\
+frame,id,x,y,heading,extra
+0,7,1.0,1.0,,a
+0,8,2.0,2.0,0.5,b
+1,7,1.0,2.0,,c
+1,8,2.0,2.0,0.5,d
+";
+
+    #[test]
+    fn headings_are_estimated_from_displacements() {
+        let tracks = TrackingData::from_csv(TRACKS.as_bytes()).unwrap();
+        assert_eq!(tracks.frames().collect::<Vec<_>>(), [0, 1]);
+
+        // Particle 7 moves straight up, while 8 stays put and keeps its recorded heading
+        let frame = tracks.to_headed_frame(0, true).unwrap();
+        assert!((frame[0].heading.unwrap() - 0.5 * PI).abs() < 1e-9);
+        assert_eq!(frame[1].heading, Some(0.5));
+
+        // Without estimating, particle 7 has no heading to start from
+        assert!(tracks.to_headed_frame(0, false).is_err());
+
+        let sim = Simulation::from_tracking(
+            &frame,
+            DomainBoundaryLength(5.0),
+            Noise(0.1),
+            Speed(0.1),
+            RelativeTime(1.0),
+            ParticleDistanceThreshold(1.0),
+        )
+        .unwrap();
+        assert_eq!(sim.num_particles(), 2);
+    }
+}
@@ -0,0 +1,277 @@
+use std::ops::RangeBounds;
+
+use crate::{
+    Simulation,
+    error::{SimulationError, invalid_parameter},
+    observer::StepObserver,
+    types::{AbsoluteTime, Float},
+};
+
+/// Positions and headings of every particle at one recorded step
+#[derive(Clone, Debug)]
+pub struct TrajectoryFrame {
+    /// Steps taken since recording started
+    pub step: usize,
+    pub time: AbsoluteTime,
+
+    /// ID of each particle, which stays the same as particles are added and removed
+    pub id: Vec<usize>,
+    pub x: Vec<Float>,
+    pub y: Vec<Float>,
+    pub theta: Vec<Float>,
+}
+
+impl TrajectoryFrame {
+    /// Capture the current state of a simulation
+    fn from_simulation(sim: &Simulation, step: usize) -> Self {
+        let num_particles = sim.particles.len();
+        let mut frame = Self {
+            step,
+            time: sim.current_time,
+            id: Vec::with_capacity(num_particles),
+            x: Vec::with_capacity(num_particles),
+            y: Vec::with_capacity(num_particles),
+            theta: Vec::with_capacity(num_particles),
+        };
+
+        for particle in sim.particles.iter() {
+            frame.id.push(particle.stable_id);
+            frame.x.push(particle.pos_x);
+            frame.y.push(particle.pos_y);
+            frame.theta.push(particle.theta);
+        }
+
+        frame
+    }
+
+    /// Get the number of particles in the frame
+    pub fn len(&self) -> usize {
+        self.id.len()
+    }
+
+    /// Check whether the frame holds no particles
+    pub fn is_empty(&self) -> bool {
+        self.id.is_empty()
+    }
+}
+
+/// A time-ordered history of recorded frames
+#[derive(Clone, Debug, Default)]
+pub struct Trajectory {
+    pub(crate) frames: Vec<TrajectoryFrame>,
+}
+
+impl Trajectory {
+    /// Get the number of recorded frames
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Check whether nothing has been recorded
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Every recorded frame, oldest first
+    pub fn frames(&self) -> &[TrajectoryFrame] {
+        &self.frames
+    }
+
+    /// Get a frame by index
+    pub fn frame(&self, idx: usize) -> Option<&TrajectoryFrame> {
+        self.frames.get(idx)
+    }
+
+    /// The simulated time of each frame
+    pub fn times(&self) -> Vec<Float> {
+        self.frames.iter().map(|frame| frame.time.0).collect()
+    }
+
+    /// The frame recorded closest to `time`
+    pub fn frame_at(&self, time: AbsoluteTime) -> Option<&TrajectoryFrame> {
+        self.frames.iter().min_by(|a, b| {
+            (a.time.0 - time.0)
+                .abs()
+                .total_cmp(&(b.time.0 - time.0).abs())
+        })
+    }
+
+    /// A copy of a range of frames, by index
+    pub fn to_sliced(&self, range: impl RangeBounds<usize>) -> Self {
+        let range = (range.start_bound().cloned(), range.end_bound().cloned());
+
+        Self {
+            frames: self.frames.get(range).unwrap_or_default().to_vec(),
+        }
+    }
+
+    /// A copy of the frames recorded between two times, inclusive
+    pub fn to_time_sliced(&self, start: AbsoluteTime, end: AbsoluteTime) -> Self {
+        Self {
+            frames: self
+                .frames
+                .iter()
+                .filter(|frame| frame.time.0 >= start.0 && frame.time.0 <= end.0)
+                .cloned()
+                .collect(),
+        }
+    }
+
+    /// A copy keeping every `stride`-th frame, starting from the first
+    pub fn to_thinned(&self, stride: usize) -> Result<Self, SimulationError> {
+        if stride == 0 {
+            invalid_parameter!("frame stride must be at least 1");
+        }
+
+        Ok(Self {
+            frames: self.frames.iter().step_by(stride).cloned().collect(),
+        })
+    }
+
+    /// Follow one particle through the frames it appears in, as `(time, x, y, theta)`
+    ///
+    /// # Notes
+    /// Positions are wrapped into the domain, so tracks jump across the periodic boundaries.
+    pub fn particle_track(&self, id: usize) -> Vec<(Float, Float, Float, Float)> {
+        self.frames
+            .iter()
+            .filter_map(|frame| {
+                let idx = frame.id.iter().position(|&frame_id| frame_id == id)?;
+                Some((frame.time.0, frame.x[idx], frame.y[idx], frame.theta[idx]))
+            })
+            .collect()
+    }
+}
+
+/// Records positions and headings every `stride` steps, as a [`StepObserver`] or through
+/// [`Simulation::record_trajectory`]
+#[derive(Clone, Debug)]
+pub struct TrajectoryRecorder {
+    stride: usize,
+    num_steps: usize,
+    trajectory: Trajectory,
+}
+
+impl TrajectoryRecorder {
+    /// Record every `stride`-th step, from the first one observed
+    pub fn new(stride: usize) -> Result<Self, SimulationError> {
+        if stride == 0 {
+            invalid_parameter!("recording stride must be at least 1");
+        }
+
+        Ok(Self {
+            stride,
+            num_steps: 0,
+            trajectory: Trajectory::default(),
+        })
+    }
+
+    /// Record the current state as an extra frame, e.g. the starting state
+    pub fn record(&mut self, sim: &Simulation) {
+        self.trajectory
+            .frames
+            .push(TrajectoryFrame::from_simulation(sim, self.num_steps));
+    }
+
+    /// The frames recorded so far
+    pub fn trajectory(&self) -> &Trajectory {
+        &self.trajectory
+    }
+
+    /// Take the frames recorded so far, leaving the recorder empty
+    pub fn take_trajectory(&mut self) -> Trajectory {
+        std::mem::take(&mut self.trajectory)
+    }
+}
+
+impl StepObserver for TrajectoryRecorder {
+    fn on_step(&mut self, sim: &Simulation) {
+        self.num_steps += 1;
+
+        if self.num_steps.is_multiple_of(self.stride) {
+            self.record(sim);
+        }
+    }
+}
+
+impl Simulation {
+    /// Step `num_steps` times in place, recording the starting state and then every `stride`-th
+    /// step
+    pub fn record_trajectory(
+        &mut self,
+        num_steps: usize,
+        stride: usize,
+    ) -> Result<Trajectory, SimulationError> {
+        let mut recorder = TrajectoryRecorder::new(stride)?;
+        recorder.record(self);
+
+        for _ in 0..num_steps {
+            self.step_in_place()?;
+            recorder.on_step(self);
+        }
+
+        Ok(recorder.take_trajectory())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{
+        DomainBoundaryLength, Noise, ParticleDistanceThreshold, RelativeTime, Speed,
+    };
+
+    fn simulation() -> Simulation {
+        Simulation::new(
+            3,
+            DomainBoundaryLength(5.0),
+            Noise(0.1),
+            Speed(0.1),
+            RelativeTime(1.0),
+            ParticleDistanceThreshold(1.0),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn recording_keeps_the_start_and_every_stride_steps() {
+        let mut sim = simulation();
+        let trajectory = sim.record_trajectory(6, 2).unwrap();
+
+        assert_eq!(trajectory.times(), [0.0, 2.0, 4.0, 6.0]);
+        assert_eq!(trajectory.frame(3).unwrap().step, 6);
+        assert!(trajectory.frames().iter().all(|frame| frame.len() == 3));
+
+        // The last frame is the simulation as it ended
+        let last = trajectory.frame(3).unwrap();
+        for (idx, particle) in sim.particles.iter().enumerate() {
+            assert_eq!(last.id[idx], particle.stable_id);
+            assert_eq!(last.x[idx], particle.pos_x);
+            assert_eq!(last.theta[idx], particle.theta);
+        }
+
+        let track = trajectory.particle_track(last.id[1]);
+        assert_eq!(track.len(), 4);
+        assert_eq!(track[3], (6.0, last.x[1], last.y[1], last.theta[1]));
+    }
+
+    #[test]
+    fn trajectories_slice_by_index_time_and_stride() {
+        let trajectory = simulation().record_trajectory(6, 1).unwrap();
+
+        assert_eq!(trajectory.to_sliced(2..4).times(), [2.0, 3.0]);
+        assert_eq!(trajectory.to_sliced(5..).times(), [5.0, 6.0]);
+        assert!(trajectory.to_sliced(10..).is_empty());
+        assert_eq!(
+            trajectory
+                .to_time_sliced(AbsoluteTime(1.5), AbsoluteTime(3.0))
+                .times(),
+            [2.0, 3.0]
+        );
+        assert_eq!(trajectory.to_thinned(3).unwrap().times(), [0.0, 3.0, 6.0]);
+        assert_eq!(trajectory.frame_at(AbsoluteTime(4.4)).unwrap().step, 4);
+
+        assert!(trajectory.to_thinned(0).is_err());
+        assert!(TrajectoryRecorder::new(0).is_err());
+    }
+}
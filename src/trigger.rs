@@ -0,0 +1,531 @@
+use std::{collections::VecDeque, fmt::Debug, io::Write, sync::Arc};
+
+use anyhow::bail;
+
+use crate::{
+    Simulation,
+    control::CancellationToken,
+    export::{QuantizedFrame, QuantizedTrajectoryWriter},
+    types::{Float, Noise, ParticleDistanceThreshold, Speed},
+};
+
+/// A scalar watched by a trigger
+#[derive(Clone)]
+pub enum Observable {
+    /// The instantaneous order parameter
+    Order,
+
+    /// The number of particles, which only changes with births and deaths
+    NumParticles,
+
+    /// The simulated time
+    Time,
+
+    /// The mean of cos θ, i.e. how much the flock heads along +x. Crossing zero marks a
+    /// direction switch along x.
+    PolarizationX,
+
+    /// The mean of sin θ, i.e. how much the flock heads along +y
+    PolarizationY,
+
+    /// Any function of the simulation
+    Function(Arc<dyn Fn(&Simulation) -> Float + Send + Sync>),
+}
+
+impl Observable {
+    /// Watch an arbitrary function of the simulation
+    pub fn function(f: impl Fn(&Simulation) -> Float + Send + Sync + 'static) -> Self {
+        Self::Function(Arc::new(f))
+    }
+
+    pub(crate) fn evaluate(&self, sim: &Simulation) -> Float {
+        match self {
+            Self::Order => sim.instantaneous_order.0,
+            Self::NumParticles => sim.num_particles() as Float,
+            Self::Time => sim.current_time.0,
+            Self::PolarizationX => mean(sim.particles.iter().map(|particle| particle.theta.cos())),
+            Self::PolarizationY => mean(sim.particles.iter().map(|particle| particle.theta.sin())),
+            Self::Function(f) => f(sim),
+        }
+    }
+}
+
+impl Debug for Observable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Order => write!(f, "Order"),
+            Self::NumParticles => write!(f, "NumParticles"),
+            Self::Time => write!(f, "Time"),
+            Self::PolarizationX => write!(f, "PolarizationX"),
+            Self::PolarizationY => write!(f, "PolarizationY"),
+            Self::Function(_) => write!(f, "Function(..)"),
+        }
+    }
+}
+
+fn mean(values: impl ExactSizeIterator<Item = Float>) -> Float {
+    let len = values.len();
+    values.sum::<Float>() / len as Float
+}
+
+/// Which side of a threshold sets a trigger off
+#[derive(Copy, Clone, Debug)]
+pub enum Crossing {
+    Above(Float),
+    Below(Float),
+}
+
+impl Crossing {
+    fn holds(self, value: Float) -> bool {
+        match self {
+            Self::Above(threshold) => value > threshold,
+            Self::Below(threshold) => value < threshold,
+        }
+    }
+}
+
+/// What a trigger does when it fires
+#[derive(Copy, Clone, Debug)]
+pub enum TriggerAction {
+    /// Only note the event, e.g. to record densely around it
+    Mark,
+
+    /// End the run
+    Stop,
+
+    /// Keep a copy of the simulation as it is
+    Snapshot,
+
+    /// Record a frame every `stride` steps from here on
+    StartRecording {
+        stride: usize,
+    },
+
+    SetNoise(Noise),
+    SetSpeed(Speed),
+    SetDistanceThreshold(ParticleDistanceThreshold),
+}
+
+/// Fires an action once an observable has been past a threshold for long enough
+#[derive(Clone, Debug)]
+pub struct Trigger {
+    pub observable: Observable,
+    pub crossing: Crossing,
+
+    /// Consecutive steps the crossing must hold before firing, at least 1
+    pub sustained_steps: usize,
+
+    pub action: TriggerAction,
+
+    /// Whether the trigger can fire again once the crossing stops holding, rather than only once
+    pub rearm: bool,
+}
+
+impl Trigger {
+    /// Fire once, as soon as the crossing has held for `sustained_steps` steps in a row
+    pub fn new(
+        observable: Observable,
+        crossing: Crossing,
+        sustained_steps: usize,
+        action: TriggerAction,
+    ) -> Self {
+        Self {
+            observable,
+            crossing,
+            sustained_steps,
+            action,
+            rearm: false,
+        }
+    }
+}
+
+/// One firing of a trigger
+#[derive(Copy, Clone, Debug)]
+pub struct TriggerEvent {
+    /// Position of the trigger in the list given to the run
+    pub trigger: usize,
+
+    /// Number of steps taken when it fired
+    pub step: usize,
+
+    /// The observable's value when it fired
+    pub value: Float,
+}
+
+/// The outcome of a run with triggers
+#[derive(Clone)]
+pub struct TriggerRunReport {
+    /// The simulation where the run ended
+    pub simulation: Simulation,
+
+    /// Number of steps taken
+    pub num_steps: usize,
+
+    /// Every firing, in order
+    pub events: Vec<TriggerEvent>,
+
+    /// Copies taken by snapshot actions, in order
+    pub snapshots: Vec<Simulation>,
+
+    /// Frames taken once recording started, with the step each was taken at
+    pub frames: Vec<(usize, Simulation)>,
+
+    /// The trigger that stopped the run, if one did
+    pub stopped_by: Option<usize>,
+
+    /// Whether the run was cancelled
+    pub cancelled: bool,
+}
+
+/// Controls for recording sparsely, but densely around events
+#[derive(Copy, Clone, Debug)]
+pub struct AdaptiveRecording {
+    /// Steps between frames away from events
+    pub sparse_stride: usize,
+
+    /// Steps between frames around events
+    pub dense_stride: usize,
+
+    /// Steps before each event recorded densely
+    pub steps_before: usize,
+
+    /// Steps after each event recorded densely
+    pub steps_after: usize,
+}
+
+impl Default for AdaptiveRecording {
+    fn default() -> Self {
+        Self {
+            sparse_stride: 100,
+            dense_stride: 1,
+            steps_before: 50,
+            steps_after: 200,
+        }
+    }
+}
+
+/// How far a trigger has got towards firing
+#[derive(Copy, Clone)]
+struct TriggerState {
+    consecutive_steps: usize,
+    armed: bool,
+}
+
+impl Simulation {
+    /// Step up to `max_steps` times, checking every trigger after each step and carrying out the
+    /// actions of those that fire
+    ///
+    /// # Notes
+    /// Triggers are checked in order, so a parameter change by one is seen by the next step, not
+    /// by the triggers after it in the same step. A run stopped by a trigger finishes its other
+    /// triggers' checks for that step first.
+    pub fn run_with_triggers(
+        self,
+        max_steps: usize,
+        triggers: &[Trigger],
+        cancellation: Option<&CancellationToken>,
+    ) -> anyhow::Result<TriggerRunReport> {
+        self.run_triggered(max_steps, triggers, cancellation, |_, _, _| Ok(()))
+    }
+
+    /// Like [`Simulation::run_with_triggers`], but also stream frames to a trajectory: sparsely
+    /// most of the time, and densely around every step at which a trigger fires
+    ///
+    /// # Notes
+    /// Dense frames from the last `steps_before` steps are held back, and only written if an event
+    /// follows, so the lead-up to an event is kept without recording everything. Use
+    /// [`TriggerAction::Mark`] for triggers that should only mark events, e.g. the order dropping
+    /// as bands collide, or [`Observable::PolarizationX`] crossing zero for a direction switch.
+    pub fn run_with_adaptive_recording<W: Write>(
+        self,
+        max_steps: usize,
+        triggers: &[Trigger],
+        recording: &AdaptiveRecording,
+        writer: &mut QuantizedTrajectoryWriter<W>,
+        cancellation: Option<&CancellationToken>,
+    ) -> anyhow::Result<TriggerRunReport> {
+        if recording.sparse_stride == 0 || recording.dense_stride == 0 {
+            bail!("recording strides must be at least one step");
+        }
+
+        let mut held_back: VecDeque<(usize, QuantizedFrame)> = VecDeque::new();
+        let mut last_written: Option<usize> = None;
+        let mut dense_until: Option<usize> = None;
+
+        self.run_triggered(max_steps, triggers, cancellation, |step, sim, fired| {
+            // An event releases the lead-up to it...
+            if fired {
+                for (held_step, frame) in held_back.drain(..) {
+                    if last_written.is_none_or(|last| held_step > last) {
+                        writer.write_quantized_frame(&frame)?;
+                        last_written = Some(held_step);
+                    }
+                }
+
+                dense_until = Some(step + recording.steps_after);
+            }
+
+            // ...then it and the steps after it are recorded densely, and the rest sparsely...
+            let since_written = last_written.map_or(usize::MAX, |last| step - last);
+            let is_dense = dense_until.is_some_and(|until| step <= until);
+            let is_due = match is_dense {
+                true => since_written >= recording.dense_stride,
+                false => step % recording.sparse_stride == 0,
+            };
+
+            if is_due {
+                writer.write_frame(sim)?;
+                last_written = Some(step);
+            } else if recording.steps_before > 0 && step % recording.dense_stride == 0 {
+                // ...while dense frames are held back in case an event comes soon.
+                held_back.push_back((step, QuantizedFrame::from_simulation(sim)));
+            }
+
+            while held_back
+                .front()
+                .is_some_and(|&(held_step, _)| held_step + recording.steps_before <= step)
+            {
+                held_back.pop_front();
+            }
+
+            Ok(())
+        })
+    }
+
+    /// Step with triggers, handing each step to `on_step` along with whether any trigger fired
+    fn run_triggered(
+        self,
+        max_steps: usize,
+        triggers: &[Trigger],
+        cancellation: Option<&CancellationToken>,
+        mut on_step: impl FnMut(usize, &Simulation, bool) -> anyhow::Result<()>,
+    ) -> anyhow::Result<TriggerRunReport> {
+        for trigger in triggers {
+            if trigger.sustained_steps == 0 {
+                bail!("triggers must require the crossing for at least one step");
+            }
+
+            match trigger.action {
+                TriggerAction::StartRecording { stride: 0 } => {
+                    bail!("recording stride must be at least one step");
+                }
+                TriggerAction::SetNoise(Noise(noise)) if noise.is_nan() || noise < 0.0 => {
+                    bail!("triggered noise must be non-negative, got `{}`", noise);
+                }
+                TriggerAction::SetSpeed(Speed(speed)) if speed.is_nan() || speed < 0.0 => {
+                    bail!("triggered speed must be non-negative, got `{}`", speed);
+                }
+                TriggerAction::SetDistanceThreshold(ParticleDistanceThreshold(threshold))
+                    if threshold.is_nan() || threshold <= 0.0 =>
+                {
+                    bail!(
+                        "triggered distance threshold must be positive, got `{}`",
+                        threshold
+                    );
+                }
+                _ => {}
+            }
+        }
+
+        let mut states = vec![
+            TriggerState {
+                consecutive_steps: 0,
+                armed: true,
+            };
+            triggers.len()
+        ];
+        let mut report = TriggerRunReport {
+            simulation: self,
+            num_steps: 0,
+            events: Vec::new(),
+            snapshots: Vec::new(),
+            frames: Vec::new(),
+            stopped_by: None,
+            cancelled: false,
+        };
+        let mut recording_stride: Option<usize> = None;
+
+        while report.num_steps < max_steps && report.stopped_by.is_none() {
+            if cancellation.is_some_and(|cancellation| cancellation.is_cancelled()) {
+                report.cancelled = true;
+                break;
+            }
+
+            report.simulation = report.simulation.to_timestepped();
+            report.num_steps += 1;
+            let step = report.num_steps;
+            let num_events = report.events.len();
+
+            for (idx, (trigger, state)) in triggers.iter().zip(&mut states).enumerate() {
+                let value = trigger.observable.evaluate(&report.simulation);
+
+                if !trigger.crossing.holds(value) {
+                    state.consecutive_steps = 0;
+                    state.armed |= trigger.rearm;
+                    continue;
+                }
+
+                state.consecutive_steps += 1;
+                if !state.armed || state.consecutive_steps < trigger.sustained_steps {
+                    continue;
+                }
+
+                state.armed = false;
+                report.events.push(TriggerEvent {
+                    trigger: idx,
+                    step,
+                    value,
+                });
+
+                let sim = &mut report.simulation;
+                match trigger.action {
+                    TriggerAction::Mark => {}
+                    TriggerAction::Stop => report.stopped_by = report.stopped_by.or(Some(idx)),
+                    TriggerAction::Snapshot => report.snapshots.push(sim.clone()),
+                    TriggerAction::StartRecording { stride } => {
+                        // The firing step is the first frame
+                        recording_stride = Some(stride);
+                        report.frames.push((step, sim.clone()));
+                    }
+                    TriggerAction::SetNoise(noise) => sim.params.noise = noise,
+                    TriggerAction::SetSpeed(speed) => sim.params.speed = speed,
+                    TriggerAction::SetDistanceThreshold(threshold) => {
+                        sim.params.particle_distance_threshold = threshold
+                    }
+                }
+            }
+
+            if let Some(stride) = recording_stride
+                && report
+                    .frames
+                    .last()
+                    .is_none_or(|(last, _)| step - last >= stride)
+            {
+                report.frames.push((step, report.simulation.clone()));
+            }
+
+            on_step(step, &report.simulation, report.events.len() > num_events)?;
+        }
+
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        export::read_quantized_trajectory,
+        types::{DomainBoundaryLength, RelativeTime},
+    };
+
+    fn sim() -> Simulation {
+        Simulation::new(
+            10,
+            DomainBoundaryLength(5.0),
+            Noise(0.1),
+            Speed(0.1),
+            RelativeTime(1.0),
+            ParticleDistanceThreshold(1.0),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn triggers_fire_once_their_crossing_is_sustained() {
+        let triggers = [
+            Trigger::new(
+                Observable::Time,
+                Crossing::Above(0.5),
+                1,
+                TriggerAction::StartRecording { stride: 2 },
+            ),
+            Trigger::new(
+                Observable::Time,
+                Crossing::Above(1.5),
+                1,
+                TriggerAction::SetNoise(Noise(0.7)),
+            ),
+            Trigger::new(
+                Observable::Time,
+                Crossing::Above(2.5),
+                2,
+                TriggerAction::Stop,
+            ),
+        ];
+
+        let report = sim().run_with_triggers(10, &triggers, None).unwrap();
+
+        let events: Vec<_> = report
+            .events
+            .iter()
+            .map(|event| (event.trigger, event.step))
+            .collect();
+        assert_eq!(events, [(0, 1), (1, 2), (2, 4)]);
+        assert_eq!(report.num_steps, 4);
+        assert_eq!(report.stopped_by, Some(2));
+        assert_eq!(
+            report
+                .frames
+                .iter()
+                .map(|(step, _)| *step)
+                .collect::<Vec<_>>(),
+            [1, 3]
+        );
+        assert_eq!(report.simulation.params.noise.0, 0.7);
+    }
+
+    #[test]
+    fn rearmed_triggers_fire_each_time_the_crossing_returns() {
+        let parity = Observable::function(|sim| sim.current_time.0 % 2.0);
+        let trigger = Trigger {
+            rearm: true,
+            ..Trigger::new(parity, Crossing::Above(0.5), 1, TriggerAction::Snapshot)
+        };
+
+        let report = sim().run_with_triggers(6, &[trigger], None).unwrap();
+        assert_eq!(
+            report
+                .events
+                .iter()
+                .map(|event| event.step)
+                .collect::<Vec<_>>(),
+            [1, 3, 5]
+        );
+        assert_eq!(report.snapshots.len(), 3);
+
+        let unsustained = Trigger::new(
+            Observable::Order,
+            Crossing::Below(0.0),
+            0,
+            TriggerAction::Mark,
+        );
+        assert!(sim().run_with_triggers(1, &[unsustained], None).is_err());
+    }
+
+    #[test]
+    fn adaptive_recording_is_dense_only_around_events() {
+        let trigger = Trigger::new(
+            Observable::Time,
+            Crossing::Above(5.5),
+            1,
+            TriggerAction::Mark,
+        );
+        let recording = AdaptiveRecording {
+            sparse_stride: 10,
+            dense_stride: 1,
+            steps_before: 2,
+            steps_after: 1,
+        };
+
+        let mut writer = QuantizedTrajectoryWriter::new(Vec::new()).unwrap();
+        sim()
+            .run_with_adaptive_recording(20, &[trigger], &recording, &mut writer, None)
+            .unwrap();
+        let frames = read_quantized_trajectory(writer.into_inner().unwrap().as_slice()).unwrap();
+
+        // Two steps leading up to the event at step 6, the step after it, then sparse frames
+        let times: Vec<Float> = frames.iter().map(|frame| frame.time.0).collect();
+        assert_eq!(times, [4.0, 5.0, 6.0, 7.0, 10.0, 20.0]);
+    }
+}
@@ -13,11 +13,34 @@ pub(crate) const PI: f64 = std::f64::consts::PI;
 #[cfg(not(feature = "f64"))]
 pub(crate) const PI: f32 = std::f32::consts::PI;
 
+/// Common interface over the quantity newtypes, so generic helpers (e.g. schedules) can unwrap and
+/// rewrap them
+pub trait Quantity: Copy {
+    /// Unwrap into the underlying float
+    fn value(self) -> Float;
+
+    /// Wrap a float into this quantity
+    fn from_value(value: Float) -> Self;
+}
+
 macro_rules! create_quantity {
     ($name:ident) => {
         #[derive(Copy, Clone, Debug)]
+        #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
         pub struct $name(pub Float);
 
+        impl Quantity for $name {
+            #[inline]
+            fn value(self) -> Float {
+                self.0
+            }
+
+            #[inline]
+            fn from_value(value: Float) -> Self {
+                Self(value)
+            }
+        }
+
         /// Implements quantity * quantity
         impl Add for $name {
             type Output = $name;
@@ -43,6 +66,7 @@ create_quantity!(Noise);
 create_quantity!(ParticleDistanceThreshold);
 create_quantity!(DomainBoundaryLength);
 create_quantity!(InstantaneosOrder);
+create_quantity!(Angle);
 
 // Sets up a nice relation for additive time
 impl Add<RelativeTime> for AbsoluteTime {
@@ -0,0 +1,345 @@
+use anyhow::bail;
+use num::Complex;
+
+use crate::{
+    Simulation,
+    particle::{Particle, Particles},
+    types::{Float, Noise, PI},
+};
+
+/// Controls for the built-in verification checks
+#[derive(Copy, Clone, Debug)]
+pub struct VerificationOptions {
+    /// Steps simulated for the noise-limit checks, whose second half is averaged
+    pub num_steps: usize,
+
+    /// Steps compared between the original and translated runs
+    pub translation_steps: usize,
+
+    /// Noise amplitude that should leave the particles disordered
+    pub high_noise: Noise,
+
+    /// Order parameter the noiseless run must reach
+    pub ordered_threshold: Float,
+
+    /// Largest difference in position or heading counted as agreement
+    pub tolerance: Float,
+}
+
+impl Default for VerificationOptions {
+    fn default() -> Self {
+        Self {
+            num_steps: 500,
+            translation_steps: 10,
+            high_noise: Noise(2.0 * PI),
+            ordered_threshold: 0.9,
+            tolerance: 1e-6,
+        }
+    }
+}
+
+/// The outcome of one check
+#[derive(Clone, Debug)]
+pub struct VerificationCheck {
+    /// A stable, machine-readable name
+    pub name: &'static str,
+
+    pub passed: bool,
+
+    /// What was measured, for diagnosing failures
+    pub detail: String,
+}
+
+/// The outcome of every check
+#[derive(Clone, Debug)]
+pub struct VerificationReport {
+    pub checks: Vec<VerificationCheck>,
+}
+
+impl VerificationReport {
+    /// Whether every check passed
+    pub fn all_passed(&self) -> bool {
+        self.checks.iter().all(|check| check.passed)
+    }
+}
+
+impl Simulation {
+    /// Run the built-in checks against this simulation's configuration (update rule, fields,
+    /// per-particle settings, and so on), each starting from a copy of its current state
+    ///
+    /// # Notes
+    /// The checks are:
+    /// - `low_noise_order`: with no noise the particles order, reaching the ordered threshold
+    /// - `high_noise_disorder`: with high noise the order stays within the 3/√N expected of random
+    ///   headings
+    /// - `translation_invariance`: shifting every particle by the same offset, then replaying the
+    ///   same noise, shifts the whole trajectory and changes nothing else
+    /// - `reference_agreement`: one noiseless Vicsek step matches a separate brute-force
+    ///   implementation of Equation 1, which checks the neighbor search
+    ///
+    /// Particles with their own noise amplitude keep it for the noise-limit checks. Spatially
+    /// varying fields and flows are legitimately not translation invariant, and translational noise
+    /// isn't replayed, so it fails that check too.
+    pub fn verify(&self, options: &VerificationOptions) -> anyhow::Result<VerificationReport> {
+        if options.num_steps < 2 || options.translation_steps == 0 {
+            bail!(
+                "verification needs at least two steps for the noise limits and one to translate"
+            );
+        }
+
+        Ok(VerificationReport {
+            checks: vec![
+                self.check_low_noise_order(options),
+                self.check_high_noise_disorder(options),
+                self.check_translation_invariance(options)?,
+                self.check_reference_agreement(options),
+            ],
+        })
+    }
+
+    fn check_low_noise_order(&self, options: &VerificationOptions) -> VerificationCheck {
+        let order = self
+            .to_with_fixed_noise(Noise(0.0))
+            .mean_late_order(options);
+
+        VerificationCheck {
+            name: "low_noise_order",
+            passed: order >= options.ordered_threshold,
+            detail: format!(
+                "mean order `{order}` with no noise, needed at least `{}`",
+                options.ordered_threshold
+            ),
+        }
+    }
+
+    fn check_high_noise_disorder(&self, options: &VerificationOptions) -> VerificationCheck {
+        let order = self
+            .to_with_fixed_noise(options.high_noise)
+            .mean_late_order(options);
+        let threshold = 3.0 / (self.particles.len() as Float).sqrt();
+
+        VerificationCheck {
+            name: "high_noise_disorder",
+            passed: order <= threshold,
+            detail: format!(
+                "mean order `{order}` with noise `{}`, needed at most `{threshold}`",
+                options.high_noise.0
+            ),
+        }
+    }
+
+    fn check_translation_invariance(
+        &self,
+        options: &VerificationOptions,
+    ) -> anyhow::Result<VerificationCheck> {
+        let boundary_side_length = self.params.boundary_side_length.0;
+        let (offset_x, offset_y) = (0.37 * boundary_side_length, 0.61 * boundary_side_length);
+
+        // Translating keeps every particle's phase, so the first recorded draws still apply
+        let translated = Simulation {
+            particles: Particles::from_reindexed(self.particles.iter().map(|particle| Particle {
+                pos_x: (particle.pos_x + offset_x).rem_euclid(boundary_side_length),
+                pos_y: (particle.pos_y + offset_y).rem_euclid(boundary_side_length),
+                ..particle.clone()
+            })),
+            ..self.clone()
+        };
+
+        let mut original = self.clone().with_noise_recording();
+        for _ in 0..options.translation_steps {
+            original = original.to_timestepped();
+        }
+
+        let Some(recording) = original.noise_recording() else {
+            bail!("noise recording was lost while verifying translation invariance");
+        };
+
+        let mut translated = translated.with_noise_replay(recording)?;
+        for _ in 0..options.translation_steps {
+            translated = translated.to_timestepped();
+        }
+
+        // Compare along the shortest periodic path, since the domain may have been resized
+        let boundary_side_length = original.params.boundary_side_length.0;
+        let scale = boundary_side_length / self.params.boundary_side_length.0;
+        let periodic_gap = |delta: Float, period: Float| {
+            let delta = delta.rem_euclid(period);
+            delta.min(period - delta)
+        };
+
+        let max_difference = original
+            .particles
+            .iter()
+            .zip(translated.particles.iter())
+            .map(|(original, translated)| {
+                let dx = translated.pos_x - original.pos_x - offset_x * scale;
+                let dy = translated.pos_y - original.pos_y - offset_y * scale;
+                let dtheta = translated.theta - original.theta;
+
+                periodic_gap(dx, boundary_side_length)
+                    .max(periodic_gap(dy, boundary_side_length))
+                    .max(periodic_gap(dtheta, 2.0 * PI))
+            })
+            .fold(0.0, Float::max);
+
+        Ok(VerificationCheck {
+            name: "translation_invariance",
+            passed: max_difference <= options.tolerance,
+            detail: format!(
+                "largest difference `{max_difference}` after `{}` steps",
+                options.translation_steps
+            ),
+        })
+    }
+
+    fn check_reference_agreement(&self, options: &VerificationOptions) -> VerificationCheck {
+        let boundary_side_length = self.params.boundary_side_length.0;
+        let threshold = self.params.particle_distance_threshold.0;
+
+        // A plain noiseless Vicsek model over the same positions and headings...
+        let plain = match Simulation::from_initial_particles(
+            Particles::from_reindexed(self.particles.iter().map(|particle| {
+                Particle::from_state(particle.pos_x, particle.pos_y, particle.theta)
+            })),
+            self.params.boundary_side_length,
+            Noise(0.0),
+            self.params.speed,
+            self.params.timestep,
+            self.params.particle_distance_threshold,
+        ) {
+            Ok(plain) => plain.to_timestepped(),
+            Err(error) => {
+                return VerificationCheck {
+                    name: "reference_agreement",
+                    passed: false,
+                    detail: format!("could not build the plain model: {error}"),
+                };
+            }
+        };
+
+        // ...checked against averaging every other particle within the threshold directly.
+        let periodic_delta = |a: Float, b: Float| {
+            let delta = (a - b).rem_euclid(boundary_side_length);
+            delta.min(boundary_side_length - delta)
+        };
+
+        let max_difference = self
+            .particles
+            .iter()
+            .zip(plain.particles.iter())
+            .map(|(particle, stepped)| {
+                let summed: Complex<Float> = self
+                    .particles
+                    .iter()
+                    .filter(|other| other.id != particle.id)
+                    .filter(|other| {
+                        let dx = periodic_delta(particle.pos_x, other.pos_x);
+                        let dy = periodic_delta(particle.pos_y, other.pos_y);
+                        (dx * dx + dy * dy).sqrt() < threshold
+                    })
+                    .map(|other| Complex::new(other.theta.cos(), other.theta.sin()))
+                    .sum();
+
+                let expected = match summed.norm() > 0.0 {
+                    true => summed.arg(),
+                    false => particle.theta,
+                };
+
+                let delta = (stepped.theta - expected).rem_euclid(2.0 * PI);
+                delta.min(2.0 * PI - delta)
+            })
+            .fold(0.0, Float::max);
+
+        VerificationCheck {
+            name: "reference_agreement",
+            passed: max_difference <= options.tolerance,
+            detail: format!("largest heading difference `{max_difference}` after one step"),
+        }
+    }
+
+    /// Copy this simulation with a constant noise everywhere the simulation's own noise applies
+    fn to_with_fixed_noise(&self, noise: Noise) -> Self {
+        let mut sim = self.clone();
+        sim.params.noise = noise;
+        sim.params.noise_field = None;
+        sim.noise_schedule = None;
+
+        sim
+    }
+
+    /// Step through the options' number of steps, averaging the order over the second half
+    fn mean_late_order(mut self, options: &VerificationOptions) -> Float {
+        let num_averaged = options.num_steps - options.num_steps / 2;
+        let mut summed_order = 0.0;
+
+        for step in 0..options.num_steps {
+            self = self.to_timestepped();
+
+            if step >= options.num_steps / 2 {
+                summed_order += self.instantaneous_order.0;
+            }
+        }
+
+        summed_order / num_averaged as Float
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        random::seed_rng,
+        types::{DomainBoundaryLength, ParticleDistanceThreshold, RelativeTime, Speed},
+    };
+
+    #[test]
+    fn vicsek_simulation_passes_every_check() {
+        seed_rng(5);
+        let sim = Simulation::new(
+            50,
+            DomainBoundaryLength(3.0),
+            Noise(0.3),
+            Speed(0.03),
+            RelativeTime(1.0),
+            ParticleDistanceThreshold(1.0),
+        )
+        .unwrap();
+
+        let report = sim
+            .verify(&VerificationOptions {
+                num_steps: 200,
+                ..Default::default()
+            })
+            .unwrap();
+        let names: Vec<_> = report.checks.iter().map(|check| check.name).collect();
+        assert_eq!(
+            names,
+            [
+                "low_noise_order",
+                "high_noise_disorder",
+                "translation_invariance",
+                "reference_agreement"
+            ]
+        );
+        assert!(report.all_passed(), "{:#?}", report.checks);
+    }
+
+    #[test]
+    fn verification_needs_steps() {
+        let sim = Simulation::new(
+            5,
+            DomainBoundaryLength(3.0),
+            Noise(0.3),
+            Speed(0.03),
+            RelativeTime(1.0),
+            ParticleDistanceThreshold(1.0),
+        )
+        .unwrap();
+
+        let options = VerificationOptions {
+            num_steps: 1,
+            ..Default::default()
+        };
+        assert!(sim.verify(&options).is_err());
+    }
+}
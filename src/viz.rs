@@ -0,0 +1,141 @@
+use anyhow::Context;
+use minifb::{Key, KeyRepeat, Window, WindowOptions};
+
+use crate::{
+    error::{SimulationError, invalid_parameter},
+    render::RenderOptions,
+    simulation::Simulation,
+};
+
+/// Controls for [`Simulation::watch`]
+#[derive(Clone, Debug)]
+pub struct WatchOptions {
+    /// Width and height of the window in pixels
+    pub size: usize,
+
+    /// Number of steps taken between redraws while running
+    pub steps_per_frame: usize,
+
+    /// Upper limit on redraws per second
+    pub max_fps: usize,
+
+    /// How the particles are drawn
+    pub render: RenderOptions,
+}
+
+impl Default for WatchOptions {
+    fn default() -> Self {
+        Self {
+            size: 600,
+            steps_per_frame: 1,
+            max_fps: 60,
+            render: RenderOptions::default(),
+        }
+    }
+}
+
+impl WatchOptions {
+    fn validate(&self) -> Result<(), SimulationError> {
+        if self.size == 0 {
+            invalid_parameter!("window size must be at least 1 pixel");
+        }
+        if self.steps_per_frame == 0 {
+            invalid_parameter!("steps per frame must be at least 1");
+        }
+
+        self.render.validate()
+    }
+}
+
+impl Simulation {
+    /// Open a window drawing the particles (as arrows along their headings, unless styled
+    /// otherwise), and step the simulation in place as it redraws until the window is closed
+    ///
+    /// # Notes
+    /// Space pauses and resumes, the right arrow key (or `S`) takes a single step while paused,
+    /// and Escape closes the window. The title shows the simulated time and the instantaneous
+    /// order. Steps go through the same path as [`Simulation::run_for`], so observers and any
+    /// watchdog see them. Some platforms only allow windows on the main thread.
+    pub fn watch(&mut self, options: &WatchOptions) -> anyhow::Result<()> {
+        options.validate()?;
+
+        let size = options.size;
+        let mut window = Window::new("pip-sim", size, size, WindowOptions::default())
+            .context("could not open visualization window")?;
+        window.set_target_fps(options.max_fps);
+
+        let mut paused = false;
+        while window.is_open() && !window.is_key_down(Key::Escape) {
+            if window.is_key_pressed(Key::Space, KeyRepeat::No) {
+                paused = !paused;
+            }
+
+            let single_step = window.is_key_pressed(Key::Right, KeyRepeat::Yes)
+                || window.is_key_pressed(Key::S, KeyRepeat::Yes);
+            let num_steps = match (paused, single_step) {
+                (false, _) => options.steps_per_frame,
+                (true, true) => 1,
+                (true, false) => 0,
+            };
+            self.run_for(num_steps)?;
+
+            window.set_title(&format!(
+                "pip-sim | t = {:.2} | order = {:.3}{}",
+                self.current_time.0,
+                self.instantaneous_order.0,
+                if paused { " | paused" } else { "" }
+            ));
+
+            // Pixels as 0RGB
+            let buffer: Vec<u32> = self
+                .to_rgb_pixels(size, &options.render)?
+                .chunks(3)
+                .map(|pixel| u32::from_be_bytes([0, pixel[0], pixel[1], pixel[2]]))
+                .collect();
+            window
+                .update_with_buffer(&buffer, size, size)
+                .context("could not draw to visualization window")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{
+        DomainBoundaryLength, Noise, ParticleDistanceThreshold, RelativeTime, Speed,
+    };
+
+    #[test]
+    fn options_are_checked_before_opening_a_window() {
+        let mut sim = Simulation::new(
+            3,
+            DomainBoundaryLength(5.0),
+            Noise(0.1),
+            Speed(0.1),
+            RelativeTime(1.0),
+            ParticleDistanceThreshold(1.0),
+        )
+        .unwrap();
+
+        for options in [
+            WatchOptions {
+                size: 0,
+                ..Default::default()
+            },
+            WatchOptions {
+                steps_per_frame: 0,
+                ..Default::default()
+            },
+        ] {
+            let error = sim.watch(&options).unwrap_err();
+            assert!(matches!(
+                error.downcast_ref(),
+                Some(SimulationError::InvalidParameter(_))
+            ));
+        }
+        assert_eq!(sim.current_time.0, 0.0);
+    }
+}
@@ -0,0 +1,319 @@
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io::{BufReader, BufWriter},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use anyhow::Context;
+use serde_json::{Value, json};
+
+use crate::{
+    Simulation,
+    error::SimulationError,
+    random::{self, RngState},
+    simulation::validate_positive,
+    types::Float,
+};
+
+/// Checks every step of a run for numerical trouble, and on finding some dumps the state needed
+/// to reproduce it before failing
+///
+/// # Notes
+/// On an anomaly `dump_dir` gets `previous.json` and `frame.json`, the states before and after
+/// the offending step in the JSON state file format, plus `rng.json`, the random state the step
+/// started from. Loading `previous.json` with [`Simulation::from_json`], which keeps the noise
+/// phases the step uses, and restoring the random state with [`read_rng_state`] and
+/// [`random::restore_rng_state`] replays the step, as long as the other settings (e.g. the update
+/// rule) are applied again too. Files from an earlier
+/// anomaly in the same directory are overwritten.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Watchdog {
+    /// Directory the dumps are written to, created if needed
+    pub dump_dir: PathBuf,
+
+    /// Furthest any particle may move in one step, across the periodic boundaries, if limited
+    pub max_displacement: Option<Float>,
+
+    /// Largest change in the order parameter over one step, if limited
+    pub max_order_jump: Option<Float>,
+}
+
+impl Watchdog {
+    /// Only check for NaN or infinite positions, headings, and order
+    pub fn new(dump_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dump_dir: dump_dir.into(),
+            max_displacement: None,
+            max_order_jump: None,
+        }
+    }
+
+    /// Describe what went wrong over a step, if anything
+    fn find_anomaly(&self, statistics: &StepStatistics) -> Option<String> {
+        if statistics.num_non_finite > 0 {
+            return Some(format!(
+                "`{}` particles have a non-finite position or heading",
+                statistics.num_non_finite
+            ));
+        }
+
+        if !statistics.order.is_finite() {
+            return Some(format!("the order parameter is `{}`", statistics.order));
+        }
+
+        if let Some(max_displacement) = self.max_displacement
+            && statistics.max_displacement > max_displacement
+        {
+            return Some(format!(
+                "a particle moved `{}` in one step, above the limit of `{}`",
+                statistics.max_displacement, max_displacement
+            ));
+        }
+
+        if let Some(max_order_jump) = self.max_order_jump
+            && statistics.order_jump > max_order_jump
+        {
+            return Some(format!(
+                "the order parameter changed by `{}` in one step, above the limit of `{}`",
+                statistics.order_jump, max_order_jump
+            ));
+        }
+
+        None
+    }
+
+    /// Write the states either side of a bad step and the random state it started from
+    fn dump(
+        &self,
+        previous: &Simulation,
+        next: &Simulation,
+        rng_state: &RngState,
+        reason: &str,
+    ) -> anyhow::Result<()> {
+        fs::create_dir_all(&self.dump_dir).with_context(|| {
+            format!(
+                "could not create dump directory `{}`",
+                self.dump_dir.display()
+            )
+        })?;
+
+        for (name, sim) in [("previous.json", previous), ("frame.json", next)] {
+            let path = self.dump_dir.join(name);
+            let file = File::create(&path)
+                .with_context(|| format!("could not create `{}`", path.display()))?;
+            sim.write_json_state(BufWriter::new(file))?;
+        }
+
+        let path = self.dump_dir.join("rng.json");
+        let file = File::create(&path)
+            .with_context(|| format!("could not create `{}`", path.display()))?;
+        let mut state = rng_state.to_json();
+        state["time"] = json!(previous.current_time.0);
+        state["reason"] = json!(reason);
+        serde_json::to_writer_pretty(BufWriter::new(file), &state)
+            .with_context(|| format!("could not write `{}`", path.display()))
+    }
+}
+
+/// What the watchdog measures over one step
+#[derive(Copy, Clone, Debug)]
+pub struct StepStatistics {
+    /// Furthest any particle moved, across the periodic boundaries
+    pub max_displacement: Float,
+
+    /// Particles with a NaN or infinite position or heading
+    pub num_non_finite: usize,
+
+    /// The order parameter after the step
+    pub order: Float,
+
+    /// How much the order parameter changed
+    pub order_jump: Float,
+}
+
+impl StepStatistics {
+    /// Measure the step from `previous` to `next`, matching particles by ID
+    ///
+    /// # Notes
+    /// Displacements are measured in `next`'s domain, so a domain resize that rescales positions
+    /// shows up as movement.
+    pub fn between(previous: &Simulation, next: &Simulation) -> Self {
+        let boundary_side_length = next.params.boundary_side_length.0;
+        let previous_positions: HashMap<usize, (Float, Float)> = previous
+            .particles
+            .iter()
+            .map(|particle| (particle.stable_id, (particle.pos_x, particle.pos_y)))
+            .collect();
+
+        // The shortest way round the periodic domain
+        let wrap =
+            |delta: Float| delta - boundary_side_length * (delta / boundary_side_length).round();
+
+        let mut max_displacement: Float = 0.0;
+        let mut num_non_finite = 0;
+
+        for particle in next.particles.iter() {
+            if !(particle.pos_x.is_finite()
+                && particle.pos_y.is_finite()
+                && particle.theta.is_finite())
+            {
+                num_non_finite += 1;
+                continue;
+            }
+
+            if let Some(&(x, y)) = previous_positions.get(&particle.stable_id) {
+                let displacement = wrap(particle.pos_x - x).hypot(wrap(particle.pos_y - y));
+                max_displacement = max_displacement.max(displacement);
+            }
+        }
+
+        Self {
+            max_displacement,
+            num_non_finite,
+            order: next.instantaneous_order.0,
+            order_jump: (next.instantaneous_order.0 - previous.instantaneous_order.0).abs(),
+        }
+    }
+}
+
+/// Read the random state from a watchdog's `rng.json` dump
+pub fn read_rng_state(path: impl AsRef<Path>) -> anyhow::Result<RngState> {
+    let path = path.as_ref();
+    let file = File::open(path).with_context(|| format!("could not open `{}`", path.display()))?;
+    let root: Value = serde_json::from_reader(BufReader::new(file))
+        .with_context(|| format!("could not read `{}`", path.display()))?;
+
+    RngState::from_json(&root)
+}
+
+impl Simulation {
+    /// Check every step taken in place from here on, or stop checking with `None`
+    ///
+    /// # Notes
+    /// Steps taken by [`Simulation::run_for`], [`Simulation::run_until`],
+    /// [`Simulation::record_trajectory`], and the stationary order parameter computations are
+    /// checked, and fail with [`SimulationError::NumericalAnomaly`] after dumping the state.
+    /// Snapshotting the random state costs a little on each checked step.
+    pub fn with_watchdog(self, watchdog: Option<Watchdog>) -> Result<Self, SimulationError> {
+        if let Some(watchdog) = &watchdog {
+            if let Some(max_displacement) = watchdog.max_displacement {
+                validate_positive("max displacement", max_displacement)?;
+            }
+            if let Some(max_order_jump) = watchdog.max_order_jump {
+                validate_positive("max order jump", max_order_jump)?;
+            }
+        }
+
+        Ok(Self {
+            watchdog: watchdog.map(Arc::new),
+            ..self
+        })
+    }
+
+    /// Take one step in place and notify the observers, checking the step if watched
+    pub(crate) fn step_in_place(&mut self) -> Result<(), SimulationError> {
+        let Some(watchdog) = self.watchdog.clone() else {
+            *self = self.to_timestepped();
+            self.notify_observers();
+            return Ok(());
+        };
+
+        let rng_state = random::rng_state();
+        let next = self.to_timestepped();
+
+        if let Some(reason) = watchdog.find_anomaly(&StepStatistics::between(self, &next)) {
+            let dumped = watchdog.dump(self, &next, &rng_state, &reason);
+
+            return Err(SimulationError::NumericalAnomaly {
+                time: next.current_time.0,
+                reason,
+                dump_dir: dumped.ok().map(|_| watchdog.dump_dir.clone()),
+            });
+        }
+
+        *self = next;
+        self.notify_observers();
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{
+        DomainBoundaryLength, Noise, ParticleDistanceThreshold, RelativeTime, Speed,
+    };
+
+    #[test]
+    fn anomalies_fail_the_step_and_dump_a_replayable_state() {
+        let dump_dir = std::env::temp_dir().join(format!("watchdog-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dump_dir);
+        let mut sim = Simulation::new(
+            3,
+            DomainBoundaryLength(5.0),
+            Noise(0.1),
+            Speed(0.1),
+            RelativeTime(1.0),
+            ParticleDistanceThreshold(1.0),
+        )
+        .unwrap();
+
+        let watchdog = Watchdog {
+            max_displacement: Some(0.05),
+            ..Watchdog::new(&dump_dir)
+        };
+        sim = sim.with_watchdog(Some(watchdog)).unwrap();
+
+        let Err(SimulationError::NumericalAnomaly {
+            time,
+            dump_dir: dumped,
+            ..
+        }) = sim.run_for(1)
+        else {
+            panic!("a step of 0.1 should exceed the 0.05 limit");
+        };
+        assert_eq!(time, 1.0);
+        assert_eq!(dumped.as_deref(), Some(dump_dir.as_path()));
+        assert_eq!(sim.current_time.0, 0.0);
+
+        // Replaying the step from the dump reproduces the frame after it
+        let load = |name| Simulation::from_json(&fs::read_to_string(dump_dir.join(name)).unwrap());
+        let previous = load("previous.json").unwrap();
+        let frame = load("frame.json").unwrap();
+        random::restore_rng_state(&read_rng_state(dump_dir.join("rng.json")).unwrap());
+        let replayed = previous.to_timestepped();
+        for (a, b) in replayed.particles.iter().zip(frame.particles.iter()) {
+            assert!((a.pos_x - b.pos_x).abs() < 1e-9 && (a.theta - b.theta).abs() < 1e-9);
+        }
+
+        let _ = fs::remove_dir_all(&dump_dir);
+    }
+
+    #[test]
+    fn unlimited_watchdogs_let_ordinary_steps_through() {
+        let sim = Simulation::new(
+            3,
+            DomainBoundaryLength(5.0),
+            Noise(0.1),
+            Speed(0.1),
+            RelativeTime(1.0),
+            ParticleDistanceThreshold(1.0),
+        )
+        .unwrap();
+        let watchdog = Watchdog::new(std::env::temp_dir().join("unused-watchdog"));
+
+        let mut sim = sim.with_watchdog(Some(watchdog.clone())).unwrap();
+        sim.run_for(3).unwrap();
+        assert_eq!(sim.current_time.0, 3.0);
+
+        let watchdog = Watchdog {
+            max_order_jump: Some(0.0),
+            ..watchdog
+        };
+        assert!(sim.with_watchdog(Some(watchdog)).is_err());
+    }
+}